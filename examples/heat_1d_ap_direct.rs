@@ -5,6 +5,7 @@ fn main() {
     let args = Args::cli_setup("heat_1d_ap_direct");
 
     let stencil = nhls::standard_stencils::heat_1d(1.0, 1.0, 0.5);
+    args.check_stability(&stencil);
 
     // Create BC
     let mut solver = Direct3Pt1DSolver::new(
@@ -6,6 +6,8 @@ fn main() {
     let args = Args::cli_setup("heat_1d_ap_fft");
 
     let stencil = nhls::standard_stencils::heat_1d(1.0, 1.0, 0.5);
+    args.check_stability(&stencil);
+    args.dry_run(&stencil);
 
     // This optimized direct solver implement a uniform boundary condition of 0.0
     let direct_solver = DirectSolver3Pt1DOpt::new(&stencil, args.chunk_size);
@@ -5,6 +5,7 @@ fn main() {
     let args = Args::cli_setup("heat_1d_p_direct");
 
     let stencil = nhls::standard_stencils::heat_1d(1.0, 1.0, 0.5);
+    args.check_stability(&stencil);
 
     let mut solver = GeneralDirectPeriodicBoxSolver::new(
         &stencil,
@@ -6,6 +6,7 @@ fn main() {
     let args = Args::cli_setup("heat_1d_p_fft");
 
     let stencil = nhls::standard_stencils::heat_1d(1.0, 1.0, 0.5);
+    args.check_stability(&stencil);
 
     // Create solver
     let grid_bound = args.grid_bounds();
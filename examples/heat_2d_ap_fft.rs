@@ -6,14 +6,25 @@ fn main() {
     let args = Args::cli_setup("heat_2d_ap_fft");
 
     let stencil = nhls::standard_stencils::heat_2d(1.0, 1.0, 1.0, 0.2, 0.2);
+    args.check_stability(&stencil);
+    args.dry_run(&stencil);
 
     // This optimized direct solver implement a uniform boundary condition of 0.0
     let direct_solver = DirectSolver5Pt2DOpt::new(&stencil);
 
     // Create AP Solver
     let solver_params = args.solver_parameters();
-    let mut solver =
-        generate_ap_solver(&stencil, direct_solver, &solver_params);
+    let ap_solver = generate_ap_solver(&stencil, direct_solver, &solver_params);
+
+    // Same boundary condition as heat_2d_ap_direct
+    let mut solver = ConstantBCSolver::new(
+        ap_solver,
+        &stencil,
+        1.0,
+        solver_params.aabb,
+        solver_params.steps,
+        args.chunk_size,
+    );
 
     args.run_solver(&mut solver);
 }
@@ -6,6 +6,7 @@ fn main() {
     let args = Args::cli_setup("heat_2d_p_fft");
 
     let stencil = nhls::standard_stencils::heat_2d(1.0, 1.0, 1.0, 0.2, 0.2);
+    args.check_stability(&stencil);
 
     // Create domains
     let grid_bound = args.grid_bounds();
@@ -8,6 +8,8 @@ fn main() {
 
     let stencil =
         nhls::standard_stencils::heat_3d(1.0, 1.0, 1.0, 1.0, 0.1, 0.1, 0.1);
+    args.check_stability(&stencil);
+    args.dry_run(&stencil);
 
     // Create BC
     let grid_bound = args.grid_bounds();
@@ -7,6 +7,7 @@ fn main() {
 
     let stencil =
         nhls::standard_stencils::heat_3d(1.0, 1.0, 1.0, 1.0, 0.1, 0.1, 0.1);
+    args.check_stability(&stencil);
 
     // Apply periodic solver
     let grid_bound = args.grid_bounds();
@@ -7,6 +7,8 @@ use std::time::*;
 
 fn main() {
     let args = Args::cli_setup("sv_heat_1d_fft");
+    args.reject_stencil_file();
+    args.reject_dry_run();
 
     // Grid size
     let mut grid_bound = args.grid_bounds();
@@ -14,6 +16,7 @@ fn main() {
     println!("Grid bound: {}", grid_bound);
 
     let stencil = nhls::standard_stencils::heat_1d(1.0, 1.0, 0.2);
+    args.check_stability(&stencil);
 
     // Create domains
     let mut buffer_11 = OwnedDomain::new(grid_bound);
@@ -6,6 +6,8 @@ fn main() {
     let args = Args::cli_setup("tv_heat_1d_ap_fft");
 
     let stencil = nhls::standard_stencils::TVHeat1D::new();
+    args.check_tv_stability(&stencil);
+    args.tv_dry_run(&stencil);
 
     let direct_solver = DirectSolver3Pt1DOpt::new(&stencil, args.chunk_size);
     // Create AP Solver
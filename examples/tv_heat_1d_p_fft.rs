@@ -5,6 +5,7 @@ fn main() {
     let args = Args::cli_setup("tv_heat_1d_p_fft");
 
     let stencil = nhls::standard_stencils::TVHeat1D::new();
+    args.check_tv_stability(&stencil);
 
     let grid_bound = args.grid_bounds();
     let mut solver = TVPeriodicSolver::new(
@@ -6,6 +6,8 @@ fn main() {
     let args = Args::cli_setup("tv_heat_2d_ap_fft");
 
     let stencil = nhls::standard_stencils::TVHeat2D::new();
+    args.check_tv_stability(&stencil);
+    args.tv_dry_run(&stencil);
 
     // This optimized direct solver implement a uniform boundary condition of 0.0
     let direct_solver = DirectSolver5Pt2DOpt::new(&stencil);
@@ -5,6 +5,7 @@ fn main() {
     let args = Args::cli_setup("tv_heat_2d_p_fft");
 
     let stencil = nhls::standard_stencils::TVHeat2D::new();
+    args.check_tv_stability(&stencil);
 
     let grid_bound = args.grid_bounds();
     let mut solver = TVPeriodicSolver::new(
@@ -8,6 +8,8 @@ fn main() {
     let freq = (2.0 * std::f64::consts::PI) / 1200.0;
     let stencil =
         nhls::standard_stencils::RotatingAdvectionStencil::new(freq, 0.2);
+    args.check_tv_stability(&stencil);
+    args.tv_dry_run(&stencil);
 
     let direct_solver = DirectSolver5Pt2DOpt::new(&stencil);
 
@@ -14,11 +14,21 @@ use std::ops::Range;
 pub struct AccountBuilder<'a, const GRID_DIMENSION: usize> {
     plan: &'a Plan<GRID_DIMENSION>,
     complex_buffer_type: ComplexBufferType,
+
+    /// Bytes per real value
+    real_size: usize,
+
+    /// Bytes per complex value
+    complex_size: usize,
+
+    /// Complex values per frequency domain buffer, see `Scalar`
+    frequency_buffer_size: fn(&AABB<GRID_DIMENSION>) -> usize,
 }
 
 impl<'a, const GRID_DIMENSION: usize> AccountBuilder<'a, GRID_DIMENSION> {
-    /// Generate the memory requirements for each node in MIN_ALIGMENT byte alignment
-    pub fn node_requirements(
+    /// Generate the memory requirements for each node in MIN_ALIGMENT byte alignment,
+    /// for domains that store `T`.
+    pub fn node_requirements<T: Scalar>(
         plan: &'a Plan<GRID_DIMENSION>,
         complex_buffer_type: ComplexBufferType,
     ) -> Vec<usize> {
@@ -26,18 +36,21 @@ impl<'a, const GRID_DIMENSION: usize> AccountBuilder<'a, GRID_DIMENSION> {
         let account_builder = AccountBuilder {
             plan,
             complex_buffer_type,
+            real_size: std::mem::size_of::<T>(),
+            complex_size: std::mem::size_of::<T::Complex>(),
+            frequency_buffer_size: T::frequency_buffer_size,
         };
         account_builder.handle_repeat_node(plan.root, &mut node_requirements);
         node_requirements
     }
 
     fn real_buffer_requirement(&self, aabb: &AABB<GRID_DIMENSION>) -> usize {
-        let min_bytes = aabb.buffer_size() * std::mem::size_of::<f64>();
+        let min_bytes = aabb.buffer_size() * self.real_size;
         min_bytes.div_ceil(MIN_ALIGNMENT)
     }
 
     fn complex_buffer_requirement(&self, aabb: &AABB<GRID_DIMENSION>) -> usize {
-        let min_bytes = aabb.complex_buffer_size() * std::mem::size_of::<c64>();
+        let min_bytes = (self.frequency_buffer_size)(aabb) * self.complex_size;
         let block_req = min_bytes.div_ceil(MIN_ALIGNMENT);
         match self.complex_buffer_type {
             ComplexBufferType::DomainOnly => block_req,
@@ -52,13 +65,11 @@ impl<'a, const GRID_DIMENSION: usize> AccountBuilder<'a, GRID_DIMENSION> {
     ) -> usize {
         let repeat_node = self.plan.unwrap_repeat_node(node_id);
 
-        let mut node_requirement = self.handle_periodic_node(
-            repeat_node.node,
-            true,
-            node_requirements,
-        );
+        // Central solves are periodic, unless no periodic solve was found
+        let mut node_requirement =
+            self.handle_unknown(repeat_node.node, true, node_requirements);
         if let Some(next) = repeat_node.next {
-            node_requirement = node_requirement.max(self.handle_periodic_node(
+            node_requirement = node_requirement.max(self.handle_unknown(
                 next,
                 true,
                 node_requirements,
@@ -6,31 +6,31 @@ use crate::util::*;
 
 /// This stores the convolution operations in
 /// an APSolver instance.
-pub struct ApPeriodicOps {
-    operations: Vec<ConvolutionOperation>,
+pub struct ApPeriodicOps<T: Scalar = f64> {
+    operations: Vec<ConvolutionOperation<T>>,
 }
 
-impl ApPeriodicOps {
-    pub fn new(operations: Vec<ConvolutionOperation>) -> Self {
+impl<T: Scalar> ApPeriodicOps<T> {
+    pub fn new(operations: Vec<ConvolutionOperation<T>>) -> Self {
         ApPeriodicOps { operations }
     }
 
-    pub fn get(&self, op: OpId) -> &ConvolutionOperation {
+    pub fn get(&self, op: OpId) -> &ConvolutionOperation<T> {
         &self.operations[op]
     }
 }
 
-impl<const GRID_DIMENSION: usize> PeriodicOps<GRID_DIMENSION>
-    for ApPeriodicOps
+impl<const GRID_DIMENSION: usize, T: Scalar> PeriodicOps<GRID_DIMENSION, T>
+    for ApPeriodicOps<T>
 {
     fn build_ops(&mut self, _global_time: usize) {}
 
     fn apply_operation<'a>(
         &self,
         op_id: OpId,
-        input: &mut SliceDomain<'a, GRID_DIMENSION>,
-        output: &mut SliceDomain<'a, GRID_DIMENSION>,
-        complex_buffer: &mut [c64],
+        input: &mut SliceDomain<'a, GRID_DIMENSION, T>,
+        output: &mut SliceDomain<'a, GRID_DIMENSION, T>,
+        complex_buffer: &mut [T::Complex],
         _global_time: usize,
         chunk_size: usize,
     ) {
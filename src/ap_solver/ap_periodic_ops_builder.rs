@@ -21,27 +21,47 @@ struct ConvolutionDescriptor<const GRID_DIMENSION: usize> {
 pub struct ApPeriodicOpsBuilder<
     'a,
     const GRID_DIMENSION: usize,
-    const NEIGHBORHOOD_SIZE: usize,
+    StencilType: TIStencil<GRID_DIMENSION, T::Weight>,
+    T: Scalar = f64,
 > {
-    stencil: &'a Stencil<GRID_DIMENSION, NEIGHBORHOOD_SIZE>,
-    operations: Vec<ConvolutionOperation>,
-    real_buffer: AlignedVec<f64>,
-    convolution_buffer: AlignedVec<c64>,
+    stencil: &'a StencilType,
+    operations: Vec<ConvolutionOperation<T>>,
+    real_buffer: AlignedVec<T>,
+    convolution_buffer: AlignedVec<T::Complex>,
     plan_type: PlanType,
     key_map: HashMap<ConvolutionDescriptor<GRID_DIMENSION>, OpId>,
     chunk_size: usize,
 }
 
-impl<'a, const GRID_DIMENSION: usize, const NEIGHBORHOOD_SIZE: usize>
-    ApPeriodicOpsBuilder<'a, GRID_DIMENSION, NEIGHBORHOOD_SIZE>
+impl<
+        'a,
+        const GRID_DIMENSION: usize,
+        StencilType: TIStencil<GRID_DIMENSION>,
+    > ApPeriodicOpsBuilder<'a, GRID_DIMENSION, StencilType>
 {
     pub fn new(
-        stencil: &'a Stencil<GRID_DIMENSION, NEIGHBORHOOD_SIZE>,
+        stencil: &'a StencilType,
+        params: &SolverParameters<GRID_DIMENSION>,
+    ) -> Self {
+        Self::new_scalar(stencil, params)
+    }
+}
+
+impl<
+        'a,
+        const GRID_DIMENSION: usize,
+        StencilType: TIStencil<GRID_DIMENSION, T::Weight>,
+        T: Scalar,
+    > ApPeriodicOpsBuilder<'a, GRID_DIMENSION, StencilType, T>
+{
+    /// Same as `new`, for solvers that don't use `f64`.
+    pub fn new_scalar(
+        stencil: &'a StencilType,
         params: &SolverParameters<GRID_DIMENSION>,
     ) -> Self {
         let max_real_size = params.aabb.buffer_size();
         let real_buffer = fftw::array::AlignedVec::new(max_real_size);
-        let max_complex_size = params.aabb.complex_buffer_size();
+        let max_complex_size = T::frequency_buffer_size(&params.aabb);
         let convolution_buffer = fftw::array::AlignedVec::new(max_complex_size);
 
         ApPeriodicOpsBuilder {
@@ -86,14 +106,18 @@ impl<'a, const GRID_DIMENSION: usize, const NEIGHBORHOOD_SIZE: usize>
         self.operations.len()
     }
 
-    pub fn finish(self) -> ApPeriodicOps {
+    pub fn finish(self) -> ApPeriodicOps<T> {
         ApPeriodicOps::new(self.operations)
     }
 }
 
-impl<'a, const GRID_DIMENSION: usize, const NEIGHBORHOOD_SIZE: usize>
-    PeriodicOpsBuilder<GRID_DIMENSION, ApPeriodicOps>
-    for ApPeriodicOpsBuilder<'a, GRID_DIMENSION, NEIGHBORHOOD_SIZE>
+impl<
+        'a,
+        const GRID_DIMENSION: usize,
+        StencilType: TIStencil<GRID_DIMENSION, T::Weight>,
+        T: Scalar,
+    > PeriodicOpsBuilder<GRID_DIMENSION, ApPeriodicOps<T>>
+    for ApPeriodicOpsBuilder<'a, GRID_DIMENSION, StencilType, T>
 {
     fn get_op_id(
         &mut self,
@@ -106,7 +130,7 @@ impl<'a, const GRID_DIMENSION: usize, const NEIGHBORHOOD_SIZE: usize>
         )
     }
 
-    fn finish(self) -> ApPeriodicOps {
+    fn finish(self) -> ApPeriodicOps<T> {
         self.finish()
     }
 }
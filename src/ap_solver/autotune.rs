@@ -0,0 +1,764 @@
+//! Search for the planner parameters that `SolverParameters` leaves to us,
+//! `ratio`, `cutoff`, `task_min` and `task_mult`.
+//! Candidates are ranked with a `CostModel` over their plans,
+//! the best few can then be timed with short trial applies.
+//! Results are kept in a `TuningDatabase`,
+//! keyed by problem shape, stencil size and thread count.
+//!
+//! ```text
+//! nhls-tuning 1
+//! dim 1 size 1000 steps 40 slopes 1 1 neighbors 3 axes aperiodic threads 8 => 0.5 40 2 1
+//! ```
+//!
+//! Each entry maps a key to `ratio cutoff task_min task_mult`.
+
+use crate::ap_solver::generate_plan::*;
+use crate::ap_solver::index_types::*;
+use crate::ap_solver::periodic_ops::*;
+use crate::ap_solver::plan::*;
+use crate::ap_solver::solver_parameters::*;
+use crate::domain::*;
+use crate::util::*;
+use crate::SolverInterface;
+use std::collections::BTreeMap;
+use std::io::prelude::*;
+use std::path::Path;
+
+/// Bumped whenever the format or the meaning of entries changes.
+pub const TUNING_FILE_VERSION: usize = 1;
+
+/// The planner parameters the autotuner searches over.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TunedParameters {
+    pub ratio: f64,
+    pub cutoff: i32,
+    pub task_min: usize,
+    pub task_mult: f64,
+}
+
+impl TunedParameters {
+    pub fn from_params<const GRID_DIMENSION: usize>(
+        params: &SolverParameters<GRID_DIMENSION>,
+    ) -> Self {
+        TunedParameters {
+            ratio: params.ratio,
+            cutoff: params.cutoff,
+            task_min: params.task_min,
+            task_mult: params.task_mult,
+        }
+    }
+
+    /// Copy of `params` using these values.
+    pub fn apply<const GRID_DIMENSION: usize>(
+        &self,
+        params: &SolverParameters<GRID_DIMENSION>,
+    ) -> SolverParameters<GRID_DIMENSION> {
+        SolverParameters {
+            ratio: self.ratio,
+            cutoff: self.cutoff,
+            task_min: self.task_min,
+            task_mult: self.task_mult,
+            ..params.clone()
+        }
+    }
+}
+
+impl std::fmt::Display for TunedParameters {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "ratio {}, cutoff {}, task_min {}, task_mult {}",
+            self.ratio, self.cutoff, self.task_min, self.task_mult
+        )
+    }
+}
+
+/// Estimates how long a plan takes to apply, in arbitrary units.
+/// Only the ranking of plans matters, not the absolute values.
+#[derive(Copy, Clone, Debug)]
+pub struct CostModel {
+    /// Cost per `n log2(n)` of each FFT over `n` values.
+    pub fft_weight: f64,
+
+    /// Cost per neighbor per value updated by direct solves.
+    pub direct_weight: f64,
+
+    /// Overhead of each task a node is split into.
+    pub task_weight: f64,
+}
+
+impl std::default::Default for CostModel {
+    fn default() -> Self {
+        CostModel {
+            fft_weight: 1.0,
+            direct_weight: 1.0,
+            task_weight: 5000.0,
+        }
+    }
+}
+
+impl CostModel {
+    /// Estimated cost of one `apply` of `plan`,
+    /// for a stencil with `neighborhood_size` neighbors
+    /// on a machine with `threads` threads.
+    pub fn plan_cost<const GRID_DIMENSION: usize>(
+        &self,
+        plan: &Plan<GRID_DIMENSION>,
+        neighborhood_size: usize,
+        threads: usize,
+    ) -> f64 {
+        let context = CostContext {
+            model: self,
+            plan,
+            neighborhood_size,
+            threads: threads.max(1),
+        };
+        context.node_cost(plan.root)
+    }
+}
+
+struct CostContext<'a, const GRID_DIMENSION: usize> {
+    model: &'a CostModel,
+    plan: &'a Plan<GRID_DIMENSION>,
+    neighborhood_size: usize,
+    threads: usize,
+}
+
+impl<const GRID_DIMENSION: usize> CostContext<'_, GRID_DIMENSION> {
+    /// Threads a node can actually use.
+    fn node_threads(&self, node_id: NodeId) -> usize {
+        let tasks = match self.plan.get_node(node_id) {
+            PlanNode::PeriodicSolve(p) => p.threads,
+            PlanNode::DirectSolve(d) => d.threads,
+            _ => self.threads,
+        };
+        tasks.clamp(1, self.threads)
+    }
+
+    fn node_cost(&self, node_id: NodeId) -> f64 {
+        match self.plan.get_node(node_id) {
+            PlanNode::PeriodicSolve(p) => {
+                let n = p.input_aabb.buffer_size() as f64;
+                // Forward, backward and the pointwise product
+                let work = self.model.fft_weight * (2.0 * n * n.log2() + n);
+                let own = work / self.node_threads(node_id) as f64
+                    + self.model.task_weight * p.threads as f64;
+                let boundary = self.concurrent_cost(p.boundary_nodes.clone());
+                let time_cut = p.time_cut.map_or(0.0, |id| self.node_cost(id));
+                own + boundary + time_cut
+            }
+            PlanNode::DirectSolve(d) => {
+                let work = self.model.direct_weight
+                    * self.neighborhood_size as f64
+                    * d.cell_steps() as f64;
+                work / self.node_threads(node_id) as f64
+                    + self.model.task_weight * d.threads as f64
+            }
+            PlanNode::Repeat(r) => {
+                r.n as f64 * self.node_cost(r.node)
+                    + r.next.map_or(0.0, |id| self.node_cost(id))
+            }
+            PlanNode::Range(r) => self.concurrent_cost(r.range.clone()),
+        }
+    }
+
+    /// Nodes that run at the same time take at least as long as
+    /// the slowest of them, and as their total work spread over all threads.
+    fn concurrent_cost(&self, nodes: std::ops::Range<NodeId>) -> f64 {
+        let mut longest: f64 = 0.0;
+        let mut total_work = 0.0;
+        for node_id in nodes {
+            let cost = self.node_cost(node_id);
+            longest = longest.max(cost);
+            total_work += cost * self.node_threads(node_id) as f64;
+        }
+        longest.max(total_work / self.threads as f64)
+    }
+}
+
+/// Why tuning couldn't pick parameters.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TuningError {
+    /// One of the candidate value lists of the `Autotuner` is empty.
+    NoCandidates,
+}
+
+impl std::fmt::Display for TuningError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TuningError::NoCandidates => {
+                write!(f, "autotuner has no candidates to choose from")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TuningError {}
+
+pub struct TuningResult<const GRID_DIMENSION: usize> {
+    pub params: SolverParameters<GRID_DIMENSION>,
+
+    /// See `CostModel::plan_cost`
+    pub cost: f64,
+
+    /// Seconds per apply, if trials were run
+    pub trial_seconds: Option<f64>,
+}
+
+/// Searches every combination of the candidate values.
+pub struct Autotuner {
+    /// Must all be less than 1.
+    pub ratios: Vec<f64>,
+    pub cutoffs: Vec<i32>,
+    pub task_mins: Vec<usize>,
+    pub task_mults: Vec<f64>,
+    pub cost_model: CostModel,
+
+    /// How many of the cheapest candidates to time
+    /// in `tune_with_trials`.
+    pub trials: usize,
+}
+
+impl std::default::Default for Autotuner {
+    fn default() -> Self {
+        Autotuner {
+            ratios: vec![0.3, 0.4, 0.5, 0.6, 0.7],
+            cutoffs: vec![20, 40, 60, 100],
+            task_mins: vec![1, 2, 4, 8],
+            task_mults: vec![1.0, 2.0, 4.0],
+            cost_model: CostModel::default(),
+            trials: 3,
+        }
+    }
+}
+
+impl Autotuner {
+    pub fn candidates(&self) -> Vec<TunedParameters> {
+        let mut result = Vec::new();
+        for &ratio in &self.ratios {
+            debug_assert!(ratio < 1.0);
+            for &cutoff in &self.cutoffs {
+                for &task_min in &self.task_mins {
+                    for &task_mult in &self.task_mults {
+                        result.push(TunedParameters {
+                            ratio,
+                            cutoff,
+                            task_min,
+                            task_mult,
+                        });
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    /// Plan every candidate and sort them by cost, cheapest first.
+    pub fn rank<const GRID_DIMENSION: usize>(
+        &self,
+        stencil_slopes: Bounds<GRID_DIMENSION>,
+        neighborhood_size: usize,
+        params: &SolverParameters<GRID_DIMENSION>,
+    ) -> Vec<(TunedParameters, f64)> {
+        profiling::scope!("autotune::rank");
+        let mut result: Vec<(TunedParameters, f64)> = self
+            .candidates()
+            .into_iter()
+            .map(|candidate| {
+                let mut candidate_params = candidate.apply(params);
+                candidate_params.plan_cache = None;
+                let planner_result = generate_plan(
+                    stencil_slopes,
+                    OpIdCollector::new,
+                    &candidate_params,
+                );
+                let cost = self.cost_model.plan_cost(
+                    &planner_result.plan,
+                    neighborhood_size,
+                    params.threads,
+                );
+                (candidate, cost)
+            })
+            .collect();
+        result.sort_by(|a, b| a.1.total_cmp(&b.1));
+        result
+    }
+
+    /// Best candidate according to the cost model alone.
+    pub fn tune<const GRID_DIMENSION: usize>(
+        &self,
+        stencil_slopes: Bounds<GRID_DIMENSION>,
+        neighborhood_size: usize,
+        params: &SolverParameters<GRID_DIMENSION>,
+    ) -> Result<TuningResult<GRID_DIMENSION>, TuningError> {
+        self.tune_impl(stencil_slopes, neighborhood_size, params, None)
+    }
+
+    /// Time the `trials` cheapest candidates with `trial`,
+    /// which should build a solver for the given parameters
+    /// and return its apply time in seconds, see `time_apply`.
+    /// Trials never read or write `params.plan_cache`.
+    /// The fastest one wins.
+    pub fn tune_with_trials<
+        const GRID_DIMENSION: usize,
+        TrialFn: FnMut(&SolverParameters<GRID_DIMENSION>) -> f64,
+    >(
+        &self,
+        stencil_slopes: Bounds<GRID_DIMENSION>,
+        neighborhood_size: usize,
+        params: &SolverParameters<GRID_DIMENSION>,
+        mut trial: TrialFn,
+    ) -> Result<TuningResult<GRID_DIMENSION>, TuningError> {
+        let trial: &mut dyn FnMut(&SolverParameters<GRID_DIMENSION>) -> f64 =
+            &mut trial;
+        self.tune_impl(stencil_slopes, neighborhood_size, params, Some(trial))
+    }
+
+    fn tune_impl<const GRID_DIMENSION: usize>(
+        &self,
+        stencil_slopes: Bounds<GRID_DIMENSION>,
+        neighborhood_size: usize,
+        params: &SolverParameters<GRID_DIMENSION>,
+        trial: Option<&mut dyn FnMut(&SolverParameters<GRID_DIMENSION>) -> f64>,
+    ) -> Result<TuningResult<GRID_DIMENSION>, TuningError> {
+        let ranked = self.rank(stencil_slopes, neighborhood_size, params);
+        let (best, cost) = *ranked.first().ok_or(TuningError::NoCandidates)?;
+        let Some(trial) = trial else {
+            return Ok(TuningResult {
+                params: best.apply(params),
+                cost,
+                trial_seconds: None,
+            });
+        };
+
+        let mut result: Option<TuningResult<GRID_DIMENSION>> = None;
+        for (candidate, cost) in ranked.into_iter().take(self.trials.max(1)) {
+            profiling::scope!("autotune::trial");
+            // Same as `rank`, trial plans must not replace the cached one
+            let mut trial_params = candidate.apply(params);
+            trial_params.plan_cache = None;
+            let seconds = trial(&trial_params);
+            if result
+                .as_ref()
+                .is_none_or(|r| r.trial_seconds.unwrap() > seconds)
+            {
+                result = Some(TuningResult {
+                    params: candidate.apply(params),
+                    cost,
+                    trial_seconds: Some(seconds),
+                });
+            }
+        }
+        Ok(result.unwrap())
+    }
+
+    /// Look the problem up in the database at `path`.
+    /// If it isn't there yet, tune with the cost model
+    /// and store the result.
+    /// Problems with the file are reported, but never fatal.
+    pub fn tune_cached<const GRID_DIMENSION: usize>(
+        &self,
+        path: &Path,
+        stencil_slopes: Bounds<GRID_DIMENSION>,
+        neighborhood_size: usize,
+        params: &SolverParameters<GRID_DIMENSION>,
+    ) -> Result<SolverParameters<GRID_DIMENSION>, TuningError> {
+        self.tune_cached_impl(
+            path,
+            stencil_slopes,
+            neighborhood_size,
+            params,
+            || self.tune(stencil_slopes, neighborhood_size, params),
+        )
+    }
+
+    /// Same as `tune_cached`, but tunes with `tune_with_trials`.
+    pub fn tune_cached_with_trials<
+        const GRID_DIMENSION: usize,
+        TrialFn: FnMut(&SolverParameters<GRID_DIMENSION>) -> f64,
+    >(
+        &self,
+        path: &Path,
+        stencil_slopes: Bounds<GRID_DIMENSION>,
+        neighborhood_size: usize,
+        params: &SolverParameters<GRID_DIMENSION>,
+        trial: TrialFn,
+    ) -> Result<SolverParameters<GRID_DIMENSION>, TuningError> {
+        self.tune_cached_impl(
+            path,
+            stencil_slopes,
+            neighborhood_size,
+            params,
+            || {
+                self.tune_with_trials(
+                    stencil_slopes,
+                    neighborhood_size,
+                    params,
+                    trial,
+                )
+            },
+        )
+    }
+
+    fn tune_cached_impl<const GRID_DIMENSION: usize>(
+        &self,
+        path: &Path,
+        stencil_slopes: Bounds<GRID_DIMENSION>,
+        neighborhood_size: usize,
+        params: &SolverParameters<GRID_DIMENSION>,
+        tune: impl FnOnce() -> Result<TuningResult<GRID_DIMENSION>, TuningError>,
+    ) -> Result<SolverParameters<GRID_DIMENSION>, TuningError> {
+        let key = tuning_key(&stencil_slopes, neighborhood_size, params);
+        let mut database = if path.exists() {
+            TuningDatabase::load(&path).unwrap_or_else(|e| {
+                eprintln!("WARNING: {e}, starting a new tuning database");
+                TuningDatabase::default()
+            })
+        } else {
+            TuningDatabase::default()
+        };
+        if let Some(tuned) = database.get(&key) {
+            println!("Loaded tuned parameters: {tuned}");
+            return Ok(tuned.apply(params));
+        }
+
+        let result = tune()?;
+        let tuned = TunedParameters::from_params(&result.params);
+        println!("Tuned parameters: {tuned}");
+        database.insert(key, tuned);
+        if let Err(e) = database.save(&path) {
+            eprintln!("WARNING: failed to write {path:?}: {e}");
+        }
+        Ok(result.params)
+    }
+}
+
+/// Seconds taken by one `apply` of `solver`, excluding setup.
+/// Use for `Autotuner::tune_with_trials`.
+pub fn time_apply<
+    const GRID_DIMENSION: usize,
+    SolverType: SolverInterface<GRID_DIMENSION>,
+>(
+    solver: &mut SolverType,
+    aabb: AABB<GRID_DIMENSION>,
+    chunk_size: usize,
+) -> f64 {
+    let mut buffer_1 = OwnedDomain::new(aabb);
+    let mut buffer_2 = OwnedDomain::new(aabb);
+    let mut input_domain = buffer_1.as_slice_domain();
+    let mut output_domain = buffer_2.as_slice_domain();
+    input_domain.par_set_values(|_| 1.0, chunk_size);
+    let now = std::time::Instant::now();
+    solver.apply(&mut input_domain, &mut output_domain, 0);
+    now.elapsed().as_secs_f64()
+}
+
+/// Problem shape, stencil size and thread count,
+/// as used by `TuningDatabase`.
+/// The neighborhood size is part of the key
+/// since `CostModel` ranks plans by it.
+pub fn tuning_key<const GRID_DIMENSION: usize>(
+    stencil_slopes: &Bounds<GRID_DIMENSION>,
+    neighborhood_size: usize,
+    params: &SolverParameters<GRID_DIMENSION>,
+) -> String {
+    let size: Vec<String> = params
+        .aabb
+        .exclusive_bounds()
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    let slopes: Vec<String> = (0..GRID_DIMENSION)
+        .flat_map(|d| [stencil_slopes[(d, 0)], stencil_slopes[(d, 1)]])
+        .map(|s| s.to_string())
+        .collect();
+    let axes: Vec<&str> = params
+        .axis_boundaries
+        .iter()
+        .map(|b| match b {
+            AxisBoundary::Aperiodic => "aperiodic",
+            AxisBoundary::Periodic => "periodic",
+        })
+        .collect();
+    format!(
+        "dim {GRID_DIMENSION} size {} steps {} slopes {} neighbors {} \
+         axes {} threads {}",
+        size.join(" "),
+        params.steps,
+        slopes.join(" "),
+        neighborhood_size,
+        axes.join(" "),
+        params.threads
+    )
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TuningFileError {
+    /// 1-based line number, 0 if the error isn't tied to a line.
+    pub line: usize,
+    pub message: String,
+}
+
+impl TuningFileError {
+    fn new(line: usize, message: String) -> Self {
+        TuningFileError { line, message }
+    }
+}
+
+impl std::fmt::Display for TuningFileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.line == 0 {
+            write!(f, "tuning file: {}", self.message)
+        } else {
+            write!(f, "tuning file line {}: {}", self.line, self.message)
+        }
+    }
+}
+
+impl std::error::Error for TuningFileError {}
+
+/// Tuned parameters by `tuning_key`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TuningDatabase {
+    pub entries: BTreeMap<String, TunedParameters>,
+}
+
+impl TuningDatabase {
+    pub fn get(&self, key: &str) -> Option<TunedParameters> {
+        self.entries.get(key).copied()
+    }
+
+    pub fn insert(&mut self, key: String, tuned: TunedParameters) {
+        self.entries.insert(key, tuned);
+    }
+
+    pub fn load<P: AsRef<Path>>(path: &P) -> Result<Self, TuningFileError> {
+        let source = std::fs::read_to_string(path).map_err(|e| {
+            TuningFileError::new(
+                0,
+                format!("failed to read {:?}: {e}", path.as_ref()),
+            )
+        })?;
+        Self::parse(&source)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: &P) -> std::io::Result<()> {
+        let mut writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+        writeln!(writer, "nhls-tuning {TUNING_FILE_VERSION}")?;
+        for (key, t) in &self.entries {
+            writeln!(
+                writer,
+                "{key} => {} {} {} {}",
+                t.ratio, t.cutoff, t.task_min, t.task_mult
+            )?;
+        }
+        writer.flush()
+    }
+
+    /// Parse a tuning database, see module documentation for the format.
+    pub fn parse(source: &str) -> Result<Self, TuningFileError> {
+        let mut result = TuningDatabase::default();
+        let mut has_header = false;
+        for (line_index, raw_line) in source.lines().enumerate() {
+            let line_number = line_index + 1;
+            let err =
+                |message: String| TuningFileError::new(line_number, message);
+            let line = match raw_line.split_once('#') {
+                Some((content, _)) => content.trim(),
+                None => raw_line.trim(),
+            };
+            if line.is_empty() {
+                continue;
+            }
+
+            if !has_header {
+                let version = line
+                    .strip_prefix("nhls-tuning")
+                    .ok_or_else(|| err("missing 'nhls-tuning' header".into()))?
+                    .trim();
+                if version != TUNING_FILE_VERSION.to_string() {
+                    return Err(err(format!(
+                        "unsupported version '{version}', \
+                         expected {TUNING_FILE_VERSION}"
+                    )));
+                }
+                has_header = true;
+                continue;
+            }
+
+            let (key, values) = line
+                .split_once("=>")
+                .ok_or_else(|| err("expected '<key> => <values>'".into()))?;
+            let values: Vec<&str> = values.split_whitespace().collect();
+            if values.len() != 4 {
+                return Err(err(format!(
+                    "expected 4 values, found {}",
+                    values.len()
+                )));
+            }
+            let invalid = |value: &str| err(format!("invalid value '{value}'"));
+            let tuned = TunedParameters {
+                ratio: values[0].parse().map_err(|_| invalid(values[0]))?,
+                cutoff: values[1].parse().map_err(|_| invalid(values[1]))?,
+                task_min: values[2].parse().map_err(|_| invalid(values[2]))?,
+                task_mult: values[3].parse().map_err(|_| invalid(values[3]))?,
+            };
+            if tuned.ratio.is_nan() || tuned.ratio <= 0.0 || tuned.ratio >= 1.0
+            {
+                return Err(err(format!(
+                    "ratio {} not in (0, 1)",
+                    tuned.ratio
+                )));
+            }
+            let key: Vec<&str> = key.split_whitespace().collect();
+            result.insert(key.join(" "), tuned);
+        }
+        if !has_header {
+            return Err(TuningFileError::new(
+                0,
+                "missing 'nhls-tuning' header".to_string(),
+            ));
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+    use nalgebra::matrix;
+
+    fn params(steps: usize, cutoff: i32) -> SolverParameters<2> {
+        SolverParameters {
+            aabb: AABB::new(matrix![0, 499; 0, 499]),
+            steps,
+            cutoff,
+            threads: 8,
+            ..Default::default()
+        }
+    }
+
+    fn cost(params: &SolverParameters<2>) -> f64 {
+        let planner_result =
+            generate_plan(matrix![1, 1; 1, 1], OpIdCollector::new, params);
+        CostModel::default().plan_cost(&planner_result.plan, 5, params.threads)
+    }
+
+    #[test]
+    fn cost_model() {
+        // Cutoff too large for any periodic solve, so all direct
+        let direct = cost(&params(50, 1000));
+        let ap = cost(&params(50, 40));
+        assert!(ap < direct, "{ap} >= {direct}");
+
+        // Direct solves scale with steps
+        let direct_longer = cost(&params(100, 1000));
+        assert!(direct_longer > 1.9 * direct);
+    }
+
+    #[test]
+    fn tune() {
+        let autotuner = Autotuner {
+            ratios: vec![0.5],
+            cutoffs: vec![40, 1000],
+            task_mins: vec![1, 2],
+            task_mults: vec![1.0],
+            ..Default::default()
+        };
+        assert_eq!(autotuner.candidates().len(), 4);
+        let params = params(200, 10);
+        let ranked = autotuner.rank(matrix![1, 1; 1, 1], 5, &params);
+        assert_eq!(ranked.len(), 4);
+        assert!(ranked.windows(2).all(|w| w[0].1 <= w[1].1));
+
+        let result = autotuner.tune(matrix![1, 1; 1, 1], 5, &params).unwrap();
+        assert_eq!(result.params.cutoff, 40);
+        assert_eq!(result.cost, ranked[0].1);
+        assert!(result.trial_seconds.is_none());
+
+        // The fastest trial wins, trials never see the plan cache
+        let params = SolverParameters {
+            plan_cache: Some(std::path::PathBuf::from("plan_cache.nhls")),
+            ..params
+        };
+        let mut trial_count = 0;
+        let result = autotuner
+            .tune_with_trials(
+                matrix![1, 1; 1, 1],
+                5,
+                &params,
+                |candidate: &SolverParameters<2>| {
+                    assert!(candidate.plan_cache.is_none());
+                    trial_count += 1;
+                    candidate.cutoff as f64
+                        - candidate.task_min as f64
+                        - candidate.ratio
+                },
+            )
+            .unwrap();
+        assert_eq!(trial_count, autotuner.trials);
+        assert_eq!(result.params.cutoff, 40);
+        assert_eq!(result.params.task_min, 2);
+        assert!(result.trial_seconds.is_some());
+        assert_eq!(result.params.plan_cache, params.plan_cache);
+
+        let empty = Autotuner {
+            cutoffs: Vec::new(),
+            ..Default::default()
+        };
+        let err = empty.tune(matrix![1, 1; 1, 1], 5, &params).err();
+        assert_eq!(err, Some(TuningError::NoCandidates));
+    }
+
+    #[test]
+    fn database_round_trip() {
+        let mut database = TuningDatabase::default();
+        let params = params(200, 10);
+        let key = tuning_key(&matrix![1, 1; 1, 1], 5, &params);
+        assert_eq!(
+            key,
+            "dim 2 size 500 500 steps 200 slopes 1 1 1 1 neighbors 5 \
+             axes aperiodic aperiodic threads 8"
+        );
+        assert_ne!(key, tuning_key(&matrix![1, 1; 1, 1], 9, &params));
+        let tuned = TunedParameters {
+            ratio: 0.45,
+            cutoff: 60,
+            task_min: 4,
+            task_mult: 2.5,
+        };
+        database.insert(key.clone(), tuned);
+
+        let path = std::env::temp_dir()
+            .join(format!("nhls_tuning_{}.db", std::process::id()));
+        database.save(&path).unwrap();
+        let loaded = TuningDatabase::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, database);
+        assert_eq!(loaded.get(&key), Some(tuned));
+
+        let applied = tuned.apply(&params);
+        assert_eq!(TunedParameters::from_params(&applied), tuned);
+        assert_eq!(applied.steps, params.steps);
+    }
+
+    #[test]
+    fn database_errors() {
+        assert_eq!(TuningDatabase::parse("").unwrap_err().line, 0);
+        assert_eq!(TuningDatabase::parse("nhls-tuning 2").unwrap_err().line, 1);
+        let err = TuningDatabase::parse("nhls-tuning 1\n\nkey => 0.5 1 1")
+            .unwrap_err();
+        assert_eq!(err.line, 3);
+        let err =
+            TuningDatabase::parse("nhls-tuning 1\nkey 0.5 1 1 1").unwrap_err();
+        assert_eq!(err.line, 2);
+        let err = TuningDatabase::parse("nhls-tuning 1\nkey => 1.5 1 1 1")
+            .unwrap_err();
+        assert_eq!(err.line, 2);
+        let database = TuningDatabase::parse(
+            "# comment\nnhls-tuning 1\n a  b => 0.5 1 1 1",
+        )
+        .unwrap();
+        assert!(database.get("a b").is_some());
+    }
+}
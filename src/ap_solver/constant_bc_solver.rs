@@ -0,0 +1,169 @@
+use crate::direct_solver::*;
+use crate::domain::*;
+use crate::par_slice;
+use crate::stencil::*;
+use crate::util::*;
+use crate::SolverInterface;
+
+/// Adds a constant Dirichlet boundary to a solver that implements
+/// a zero boundary, like the AP solver with `DirectSolver5Pt2DOpt`.
+///
+/// This works by superposition.
+/// For stencils whose weights sum to one constants are a steady state,
+/// so we shift the input by the boundary value before solving
+/// and shift the output back after.
+/// Otherwise we add what the boundary contributes over one `apply`,
+/// i.e. the solution starting from zero.
+/// This correction is a direct solve over the whole domain,
+/// so it is only found on the first `apply`,
+/// see `print_report` for what it cost.
+///
+/// Only valid for time invariant stencils.
+pub struct ConstantBCSolver<
+    const GRID_DIMENSION: usize,
+    SolverType: SolverInterface<GRID_DIMENSION>,
+> {
+    solver: SolverType,
+    value: f64,
+    aabb: AABB<GRID_DIMENSION>,
+    steps: usize,
+    chunk_size: usize,
+
+    /// Only set when the stencil needs a correction
+    stencil: Option<DynStencil<GRID_DIMENSION>>,
+    correction: Option<OwnedDomain<GRID_DIMENSION>>,
+    correction_seconds: f64,
+}
+
+impl<
+        const GRID_DIMENSION: usize,
+        SolverType: SolverInterface<GRID_DIMENSION>,
+    > ConstantBCSolver<GRID_DIMENSION, SolverType>
+{
+    /// `solver` must solve `stencil` for `steps` steps over `aabb`
+    /// with a zero boundary.
+    pub fn new<StencilType: TIStencil<GRID_DIMENSION>>(
+        solver: SolverType,
+        stencil: &StencilType,
+        value: f64,
+        aabb: AABB<GRID_DIMENSION>,
+        steps: usize,
+        chunk_size: usize,
+    ) -> Self {
+        let weight_sum: f64 = stencil.weight_slice().iter().sum();
+        let stencil = if (weight_sum - 1.0).abs() < 1e-12 {
+            None
+        } else {
+            Some(DynStencil::from_offset_weights(
+                stencil.offset_slice().to_vec(),
+                stencil.weight_slice().to_vec(),
+            ))
+        };
+        ConstantBCSolver {
+            solver,
+            value,
+            aabb,
+            steps,
+            chunk_size,
+            stencil,
+            correction: None,
+            correction_seconds: 0.0,
+        }
+    }
+
+    /// Solve for the boundary contribution starting from zero.
+    fn build_correction(
+        &mut self,
+        stencil: &DynStencil<GRID_DIMENSION>,
+    ) -> OwnedDomain<GRID_DIMENSION> {
+        profiling::scope!("constant_bc_solver::build_correction");
+        let start = std::time::Instant::now();
+        let bc = ConstantCheck::new(self.value, self.aabb);
+        let mut input = OwnedDomain::new(self.aabb);
+        let mut output = OwnedDomain::new(self.aabb);
+        box_apply(
+            &bc,
+            stencil,
+            &mut input,
+            &mut output,
+            self.steps,
+            0,
+            self.chunk_size,
+        );
+        self.correction_seconds = start.elapsed().as_secs_f64();
+        output
+    }
+}
+
+impl<
+        const GRID_DIMENSION: usize,
+        SolverType: SolverInterface<GRID_DIMENSION>,
+    > SolverInterface<GRID_DIMENSION>
+    for ConstantBCSolver<GRID_DIMENSION, SolverType>
+{
+    fn apply<'a>(
+        &mut self,
+        input_domain: &mut SliceDomain<'a, GRID_DIMENSION>,
+        output_domain: &mut SliceDomain<'a, GRID_DIMENSION>,
+        global_time: usize,
+    ) {
+        profiling::scope!("constant_bc_solver::apply");
+        if self.correction.is_none() {
+            if let Some(stencil) = self.stencil.take() {
+                self.correction = Some(self.build_correction(&stencil));
+            }
+        }
+        match self.correction.as_ref() {
+            None => {
+                par_slice::add_value(
+                    input_domain.buffer_mut(),
+                    -self.value,
+                    self.chunk_size,
+                );
+                self.solver.apply(input_domain, output_domain, global_time);
+                par_slice::add_value(
+                    output_domain.buffer_mut(),
+                    self.value,
+                    self.chunk_size,
+                );
+            }
+            Some(correction) => {
+                self.solver.apply(input_domain, output_domain, global_time);
+                par_slice::add(
+                    output_domain.buffer_mut(),
+                    correction.buffer(),
+                    self.chunk_size,
+                );
+            }
+        }
+    }
+
+    fn print_report(&self) {
+        self.solver.print_report();
+        println!("Constant BC Solver Report:");
+        println!("  - boundary value: {}", self.value);
+        match (&self.stencil, &self.correction) {
+            (None, None) => println!("  - correction: not needed"),
+            (Some(_), _) => println!("  - correction: not built yet"),
+            (None, Some(correction)) => {
+                println!(
+                    "  - correction: {} bytes, built in {:.3}s",
+                    std::mem::size_of_val(correction.buffer()),
+                    self.correction_seconds
+                );
+            }
+        }
+    }
+
+    fn to_dot_file<P: AsRef<std::path::Path>>(&self, path: &P) {
+        self.solver.to_dot_file(path);
+    }
+
+    fn enable_instrumentation(&mut self) {
+        self.solver.enable_instrumentation();
+    }
+
+    fn write_instrumentation<P: AsRef<std::path::Path>>(&self, path: &P) {
+        self.solver.write_instrumentation(path);
+    }
+}
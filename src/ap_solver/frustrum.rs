@@ -69,6 +69,16 @@ pub fn frustrum_input_aabb<const GRID_DIMENSION: usize>(
     output_box.add_bounds_diff(steps as i32 * trapezoid_slopes)
 }
 
+/// Solves only shrink along axes that the stencil slopes along.
+/// Others, like periodic axes, are never cut into boundary solves.
+#[inline]
+pub fn is_sloped_axis<const GRID_DIMENSION: usize>(
+    stencil_slopes: &Bounds<GRID_DIMENSION>,
+    d: usize,
+) -> bool {
+    stencil_slopes[(d, 0)] != 0 || stencil_slopes[(d, 1)] != 0
+}
+
 /// This needs to match logic from AABB::decomposition
 pub fn decomposition_slopes<const DIMENSION: usize>(
 ) -> [[Bounds<DIMENSION>; 2]; DIMENSION] {
@@ -210,52 +220,59 @@ impl<const GRID_DIMENSION: usize> Frustrum<GRID_DIMENSION> {
     ) -> Vec<Frustrum<GRID_DIMENSION>> {
         let mut result = Vec::new();
 
-        // The periodic solve covers `self.steps`,
-        // so the output will include `self.steps` in from each boundary face.
-        // So the boundary frustrums should be `self.steps - 1` from each face
-        let i_steps = self.steps as i32 - 1;
+        // The convolution wraps around at faces the frustrum doesn't
+        // slope on, so its output is only valid `steps * slope` in
+        // from them, using the slope towards that face.
+        // Sides with zero slope need no boundary frustrum.
+        let widths: Bounds<GRID_DIMENSION> = self.steps as i32 * stencil_slopes;
         let rec_d = self.recursion_dimension;
-        let slope_modifiers: Bounds<GRID_DIMENSION> = i_steps * stencil_slopes;
-        let remainder_modifiers: Bounds<GRID_DIMENSION> =
-            self.steps as i32 * stencil_slopes;
+        let outer_index = self.side.outer_index();
+        let inner_coef = self.side.inner_coef();
 
         // 1 for this dimension,
+        let width = widths[(rec_d, outer_index)];
         let mut output_aabb = self.output_aabb;
-        let outer_bound =
-            self.output_aabb.bounds[(rec_d, self.side.outer_index())];
-        output_aabb.bounds[(rec_d, self.side.inner_index())] = outer_bound
-            + self.side.inner_coef()
-                * slope_modifiers[(rec_d, self.side.inner_index())];
-        result.push(Frustrum::new(output_aabb, rec_d, self.side, self.steps));
+        let outer_bound = self.output_aabb.bounds[(rec_d, outer_index)];
+        output_aabb.bounds[(rec_d, self.side.inner_index())] =
+            outer_bound + inner_coef * (width - 1);
+        if width > 0 {
+            result.push(Frustrum::new(
+                output_aabb,
+                rec_d,
+                self.side,
+                self.steps,
+            ));
+        }
 
         // From here we maintain a remainder AABB,
         // to track the remaining portion of the output AABB.
         // We remove area until only the periodic solve output remains
         let mut remainder = self.output_aabb;
-        remainder.bounds[(rec_d, self.side.outer_index())] +=
-            self.side.inner_coef()
-                * remainder_modifiers[(rec_d, self.side.inner_index())];
+        remainder.bounds[(rec_d, outer_index)] += inner_coef * width;
 
         // For each lower dimension we create min and max frustrum
         // and remove from remainder
         for d in self.recursion_dimension + 1..GRID_DIMENSION {
-            let mut min_aabb = remainder;
-            let min_bound = min_aabb.bounds[(d, 0)];
-            min_aabb.bounds[(d, 1)] =
-                min_bound + slope_modifiers[(rec_d, self.side.inner_index())];
-            result.push(Frustrum::new(min_aabb, d, Side::Min, self.steps));
-
-            let mut max_aabb = remainder;
-            let max_bound = max_aabb.bounds[(d, 1)];
-            max_aabb.bounds[(d, 0)] =
-                max_bound - slope_modifiers[(rec_d, self.side.inner_index())];
-            result.push(Frustrum::new(max_aabb, d, Side::Max, self.steps));
-
-            let remainder_modifier =
-                remainder_modifiers[(rec_d, self.side.inner_index())];
-
-            remainder.bounds[(d, 0)] += remainder_modifier;
-            remainder.bounds[(d, 1)] -= remainder_modifier;
+            if !is_sloped_axis(stencil_slopes, d) {
+                continue;
+            }
+
+            if widths[(d, 0)] > 0 {
+                let mut min_aabb = remainder;
+                let min_bound = min_aabb.bounds[(d, 0)];
+                min_aabb.bounds[(d, 1)] = min_bound + widths[(d, 0)] - 1;
+                result.push(Frustrum::new(min_aabb, d, Side::Min, self.steps));
+            }
+
+            if widths[(d, 1)] > 0 {
+                let mut max_aabb = remainder;
+                let max_bound = max_aabb.bounds[(d, 1)];
+                max_aabb.bounds[(d, 0)] = max_bound - widths[(d, 1)] + 1;
+                result.push(Frustrum::new(max_aabb, d, Side::Max, self.steps));
+            }
+
+            remainder.bounds[(d, 0)] += widths[(d, 0)];
+            remainder.bounds[(d, 1)] -= widths[(d, 1)];
         }
 
         result
@@ -468,6 +485,113 @@ mod unit_tests {
         }
     }
 
+    #[test]
+    fn decompose_wide_slopes() {
+        // Boundary frustrums are `steps * slope` wide
+        let stencil_slopes = matrix![2, 2];
+        let aabb = AABB::new(matrix![0, 10]);
+        let d1 =
+            Frustrum::new(aabb, 0, Side::Min, 2).decompose(&stencil_slopes);
+        assert_eq!(
+            d1,
+            vec![Frustrum::new(AABB::new(matrix![0, 3]), 0, Side::Min, 2)]
+        );
+        let d2 =
+            Frustrum::new(aabb, 0, Side::Max, 2).decompose(&stencil_slopes);
+        assert_eq!(
+            d2,
+            vec![Frustrum::new(AABB::new(matrix![7, 10]), 0, Side::Max, 2)]
+        );
+
+        // Each side uses its own slope, zero slopes need no frustrum
+        let stencil_slopes = matrix![0, 1; 2, 1];
+        let steps = 20;
+        let aabb = AABB::new(matrix![0, 50; 0, 200]);
+        let d1 =
+            Frustrum::new(aabb, 0, Side::Min, steps).decompose(&stencil_slopes);
+        assert_eq!(
+            d1,
+            vec![
+                Frustrum::new(
+                    AABB::new(matrix![0, 50; 0, 39]),
+                    1,
+                    Side::Min,
+                    steps
+                ),
+                Frustrum::new(
+                    AABB::new(matrix![0, 50; 181, 200]),
+                    1,
+                    Side::Max,
+                    steps
+                ),
+            ]
+        );
+        let d2 =
+            Frustrum::new(aabb, 0, Side::Max, steps).decompose(&stencil_slopes);
+        assert_eq!(
+            d2,
+            vec![
+                Frustrum::new(
+                    AABB::new(matrix![31, 50; 0, 200]),
+                    0,
+                    Side::Max,
+                    steps
+                ),
+                Frustrum::new(
+                    AABB::new(matrix![0, 30; 0, 39]),
+                    1,
+                    Side::Min,
+                    steps
+                ),
+                Frustrum::new(
+                    AABB::new(matrix![0, 30; 181, 200]),
+                    1,
+                    Side::Max,
+                    steps
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn decompose_unsloped_axis() {
+        // Periodic in y, nothing to cut there
+        let stencil_slopes = matrix![1, 1; 0, 0];
+        let steps = 20;
+        let aabb = AABB::new(matrix![0, 50; 0, 200]);
+        let f1 = Frustrum::new(aabb, 0, Side::Min, steps);
+        let d1 = f1.decompose(&stencil_slopes);
+        assert_eq!(
+            d1,
+            vec![Frustrum::new(
+                AABB::new(matrix![0, 19; 0, 200]),
+                0,
+                Side::Min,
+                steps,
+            )]
+        );
+
+        let stencil_slopes = matrix![1, 1; 0, 0; 1, 1];
+        let frustrum = Frustrum::new(
+            AABB::new(matrix![0, 37; 0, 60; 0, 60]),
+            0,
+            Side::Max,
+            12,
+        );
+        let input_aabb = frustrum.input_aabb(&stencil_slopes);
+        let solve_params = PeriodicSolveParams {
+            stencil_slopes,
+            cutoff: 20,
+            ratio: 0.5,
+            max_steps: Some(12),
+        };
+        let periodic_solve =
+            find_periodic_solve(&input_aabb, &solve_params).unwrap();
+        assert_eq!(periodic_solve.output_aabb.bounds[(1, 0)], 0);
+        assert_eq!(periodic_solve.output_aabb.bounds[(1, 1)], 60);
+        test_decomp(&frustrum, &periodic_solve.output_aabb, &stencil_slopes);
+    }
+
     #[test]
     fn sloped_sides_test() {
         {
@@ -1,28 +1,61 @@
 use crate::ap_solver::periodic_ops::*;
 use crate::ap_solver::plan::*;
+use crate::ap_solver::plan_file::*;
+use crate::ap_solver::plan_verify::*;
 use crate::ap_solver::planner::*;
 use crate::ap_solver::solver_parameters::*;
-use crate::stencil::TVStencil;
+use crate::util::*;
 
 /// Create the root repeat node.
+/// Only the stencil's slopes matter for planning,
+/// so this works for every kind of stencil.
+/// Plans are loaded from and saved to `params.plan_cache` when set,
+/// see `generate_cached_plan`.
+/// In debug builds the plan is checked with `Plan::verify`.
 pub fn generate_plan<
     const GRID_DIMENSION: usize,
-    const NEIGHBORHOOD_SIZE: usize,
-    StencilType: TVStencil<GRID_DIMENSION, NEIGHBORHOOD_SIZE>,
-    PeriodicOpsType: PeriodicOps<GRID_DIMENSION>,
+    PeriodicOpsType,
     OpsBuilderType: PeriodicOpsBuilder<GRID_DIMENSION, PeriodicOpsType>,
     CreateBuilderFn: Fn() -> OpsBuilderType,
 >(
-    stencil: &StencilType,
+    stencil_slopes: Bounds<GRID_DIMENSION>,
+    create_builder: CreateBuilderFn,
+    params: &SolverParameters<GRID_DIMENSION>,
+) -> PlannerResult<GRID_DIMENSION, PeriodicOpsType> {
+    match params.plan_cache.as_ref() {
+        Some(path) => {
+            generate_cached_plan(path, stencil_slopes, create_builder, params)
+        }
+        None => generate_inhomogeneous_plan(
+            stencil_slopes,
+            &|_| true,
+            create_builder,
+            params,
+        ),
+    }
+}
+
+/// Like `generate_plan`, but periodic solves are restricted to
+/// regions where `is_homogeneous` returns true.
+/// Everything else is covered by direct solves.
+/// In debug builds the plan is checked with `Plan::verify`.
+pub fn generate_inhomogeneous_plan<
+    const GRID_DIMENSION: usize,
+    PeriodicOpsType,
+    OpsBuilderType: PeriodicOpsBuilder<GRID_DIMENSION, PeriodicOpsType>,
+    CreateBuilderFn: Fn() -> OpsBuilderType,
+>(
+    stencil_slopes: Bounds<GRID_DIMENSION>,
+    is_homogeneous: &dyn Fn(&AABB<GRID_DIMENSION>) -> bool,
     create_builder: CreateBuilderFn,
     params: &SolverParameters<GRID_DIMENSION>,
 ) -> PlannerResult<GRID_DIMENSION, PeriodicOpsType> {
-    let stencil_slopes = stencil.slopes();
     let nodes = Vec::new();
     let mut planner = Planner {
-        stencil_slopes,
+        stencil_slopes: params.planning_slopes(&stencil_slopes),
         params,
         nodes,
+        is_homogeneous,
         ops_builder: create_builder(),
         ops_type_marker: std::marker::PhantomData,
     };
@@ -60,6 +93,7 @@ pub fn generate_plan<
         nodes: planner.nodes,
         root,
     };
+    debug_verify(&plan, &stencil_slopes, params);
 
     PlannerResult {
         plan,
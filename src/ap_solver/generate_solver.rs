@@ -4,21 +4,52 @@ use crate::ap_solver::scratch_builder::ComplexBufferType;
 use crate::ap_solver::solver::*;
 use crate::ap_solver::solver_parameters::*;
 use crate::ap_solver::tv_periodic_ops_collector::*;
+use crate::ap_solver::two_level_periodic_ops::*;
+use crate::ap_solver::two_level_solver::*;
 use crate::direct_solver::*;
+use crate::domain::Mask;
 use crate::stencil::*;
+use crate::util::*;
 use crate::SolverInterface;
+use crate::TwoLevelSolverInterface;
 
 pub fn generate_ap_solver<
     const GRID_DIMENSION: usize,
-    const NEIGHBORHOOD_SIZE: usize,
+    StencilType: TIStencil<GRID_DIMENSION>,
     DirectSolverType: DirectSolverInterface<GRID_DIMENSION>,
 >(
-    stencil: &Stencil<GRID_DIMENSION, NEIGHBORHOOD_SIZE>,
+    stencil: &StencilType,
     direct_solver: DirectSolverType,
     params: &SolverParameters<GRID_DIMENSION>,
 ) -> impl SolverInterface<GRID_DIMENSION> {
     let create_ops_builder = || ApPeriodicOpsBuilder::new(stencil, params);
-    let planner_result = generate_plan(stencil, create_ops_builder, params);
+    let planner_result =
+        generate_plan(stencil.slopes(), create_ops_builder, params);
+    let complex_buffer_type = ComplexBufferType::DomainOnly;
+    Solver::new(direct_solver, params, planner_result, complex_buffer_type)
+}
+
+/// Same as `generate_ap_solver`, but domains store `T` instead of `f64`,
+/// e.g. `f32` to halve memory use and bandwidth,
+/// or `c64` along with a stencil with `c64` weights.
+/// The direct solver must support `T` as well, e.g. `DirectFrustrumSolver`.
+pub fn generate_scalar_ap_solver<
+    const GRID_DIMENSION: usize,
+    T: Scalar,
+    StencilType: TIStencil<GRID_DIMENSION, T::Weight>,
+    DirectSolverType: DirectSolverInterface<GRID_DIMENSION, T>,
+>(
+    stencil: &StencilType,
+    direct_solver: DirectSolverType,
+    params: &SolverParameters<GRID_DIMENSION>,
+) -> impl SolverInterface<GRID_DIMENSION, T> {
+    let create_ops_builder = || {
+        ApPeriodicOpsBuilder::<GRID_DIMENSION, StencilType, T>::new_scalar(
+            stencil, params,
+        )
+    };
+    let planner_result =
+        generate_plan(stencil.slopes(), create_ops_builder, params);
     let complex_buffer_type = ComplexBufferType::DomainOnly;
     Solver::new(direct_solver, params, planner_result, complex_buffer_type)
 }
@@ -35,7 +66,84 @@ pub fn generate_tv_ap_solver<
     params: &'a SolverParameters<GRID_DIMENSION>,
 ) -> impl SolverInterface<GRID_DIMENSION> + 'a {
     let create_ops_builder = || TvPeriodicOpsCollector::new(stencil, params);
-    let planner_result = generate_plan(stencil, create_ops_builder, params);
+    let planner_result =
+        generate_plan(stencil.slopes(), create_ops_builder, params);
     let complex_buffer_type = ComplexBufferType::DomainAndOp;
     Solver::new(direct_solver, params, planner_result, complex_buffer_type)
 }
+
+/// AP solver for spatially varying stencils.
+/// Periodic solves use the stencil's homogeneous weights,
+/// and are only planned over regions where the stencil is homogeneous.
+/// The direct solver must handle the spatially varying weights,
+/// e.g. `SpatialDirectFrustrumSolver`.
+pub fn generate_spatial_ap_solver<
+    const GRID_DIMENSION: usize,
+    const NEIGHBORHOOD_SIZE: usize,
+    StencilType: SpatialStencil<GRID_DIMENSION, NEIGHBORHOOD_SIZE>,
+    DirectSolverType: DirectSolverInterface<GRID_DIMENSION>,
+>(
+    stencil: &StencilType,
+    direct_solver: DirectSolverType,
+    params: &SolverParameters<GRID_DIMENSION>,
+) -> impl SolverInterface<GRID_DIMENSION> {
+    let homogeneous_stencil = stencil.homogeneous_stencil();
+    let create_ops_builder =
+        || ApPeriodicOpsBuilder::new(&homogeneous_stencil, params);
+    let is_homogeneous =
+        |aabb: &AABB<GRID_DIMENSION>| stencil.is_homogeneous(aabb);
+    let planner_result = generate_inhomogeneous_plan(
+        stencil.slopes(),
+        &is_homogeneous,
+        create_ops_builder,
+        params,
+    );
+    let complex_buffer_type = ComplexBufferType::DomainOnly;
+    Solver::new(direct_solver, params, planner_result, complex_buffer_type)
+}
+
+/// AP solver for two level stencils, e.g. the wave equation.
+/// Plans are the same as for `generate_ap_solver`,
+/// using slopes that cover both time levels.
+pub fn generate_two_level_ap_solver<
+    const GRID_DIMENSION: usize,
+    DirectSolverType: TwoLevelDirectSolverInterface<GRID_DIMENSION>,
+>(
+    stencil: &TwoLevelStencil<GRID_DIMENSION>,
+    direct_solver: DirectSolverType,
+    params: &SolverParameters<GRID_DIMENSION>,
+) -> impl TwoLevelSolverInterface<GRID_DIMENSION> {
+    let create_ops_builder =
+        || TwoLevelPeriodicOpsBuilder::new(stencil, params);
+    let planner_result =
+        generate_plan(stencil.slopes(), create_ops_builder, params);
+    TwoLevelSolver::new(direct_solver, params, planner_result)
+}
+
+/// AP solver for domains with obstacles.
+/// Periodic solves are only planned over obstacle free regions of `mask`,
+/// everything touching an obstacle is left to direct solves.
+/// The direct solver must handle the obstacles,
+/// e.g. a `DirectFrustrumSolver` with a `MaskedCheck` for the same mask.
+pub fn generate_masked_ap_solver<
+    const GRID_DIMENSION: usize,
+    StencilType: TIStencil<GRID_DIMENSION>,
+    DirectSolverType: DirectSolverInterface<GRID_DIMENSION>,
+>(
+    stencil: &StencilType,
+    mask: &Mask<GRID_DIMENSION>,
+    direct_solver: DirectSolverType,
+    params: &SolverParameters<GRID_DIMENSION>,
+) -> impl SolverInterface<GRID_DIMENSION> {
+    debug_assert_eq!(mask.aabb(), &params.aabb);
+    let create_ops_builder = || ApPeriodicOpsBuilder::new(stencil, params);
+    let is_clear = |aabb: &AABB<GRID_DIMENSION>| mask.is_clear(aabb);
+    let planner_result = generate_inhomogeneous_plan(
+        stencil.slopes(),
+        &is_clear,
+        create_ops_builder,
+        params,
+    );
+    let complex_buffer_type = ComplexBufferType::DomainOnly;
+    Solver::new(direct_solver, params, planner_result, complex_buffer_type)
+}
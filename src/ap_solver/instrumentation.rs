@@ -0,0 +1,490 @@
+//! Per node runtime records for `Solver::apply`.
+//!
+//! Enabled with `Solver::enable_instrumentation`,
+//! every plan node records its wall time, planned threads,
+//! and an estimate of the bytes it touched each time it is applied.
+//! Records are summed over all applies,
+//! and can be written as JSON or CSV,
+//! or annotated onto the plan's dot file.
+
+use crate::ap_solver::index_types::*;
+use crate::ap_solver::plan::*;
+use crate::mem_fmt::human_readable_bytes;
+use crate::util::*;
+use std::collections::BTreeMap;
+use std::io::prelude::*;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Node types, named as in plan files.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum NodeKind {
+    Periodic,
+    Direct,
+    Repeat,
+    Range,
+}
+
+impl NodeKind {
+    pub fn new<const GRID_DIMENSION: usize>(
+        node: &PlanNode<GRID_DIMENSION>,
+    ) -> Self {
+        match node {
+            PlanNode::PeriodicSolve(_) => NodeKind::Periodic,
+            PlanNode::DirectSolve(_) => NodeKind::Direct,
+            PlanNode::Repeat(_) => NodeKind::Repeat,
+            PlanNode::Range(_) => NodeKind::Range,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            NodeKind::Periodic => "periodic",
+            NodeKind::Direct => "direct",
+            NodeKind::Repeat => "repeat",
+            NodeKind::Range => "range",
+        }
+    }
+}
+
+/// What we know about a node before applying it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NodeInfo<const GRID_DIMENSION: usize> {
+    pub kind: NodeKind,
+
+    /// Distance from the root
+    pub depth: usize,
+
+    /// Threads the plan gives the node, 0 for repeat and range nodes
+    pub threads: usize,
+
+    /// Estimated bytes read and written per apply,
+    /// excluding child nodes.
+    /// Copies, transforms and direct steps each read and write
+    /// every value once.
+    pub bytes: usize,
+
+    /// Output of periodic and direct solves
+    pub output_aabb: Option<AABB<GRID_DIMENSION>>,
+}
+
+/// Totals for one node over all recorded applies.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct NodeRecord {
+    pub calls: usize,
+
+    /// Wall time, including child nodes
+    pub seconds: f64,
+
+    /// Wall time spent waiting on child nodes,
+    /// boundary solves run concurrently so this is
+    /// less than the sum of their `seconds`.
+    pub child_seconds: f64,
+
+    pub bytes: usize,
+}
+
+impl NodeRecord {
+    /// Wall time excluding child nodes
+    pub fn self_seconds(&self) -> f64 {
+        self.seconds - self.child_seconds
+    }
+}
+
+/// Records summed over the nodes of one type,
+/// and optionally one depth.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NodeSummary {
+    pub kind: NodeKind,
+    pub depth: Option<usize>,
+    pub nodes: usize,
+    pub calls: usize,
+    pub self_seconds: f64,
+    pub bytes: usize,
+}
+
+pub struct Instrumentation<const GRID_DIMENSION: usize> {
+    pub root: NodeId,
+    pub node_info: Vec<NodeInfo<GRID_DIMENSION>>,
+    pub applies: usize,
+    records: Vec<Mutex<NodeRecord>>,
+}
+
+impl<const GRID_DIMENSION: usize> Instrumentation<GRID_DIMENSION> {
+    /// Empty records for a plan over domains that store `T`.
+    pub fn new<T: Scalar>(plan: &Plan<GRID_DIMENSION>) -> Self {
+        let mut depths = vec![0; plan.len()];
+        let mut stack = vec![(plan.root, 0)];
+        while let Some((node_id, depth)) = stack.pop() {
+            depths[node_id] = depth;
+            let children: Vec<NodeId> = match plan.get_node(node_id) {
+                PlanNode::PeriodicSolve(p) => {
+                    p.boundary_nodes.clone().chain(p.time_cut).collect()
+                }
+                PlanNode::DirectSolve(_) => Vec::new(),
+                PlanNode::Repeat(r) => {
+                    std::iter::once(r.node).chain(r.next).collect()
+                }
+                PlanNode::Range(r) => r.range.clone().collect(),
+            };
+            stack.extend(children.into_iter().map(|id| (id, depth + 1)));
+        }
+
+        let real_size = std::mem::size_of::<T>();
+        let complex_size = std::mem::size_of::<T::Complex>();
+        let node_info = plan
+            .nodes
+            .iter()
+            .zip(depths)
+            .map(|(node, depth)| {
+                let (threads, bytes, output_aabb) = match node {
+                    PlanNode::PeriodicSolve(p) => {
+                        let n = p.input_aabb.buffer_size();
+                        let out = p.output_aabb.buffer_size();
+                        let m = T::frequency_buffer_size(&p.input_aabb);
+                        let bytes = (4 * n + 2 * out) * real_size
+                            + 2 * m * complex_size;
+                        (p.threads, bytes, Some(p.output_aabb))
+                    }
+                    PlanNode::DirectSolve(d) => {
+                        let copies = 2
+                            * (d.input_aabb.buffer_size()
+                                + d.output_aabb.buffer_size());
+                        let bytes = (copies + 2 * d.cell_steps()) * real_size;
+                        (d.threads, bytes, Some(d.output_aabb))
+                    }
+                    PlanNode::Repeat(_) | PlanNode::Range(_) => (0, 0, None),
+                };
+                NodeInfo {
+                    kind: NodeKind::new(node),
+                    depth,
+                    threads,
+                    bytes,
+                    output_aabb,
+                }
+            })
+            .collect();
+
+        Instrumentation {
+            root: plan.root,
+            node_info,
+            applies: 0,
+            records: (0..plan.len())
+                .map(|_| Mutex::new(NodeRecord::default()))
+                .collect(),
+        }
+    }
+
+    /// Adds one call of `node_id` that took `elapsed`.
+    pub fn record(&self, node_id: NodeId, elapsed: Duration) {
+        let mut record = self.records[node_id].lock().unwrap();
+        record.calls += 1;
+        record.seconds += elapsed.as_secs_f64();
+        record.bytes += self.node_info[node_id].bytes;
+    }
+
+    /// Adds time `node_id` spent waiting on its children.
+    pub fn record_children(&self, node_id: NodeId, elapsed: Duration) {
+        let mut record = self.records[node_id].lock().unwrap();
+        record.child_seconds += elapsed.as_secs_f64();
+    }
+
+    /// Runs `f`, recording it as a call of `node_id`.
+    pub fn time<R, F: FnOnce() -> R>(&self, node_id: NodeId, f: F) -> R {
+        let start = Instant::now();
+        let result = f();
+        self.record(node_id, start.elapsed());
+        result
+    }
+
+    /// Runs `f`, recording it as time `node_id` spent on its children.
+    pub fn time_children<R, F: FnOnce() -> R>(
+        &self,
+        node_id: NodeId,
+        f: F,
+    ) -> R {
+        let start = Instant::now();
+        let result = f();
+        self.record_children(node_id, start.elapsed());
+        result
+    }
+
+    pub fn node_record(&self, node_id: NodeId) -> NodeRecord {
+        *self.records[node_id].lock().unwrap()
+    }
+
+    pub fn node_records(&self) -> Vec<NodeRecord> {
+        (0..self.records.len())
+            .map(|i| self.node_record(i))
+            .collect()
+    }
+
+    /// Wall time of all recorded applies
+    pub fn total_seconds(&self) -> f64 {
+        self.node_record(self.root).seconds
+    }
+
+    /// Sums by node type, then by node type and depth.
+    /// Self times are used so nested nodes aren't counted twice.
+    pub fn summaries(&self) -> (Vec<NodeSummary>, Vec<NodeSummary>) {
+        let mut by_kind = BTreeMap::new();
+        let mut by_depth = BTreeMap::new();
+        for (info, record) in self.node_info.iter().zip(self.node_records()) {
+            for (map, depth) in
+                [(&mut by_kind, None), (&mut by_depth, Some(info.depth))]
+            {
+                let summary =
+                    map.entry((info.kind, depth)).or_insert(NodeSummary {
+                        kind: info.kind,
+                        depth,
+                        nodes: 0,
+                        calls: 0,
+                        self_seconds: 0.0,
+                        bytes: 0,
+                    });
+                summary.nodes += 1;
+                summary.calls += record.calls;
+                summary.self_seconds += record.self_seconds();
+                summary.bytes += record.bytes;
+            }
+        }
+        (
+            by_kind.into_values().collect(),
+            by_depth.into_values().collect(),
+        )
+    }
+
+    /// Dot labels and heat, the share of total time, for each node.
+    pub fn dot_annotations(&self) -> Vec<NodeAnnotation> {
+        let total = self.total_seconds();
+        self.node_records()
+            .iter()
+            .map(|record| NodeAnnotation {
+                label: format!(
+                    "calls: {}\ntime: {:.3e} s\nself: {:.3e} s\nbytes: {}",
+                    record.calls,
+                    record.seconds,
+                    record.self_seconds(),
+                    human_readable_bytes(record.bytes),
+                ),
+                heat: if total > 0.0 {
+                    (record.seconds / total).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                },
+            })
+            .collect()
+    }
+
+    pub fn print_report(&self) {
+        let (by_kind, _) = self.summaries();
+        println!("Instrumentation Report:");
+        println!("  - applies: {}", self.applies);
+        println!("  - total time: {:.3e} s", self.total_seconds());
+        for summary in by_kind {
+            println!(
+                "  - {}: {} calls, {:.3e} s, {}",
+                summary.kind.name(),
+                summary.calls,
+                summary.self_seconds,
+                human_readable_bytes(summary.bytes),
+            );
+        }
+    }
+
+    /// One line per node.
+    pub fn write_csv<P: AsRef<std::path::Path>>(&self, path: &P) {
+        println!("Writing instrumentation csv: {:?}", path.as_ref());
+        let mut writer =
+            std::io::BufWriter::new(std::fs::File::create(path).unwrap());
+        writeln!(
+            writer,
+            "node,kind,depth,threads,calls,seconds,self_seconds,bytes,\
+             output_aabb"
+        )
+        .unwrap();
+        for (i, (info, record)) in
+            self.node_info.iter().zip(self.node_records()).enumerate()
+        {
+            writeln!(
+                writer,
+                "{i},{},{},{},{},{:e},{:e},{},\"{}\"",
+                info.kind.name(),
+                info.depth,
+                info.threads,
+                record.calls,
+                record.seconds,
+                record.self_seconds(),
+                record.bytes,
+                aabb_string(&info.output_aabb),
+            )
+            .unwrap();
+        }
+    }
+
+    /// Nodes along with the summaries by type and by depth.
+    pub fn write_json<P: AsRef<std::path::Path>>(&self, path: &P) {
+        println!("Writing instrumentation json: {:?}", path.as_ref());
+        let mut writer =
+            std::io::BufWriter::new(std::fs::File::create(path).unwrap());
+        let (by_kind, by_depth) = self.summaries();
+        writeln!(writer, "{{").unwrap();
+        writeln!(writer, "  \"applies\": {},", self.applies).unwrap();
+        writeln!(writer, "  \"total_seconds\": {:e},", self.total_seconds())
+            .unwrap();
+
+        writeln!(writer, "  \"nodes\": [").unwrap();
+        let records = self.node_records();
+        for (i, (info, record)) in
+            self.node_info.iter().zip(records.iter()).enumerate()
+        {
+            let separator = if i + 1 < records.len() { "," } else { "" };
+            writeln!(
+                writer,
+                "    {{\"node\": {i}, \"kind\": \"{}\", \"depth\": {}, \
+                 \"threads\": {}, \"calls\": {}, \"seconds\": {:e}, \
+                 \"self_seconds\": {:e}, \"bytes\": {}, \
+                 \"output_aabb\": \"{}\"}}{separator}",
+                info.kind.name(),
+                info.depth,
+                info.threads,
+                record.calls,
+                record.seconds,
+                record.self_seconds(),
+                record.bytes,
+                aabb_string(&info.output_aabb),
+            )
+            .unwrap();
+        }
+        writeln!(writer, "  ],").unwrap();
+
+        for (name, summaries, last) in
+            [("by_kind", by_kind, false), ("by_depth", by_depth, true)]
+        {
+            writeln!(writer, "  \"{name}\": [").unwrap();
+            for (i, summary) in summaries.iter().enumerate() {
+                let separator = if i + 1 < summaries.len() { "," } else { "" };
+                let depth = summary
+                    .depth
+                    .map_or(String::new(), |d| format!("\"depth\": {d}, "));
+                writeln!(
+                    writer,
+                    "    {{\"kind\": \"{}\", {depth}\"nodes\": {}, \
+                     \"calls\": {}, \"self_seconds\": {:e}, \
+                     \"bytes\": {}}}{separator}",
+                    summary.kind.name(),
+                    summary.nodes,
+                    summary.calls,
+                    summary.self_seconds,
+                    summary.bytes,
+                )
+                .unwrap();
+            }
+            writeln!(writer, "  ]{}", if last { "" } else { "," }).unwrap();
+        }
+        writeln!(writer, "}}").unwrap();
+    }
+}
+
+/// Bounds as `min:max` for each axis, '-' when there are none.
+fn aabb_string<const GRID_DIMENSION: usize>(
+    aabb: &Option<AABB<GRID_DIMENSION>>,
+) -> String {
+    match aabb {
+        Some(aabb) => (0..GRID_DIMENSION)
+            .map(|d| format!("{}:{}", aabb.bounds[(d, 0)], aabb.bounds[(d, 1)]))
+            .collect::<Vec<_>>()
+            .join(" "),
+        None => "-".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+    use crate::ap_solver::generate_plan::*;
+    use crate::ap_solver::periodic_ops::*;
+    use crate::ap_solver::solver_parameters::*;
+    use nalgebra::matrix;
+
+    fn test_plan() -> Plan<2> {
+        let params = SolverParameters {
+            aabb: AABB::new(matrix![0, 199; 0, 199]),
+            steps: 250,
+            cutoff: 20,
+            ratio: 0.5,
+            ..Default::default()
+        };
+        generate_plan(matrix![1, 1; 1, 1], OpIdCollector::new, &params).plan
+    }
+
+    #[test]
+    fn node_info() {
+        let plan = test_plan();
+        let instrumentation = Instrumentation::new::<f64>(&plan);
+        assert_eq!(instrumentation.node_info.len(), plan.len());
+        let root = &instrumentation.node_info[plan.root];
+        assert_eq!(root.kind, NodeKind::Repeat);
+        assert_eq!(root.depth, 0);
+        assert_eq!(root.bytes, 0);
+
+        let repeat = plan.unwrap_repeat_node(plan.root);
+        let central = &instrumentation.node_info[repeat.node];
+        assert_eq!(central.kind, NodeKind::Periodic);
+        assert_eq!(central.depth, 1);
+        assert!(central.bytes > 200 * 200 * 8);
+        let periodic = plan.unwrap_periodic_node(repeat.node);
+        for id in periodic.boundary_nodes.clone() {
+            assert_eq!(instrumentation.node_info[id].depth, 2);
+        }
+    }
+
+    #[test]
+    fn records() {
+        let plan = test_plan();
+        let mut instrumentation = Instrumentation::new::<f64>(&plan);
+        let repeat = plan.unwrap_repeat_node(plan.root);
+        let central = repeat.node;
+        let boundary = plan.unwrap_periodic_node(central).boundary_nodes.start;
+
+        instrumentation.applies += 1;
+        instrumentation.record(plan.root, Duration::from_millis(10));
+        instrumentation.record(central, Duration::from_millis(8));
+        instrumentation.record_children(central, Duration::from_millis(6));
+        instrumentation.record(boundary, Duration::from_millis(3));
+        instrumentation.record(boundary, Duration::from_millis(3));
+
+        let record = instrumentation.node_record(central);
+        assert_eq!(record.calls, 1);
+        assert!((record.self_seconds() - 0.002).abs() < 1e-9);
+        let record = instrumentation.node_record(boundary);
+        assert_eq!(record.calls, 2);
+        assert_eq!(record.bytes, 2 * instrumentation.node_info[boundary].bytes);
+        assert!((instrumentation.total_seconds() - 0.01).abs() < 1e-9);
+
+        let (by_kind, by_depth) = instrumentation.summaries();
+        let periodic_calls: usize = by_kind
+            .iter()
+            .filter(|s| s.kind == NodeKind::Periodic)
+            .map(|s| s.calls)
+            .sum();
+        assert!(periodic_calls >= 1);
+        assert!(by_kind.iter().all(|s| s.depth.is_none()));
+        let depth_calls: usize = by_depth.iter().map(|s| s.calls).sum();
+        let kind_calls: usize = by_kind.iter().map(|s| s.calls).sum();
+        assert_eq!(depth_calls, 4);
+        assert_eq!(kind_calls, 4);
+
+        let annotations = instrumentation.dot_annotations();
+        assert_eq!(annotations[plan.root].heat, 1.0);
+        assert!((annotations[central].heat - 0.8).abs() < 1e-9);
+        assert!(annotations[boundary].label.starts_with("calls: 2"));
+    }
+
+    #[test]
+    fn aabb_strings() {
+        let aabb = AABB::new(matrix![0, 9; -2, 5]);
+        assert_eq!(aabb_string(&Some(aabb)), "0:9 -2:5");
+        assert_eq!(aabb_string::<2>(&None), "-");
+    }
+}
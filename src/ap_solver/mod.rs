@@ -1,9 +1,15 @@
 pub mod account_builder;
+pub mod autotune;
+pub mod constant_bc_solver;
 pub mod find_periodic_solve;
 pub mod frustrum;
 pub mod index_types;
+pub mod instrumentation;
 pub mod periodic_ops;
 pub mod plan;
+pub mod plan_file;
+pub mod plan_report;
+pub mod plan_verify;
 pub mod planner;
 pub mod scratch;
 pub mod scratch_builder;
@@ -16,10 +22,13 @@ pub mod generate_plan;
 pub mod tv_periodic_ops;
 pub mod tv_periodic_ops_builder;
 pub mod tv_periodic_ops_collector;
+pub mod two_level_periodic_ops;
+pub mod two_level_solver;
 
 pub mod generate_solver;
 
 pub use crate::fft_solver::PlanType;
+pub use constant_bc_solver::*;
 pub use generate_solver::*;
 pub use solver_parameters::*;
 
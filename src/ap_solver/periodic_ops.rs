@@ -1,6 +1,7 @@
 use crate::ap_solver::index_types::*;
 use crate::domain::*;
 use crate::util::*;
+use std::collections::HashMap;
 
 /// Describes a periodic solve,
 /// including time-varying
@@ -25,11 +26,10 @@ impl<const GRID_DIMENSION: usize> PeriodicOpDescriptor<GRID_DIMENSION> {
     }
 }
 
-pub trait PeriodicOpsBuilder<
-    const GRID_DIMENSION: usize,
-    SolverType: PeriodicOps<GRID_DIMENSION>,
->
-{
+/// Creates the periodic operations for a plan.
+/// `SolverType` is usually some `PeriodicOps<GRID_DIMENSION, T>`,
+/// two level solvers use `TwoLevelPeriodicOps` instead.
+pub trait PeriodicOpsBuilder<const GRID_DIMENSION: usize, SolverType> {
     fn get_op_id(
         &mut self,
         descriptor: PeriodicOpDescriptor<GRID_DIMENSION>,
@@ -38,16 +38,47 @@ pub trait PeriodicOpsBuilder<
     fn finish(self) -> SolverType;
 }
 
-pub trait PeriodicOps<const GRID_DIMENSION: usize>: Send + Sync {
+/// Hands out op ids without building any operations,
+/// for when only the plan is needed.
+#[derive(Default)]
+pub struct OpIdCollector<const GRID_DIMENSION: usize> {
+    ids: HashMap<PeriodicOpDescriptor<GRID_DIMENSION>, OpId>,
+}
+
+impl<const GRID_DIMENSION: usize> OpIdCollector<GRID_DIMENSION> {
+    pub fn new() -> Self {
+        OpIdCollector {
+            ids: HashMap::new(),
+        }
+    }
+}
+
+impl<const GRID_DIMENSION: usize> PeriodicOpsBuilder<GRID_DIMENSION, ()>
+    for OpIdCollector<GRID_DIMENSION>
+{
+    fn get_op_id(
+        &mut self,
+        descriptor: PeriodicOpDescriptor<GRID_DIMENSION>,
+    ) -> OpId {
+        let next_id = self.ids.len();
+        *self.ids.entry(descriptor).or_insert(next_id)
+    }
+
+    fn finish(self) {}
+}
+
+pub trait PeriodicOps<const GRID_DIMENSION: usize, T: Scalar = f64>:
+    Send + Sync
+{
     fn build_ops(&mut self, global_time: usize);
 
     fn apply_operation<'a>(
         &self,
         op_id: OpId,
-        input_domain: &mut SliceDomain<'a, GRID_DIMENSION>,
-        output_domain: &mut SliceDomain<'a, GRID_DIMENSION>,
+        input_domain: &mut SliceDomain<'a, GRID_DIMENSION, T>,
+        output_domain: &mut SliceDomain<'a, GRID_DIMENSION, T>,
         // NOTE: TV will need twice the buffer, and we can split it as needed
-        complex_buffer: &mut [c64],
+        complex_buffer: &mut [T::Complex],
         central_global_time: usize,
         chunk_size: usize,
     );
@@ -8,7 +8,7 @@ use std::ops::Range;
 /// periodic solve and the boundary solves.
 /// The boundary solve nodes are assumed to be a contiguous range of
 /// nodes.
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct PeriodicSolveNode<const GRID_DIMENSION: usize> {
     /// Required input buffer
     pub input_aabb: AABB<GRID_DIMENSION>,
@@ -36,7 +36,7 @@ pub struct PeriodicSolveNode<const GRID_DIMENSION: usize> {
 /// steps and sloped sides.
 /// Strictly speaking we don't need the output_aabb,
 /// but its remains useful for debugging.
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct DirectSolveNode<const GRID_DIMENSION: usize> {
     /// Required Input buffer
     pub input_aabb: AABB<GRID_DIMENSION>,
@@ -56,12 +56,22 @@ pub struct DirectSolveNode<const GRID_DIMENSION: usize> {
     pub threads: usize,
 }
 
+impl<const GRID_DIMENSION: usize> DirectSolveNode<GRID_DIMENSION> {
+    /// Approximate number of values updated over all steps.
+    /// Frustrums shrink linearly, so we average the ends.
+    pub fn cell_steps(&self) -> usize {
+        (self.input_aabb.buffer_size() + self.output_aabb.buffer_size())
+            * self.steps
+            / 2
+    }
+}
+
 /// Used for central periodic solve, can't appear in frustrums.
 /// However, the largest central periodic solve we can find may
 /// need to be repeated many times to achieve the desired number of steps.
 /// Possible followed by a single periodic solve to get the remainder
 /// of steps.
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct RepeatNode {
     pub n: usize,
     pub node: NodeId,
@@ -69,13 +79,13 @@ pub struct RepeatNode {
 }
 
 /// Use for root node in time-varying solvers
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct RangeNode {
     pub range: Range<NodeId>,
 }
 
 /// These nodes form a tree.
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum PlanNode<const GRID_DIMENSION: usize> {
     PeriodicSolve(PeriodicSolveNode<GRID_DIMENSION>),
     DirectSolve(DirectSolveNode<GRID_DIMENSION>),
@@ -83,9 +93,19 @@ pub enum PlanNode<const GRID_DIMENSION: usize> {
     Range(RangeNode),
 }
 
+/// Extra dot label for a node, see `Plan::to_annotated_dot_file`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NodeAnnotation {
+    pub label: String,
+
+    /// Fill color, from 0 (light) to 1 (dark)
+    pub heat: f64,
+}
+
 /// An `Plan` describes an aperiodic solve over a fixed AABB
 /// for fixed number of time steps.
 /// The root node should always be the only repeat node in the tree.
+#[derive(Debug, PartialEq)]
 pub struct Plan<const GRID_DIMENSION: usize> {
     pub nodes: Vec<PlanNode<GRID_DIMENSION>>,
     pub root: NodeId,
@@ -145,51 +165,65 @@ impl<const GRID_DIMENSION: usize> Plan<GRID_DIMENSION> {
 
     /// Write out the plan as a dot language graph to specified path.
     pub fn to_dot_file<P: AsRef<std::path::Path>>(&self, path: &P) {
+        self.write_dot(path, None);
+    }
+
+    /// Like `to_dot_file`, with an extra label for each node,
+    /// filled by heat.
+    pub fn to_annotated_dot_file<P: AsRef<std::path::Path>>(
+        &self,
+        path: &P,
+        annotations: &[NodeAnnotation],
+    ) {
+        debug_assert_eq!(annotations.len(), self.len());
+        self.write_dot(path, Some(annotations));
+    }
+
+    fn write_dot<P: AsRef<std::path::Path>>(
+        &self,
+        path: &P,
+        annotations: Option<&[NodeAnnotation]>,
+    ) {
         println!("Writing plan dot: {:?}", path.as_ref());
         let mut writer =
             std::io::BufWriter::new(std::fs::File::create(path).unwrap());
         writeln!(writer, "digraph plan {{").unwrap();
 
         for (i, node) in self.nodes.iter().enumerate() {
-            match node {
+            let mut label = match node {
                 PlanNode::PeriodicSolve(periodic_solve) => {
-                    writeln!(
-                        writer,
-                        " n_{id} [label=\"n_{id}: PERIODIC\nsteps: {s}\nin: {in}\nout: {out}\nconv_id: {c_id}\"];",
+                    format!(
+                        "n_{id}: PERIODIC\nsteps: {s}\nin: {in}\nout: {out}\nconv_id: {c_id}",
                         id = i,
                         s = periodic_solve.steps,
                         in = periodic_solve.input_aabb,
                         out = periodic_solve.output_aabb,
                         c_id = periodic_solve.convolution_id,
                     )
-                    .unwrap();
                 }
                 PlanNode::DirectSolve(direct_solve) => {
-                    writeln!(
-                        writer,
-                        " n_{id} [label=\"n_{id}: DIRECT\nsteps: {s}\nin: {in}\nout: {out}\nsloped_sides: {slope:?}\"];",
+                    format!(
+                        "n_{id}: DIRECT\nsteps: {s}\nin: {in}\nout: {out}\nsloped_sides: {slope:?}",
                         id = i,
                         s = direct_solve.steps,
                         in = direct_solve.input_aabb,
                         out = direct_solve.output_aabb,
                         slope = direct_solve.sloped_sides,
                     )
-                    .unwrap();
                 }
                 PlanNode::Repeat(repeat_node) => {
-                    writeln!(
-                        writer,
-                        " n_{id} [label=\"n_{id}: REPEAT\nn: {n}\"];",
-                        id = i,
-                        n = repeat_node.n,
-                    )
-                    .unwrap();
-                }
-                PlanNode::Range(_) => {
-                    writeln!(writer, " n_{i} [label=\"n_{i}: RANGE\"];",)
-                        .unwrap();
+                    format!("n_{i}: REPEAT\nn: {n}", n = repeat_node.n)
                 }
+                PlanNode::Range(_) => format!("n_{i}: RANGE"),
+            };
+            let mut style = String::new();
+            if let Some(annotation) = annotations.map(|a| &a[i]) {
+                label = format!("{label}\n{}", annotation.label);
+                let color = colorous::YELLOW_ORANGE_RED
+                    .eval_continuous(annotation.heat.clamp(0.0, 1.0));
+                style = format!(" style=filled fillcolor=\"#{color:x}\"");
             }
+            writeln!(writer, " n_{i} [label=\"{label}\"{style}];").unwrap();
         }
 
         for (i, node) in self.nodes.iter().enumerate() {
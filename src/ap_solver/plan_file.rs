@@ -0,0 +1,800 @@
+//! Plain-text `Plan` files, so repeated launches can skip planning.
+//! Only the plan and the periodic operation descriptors are saved,
+//! FFTW plans and convolutions are rebuilt from the descriptors.
+//!
+//! ```text
+//! nhls-plan 1
+//! dimension 2
+//!
+//! # Key, a file is only used for the parameters it was planned with
+//! slopes 1 1 1 1
+//! aabb 0 99 0 99
+//! steps 40
+//! cutoff 10
+//! ratio 0.5
+//! threads 8
+//! task_min 1
+//! task_mult 1
+//! axes aperiodic aperiodic
+//!
+//! # Descriptors in OpId order, step_min step_max steps threads bounds
+//! op 0 20 20 8 100 100
+//! remainder_op 0 12 12 8 100 100
+//!
+//! # Nodes in NodeId order, AABBs like 'aabb' above, '-' for None
+//! direct <input> <output> <sloped sides> <steps> <threads>
+//! periodic <input> <output> <op> <steps> <first> <last> <cut> <threads>
+//! repeat <n> <node> <next>
+//! range <first> <last>
+//! root 9
+//! ```
+
+use crate::ap_solver::generate_plan::*;
+use crate::ap_solver::index_types::*;
+use crate::ap_solver::periodic_ops::*;
+use crate::ap_solver::plan::*;
+use crate::ap_solver::plan_verify::*;
+use crate::ap_solver::planner::*;
+use crate::ap_solver::solver_parameters::*;
+use crate::util::*;
+use std::io::prelude::*;
+use std::path::Path;
+
+/// Bumped whenever the format or the planner's output changes.
+pub const PLAN_FILE_VERSION: usize = 1;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlanFileError {
+    /// 1-based line number, 0 if the error isn't tied to a line.
+    pub line: usize,
+    pub message: String,
+}
+
+impl PlanFileError {
+    fn new(line: usize, message: String) -> Self {
+        PlanFileError { line, message }
+    }
+}
+
+impl std::fmt::Display for PlanFileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.line == 0 {
+            write!(f, "plan file: {}", self.message)
+        } else {
+            write!(f, "plan file line {}: {}", self.line, self.message)
+        }
+    }
+}
+
+impl std::error::Error for PlanFileError {}
+
+/// Everything the planner's output depends on.
+/// Plan type and chunk size only matter once the plan is built.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlanKey<const GRID_DIMENSION: usize> {
+    pub stencil_slopes: Bounds<GRID_DIMENSION>,
+    pub aabb: AABB<GRID_DIMENSION>,
+    pub steps: usize,
+    pub cutoff: i32,
+    pub ratio: f64,
+    pub threads: usize,
+    pub task_min: usize,
+    pub task_mult: f64,
+    pub axis_boundaries: [AxisBoundary; GRID_DIMENSION],
+}
+
+impl<const GRID_DIMENSION: usize> PlanKey<GRID_DIMENSION> {
+    pub fn new(
+        stencil_slopes: &Bounds<GRID_DIMENSION>,
+        params: &SolverParameters<GRID_DIMENSION>,
+    ) -> Self {
+        PlanKey {
+            stencil_slopes: *stencil_slopes,
+            aabb: params.aabb,
+            steps: params.steps,
+            cutoff: params.cutoff,
+            ratio: params.ratio,
+            threads: params.threads,
+            task_min: params.task_min,
+            task_mult: params.task_mult,
+            axis_boundaries: params.axis_boundaries,
+        }
+    }
+}
+
+/// A `Plan` along with the descriptors of the periodic operations
+/// it refers to, indexed by `OpId`.
+#[derive(Debug, PartialEq)]
+pub struct SavedPlan<const GRID_DIMENSION: usize> {
+    pub key: PlanKey<GRID_DIMENSION>,
+    pub plan: Plan<GRID_DIMENSION>,
+    pub op_descriptors: Vec<PeriodicOpDescriptor<GRID_DIMENSION>>,
+    pub remainder_op_descriptors: Vec<PeriodicOpDescriptor<GRID_DIMENSION>>,
+}
+
+impl<const GRID_DIMENSION: usize> SavedPlan<GRID_DIMENSION> {
+    pub fn load<P: AsRef<Path>>(path: &P) -> Result<Self, PlanFileError> {
+        let source = std::fs::read_to_string(path).map_err(|e| {
+            PlanFileError::new(
+                0,
+                format!("failed to read {:?}: {e}", path.as_ref()),
+            )
+        })?;
+        Self::parse(&source)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: &P) -> std::io::Result<()> {
+        let mut writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+        self.write(&mut writer)?;
+        writer.flush()
+    }
+
+    pub fn write<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        let key = &self.key;
+        writeln!(writer, "nhls-plan {PLAN_FILE_VERSION}")?;
+        writeln!(writer, "dimension {GRID_DIMENSION}")?;
+        writeln!(writer, "slopes {}", bounds_string(&key.stencil_slopes))?;
+        writeln!(writer, "aabb {}", bounds_string(&key.aabb.bounds))?;
+        writeln!(writer, "steps {}", key.steps)?;
+        writeln!(writer, "cutoff {}", key.cutoff)?;
+        writeln!(writer, "ratio {}", key.ratio)?;
+        writeln!(writer, "threads {}", key.threads)?;
+        writeln!(writer, "task_min {}", key.task_min)?;
+        writeln!(writer, "task_mult {}", key.task_mult)?;
+        let axes: Vec<&str> = key
+            .axis_boundaries
+            .iter()
+            .map(|b| match b {
+                AxisBoundary::Aperiodic => "aperiodic",
+                AxisBoundary::Periodic => "periodic",
+            })
+            .collect();
+        writeln!(writer, "axes {}", axes.join(" "))?;
+
+        for (keyword, descriptors) in [
+            ("op", &self.op_descriptors),
+            ("remainder_op", &self.remainder_op_descriptors),
+        ] {
+            for d in descriptors {
+                writeln!(
+                    writer,
+                    "{keyword} {} {} {} {} {}",
+                    d.step_min,
+                    d.step_max,
+                    d.steps,
+                    d.threads,
+                    coord_string(&d.exclusive_bounds)
+                )?;
+            }
+        }
+
+        for node in &self.plan.nodes {
+            match node {
+                PlanNode::PeriodicSolve(p) => writeln!(
+                    writer,
+                    "periodic {} {} {} {} {} {} {} {}",
+                    bounds_string(&p.input_aabb.bounds),
+                    bounds_string(&p.output_aabb.bounds),
+                    p.convolution_id,
+                    p.steps,
+                    p.boundary_nodes.start,
+                    p.boundary_nodes.end,
+                    option_string(p.time_cut),
+                    p.threads,
+                )?,
+                PlanNode::DirectSolve(d) => writeln!(
+                    writer,
+                    "direct {} {} {} {} {}",
+                    bounds_string(&d.input_aabb.bounds),
+                    bounds_string(&d.output_aabb.bounds),
+                    bounds_string(&d.sloped_sides),
+                    d.steps,
+                    d.threads,
+                )?,
+                PlanNode::Repeat(r) => writeln!(
+                    writer,
+                    "repeat {} {} {}",
+                    r.n,
+                    r.node,
+                    option_string(r.next)
+                )?,
+                PlanNode::Range(r) => {
+                    writeln!(writer, "range {} {}", r.range.start, r.range.end)?
+                }
+            }
+        }
+        writeln!(writer, "root {}", self.plan.root)
+    }
+
+    /// Parse a plan file, see module documentation for the format.
+    pub fn parse(source: &str) -> Result<Self, PlanFileError> {
+        let mut version = None;
+        let mut dimension = None;
+        let mut stencil_slopes = None;
+        let mut aabb = None;
+        let mut steps = None;
+        let mut cutoff = None;
+        let mut ratio = None;
+        let mut threads = None;
+        let mut task_min = None;
+        let mut task_mult = None;
+        let mut axis_boundaries = None;
+        let mut op_descriptors = Vec::new();
+        let mut remainder_op_descriptors = Vec::new();
+        let mut nodes = Vec::new();
+        let mut root = None;
+
+        for (line_index, raw_line) in source.lines().enumerate() {
+            let line_number = line_index + 1;
+            let err =
+                |message: String| PlanFileError::new(line_number, message);
+            let line = match raw_line.split_once('#') {
+                Some((content, _)) => content.trim(),
+                None => raw_line.trim(),
+            };
+            if line.is_empty() {
+                continue;
+            }
+            let mut fields = Fields {
+                tokens: line.split_whitespace().collect(),
+                position: 1,
+            };
+            let keyword = fields.tokens[0];
+
+            if keyword != "nhls-plan" && version.is_none() {
+                return Err(err("missing 'nhls-plan' header".to_string()));
+            }
+            if keyword != "nhls-plan"
+                && keyword != "dimension"
+                && dimension.is_none()
+            {
+                return Err(err(
+                    "'dimension' must follow the header".to_string()
+                ));
+            }
+
+            match keyword {
+                "nhls-plan" => {
+                    let v: usize = fields.value().map_err(err)?;
+                    if v != PLAN_FILE_VERSION {
+                        return Err(err(format!(
+                            "unsupported version {v}, \
+                             expected {PLAN_FILE_VERSION}"
+                        )));
+                    }
+                    version = Some(v);
+                }
+                "dimension" => {
+                    let d: usize = fields.value().map_err(err)?;
+                    if d != GRID_DIMENSION {
+                        return Err(err(format!(
+                            "expected a {GRID_DIMENSION}D plan, found {d}D"
+                        )));
+                    }
+                    dimension = Some(d);
+                }
+                "slopes" => {
+                    stencil_slopes = Some(fields.bounds().map_err(err)?);
+                }
+                "aabb" => aabb = Some(fields.aabb().map_err(err)?),
+                "steps" => steps = Some(fields.value().map_err(err)?),
+                "cutoff" => cutoff = Some(fields.value().map_err(err)?),
+                "ratio" => ratio = Some(fields.value().map_err(err)?),
+                "threads" => threads = Some(fields.value().map_err(err)?),
+                "task_min" => task_min = Some(fields.value().map_err(err)?),
+                "task_mult" => task_mult = Some(fields.value().map_err(err)?),
+                "axes" => {
+                    let mut result = [AxisBoundary::Aperiodic; GRID_DIMENSION];
+                    for b in result.iter_mut() {
+                        *b = match fields.token().map_err(err)? {
+                            "aperiodic" => AxisBoundary::Aperiodic,
+                            "periodic" => AxisBoundary::Periodic,
+                            other => {
+                                return Err(err(format!(
+                                    "invalid axis boundary '{other}'"
+                                )))
+                            }
+                        };
+                    }
+                    axis_boundaries = Some(result);
+                }
+                "op" | "remainder_op" => {
+                    let descriptor = PeriodicOpDescriptor {
+                        step_min: fields.value().map_err(err)?,
+                        step_max: fields.value().map_err(err)?,
+                        steps: fields.value().map_err(err)?,
+                        threads: fields.value().map_err(err)?,
+                        exclusive_bounds: fields.coord().map_err(err)?,
+                    };
+                    if keyword == "op" {
+                        op_descriptors.push(descriptor);
+                    } else {
+                        remainder_op_descriptors.push(descriptor);
+                    }
+                }
+                "periodic" => {
+                    nodes.push(PlanNode::PeriodicSolve(PeriodicSolveNode {
+                        input_aabb: fields.aabb().map_err(err)?,
+                        output_aabb: fields.aabb().map_err(err)?,
+                        convolution_id: fields.value().map_err(err)?,
+                        steps: fields.value().map_err(err)?,
+                        boundary_nodes: fields.value().map_err(err)?
+                            ..fields.value().map_err(err)?,
+                        time_cut: fields.option().map_err(err)?,
+                        threads: fields.value().map_err(err)?,
+                    }));
+                }
+                "direct" => {
+                    nodes.push(PlanNode::DirectSolve(DirectSolveNode {
+                        input_aabb: fields.aabb().map_err(err)?,
+                        output_aabb: fields.aabb().map_err(err)?,
+                        sloped_sides: fields.bounds().map_err(err)?,
+                        steps: fields.value().map_err(err)?,
+                        threads: fields.value().map_err(err)?,
+                    }));
+                }
+                "repeat" => {
+                    nodes.push(PlanNode::Repeat(RepeatNode {
+                        n: fields.value().map_err(err)?,
+                        node: fields.value().map_err(err)?,
+                        next: fields.option().map_err(err)?,
+                    }));
+                }
+                "range" => {
+                    nodes.push(PlanNode::Range(RangeNode {
+                        range: fields.value().map_err(err)?
+                            ..fields.value().map_err(err)?,
+                    }));
+                }
+                "root" => root = Some(fields.value().map_err(err)?),
+                _ => {
+                    return Err(err(format!("unknown keyword '{keyword}'")));
+                }
+            }
+
+            if fields.position != fields.tokens.len() {
+                return Err(err(format!("unexpected trailing input '{line}'")));
+            }
+        }
+
+        let missing =
+            |name: &str| PlanFileError::new(0, format!("missing '{name}'"));
+        if version.is_none() {
+            return Err(missing("nhls-plan"));
+        }
+        let key = PlanKey {
+            stencil_slopes: stencil_slopes.ok_or_else(|| missing("slopes"))?,
+            aabb: aabb.ok_or_else(|| missing("aabb"))?,
+            steps: steps.ok_or_else(|| missing("steps"))?,
+            cutoff: cutoff.ok_or_else(|| missing("cutoff"))?,
+            ratio: ratio.ok_or_else(|| missing("ratio"))?,
+            threads: threads.ok_or_else(|| missing("threads"))?,
+            task_min: task_min.ok_or_else(|| missing("task_min"))?,
+            task_mult: task_mult.ok_or_else(|| missing("task_mult"))?,
+            axis_boundaries: axis_boundaries.ok_or_else(|| missing("axes"))?,
+        };
+        let plan = Plan {
+            nodes,
+            root: root.ok_or_else(|| missing("root"))?,
+        };
+        let result = SavedPlan {
+            key,
+            plan,
+            op_descriptors,
+            remainder_op_descriptors,
+        };
+        result.check_references()?;
+        Ok(result)
+    }
+
+    /// Ensure every node and op a node refers to exists,
+    /// so a damaged file can't cause out of bounds accesses later.
+    fn check_references(&self) -> Result<(), PlanFileError> {
+        let node_count = self.plan.len();
+        let op_count = self
+            .op_descriptors
+            .len()
+            .max(self.remainder_op_descriptors.len());
+        let check_node = |id: NodeId| {
+            if id < node_count {
+                Ok(())
+            } else {
+                Err(PlanFileError::new(0, format!("invalid node id {id}")))
+            }
+        };
+        let check_range = |range: &std::ops::Range<NodeId>| {
+            if range.start <= range.end && range.end <= node_count {
+                Ok(())
+            } else {
+                Err(PlanFileError::new(
+                    0,
+                    format!("invalid node range {range:?}"),
+                ))
+            }
+        };
+
+        check_node(self.plan.root)?;
+        if !matches!(self.plan.get_node(self.plan.root), PlanNode::Repeat(_)) {
+            return Err(PlanFileError::new(
+                0,
+                "root is not a repeat node".to_string(),
+            ));
+        }
+        for node in &self.plan.nodes {
+            match node {
+                PlanNode::PeriodicSolve(p) => {
+                    if p.convolution_id >= op_count {
+                        return Err(PlanFileError::new(
+                            0,
+                            format!("invalid op id {}", p.convolution_id),
+                        ));
+                    }
+                    check_range(&p.boundary_nodes)?;
+                    if let Some(id) = p.time_cut {
+                        check_node(id)?;
+                    }
+                }
+                PlanNode::DirectSolve(_) => {}
+                PlanNode::Repeat(r) => {
+                    check_node(r.node)?;
+                    if let Some(id) = r.next {
+                        check_node(id)?;
+                    }
+                }
+                PlanNode::Range(r) => check_range(&r.range)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Rebuild the periodic operations by replaying the descriptors,
+    /// builders hand out ids in order of first request.
+    pub fn into_planner_result<
+        PeriodicOpsType,
+        OpsBuilderType: PeriodicOpsBuilder<GRID_DIMENSION, PeriodicOpsType>,
+        CreateBuilderFn: Fn() -> OpsBuilderType,
+    >(
+        self,
+        create_builder: CreateBuilderFn,
+    ) -> PlannerResult<GRID_DIMENSION, PeriodicOpsType> {
+        let replay =
+            |descriptors: &[PeriodicOpDescriptor<GRID_DIMENSION>]| {
+                let mut builder = create_builder();
+                for (i, descriptor) in descriptors.iter().enumerate() {
+                    let op_id = builder.get_op_id(*descriptor);
+                    assert_eq!(
+                        op_id, i,
+                        "ERROR: descriptor {descriptor:?} got op id {op_id}"
+                    );
+                }
+                builder.finish()
+            };
+        PlannerResult {
+            periodic_ops: replay(&self.op_descriptors),
+            remainder_periodic_ops: replay(&self.remainder_op_descriptors),
+            plan: self.plan,
+            stencil_slopes: self.key.stencil_slopes,
+        }
+    }
+}
+
+/// Wraps a `PeriodicOpsBuilder` and records the descriptor
+/// behind each new `OpId`, so the operations can be rebuilt later.
+pub struct RecordingOpsBuilder<
+    const GRID_DIMENSION: usize,
+    PeriodicOpsType,
+    OpsBuilderType: PeriodicOpsBuilder<GRID_DIMENSION, PeriodicOpsType>,
+> {
+    builder: OpsBuilderType,
+    descriptors: Vec<PeriodicOpDescriptor<GRID_DIMENSION>>,
+    ops_type_marker: std::marker::PhantomData<PeriodicOpsType>,
+}
+
+impl<
+        const GRID_DIMENSION: usize,
+        PeriodicOpsType,
+        OpsBuilderType: PeriodicOpsBuilder<GRID_DIMENSION, PeriodicOpsType>,
+    > RecordingOpsBuilder<GRID_DIMENSION, PeriodicOpsType, OpsBuilderType>
+{
+    pub fn new(builder: OpsBuilderType) -> Self {
+        RecordingOpsBuilder {
+            builder,
+            descriptors: Vec::new(),
+            ops_type_marker: std::marker::PhantomData,
+        }
+    }
+}
+
+/// Output of `RecordingOpsBuilder`.
+pub struct RecordedOps<const GRID_DIMENSION: usize, PeriodicOpsType> {
+    pub ops: PeriodicOpsType,
+    pub descriptors: Vec<PeriodicOpDescriptor<GRID_DIMENSION>>,
+}
+
+impl<
+        const GRID_DIMENSION: usize,
+        PeriodicOpsType,
+        OpsBuilderType: PeriodicOpsBuilder<GRID_DIMENSION, PeriodicOpsType>,
+    >
+    PeriodicOpsBuilder<
+        GRID_DIMENSION,
+        RecordedOps<GRID_DIMENSION, PeriodicOpsType>,
+    > for RecordingOpsBuilder<GRID_DIMENSION, PeriodicOpsType, OpsBuilderType>
+{
+    fn get_op_id(
+        &mut self,
+        descriptor: PeriodicOpDescriptor<GRID_DIMENSION>,
+    ) -> OpId {
+        let op_id = self.builder.get_op_id(descriptor);
+        if op_id == self.descriptors.len() {
+            self.descriptors.push(descriptor);
+        }
+        debug_assert!(op_id < self.descriptors.len());
+        op_id
+    }
+
+    fn finish(self) -> RecordedOps<GRID_DIMENSION, PeriodicOpsType> {
+        RecordedOps {
+            ops: self.builder.finish(),
+            descriptors: self.descriptors,
+        }
+    }
+}
+
+/// Same as `generate_plan`, but the plan is loaded from `path`
+/// when it was saved for the same `PlanKey`.
+/// Otherwise we plan as usual and save the result to `path`.
+/// Problems with the file are reported, but never fatal.
+pub fn generate_cached_plan<
+    const GRID_DIMENSION: usize,
+    PeriodicOpsType,
+    OpsBuilderType: PeriodicOpsBuilder<GRID_DIMENSION, PeriodicOpsType>,
+    CreateBuilderFn: Fn() -> OpsBuilderType,
+>(
+    path: &Path,
+    stencil_slopes: Bounds<GRID_DIMENSION>,
+    create_builder: CreateBuilderFn,
+    params: &SolverParameters<GRID_DIMENSION>,
+) -> PlannerResult<GRID_DIMENSION, PeriodicOpsType> {
+    profiling::scope!("ap_solver::generate_cached_plan");
+    let key = PlanKey::new(&stencil_slopes, params);
+    if path.exists() {
+        match SavedPlan::load(&path) {
+            Ok(saved) if saved.key == key => {
+                println!("Loading plan: {path:?}");
+                let result = saved.into_planner_result(create_builder);
+                debug_verify(&result.plan, &stencil_slopes, params);
+                return result;
+            }
+            Ok(_) => {
+                eprintln!(
+                    "WARNING: {path:?} was planned with different \
+                     parameters, replanning"
+                );
+            }
+            Err(e) => {
+                eprintln!("WARNING: {e}, replanning");
+            }
+        }
+    }
+
+    let create_recorder = || RecordingOpsBuilder::new(create_builder());
+    let recorded = generate_inhomogeneous_plan(
+        stencil_slopes,
+        &|_| true,
+        create_recorder,
+        params,
+    );
+    let saved = SavedPlan {
+        key,
+        plan: recorded.plan,
+        op_descriptors: recorded.periodic_ops.descriptors,
+        remainder_op_descriptors: recorded.remainder_periodic_ops.descriptors,
+    };
+    println!("Writing plan: {path:?}");
+    if let Err(e) = saved.save(&path) {
+        eprintln!("WARNING: failed to write {path:?}: {e}");
+    }
+
+    PlannerResult {
+        plan: saved.plan,
+        periodic_ops: recorded.periodic_ops.ops,
+        remainder_periodic_ops: recorded.remainder_periodic_ops.ops,
+        stencil_slopes,
+    }
+}
+
+/// Fields of a line, parsed in order.
+struct Fields<'a> {
+    tokens: Vec<&'a str>,
+    position: usize,
+}
+
+impl<'a> Fields<'a> {
+    fn token(&mut self) -> Result<&'a str, String> {
+        let result = self
+            .tokens
+            .get(self.position)
+            .copied()
+            .ok_or_else(|| "missing value".to_string())?;
+        self.position += 1;
+        Ok(result)
+    }
+
+    fn value<V: std::str::FromStr>(&mut self) -> Result<V, String> {
+        let token = self.token()?;
+        token
+            .parse::<V>()
+            .map_err(|_| format!("invalid value '{token}'"))
+    }
+
+    fn option<V: std::str::FromStr>(&mut self) -> Result<Option<V>, String> {
+        if self.tokens.get(self.position) == Some(&"-") {
+            self.position += 1;
+            Ok(None)
+        } else {
+            self.value().map(Some)
+        }
+    }
+
+    fn coord<const D: usize>(&mut self) -> Result<Coord<D>, String> {
+        let mut result = Coord::zeros();
+        for d in 0..D {
+            result[d] = self.value()?;
+        }
+        Ok(result)
+    }
+
+    fn bounds<const D: usize>(&mut self) -> Result<Bounds<D>, String> {
+        let mut result = Bounds::zeros();
+        for d in 0..D {
+            result[(d, 0)] = self.value()?;
+            result[(d, 1)] = self.value()?;
+        }
+        Ok(result)
+    }
+
+    fn aabb<const D: usize>(&mut self) -> Result<AABB<D>, String> {
+        let result = AABB::new(self.bounds()?);
+        if !result.check_validity() {
+            return Err(format!("invalid aabb {result}"));
+        }
+        Ok(result)
+    }
+}
+
+fn coord_string<const D: usize>(coord: &Coord<D>) -> String {
+    let values: Vec<String> = coord.iter().map(|c| c.to_string()).collect();
+    values.join(" ")
+}
+
+/// Axis by axis, min then max.
+fn bounds_string<const D: usize>(bounds: &Bounds<D>) -> String {
+    let values: Vec<String> = (0..D)
+        .flat_map(|d| [bounds[(d, 0)], bounds[(d, 1)]])
+        .map(|b| b.to_string())
+        .collect();
+    values.join(" ")
+}
+
+fn option_string(value: Option<usize>) -> String {
+    value.map_or_else(|| "-".to_string(), |v| v.to_string())
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+    use nalgebra::matrix;
+
+    /// Records descriptors without building anything.
+    struct DescriptorBuilder {
+        descriptors: Descriptors,
+    }
+
+    type Descriptors = Vec<PeriodicOpDescriptor<2>>;
+
+    impl PeriodicOpsBuilder<2, Descriptors> for DescriptorBuilder {
+        fn get_op_id(&mut self, descriptor: PeriodicOpDescriptor<2>) -> OpId {
+            match self.descriptors.iter().position(|d| *d == descriptor) {
+                Some(id) => id,
+                None => {
+                    self.descriptors.push(descriptor);
+                    self.descriptors.len() - 1
+                }
+            }
+        }
+
+        fn finish(self) -> Descriptors {
+            self.descriptors
+        }
+    }
+
+    fn saved_plan() -> SavedPlan<2> {
+        let params = SolverParameters {
+            aabb: AABB::new(matrix![0, 99; 0, 79]),
+            steps: 45,
+            cutoff: 10,
+            ratio: 0.3,
+            threads: 4,
+            task_mult: 1.5,
+            axis_boundaries: [AxisBoundary::Aperiodic, AxisBoundary::Periodic],
+            ..Default::default()
+        };
+        let slopes = matrix![1, 1; 1, 1];
+        let create_recorder = || {
+            RecordingOpsBuilder::new(DescriptorBuilder {
+                descriptors: Vec::new(),
+            })
+        };
+        let recorded = generate_plan(slopes, create_recorder, &params);
+        assert_eq!(
+            recorded.periodic_ops.ops,
+            recorded.periodic_ops.descriptors
+        );
+        SavedPlan {
+            key: PlanKey::new(&slopes, &params),
+            plan: recorded.plan,
+            op_descriptors: recorded.periodic_ops.descriptors,
+            remainder_op_descriptors: recorded
+                .remainder_periodic_ops
+                .descriptors,
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let saved = saved_plan();
+        assert!(!saved.op_descriptors.is_empty());
+        assert!(!saved.remainder_op_descriptors.is_empty());
+        let mut source = Vec::new();
+        saved.write(&mut source).unwrap();
+        let source = String::from_utf8(source).unwrap();
+        let parsed = SavedPlan::<2>::parse(&source).unwrap();
+        assert_eq!(parsed, saved);
+
+        let result = parsed.into_planner_result(|| DescriptorBuilder {
+            descriptors: Vec::new(),
+        });
+        assert_eq!(result.periodic_ops, saved.op_descriptors);
+        assert_eq!(
+            result.remainder_periodic_ops,
+            saved.remainder_op_descriptors
+        );
+        assert_eq!(result.plan, saved.plan);
+    }
+
+    #[test]
+    fn errors() {
+        let mut source = Vec::new();
+        saved_plan().write(&mut source).unwrap();
+        let source = String::from_utf8(source).unwrap();
+
+        let err = SavedPlan::<2>::parse(
+            &source.replace("nhls-plan 1", "nhls-plan 0"),
+        )
+        .unwrap_err();
+        assert_eq!(err.line, 1);
+
+        let err = SavedPlan::<3>::parse(&source).unwrap_err();
+        assert_eq!(err.line, 2);
+
+        let err = SavedPlan::<2>::parse(&source.replace("steps 45", "steps"))
+            .unwrap_err();
+        assert_eq!(err.line, 5);
+
+        let err =
+            SavedPlan::<2>::parse(&source.replace("cutoff 10", "cutoff 10 2"))
+                .unwrap_err();
+        assert_eq!(err.line, 6);
+
+        let err = SavedPlan::<2>::parse(&source.replace("root", "# root"))
+            .unwrap_err();
+        assert_eq!(err.line, 0);
+
+        let lines: Vec<&str> = source.lines().collect();
+        let truncated = lines[..lines.len() - 3].join("\n") + "\nroot 0";
+        assert!(SavedPlan::<2>::parse(&truncated).is_err());
+
+        assert!(SavedPlan::<2>::parse("dimension 2").is_err());
+    }
+}
@@ -0,0 +1,271 @@
+use crate::ap_solver::account_builder::*;
+use crate::ap_solver::generate_plan::*;
+use crate::ap_solver::index_types::*;
+use crate::ap_solver::periodic_ops::*;
+use crate::ap_solver::plan::*;
+use crate::ap_solver::scratch_builder::ComplexBufferType;
+use crate::ap_solver::solver_parameters::*;
+use crate::ap_solver::MIN_ALIGNMENT;
+use crate::mem_fmt::human_readable_bytes;
+use crate::util::*;
+
+/// Static estimates for one `apply` of a `Plan`,
+/// computed without creating any FFTW plans or convolutions.
+/// Counts include every repetition of the central solve.
+///
+/// FLOPs use the usual FFT estimate of `5 m log2(n)` per transform,
+/// for `n` values and `m` complex frequencies,
+/// and two FLOPs per neighbor for each value a direct solve updates.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlanReport {
+    pub plan_size: usize,
+
+    /// Number of periodic solves applied
+    pub periodic_solves: usize,
+
+    /// Number of direct solves applied
+    pub direct_solves: usize,
+
+    /// FFTs, forward and backward, and convolution products
+    pub periodic_flops: f64,
+
+    pub direct_flops: f64,
+
+    /// Total number of values transformed by forward FFTs
+    pub fft_volume: usize,
+
+    /// Approximate number of values updated by direct solves,
+    /// see `DirectSolveNode::cell_steps`
+    pub direct_cell_steps: usize,
+
+    /// Scratch space `Solver` allocates, see `AccountBuilder`
+    pub peak_scratch_bytes: usize,
+
+    /// FLOPs along the longest chain of dependent solves,
+    /// assuming concurrent boundary solves run in parallel.
+    pub critical_path_flops: f64,
+}
+
+impl PlanReport {
+    /// Report for `plan` over domains that store `T`.
+    pub fn new<const GRID_DIMENSION: usize, T: Scalar>(
+        plan: &Plan<GRID_DIMENSION>,
+        neighborhood_size: usize,
+        complex_buffer_type: ComplexBufferType,
+    ) -> Self {
+        let node_requirements =
+            AccountBuilder::node_requirements::<T>(plan, complex_buffer_type);
+        let mut result = PlanReport {
+            plan_size: plan.len(),
+            periodic_solves: 0,
+            direct_solves: 0,
+            periodic_flops: 0.0,
+            direct_flops: 0.0,
+            fft_volume: 0,
+            direct_cell_steps: 0,
+            peak_scratch_bytes: node_requirements[plan.root] * MIN_ALIGNMENT,
+            critical_path_flops: 0.0,
+        };
+        let mut counter = PlanCounter {
+            plan,
+            neighborhood_size,
+            frequency_buffer_size: T::frequency_buffer_size,
+            report: &mut result,
+        };
+        let critical_path_flops = counter.count(plan.root, 1);
+        result.critical_path_flops = critical_path_flops;
+        result
+    }
+
+    pub fn total_flops(&self) -> f64 {
+        self.periodic_flops + self.direct_flops
+    }
+
+    pub fn print_report(&self) {
+        println!("Plan Report:");
+        println!("  - plan size: {}", self.plan_size);
+        println!(
+            "  - periodic solves: {} ({})",
+            self.periodic_solves,
+            human_readable_flops(self.periodic_flops)
+        );
+        println!(
+            "  - direct solves: {} ({})",
+            self.direct_solves,
+            human_readable_flops(self.direct_flops)
+        );
+        println!("  - FFT volume: {:.3e} values", self.fft_volume as f64);
+        println!(
+            "  - direct cell-steps: {:.3e}",
+            self.direct_cell_steps as f64
+        );
+        println!(
+            "  - peak scratch size: {}",
+            human_readable_bytes(self.peak_scratch_bytes)
+        );
+        println!(
+            "  - critical path: {} (parallelism {:.1})",
+            human_readable_flops(self.critical_path_flops),
+            self.total_flops() / self.critical_path_flops.max(1.0)
+        );
+    }
+}
+
+/// Plan the solve for `params` and report on it,
+/// the plan cache is neither read nor written.
+pub fn generate_plan_report<const GRID_DIMENSION: usize, T: Scalar>(
+    stencil_slopes: Bounds<GRID_DIMENSION>,
+    neighborhood_size: usize,
+    params: &SolverParameters<GRID_DIMENSION>,
+    complex_buffer_type: ComplexBufferType,
+) -> PlanReport {
+    let params = SolverParameters {
+        plan_cache: None,
+        ..params.clone()
+    };
+    let planner_result =
+        generate_plan(stencil_slopes, OpIdCollector::new, &params);
+    PlanReport::new::<GRID_DIMENSION, T>(
+        &planner_result.plan,
+        neighborhood_size,
+        complex_buffer_type,
+    )
+}
+
+struct PlanCounter<'a, const GRID_DIMENSION: usize> {
+    plan: &'a Plan<GRID_DIMENSION>,
+    neighborhood_size: usize,
+    frequency_buffer_size: fn(&AABB<GRID_DIMENSION>) -> usize,
+    report: &'a mut PlanReport,
+}
+
+impl<const GRID_DIMENSION: usize> PlanCounter<'_, GRID_DIMENSION> {
+    /// Adds the node, applied `times` times, to the report.
+    /// Returns the node's critical path.
+    fn count(&mut self, node_id: NodeId, times: usize) -> f64 {
+        match self.plan.get_node(node_id) {
+            PlanNode::PeriodicSolve(p) => {
+                let n = p.input_aabb.buffer_size();
+                let m = (self.frequency_buffer_size)(&p.input_aabb) as f64;
+                let flops = 2.0 * 5.0 * m * (n as f64).log2() + 6.0 * m;
+                self.report.periodic_solves += times;
+                self.report.periodic_flops += times as f64 * flops;
+                self.report.fft_volume += times * n;
+
+                let mut boundary: f64 = 0.0;
+                for id in p.boundary_nodes.clone() {
+                    boundary = boundary.max(self.count(id, times));
+                }
+                let time_cut =
+                    p.time_cut.map_or(0.0, |id| self.count(id, times));
+                flops + boundary + time_cut
+            }
+            PlanNode::DirectSolve(d) => {
+                let cell_steps = d.cell_steps();
+                let flops =
+                    2.0 * self.neighborhood_size as f64 * cell_steps as f64;
+                self.report.direct_solves += times;
+                self.report.direct_flops += times as f64 * flops;
+                self.report.direct_cell_steps += times * cell_steps;
+                flops
+            }
+            PlanNode::Repeat(r) => {
+                let central = self.count(r.node, times * r.n);
+                let next = r.next.map_or(0.0, |id| self.count(id, times));
+                r.n as f64 * central + next
+            }
+            PlanNode::Range(r) => {
+                let mut longest: f64 = 0.0;
+                for id in r.range.clone() {
+                    longest = longest.max(self.count(id, times));
+                }
+                longest
+            }
+        }
+    }
+}
+
+fn human_readable_flops(flops: f64) -> String {
+    let units = ["FLOP", "kFLOP", "MFLOP", "GFLOP", "TFLOP", "PFLOP"];
+    let mut value = flops;
+    let mut unit = 0;
+    while value >= 1000.0 && unit + 1 < units.len() {
+        value /= 1000.0;
+        unit += 1;
+    }
+    format!("{value:.2} {}", units[unit])
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+    use nalgebra::matrix;
+
+    #[test]
+    fn direct_only() {
+        // Cutoff too large for any periodic solve
+        let params = SolverParameters {
+            aabb: AABB::new(matrix![0, 99; 0, 49]),
+            steps: 10,
+            cutoff: 1000,
+            ..Default::default()
+        };
+        let report = generate_plan_report::<2, f64>(
+            matrix![1, 1; 1, 1],
+            5,
+            &params,
+            ComplexBufferType::DomainOnly,
+        );
+        assert_eq!(report.periodic_solves, 0);
+        assert_eq!(report.direct_solves, 1);
+        assert_eq!(report.fft_volume, 0);
+        assert_eq!(report.direct_cell_steps, 5000 * 10);
+        assert_eq!(report.direct_flops, 2.0 * 5.0 * 50000.0);
+        assert_eq!(report.critical_path_flops, report.total_flops());
+    }
+
+    #[test]
+    fn repeats() {
+        let params = SolverParameters {
+            aabb: AABB::new(matrix![0, 199; 0, 199]),
+            steps: 250,
+            cutoff: 20,
+            ratio: 0.5,
+            ..Default::default()
+        };
+        let slopes = matrix![1, 1; 1, 1];
+        let planner_result = generate_plan(slopes, OpIdCollector::new, &params);
+        let plan = &planner_result.plan;
+        let repeat = plan.unwrap_repeat_node(plan.root);
+        assert!(repeat.n > 1);
+
+        let report = generate_plan_report::<2, f64>(
+            slopes,
+            5,
+            &params,
+            ComplexBufferType::DomainOnly,
+        );
+        assert_eq!(report.plan_size, plan.len());
+        assert!(report.periodic_solves >= repeat.n);
+        assert!(report.fft_volume >= repeat.n * 200 * 200);
+        assert!(report.critical_path_flops <= report.total_flops());
+        assert!(report.critical_path_flops > 0.0);
+
+        // Complex domains need twice the frequencies, and larger scratch
+        let complex_report = generate_plan_report::<2, c64>(
+            slopes,
+            5,
+            &params,
+            ComplexBufferType::DomainOnly,
+        );
+        assert!(complex_report.periodic_flops > report.periodic_flops);
+        assert_eq!(complex_report.direct_flops, report.direct_flops);
+        assert!(complex_report.peak_scratch_bytes > report.peak_scratch_bytes);
+    }
+
+    #[test]
+    fn flops_format() {
+        assert_eq!(human_readable_flops(12.0), "12.00 FLOP");
+        assert_eq!(human_readable_flops(2.5e9), "2.50 GFLOP");
+    }
+}
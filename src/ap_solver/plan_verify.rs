@@ -0,0 +1,494 @@
+//! Consistency checks for plans.
+//!
+//! Mistakes in `Frustrum::decompose` or `Frustrum::time_cut`
+//! don't fail at solve time, they just produce wrong values.
+//! `Plan::verify` checks the invariants `Solver` relies on instead:
+//!   - Every node is reachable from the root exactly once
+//!   - Input AABBs are inside the domain
+//!   - Boundary solves exactly tile the part of a periodic solve's
+//!     output that the convolution doesn't cover
+//!   - Direct solves slope by the stencil slopes,
+//!     except on sides that face the boundary condition
+//!   - Steps add up along time cut chains, and over the root
+
+use crate::ap_solver::frustrum::*;
+use crate::ap_solver::index_types::*;
+use crate::ap_solver::plan::*;
+use crate::ap_solver::solver_parameters::*;
+use crate::util::*;
+
+/// The first problem `Plan::verify` found.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlanVerifyError {
+    pub node: NodeId,
+    pub message: String,
+}
+
+impl std::fmt::Display for PlanVerifyError {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> Result<(), std::fmt::Error> {
+        write!(f, "Invalid plan, node {}: {}", self.node, self.message)
+    }
+}
+
+impl std::error::Error for PlanVerifyError {}
+
+impl<const GRID_DIMENSION: usize> Plan<GRID_DIMENSION> {
+    /// Check that the plan solves `params.aabb` for `params.steps`,
+    /// for a stencil with `stencil_slopes`.
+    pub fn verify(
+        &self,
+        stencil_slopes: &Bounds<GRID_DIMENSION>,
+        params: &SolverParameters<GRID_DIMENSION>,
+    ) -> Result<(), PlanVerifyError> {
+        let mut verifier = PlanVerifier {
+            plan: self,
+            stencil_slopes: params.planning_slopes(stencil_slopes),
+            aabb: params.aabb,
+            visited: vec![false; self.len()],
+        };
+        verifier.verify_root(params.steps)?;
+        match verifier.visited.iter().position(|v| !v) {
+            Some(node) => Err(PlanVerifyError {
+                node,
+                message: "not reachable from the root".to_string(),
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Panics if `plan` fails `Plan::verify`, in debug builds only.
+pub fn debug_verify<const GRID_DIMENSION: usize>(
+    plan: &Plan<GRID_DIMENSION>,
+    stencil_slopes: &Bounds<GRID_DIMENSION>,
+    params: &SolverParameters<GRID_DIMENSION>,
+) {
+    if cfg!(debug_assertions) {
+        if let Err(e) = plan.verify(stencil_slopes, params) {
+            panic!("ERROR: {e}");
+        }
+    }
+}
+
+struct PlanVerifier<'a, const GRID_DIMENSION: usize> {
+    plan: &'a Plan<GRID_DIMENSION>,
+
+    /// Planning slopes, zero along periodic axes
+    stencil_slopes: Bounds<GRID_DIMENSION>,
+    aabb: AABB<GRID_DIMENSION>,
+    visited: Vec<bool>,
+}
+
+/// Early return with a `PlanVerifyError` unless `$condition` holds.
+macro_rules! check {
+    ($node:expr, $condition:expr, $($message:tt)+) => {
+        if !$condition {
+            return Err(PlanVerifyError {
+                node: $node,
+                message: format!($($message)+),
+            });
+        }
+    };
+}
+
+impl<'a, const GRID_DIMENSION: usize> PlanVerifier<'a, GRID_DIMENSION> {
+    /// Marks `node_id` as visited, it must exist and be new.
+    fn visit(
+        &mut self,
+        parent: NodeId,
+        node_id: NodeId,
+    ) -> Result<&'a PlanNode<GRID_DIMENSION>, PlanVerifyError> {
+        check!(
+            parent,
+            node_id < self.plan.len(),
+            "child {node_id} is out of range"
+        );
+        check!(node_id, !self.visited[node_id], "reached more than once");
+        self.visited[node_id] = true;
+        Ok(self.plan.get_node(node_id))
+    }
+
+    fn verify_root(&mut self, steps: usize) -> Result<(), PlanVerifyError> {
+        let root = self.plan.root;
+        check!(root, root < self.plan.len(), "root is out of range");
+        let PlanNode::Repeat(repeat) = self.visit(root, root)? else {
+            return Err(PlanVerifyError {
+                node: root,
+                message: "root is not a repeat node".to_string(),
+            });
+        };
+        let mut total = repeat.n * self.verify_central(root, repeat.node)?;
+        if let Some(next) = repeat.next {
+            total += self.verify_central(root, next)?;
+        }
+        check!(root, total == steps, "solves {total} steps, not {steps}");
+        Ok(())
+    }
+
+    /// Central solves cover the whole domain, returns their steps.
+    fn verify_central(
+        &mut self,
+        parent: NodeId,
+        node_id: NodeId,
+    ) -> Result<usize, PlanVerifyError> {
+        match self.visit(parent, node_id)? {
+            PlanNode::PeriodicSolve(p) => {
+                check!(
+                    node_id,
+                    p.input_aabb == self.aabb,
+                    "central input {} is not the domain",
+                    p.input_aabb
+                );
+                check!(
+                    node_id,
+                    p.time_cut.is_none(),
+                    "central solves can't have time cuts"
+                );
+
+                // Central output is only the convolution's part
+                let valid = self.convolution_output(p);
+                check!(
+                    node_id,
+                    p.output_aabb.check_validity()
+                        && valid.contains_aabb(&p.output_aabb),
+                    "output {} isn't inside the valid convolution output {}",
+                    p.output_aabb,
+                    valid
+                );
+                let domain = self.aabb;
+                self.verify_boundary(node_id, p, &domain, Some(p.output_aabb))?;
+                Ok(p.steps)
+            }
+            PlanNode::DirectSolve(d) => {
+                check!(
+                    node_id,
+                    d.input_aabb == self.aabb && d.output_aabb == self.aabb,
+                    "central direct solve doesn't cover the domain"
+                );
+                check!(
+                    node_id,
+                    d.sloped_sides == Bounds::<GRID_DIMENSION>::zero(),
+                    "central direct solve has sloped sides"
+                );
+                Ok(d.steps)
+            }
+            _ => Err(PlanVerifyError {
+                node: node_id,
+                message: "central solves must be periodic or direct"
+                    .to_string(),
+            }),
+        }
+    }
+
+    /// Boundary and time cut solves.
+    /// Returns the steps taken along the time cut chain
+    /// and the output of its last node.
+    fn verify_frustrum(
+        &mut self,
+        parent: NodeId,
+        node_id: NodeId,
+    ) -> Result<(usize, AABB<GRID_DIMENSION>), PlanVerifyError> {
+        match self.visit(parent, node_id)? {
+            PlanNode::PeriodicSolve(p) => {
+                self.verify_input(node_id, &p.input_aabb, &p.output_aabb)?;
+                let periodic_output =
+                    intersection(&self.convolution_output(p), &p.output_aabb);
+                self.verify_boundary(
+                    node_id,
+                    p,
+                    &p.output_aabb,
+                    periodic_output,
+                )?;
+                let Some(time_cut) = p.time_cut else {
+                    return Ok((p.steps, p.output_aabb));
+                };
+                let (steps, output_aabb) =
+                    self.verify_frustrum(node_id, time_cut)?;
+                let next_input = match self.plan.get_node(time_cut) {
+                    PlanNode::PeriodicSolve(next) => next.input_aabb,
+                    PlanNode::DirectSolve(next) => next.input_aabb,
+                    _ => p.output_aabb,
+                };
+                check!(
+                    node_id,
+                    p.output_aabb.contains_aabb(&next_input),
+                    "time cut input {next_input} isn't inside output {}",
+                    p.output_aabb
+                );
+                Ok((p.steps + steps, output_aabb))
+            }
+            PlanNode::DirectSolve(d) => {
+                self.verify_input(node_id, &d.input_aabb, &d.output_aabb)?;
+                for dim in 0..GRID_DIMENSION {
+                    let sloped_axis = is_sloped_axis(&self.stencil_slopes, dim);
+                    for side in 0..2 {
+                        let sloped = d.sloped_sides[(dim, side)];
+                        check!(
+                            node_id,
+                            sloped == 0 || (sloped == 1 && sloped_axis),
+                            "unexpected sloped side {sloped} on axis {dim}"
+                        );
+                        check!(
+                            node_id,
+                            sloped == 1
+                                || d.output_aabb.bounds[(dim, side)]
+                                    == self.aabb.bounds[(dim, side)],
+                            "unsloped side on axis {dim} doesn't face \
+                             the boundary"
+                        );
+                    }
+                }
+                let expected_input = frustrum_input_aabb(
+                    d.steps,
+                    &d.output_aabb,
+                    &d.sloped_sides,
+                    &self.stencil_slopes,
+                );
+                check!(
+                    node_id,
+                    d.input_aabb == expected_input,
+                    "input {} doesn't match the stencil slopes, \
+                     expected {expected_input}",
+                    d.input_aabb
+                );
+                Ok((d.steps, d.output_aabb))
+            }
+            _ => Err(PlanVerifyError {
+                node: node_id,
+                message: "only the root can be a repeat or range node"
+                    .to_string(),
+            }),
+        }
+    }
+
+    /// Inputs are inside the domain and contain their output.
+    fn verify_input(
+        &self,
+        node_id: NodeId,
+        input_aabb: &AABB<GRID_DIMENSION>,
+        output_aabb: &AABB<GRID_DIMENSION>,
+    ) -> Result<(), PlanVerifyError> {
+        check!(
+            node_id,
+            output_aabb.check_validity(),
+            "output {output_aabb} is empty"
+        );
+        check!(
+            node_id,
+            self.aabb.contains_aabb(input_aabb),
+            "input {input_aabb} isn't inside the domain {}",
+            self.aabb
+        );
+        check!(
+            node_id,
+            input_aabb.contains_aabb(output_aabb),
+            "output {output_aabb} isn't inside input {input_aabb}"
+        );
+        Ok(())
+    }
+
+    /// Boundary solves take the same steps as the periodic solve,
+    /// and together with `periodic_output` exactly tile `region`.
+    fn verify_boundary(
+        &mut self,
+        node_id: NodeId,
+        periodic_solve: &'a PeriodicSolveNode<GRID_DIMENSION>,
+        region: &AABB<GRID_DIMENSION>,
+        periodic_output: Option<AABB<GRID_DIMENSION>>,
+    ) -> Result<(), PlanVerifyError> {
+        let mut covered: Vec<AABB<GRID_DIMENSION>> =
+            periodic_output.into_iter().collect();
+        for child in periodic_solve.boundary_nodes.clone() {
+            let (steps, output_aabb) = self.verify_frustrum(node_id, child)?;
+            check!(
+                child,
+                steps == periodic_solve.steps,
+                "takes {steps} steps, the periodic solve takes {}",
+                periodic_solve.steps
+            );
+            check!(
+                child,
+                region.contains_aabb(&output_aabb),
+                "output {output_aabb} isn't inside {region}"
+            );
+            if let Some(other) =
+                covered.iter().find(|other| other.intersects(&output_aabb))
+            {
+                return Err(PlanVerifyError {
+                    node: child,
+                    message: format!("output {output_aabb} overlaps {other}"),
+                });
+            }
+            covered.push(output_aabb);
+        }
+        let covered_size: usize = covered.iter().map(|c| c.buffer_size()).sum();
+        check!(
+            node_id,
+            covered_size == region.buffer_size(),
+            "{} values of {region} are never solved",
+            region.buffer_size() - covered_size
+        );
+        Ok(())
+    }
+
+    /// Where the convolution result is valid,
+    /// values closer to the input faces wrap around.
+    fn convolution_output(
+        &self,
+        periodic_solve: &PeriodicSolveNode<GRID_DIMENSION>,
+    ) -> AABB<GRID_DIMENSION> {
+        let steps = periodic_solve.steps as i32;
+        periodic_solve.input_aabb.add_bounds_diff(
+            steps * slopes_to_inward_diff(&self.stencil_slopes),
+        )
+    }
+}
+
+/// Coordinates in both, if any.
+fn intersection<const GRID_DIMENSION: usize>(
+    a: &AABB<GRID_DIMENSION>,
+    b: &AABB<GRID_DIMENSION>,
+) -> Option<AABB<GRID_DIMENSION>> {
+    let mut result = *a;
+    result.trim_to_aabb(b);
+    if result.check_validity() {
+        Some(result)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+    use crate::ap_solver::generate_plan::*;
+    use crate::ap_solver::periodic_ops::*;
+
+    fn test_params() -> SolverParameters<2> {
+        SolverParameters {
+            aabb: AABB::new(matrix![0, 199; 0, 149]),
+            steps: 250,
+            cutoff: 20,
+            ratio: 0.5,
+            ..Default::default()
+        }
+    }
+
+    fn test_plan(slopes: &Bounds<2>) -> Plan<2> {
+        generate_plan(*slopes, OpIdCollector::new, &test_params()).plan
+    }
+
+    /// First direct node with a sloped side
+    fn sloped_direct_node(plan: &Plan<2>) -> NodeId {
+        plan.nodes
+            .iter()
+            .position(|node| match node {
+                PlanNode::DirectSolve(d) => d.sloped_sides.max() == 1,
+                _ => false,
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn valid_plans() {
+        let params = test_params();
+        for slopes in [
+            matrix![1, 1; 1, 1],
+            matrix![2, 2; 1, 1],
+            matrix![1, 2; 0, 1],
+        ] {
+            assert_eq!(test_plan(&slopes).verify(&slopes, &params), Ok(()));
+        }
+    }
+
+    #[test]
+    fn steps() {
+        let slopes = matrix![1, 1; 1, 1];
+        let plan = test_plan(&slopes);
+        let params = SolverParameters {
+            steps: 251,
+            ..test_params()
+        };
+        let error = plan.verify(&slopes, &params).unwrap_err();
+        assert_eq!(error.node, plan.root);
+        assert_eq!(error.message, "solves 250 steps, not 251");
+    }
+
+    #[test]
+    fn coverage() {
+        let slopes = matrix![1, 1; 1, 1];
+        let params = test_params();
+        let mut plan = test_plan(&slopes);
+        let central = plan.unwrap_repeat_node(plan.root).node;
+        let PlanNode::PeriodicSolve(p) = &mut plan.nodes[central] else {
+            panic!("expected a central periodic solve");
+        };
+        p.boundary_nodes.end -= 1;
+        let error = plan.verify(&slopes, &params).unwrap_err();
+        assert_eq!(error.node, central);
+        assert!(error.message.ends_with("are never solved"));
+
+        // Growing a boundary solve into its neighbour
+        let mut plan = test_plan(&slopes);
+        let direct = sloped_direct_node(&plan);
+        let PlanNode::DirectSolve(d) = &mut plan.nodes[direct] else {
+            unreachable!();
+        };
+        let (dim, side) = (0..2)
+            .flat_map(|dim| [(dim, 0), (dim, 1)])
+            .find(|&(dim, side)| d.sloped_sides[(dim, side)] == 1)
+            .unwrap();
+        d.output_aabb.bounds[(dim, side)] += if side == 0 { -1 } else { 1 };
+        d.input_aabb = frustrum_input_aabb(
+            d.steps,
+            &d.output_aabb,
+            &d.sloped_sides,
+            &slopes,
+        );
+        let error = plan.verify(&slopes, &params).unwrap_err();
+        assert!(error.message.contains("overlaps"), "{error}");
+    }
+
+    #[test]
+    fn sloped_sides() {
+        let slopes = matrix![1, 1; 1, 1];
+        let params = test_params();
+        let mut plan = test_plan(&slopes);
+        let direct = sloped_direct_node(&plan);
+        let PlanNode::DirectSolve(d) = &mut plan.nodes[direct] else {
+            unreachable!();
+        };
+        d.input_aabb = d.output_aabb;
+        let error = plan.verify(&slopes, &params).unwrap_err();
+        assert_eq!(error.node, direct);
+        assert!(error.message.contains("stencil slopes"), "{error}");
+
+        // The plan was made for smaller slopes
+        let plan = test_plan(&slopes);
+        let error = plan.verify(&(2 * slopes), &params).unwrap_err();
+        assert!(!error.message.is_empty());
+    }
+
+    #[test]
+    fn structure() {
+        let slopes = matrix![1, 1; 1, 1];
+        let params = test_params();
+        let mut plan = test_plan(&slopes);
+        let central = plan.unwrap_repeat_node(plan.root).node;
+        let PlanNode::Repeat(r) = &mut plan.nodes[plan.root] else {
+            unreachable!();
+        };
+        r.next = Some(central);
+        let error = plan.verify(&slopes, &params).unwrap_err();
+        assert_eq!(error.node, central);
+        assert_eq!(error.message, "reached more than once");
+
+        let mut plan = test_plan(&slopes);
+        plan.root = central;
+        let error = plan.verify(&slopes, &params).unwrap_err();
+        assert_eq!(error.message, "root is not a repeat node");
+    }
+}
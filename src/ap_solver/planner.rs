@@ -7,12 +7,8 @@ use crate::ap_solver::solver_parameters::*;
 use crate::util::*;
 
 /// Creating a plan results in both a plan and convolution store.
-/// Someday we may separate the creation, if for example we
-/// we add support for saving Plans to file.
-pub struct PlannerResult<
-    const GRID_DIMENSION: usize,
-    PeriodicOpsType: PeriodicOps<GRID_DIMENSION>,
-> {
+/// See `plan_file` for saving plans and rebuilding the store.
+pub struct PlannerResult<const GRID_DIMENSION: usize, PeriodicOpsType> {
     pub plan: Plan<GRID_DIMENSION>,
     pub periodic_ops: PeriodicOpsType,
     pub remainder_periodic_ops: PeriodicOpsType,
@@ -23,12 +19,18 @@ pub struct PlannerResult<
 pub struct Planner<
     'a,
     const GRID_DIMENSION: usize,
-    PeriodicOpsType: PeriodicOps<GRID_DIMENSION>,
+    PeriodicOpsType,
     OpsBuilderType: PeriodicOpsBuilder<GRID_DIMENSION, PeriodicOpsType>,
 > {
+    /// Zero along axes that are never cut, see `is_sloped_axis`.
     pub stencil_slopes: Bounds<GRID_DIMENSION>,
     pub nodes: Vec<PlanNode<GRID_DIMENSION>>,
     pub params: &'a SolverParameters<GRID_DIMENSION>,
+
+    /// Periodic solves are only valid where the stencil weights
+    /// don't vary in space, i.e. where this returns true
+    /// for the solve's input AABB.
+    pub is_homogeneous: &'a dyn Fn(&AABB<GRID_DIMENSION>) -> bool,
     pub ops_builder: OpsBuilderType,
     pub ops_type_marker: std::marker::PhantomData<PeriodicOpsType>,
 }
@@ -36,7 +38,7 @@ pub struct Planner<
 impl<
         'a,
         const GRID_DIMENSION: usize,
-        PeriodicOpsType: PeriodicOps<GRID_DIMENSION>,
+        PeriodicOpsType,
         OpsBuilderType: PeriodicOpsBuilder<GRID_DIMENSION, PeriodicOpsType>,
     > Planner<'a, GRID_DIMENSION, PeriodicOpsType, OpsBuilderType>
 {
@@ -54,10 +56,20 @@ impl<
         tasks: usize,
     ) -> PlanNode<GRID_DIMENSION> {
         let input_aabb = frustrum.input_aabb(&self.stencil_slopes);
+
+        // Direct solves don't shrink along axes that aren't cut,
+        // they rely on the boundary condition to wrap instead
+        let mut sloped_sides = frustrum.sloped_sides();
+        for d in 0..GRID_DIMENSION {
+            if !is_sloped_axis(&self.stencil_slopes, d) {
+                sloped_sides[(d, 0)] = 0;
+                sloped_sides[(d, 1)] = 0;
+            }
+        }
         let direct_node = DirectSolveNode {
             input_aabb,
             output_aabb: frustrum.output_aabb,
-            sloped_sides: frustrum.sloped_sides(),
+            sloped_sides,
             steps: frustrum.steps,
             threads: tasks,
         };
@@ -146,8 +158,11 @@ impl<
         debug_assert!(self.params.aabb.contains_aabb(&input_aabb));
 
         // Can we do a periodic solve or do we direct solve?
-        let maybe_periodic_solve =
-            find_periodic_solve(&input_aabb, &solve_params);
+        let maybe_periodic_solve = if (self.is_homogeneous)(&input_aabb) {
+            find_periodic_solve(&input_aabb, &solve_params)
+        } else {
+            None
+        };
 
         if let Some(periodic_solve) = maybe_periodic_solve {
             self.generate_periodic_node(
@@ -161,9 +176,74 @@ impl<
         }
     }
 
+    /// Find the central periodic solve.
+    /// If the stencil isn't homogeneous over the whole domain,
+    /// we search for homogeneous sub-boxes by repeatedly halving
+    /// the inhomogeneous ones, and use the largest periodic solve
+    /// found at the shallowest depth.
+    /// Only sloped axes are halved, boundary solves can't cover
+    /// the rest of an axis that is never cut.
+    fn find_central_solve(
+        &self,
+        solve_params: &PeriodicSolveParams<GRID_DIMENSION>,
+    ) -> Option<PeriodicSolve<GRID_DIMENSION>> {
+        let mut level = vec![self.params.aabb];
+        while !level.is_empty() {
+            let mut best: Option<PeriodicSolve<GRID_DIMENSION>> = None;
+            let mut next_level = Vec::new();
+            for aabb in level {
+                if (self.is_homogeneous)(&aabb) {
+                    if let Some(solve) =
+                        find_periodic_solve(&aabb, solve_params)
+                    {
+                        let is_better = best.as_ref().is_none_or(|b| {
+                            solve.output_aabb.buffer_size()
+                                > b.output_aabb.buffer_size()
+                        });
+                        if is_better {
+                            best = Some(solve);
+                        }
+                    }
+                } else if aabb.min_size_len() / 2 > solve_params.cutoff
+                    && (0..GRID_DIMENSION)
+                        .any(|d| is_sloped_axis(&self.stencil_slopes, d))
+                {
+                    next_level
+                        .extend(split_halves(&aabb, &self.stencil_slopes));
+                }
+            }
+            if best.is_some() {
+                return best;
+            }
+            level = next_level;
+        }
+        None
+    }
+
+    /// If no central periodic solve exists,
+    /// we directly solve the whole domain.
+    fn generate_central_direct(
+        &mut self,
+        max_steps: usize,
+        threads: usize,
+    ) -> (NodeId, usize) {
+        let direct_node = DirectSolveNode {
+            input_aabb: self.params.aabb,
+            output_aabb: self.params.aabb,
+            sloped_sides: Bounds::zero(),
+            steps: max_steps,
+            threads,
+        };
+        let root_node = self.add_node(PlanNode::DirectSolve(direct_node));
+        (root_node, max_steps)
+    }
+
     /// The root AABB requires special treatment.
     /// This function creates a plan for the larges periodic solve
     /// it can find within the box and max_steps.
+    /// The convolution always covers the whole domain,
+    /// but for inhomogeneous stencils we only keep the output
+    /// of a homogeneous sub-box.
     ///
     /// Note also that the boundary solve decomposition
     /// is based on `AABB` and not `Frustrum`.
@@ -180,8 +260,10 @@ impl<
             max_steps: Some(max_steps),
         };
 
-        let periodic_solve =
-            find_periodic_solve(&self.params.aabb, &solve_params).unwrap();
+        let Some(periodic_solve) = self.find_central_solve(&solve_params)
+        else {
+            return self.generate_central_direct(max_steps, threads);
+        };
 
         let op_descriptor = PeriodicOpDescriptor {
             step_min: 0,
@@ -195,20 +277,27 @@ impl<
         let decomposition =
             self.params.aabb.decomposition(&periodic_solve.output_aabb);
         let mut sub_nodes = Vec::with_capacity(2 * GRID_DIMENSION);
+        let cut_axes = (0..GRID_DIMENSION)
+            .filter(|d| is_sloped_axis(&self.stencil_slopes, *d))
+            .count()
+            .max(1);
         let sub_tasks = self.params.task_min.max(
-            (self.params.task_mult * threads as f64 / GRID_DIMENSION as f64)
-                .ceil() as usize,
+            (self.params.task_mult * threads as f64 / cut_axes as f64).ceil()
+                as usize,
         );
 
         for d in 0..GRID_DIMENSION {
+            if !is_sloped_axis(&self.stencil_slopes, d) {
+                continue;
+            }
             for side in [Side::Min, Side::Max] {
+                // Empty when the stencil doesn't slope towards this side
+                let output_aabb = decomposition[d][side.outer_index()];
+                if !output_aabb.check_validity() {
+                    continue;
+                }
                 sub_nodes.push(self.generate_frustrum(
-                    Frustrum::new(
-                        decomposition[d][side.outer_index()],
-                        d,
-                        side,
-                        periodic_solve.steps,
-                    ),
+                    Frustrum::new(output_aabb, d, side, periodic_solve.steps),
                     rel_time_0,
                     sub_tasks,
                 ));
@@ -236,3 +325,51 @@ impl<
         (root_node, periodic_solve.steps)
     }
 }
+
+/// Split an AABB in half along every sloped axis.
+fn split_halves<const GRID_DIMENSION: usize>(
+    aabb: &AABB<GRID_DIMENSION>,
+    stencil_slopes: &Bounds<GRID_DIMENSION>,
+) -> Vec<AABB<GRID_DIMENSION>> {
+    let axes: Vec<usize> = (0..GRID_DIMENSION)
+        .filter(|d| is_sloped_axis(stencil_slopes, *d))
+        .collect();
+    let mut result = Vec::with_capacity(1 << axes.len());
+    for mask in 0..(1 << axes.len()) {
+        let mut child = *aabb;
+        for (i, d) in axes.iter().copied().enumerate() {
+            let mid = (aabb.bounds[(d, 0)] + aabb.bounds[(d, 1)]) / 2;
+            if mask & (1 << i) == 0 {
+                child.bounds[(d, 1)] = mid;
+            } else {
+                child.bounds[(d, 0)] = mid + 1;
+            }
+        }
+        result.push(child);
+    }
+    result
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn split_halves_test() {
+        let aabb = AABB::new(matrix![0, 9; 0, 4]);
+        let halves = split_halves(&aabb, &Bounds::from_element(1));
+        assert_eq!(halves.len(), 4);
+        assert_eq!(halves[0], AABB::new(matrix![0, 4; 0, 2]));
+        assert_eq!(halves[1], AABB::new(matrix![5, 9; 0, 2]));
+        assert_eq!(halves[2], AABB::new(matrix![0, 4; 3, 4]));
+        assert_eq!(halves[3], AABB::new(matrix![5, 9; 3, 4]));
+        let total: usize = halves.iter().map(|h| h.buffer_size()).sum();
+        assert_eq!(total, aabb.buffer_size());
+
+        // Axes that aren't sloped are never split
+        let halves = split_halves(&aabb, &matrix![0, 0; 1, 1]);
+        assert_eq!(halves.len(), 2);
+        assert_eq!(halves[0], AABB::new(matrix![0, 9; 0, 2]));
+        assert_eq!(halves[1], AABB::new(matrix![0, 9; 3, 4]));
+    }
+}
@@ -1,7 +1,15 @@
 use crate::ap_solver::MIN_ALIGNMENT;
+use crate::domain::{print_residency, resident_bytes};
+use crate::mem_fmt::human_readable_bytes;
 
+use std::fs::OpenOptions;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use sync_ptr::SyncConstPtr;
 
+/// Used to name the files of `create_in`.
+static SCRATCH_FILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
 pub type AllocationType = f64;
 
 /// Note all nodes will need all of these
@@ -27,10 +35,17 @@ pub struct ScratchDescriptor {
 pub struct Scratch {
     scratch_ptr: SyncConstPtr<u8>,
     pub size: usize,
+
+    /// Set when backed by a file, see `create_in`
+    map: Option<memmap2::MmapMut>,
 }
 
 impl Drop for Scratch {
     fn drop(&mut self) {
+        // Mappings are released on their own
+        if self.map.is_some() {
+            return;
+        }
         let alloc_layout =
             std::alloc::Layout::from_size_align(self.size, MIN_ALIGNMENT)
                 .unwrap();
@@ -57,7 +72,68 @@ impl Scratch {
             scratch_ptr.inner() as usize,
             scratch_ptr.inner() as usize % MIN_ALIGNMENT
         );
-        Scratch { scratch_ptr, size }
+        Scratch {
+            scratch_ptr,
+            size,
+            map: None,
+        }
+    }
+
+    /// Same as `new`, but backed by a memory-mapped file in `dir`,
+    /// like `MappedDomain::create_in`.
+    /// The file is removed right away,
+    /// the space is freed once the scratch is dropped.
+    pub fn create_in<P: AsRef<Path>>(
+        dir: P,
+        size: usize,
+    ) -> std::io::Result<Self> {
+        let id = SCRATCH_FILE_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let path = dir
+            .as_ref()
+            .join(format!("nhls_{}_{id}.scratch", std::process::id()));
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&path)?;
+        file.set_len(size as u64)?;
+        // Maps are page aligned, which satisfies MIN_ALIGNMENT
+        let mut map = unsafe { memmap2::MmapMut::map_mut(&file)? };
+        std::fs::remove_file(&path)?;
+        let scratch_ptr = SyncConstPtr::new(map.as_mut_ptr() as *const u8);
+        debug_assert!(
+            (scratch_ptr.inner() as usize).is_multiple_of(MIN_ALIGNMENT)
+        );
+        Ok(Scratch {
+            scratch_ptr,
+            size,
+            map: Some(map),
+        })
+    }
+
+    /// Whether this is backed by a file, see `create_in`.
+    pub fn is_mapped(&self) -> bool {
+        self.map.is_some()
+    }
+
+    /// Bytes of a file-backed scratch currently held in RAM,
+    /// `None` for heap allocated ones.
+    pub fn resident_bytes(&self) -> Option<usize> {
+        self.map.as_ref().map(resident_bytes)
+    }
+
+    pub fn print_report(&self) {
+        match self.map.as_ref() {
+            Some(map) => {
+                println!("Scratch Report (file-backed):");
+                print_residency(map.len(), resident_bytes(map));
+            }
+            None => {
+                println!("Scratch Report (heap):");
+                println!("  - size: {}", human_readable_bytes(self.size));
+            }
+        }
     }
 
     /// Only use this function with the values provided by ScratchBuilder
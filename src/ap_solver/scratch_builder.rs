@@ -25,6 +25,15 @@ pub struct ScratchBuilder<'a, const GRID_DIMENSION: usize> {
     plan: &'a Plan<GRID_DIMENSION>,
     node_block_requirements: Vec<usize>,
     complex_buffer_type: ComplexBufferType,
+
+    /// Bytes per real value
+    real_size: usize,
+
+    /// Bytes per complex value
+    complex_size: usize,
+
+    /// Complex values per frequency domain buffer, see `Scalar`
+    frequency_buffer_size: fn(&AABB<GRID_DIMENSION>) -> usize,
 }
 
 impl<'a, const GRID_DIMENSION: usize> ScratchBuilder<'a, GRID_DIMENSION> {
@@ -33,9 +42,20 @@ impl<'a, const GRID_DIMENSION: usize> ScratchBuilder<'a, GRID_DIMENSION> {
     pub fn build(
         plan: &'a Plan<GRID_DIMENSION>,
         complex_buffer_type: ComplexBufferType,
+    ) -> (Vec<ScratchDescriptor>, Scratch) {
+        Self::build_scalar::<f64>(plan, complex_buffer_type, None)
+    }
+
+    /// Same as `build`, for domains that store `T`.
+    /// The scratch is backed by a file in `scratch_dir` if given,
+    /// see `Scratch::create_in`.
+    pub fn build_scalar<T: Scalar>(
+        plan: &'a Plan<GRID_DIMENSION>,
+        complex_buffer_type: ComplexBufferType,
+        scratch_dir: Option<&std::path::Path>,
     ) -> (Vec<ScratchDescriptor>, Scratch) {
         let node_block_requirements =
-            AccountBuilder::node_requirements(plan, complex_buffer_type);
+            AccountBuilder::node_requirements::<T>(plan, complex_buffer_type);
         let mut scratch_descriptors =
             vec![ScratchDescriptor::default(); plan.len()];
 
@@ -43,20 +63,36 @@ impl<'a, const GRID_DIMENSION: usize> ScratchBuilder<'a, GRID_DIMENSION> {
             plan,
             node_block_requirements,
             complex_buffer_type,
+            real_size: std::mem::size_of::<T>(),
+            complex_size: std::mem::size_of::<T::Complex>(),
+            frequency_buffer_size: T::frequency_buffer_size,
         };
         builder.handle_repeat(plan.root, 0, &mut scratch_descriptors);
-        let scratch_space = Scratch::new(
-            builder.blocks_to_bytes(builder.node_block_requirements[plan.root]),
-        );
+        let size =
+            builder.blocks_to_bytes(builder.node_block_requirements[plan.root]);
+        let scratch_space = Self::allocate(size, scratch_dir);
         (scratch_descriptors, scratch_space)
     }
 
+    fn allocate(size: usize, scratch_dir: Option<&std::path::Path>) -> Scratch {
+        match scratch_dir {
+            Some(dir) => Scratch::create_in(dir, size).unwrap_or_else(|e| {
+                panic!("ERROR: Failed to map scratch in {dir:?}: {e}")
+            }),
+            None => Scratch::new(size),
+        }
+    }
+
+    /// Two scratch spaces sharing the same descriptors,
+    /// for solvers that carry two domains through the plan.
+    /// Both are backed by files in `scratch_dir` if given.
     pub fn build_double(
         plan: &'a Plan<GRID_DIMENSION>,
         complex_buffer_type: ComplexBufferType,
+        scratch_dir: Option<&std::path::Path>,
     ) -> (Vec<ScratchDescriptor>, Scratch, Scratch) {
         let node_block_requirements =
-            AccountBuilder::node_requirements(plan, complex_buffer_type);
+            AccountBuilder::node_requirements::<f64>(plan, complex_buffer_type);
         let mut scratch_descriptors =
             vec![ScratchDescriptor::default(); plan.len()];
 
@@ -64,14 +100,15 @@ impl<'a, const GRID_DIMENSION: usize> ScratchBuilder<'a, GRID_DIMENSION> {
             plan,
             node_block_requirements,
             complex_buffer_type,
+            real_size: std::mem::size_of::<f64>(),
+            complex_size: std::mem::size_of::<c64>(),
+            frequency_buffer_size: f64::frequency_buffer_size,
         };
         builder.handle_repeat(plan.root, 0, &mut scratch_descriptors);
-        let scratch_space_1 = Scratch::new(
-            builder.blocks_to_bytes(builder.node_block_requirements[plan.root]),
-        );
-        let scratch_space_2 = Scratch::new(
-            builder.blocks_to_bytes(builder.node_block_requirements[plan.root]),
-        );
+        let size =
+            builder.blocks_to_bytes(builder.node_block_requirements[plan.root]);
+        let scratch_space_1 = Self::allocate(size, scratch_dir);
+        let scratch_space_2 = Self::allocate(size, scratch_dir);
         (scratch_descriptors, scratch_space_1, scratch_space_2)
     }
 
@@ -80,12 +117,12 @@ impl<'a, const GRID_DIMENSION: usize> ScratchBuilder<'a, GRID_DIMENSION> {
     }
 
     fn real_buffer_bytes(&self, aabb: &AABB<GRID_DIMENSION>) -> usize {
-        let min_bytes = aabb.buffer_size() * std::mem::size_of::<f64>();
+        let min_bytes = aabb.buffer_size() * self.real_size;
         min_bytes.div_ceil(MIN_ALIGNMENT) * MIN_ALIGNMENT
     }
 
     fn complex_buffer_bytes(&self, aabb: &AABB<GRID_DIMENSION>) -> usize {
-        let min_bytes = aabb.complex_buffer_size() * std::mem::size_of::<c64>();
+        let min_bytes = (self.frequency_buffer_size)(aabb) * self.complex_size;
         let byte_req = min_bytes.div_ceil(MIN_ALIGNMENT) * MIN_ALIGNMENT;
         match self.complex_buffer_type {
             ComplexBufferType::DomainOnly => byte_req,
@@ -101,7 +138,7 @@ impl<'a, const GRID_DIMENSION: usize> ScratchBuilder<'a, GRID_DIMENSION> {
     ) {
         let repeat_node = self.plan.unwrap_repeat_node(node_id);
 
-        self.handle_periodic(
+        self.handle_unknown(
             repeat_node.node,
             offset,
             true,
@@ -1,4 +1,5 @@
 use crate::ap_solver::index_types::*;
+use crate::ap_solver::instrumentation::*;
 use crate::ap_solver::periodic_ops::*;
 use crate::ap_solver::plan::*;
 use crate::ap_solver::planner::*;
@@ -13,18 +14,20 @@ use crate::domain::*;
 use crate::mem_fmt::*;
 use crate::util::*;
 use std::io::prelude::*;
+use std::time::Instant;
 
 impl<
         const GRID_DIMENSION: usize,
-        DirectSolverType: DirectSolverInterface<GRID_DIMENSION>,
-        PeriodicOpsType: PeriodicOps<GRID_DIMENSION>,
-    > SolverInterface<GRID_DIMENSION>
-    for Solver<GRID_DIMENSION, DirectSolverType, PeriodicOpsType>
+        DirectSolverType: DirectSolverInterface<GRID_DIMENSION, T>,
+        PeriodicOpsType: PeriodicOps<GRID_DIMENSION, T>,
+        T: Scalar,
+    > SolverInterface<GRID_DIMENSION, T>
+    for Solver<GRID_DIMENSION, DirectSolverType, PeriodicOpsType, T>
 {
     fn apply<'a>(
         &mut self,
-        input_domain: &mut SliceDomain<'a, GRID_DIMENSION>,
-        output_domain: &mut SliceDomain<'a, GRID_DIMENSION>,
+        input_domain: &mut SliceDomain<'a, GRID_DIMENSION, T>,
+        output_domain: &mut SliceDomain<'a, GRID_DIMENSION, T>,
         global_time: usize,
     ) {
         self.apply(input_domain, output_domain, global_time);
@@ -35,14 +38,25 @@ impl<
     }
 
     fn to_dot_file<P: AsRef<std::path::Path>>(&self, path: &P) {
-        self.plan.to_dot_file(path);
+        self.to_dot_file(path);
+    }
+
+    fn enable_instrumentation(&mut self) {
+        self.enable_instrumentation();
+    }
+
+    fn write_instrumentation<P: AsRef<std::path::Path>>(&self, path: &P) {
+        self.write_instrumentation(path);
     }
 }
 
+/// Domains are stored as `T`, see `Scalar`.
+/// The direct solver and periodic operations must use the same `T`.
 pub struct Solver<
     const GRID_DIMENSION: usize,
-    DirectSolverType: DirectSolverInterface<GRID_DIMENSION>,
-    PeriodicOpsType: PeriodicOps<GRID_DIMENSION>,
+    DirectSolverType: DirectSolverInterface<GRID_DIMENSION, T>,
+    PeriodicOpsType: PeriodicOps<GRID_DIMENSION, T>,
+    T: Scalar = f64,
 > {
     pub direct_solver: DirectSolverType,
     pub periodic_ops: PeriodicOpsType,
@@ -52,14 +66,19 @@ pub struct Solver<
     pub scratch_space: Scratch,
     pub central_global_time: usize,
     pub chunk_size: usize,
+
+    /// Per node runtime records, see `enable_instrumentation`
+    pub instrumentation: Option<Instrumentation<GRID_DIMENSION>>,
+    pub scalar_marker: std::marker::PhantomData<T>,
 }
 
 impl<
         'a,
         const GRID_DIMENSION: usize,
-        DirectSolverType: DirectSolverInterface<GRID_DIMENSION>,
-        PeriodicOpsType: PeriodicOps<GRID_DIMENSION>,
-    > Solver<GRID_DIMENSION, DirectSolverType, PeriodicOpsType>
+        DirectSolverType: DirectSolverInterface<GRID_DIMENSION, T>,
+        PeriodicOpsType: PeriodicOps<GRID_DIMENSION, T>,
+        T: Scalar,
+    > Solver<GRID_DIMENSION, DirectSolverType, PeriodicOpsType, T>
 {
     pub fn new(
         direct_solver: DirectSolverType,
@@ -70,7 +89,11 @@ impl<
         profiling::scope!("ap_solver::new");
 
         let (node_scratch_descriptors, scratch_space) =
-            ScratchBuilder::build(&planner_result.plan, complex_buffer_type);
+            ScratchBuilder::build_scalar::<T>(
+                &planner_result.plan,
+                complex_buffer_type,
+                params.scratch_dir.as_deref(),
+            );
 
         Solver {
             direct_solver,
@@ -81,6 +104,8 @@ impl<
             scratch_space,
             chunk_size: params.chunk_size,
             central_global_time: 0,
+            instrumentation: None,
+            scalar_marker: std::marker::PhantomData,
         }
     }
 
@@ -91,21 +116,76 @@ impl<
             "  - scratch size: {}",
             human_readable_bytes(self.scratch_space.size)
         );
+        if self.scratch_space.is_mapped() {
+            self.scratch_space.print_report();
+        }
     }
 
     pub fn apply(
         &mut self,
-        input_domain: &mut SliceDomain<'a, GRID_DIMENSION>,
-        output_domain: &mut SliceDomain<'a, GRID_DIMENSION>,
+        input_domain: &mut SliceDomain<'a, GRID_DIMENSION, T>,
+        output_domain: &mut SliceDomain<'a, GRID_DIMENSION, T>,
         global_time: usize,
     ) {
         profiling::scope!("ap_solver::apply");
         self.central_global_time = global_time;
+        if let Some(instrumentation) = self.instrumentation.as_mut() {
+            instrumentation.applies += 1;
+        }
+        let start = Instant::now();
         self.solve_root(input_domain, output_domain, global_time);
+        if let Some(instrumentation) = self.instrumentation.as_ref() {
+            instrumentation.record(self.plan.root, start.elapsed());
+        }
     }
 
+    /// With instrumentation enabled, nodes are annotated with their
+    /// runtimes.
     pub fn to_dot_file<P: AsRef<std::path::Path>>(&self, path: &P) {
-        self.plan.to_dot_file(path);
+        match self.instrumentation.as_ref() {
+            None => self.plan.to_dot_file(path),
+            Some(instrumentation) => self.plan.to_annotated_dot_file(
+                path,
+                &instrumentation.dot_annotations(),
+            ),
+        }
+    }
+
+    /// Record the runtime of every plan node in later applies,
+    /// discarding any earlier records.
+    pub fn enable_instrumentation(&mut self) {
+        self.instrumentation = Some(Instrumentation::new::<T>(&self.plan));
+    }
+
+    /// Report on the instrumentation records, writing them to `path`
+    /// with json and csv extensions.
+    pub fn write_instrumentation<P: AsRef<std::path::Path>>(&self, path: &P) {
+        let Some(instrumentation) = self.instrumentation.as_ref() else {
+            eprintln!("WARNING: Solver instrumentation is not enabled");
+            return;
+        };
+        instrumentation.print_report();
+        instrumentation.write_json(&path.as_ref().with_extension("json"));
+        instrumentation.write_csv(&path.as_ref().with_extension("csv"));
+    }
+
+    /// Runs `solve`, recording it as a call of `node_id` if instrumented.
+    fn timed<F: FnOnce()>(&self, node_id: NodeId, solve: F) {
+        match self.instrumentation.as_ref() {
+            None => solve(),
+            Some(instrumentation) => instrumentation.time(node_id, solve),
+        }
+    }
+
+    /// Runs `solve`, recording it as child time of `node_id`
+    /// if instrumented.
+    fn timed_children<F: FnOnce()>(&self, node_id: NodeId, solve: F) {
+        match self.instrumentation.as_ref() {
+            None => solve(),
+            Some(instrumentation) => {
+                instrumentation.time_children(node_id, solve)
+            }
+        }
     }
 
     pub fn scratch_descriptor_file<P: AsRef<std::path::Path>>(&self, path: &P) {
@@ -121,8 +201,8 @@ impl<
         node_id: usize,
         aabb: &AABB<GRID_DIMENSION>,
     ) -> (
-        SliceDomain<'b, GRID_DIMENSION>,
-        SliceDomain<'b, GRID_DIMENSION>,
+        SliceDomain<'b, GRID_DIMENSION, T>,
+        SliceDomain<'b, GRID_DIMENSION, T>,
     ) {
         let scratch_descriptor = &self.node_scratch_descriptors[node_id];
         let input_buffer = self.scratch_space.unsafe_get_buffer(
@@ -141,7 +221,7 @@ impl<
         (input_domain, output_domain)
     }
 
-    fn get_complex(&self, node_id: usize) -> &mut [c64] {
+    fn get_complex(&self, node_id: usize) -> &mut [T::Complex] {
         let scratch_descriptor = &self.node_scratch_descriptors[node_id];
         self.scratch_space.unsafe_get_buffer(
             scratch_descriptor.complex_offset,
@@ -151,18 +231,16 @@ impl<
 
     pub fn solve_root(
         &mut self,
-        input_domain: &mut SliceDomain<'a, GRID_DIMENSION>,
-        output_domain: &mut SliceDomain<'a, GRID_DIMENSION>,
+        input_domain: &mut SliceDomain<'a, GRID_DIMENSION, T>,
+        output_domain: &mut SliceDomain<'a, GRID_DIMENSION, T>,
         mut global_time: usize,
     ) {
         let repeat_solve = self.plan.unwrap_repeat_node(self.plan.root);
-        let repeat_periodic_solve =
-            self.plan.unwrap_periodic_node(repeat_solve.node);
-        let repeat_steps = repeat_periodic_solve.steps;
+        let repeat_steps = self.central_steps(repeat_solve.node);
 
         for _ in 0..repeat_solve.n {
             self.periodic_ops.build_ops(global_time);
-            self.periodic_solve(
+            self.central_solve(
                 repeat_solve.node,
                 input_domain,
                 output_domain,
@@ -178,7 +256,7 @@ impl<
                 &mut self.remainder_periodic_ops,
             );
             self.remainder_periodic_ops.build_ops(global_time);
-            self.periodic_solve(next, input_domain, output_domain, global_time);
+            self.central_solve(next, input_domain, output_domain, global_time);
             std::mem::swap(
                 &mut self.periodic_ops,
                 &mut self.remainder_periodic_ops,
@@ -189,14 +267,58 @@ impl<
         }
     }
 
+    /// Steps taken by a central solve node.
+    fn central_steps(&self, node_id: NodeId) -> usize {
+        match self.plan.get_node(node_id) {
+            PlanNode::PeriodicSolve(periodic_solve) => periodic_solve.steps,
+            PlanNode::DirectSolve(direct_solve) => direct_solve.steps,
+            _ => panic!("ERROR: Unexpected central node, {node_id}"),
+        }
+    }
+
+    /// Central solves are periodic solves over the whole domain,
+    /// unless the planner found no periodic solve
+    /// and fell back to directly solving the whole domain.
+    fn central_solve(
+        &self,
+        node_id: NodeId,
+        input_domain: &mut SliceDomain<'a, GRID_DIMENSION, T>,
+        output_domain: &mut SliceDomain<'a, GRID_DIMENSION, T>,
+        global_time: usize,
+    ) {
+        self.timed_children(self.plan.root, || {
+            self.timed(node_id, || match self.plan.get_node(node_id) {
+                PlanNode::PeriodicSolve(_) => {
+                    self.periodic_solve(
+                        node_id,
+                        input_domain,
+                        output_domain,
+                        global_time,
+                    );
+                }
+                PlanNode::DirectSolve(direct_solve) => {
+                    self.direct_solver.apply(
+                        input_domain,
+                        output_domain,
+                        &direct_solve.sloped_sides,
+                        direct_solve.steps,
+                        global_time,
+                        direct_solve.threads,
+                    );
+                }
+                _ => panic!("ERROR: Unexpected central node, {node_id}"),
+            })
+        });
+    }
+
     pub fn unknown_solve_allocate_io(
         &self,
         node_id: NodeId,
-        input: &SliceDomain<'a, GRID_DIMENSION>,
-        output: &mut SliceDomain<'a, GRID_DIMENSION>,
+        input: &SliceDomain<'a, GRID_DIMENSION, T>,
+        output: &mut SliceDomain<'a, GRID_DIMENSION, T>,
         global_time: usize,
     ) {
-        match self.plan.get_node(node_id) {
+        self.timed(node_id, || match self.plan.get_node(node_id) {
             PlanNode::DirectSolve(_) => {
                 self.direct_solve_allocate_io(
                     node_id,
@@ -219,17 +341,17 @@ impl<
             PlanNode::Range(_) => {
                 panic!("ERROR: Not expecting range node");
             }
-        }
+        });
     }
 
     pub fn unknown_solve_preallocated_io(
         &self,
         node_id: NodeId,
-        input: &mut SliceDomain<'a, GRID_DIMENSION>,
-        output: &mut SliceDomain<'a, GRID_DIMENSION>,
+        input: &mut SliceDomain<'a, GRID_DIMENSION, T>,
+        output: &mut SliceDomain<'a, GRID_DIMENSION, T>,
         global_time: usize,
     ) {
-        match self.plan.get_node(node_id) {
+        self.timed(node_id, || match self.plan.get_node(node_id) {
             PlanNode::DirectSolve(_) => {
                 self.direct_solve_preallocated_io(
                     node_id,
@@ -252,14 +374,14 @@ impl<
             PlanNode::Range(_) => {
                 panic!("ERROR: Not expecting range node");
             }
-        }
+        });
     }
 
     pub fn periodic_solve_preallocated_io(
         &self,
         node_id: NodeId,
-        input_domain: &mut SliceDomain<'a, GRID_DIMENSION>,
-        output_domain: &mut SliceDomain<'a, GRID_DIMENSION>,
+        input_domain: &mut SliceDomain<'a, GRID_DIMENSION, T>,
+        output_domain: &mut SliceDomain<'a, GRID_DIMENSION, T>,
         mut global_time: usize,
     ) {
         profiling::scope!("ap_solver::periodic_solve_preallocated_io");
@@ -280,20 +402,22 @@ impl<
         if let Some(next_id) = periodic_solve.time_cut {
             global_time += periodic_solve.steps;
             std::mem::swap(input_domain, output_domain);
-            self.unknown_solve_preallocated_io(
-                next_id,
-                input_domain,
-                output_domain,
-                global_time,
-            );
+            self.timed_children(node_id, || {
+                self.unknown_solve_preallocated_io(
+                    next_id,
+                    input_domain,
+                    output_domain,
+                    global_time,
+                );
+            });
         }
     }
 
     pub fn periodic_solve_allocate_io(
         &self,
         node_id: NodeId,
-        input: &SliceDomain<'a, GRID_DIMENSION>,
-        output: &mut SliceDomain<'a, GRID_DIMENSION>,
+        input: &SliceDomain<'a, GRID_DIMENSION, T>,
+        output: &mut SliceDomain<'a, GRID_DIMENSION, T>,
         mut global_time: usize,
     ) {
         profiling::scope!("ap_solver::periodic_solve_allocate_io");
@@ -324,12 +448,14 @@ impl<
         if let Some(next_id) = periodic_solve.time_cut {
             global_time += periodic_solve.steps;
             std::mem::swap(&mut input_domain, &mut output_domain);
-            self.unknown_solve_preallocated_io(
-                next_id,
-                &mut input_domain,
-                &mut output_domain,
-                global_time,
-            );
+            self.timed_children(node_id, || {
+                self.unknown_solve_preallocated_io(
+                    next_id,
+                    &mut input_domain,
+                    &mut output_domain,
+                    global_time,
+                );
+            });
         }
 
         // copy output to output
@@ -339,8 +465,8 @@ impl<
     pub fn periodic_solve(
         &self,
         node_id: NodeId,
-        input_domain: &mut SliceDomain<'a, GRID_DIMENSION>,
-        output_domain: &mut SliceDomain<'a, GRID_DIMENSION>,
+        input_domain: &mut SliceDomain<'a, GRID_DIMENSION, T>,
+        output_domain: &mut SliceDomain<'a, GRID_DIMENSION, T>,
         global_time: usize,
     ) {
         profiling::scope!("ap_solver::periodic_solve");
@@ -360,26 +486,28 @@ impl<
         // In a rayon scope, we fork for each of the boundary solves,
         // each of which will fill in their part of of output_domain
         {
-            let input_domain_const: &SliceDomain<'a, GRID_DIMENSION> =
+            let input_domain_const: &SliceDomain<'a, GRID_DIMENSION, T> =
                 input_domain;
-            rayon::scope(|s| {
-                for node_id in periodic_solve.boundary_nodes.clone() {
-                    // Our plan should provide the guarantee that
-                    // that boundary nodes have mutually exclusive
-                    // access to the output_domain
-                    let mut node_output = output_domain.unsafe_mut_access();
-
-                    // Each boundary solve will need
-                    // new input / output domains from the scratch space
-                    s.spawn(move |_| {
-                        self.unknown_solve_allocate_io(
-                            node_id,
-                            input_domain_const,
-                            &mut node_output,
-                            global_time,
-                        );
-                    });
-                }
+            self.timed_children(node_id, || {
+                rayon::scope(|s| {
+                    for node_id in periodic_solve.boundary_nodes.clone() {
+                        // Our plan should provide the guarantee that
+                        // that boundary nodes have mutually exclusive
+                        // access to the output_domain
+                        let mut node_output = output_domain.unsafe_mut_access();
+
+                        // Each boundary solve will need
+                        // new input / output domains from the scratch space
+                        s.spawn(move |_| {
+                            self.unknown_solve_allocate_io(
+                                node_id,
+                                input_domain_const,
+                                &mut node_output,
+                                global_time,
+                            );
+                        });
+                    }
+                });
             });
         }
     }
@@ -387,8 +515,8 @@ impl<
     pub fn direct_solve_allocate_io<'b>(
         &self,
         node_id: NodeId,
-        input: &SliceDomain<'b, GRID_DIMENSION>,
-        output: &mut SliceDomain<'b, GRID_DIMENSION>,
+        input: &SliceDomain<'b, GRID_DIMENSION, T>,
+        output: &mut SliceDomain<'b, GRID_DIMENSION, T>,
         global_time: usize,
     ) {
         profiling::scope!("ap_solver::direct_solve_allocate_io");
@@ -429,8 +557,8 @@ impl<
     pub fn direct_solve_preallocated_io(
         &self,
         node_id: NodeId,
-        input_domain: &mut SliceDomain<'a, GRID_DIMENSION>,
-        output_domain: &mut SliceDomain<'a, GRID_DIMENSION>,
+        input_domain: &mut SliceDomain<'a, GRID_DIMENSION, T>,
+        output_domain: &mut SliceDomain<'a, GRID_DIMENSION, T>,
         global_time: usize,
     ) {
         profiling::scope!("ap_solver::direct_solve_preallocated_io");
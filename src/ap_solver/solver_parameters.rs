@@ -1,8 +1,23 @@
+use crate::domain::*;
 use crate::fft_solver::PlanType;
 use crate::util::*;
+use std::path::PathBuf;
+
+/// How the domain is bounded along an axis.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AxisBoundary {
+    /// Boundary conditions on both faces, handled with boundary solves.
+    Aperiodic,
+
+    /// The domain wraps around, the planner never cuts this axis.
+    /// The direct solver's boundary condition must wrap along it,
+    /// see `SolverParameters::axis_bc`.
+    Periodic,
+}
 
 /// Solver generation is configurable.
 /// These are all the parameters.
+#[derive(Clone)]
 pub struct SolverParameters<const GRID_DIMENSION: usize> {
     /// Number of steps for one `apply` operation.
     pub steps: usize,
@@ -30,6 +45,54 @@ pub struct SolverParameters<const GRID_DIMENSION: usize> {
 
     /// Assume total tasks available relative to threads
     pub task_mult: f64,
+
+    /// Boundary type of each axis
+    pub axis_boundaries: [AxisBoundary; GRID_DIMENSION],
+
+    /// Load the plan from this file if it was saved with
+    /// the same parameters, otherwise plan and save it there.
+    pub plan_cache: Option<PathBuf>,
+
+    /// Back the scratch space with a memory-mapped file in this directory,
+    /// for solves whose scratch doesn't fit in RAM, see `Scratch::create_in`.
+    pub scratch_dir: Option<PathBuf>,
+}
+
+impl<const GRID_DIMENSION: usize> SolverParameters<GRID_DIMENSION> {
+    pub fn is_periodic(&self, d: usize) -> bool {
+        self.axis_boundaries[d] == AxisBoundary::Periodic
+    }
+
+    /// Stencil slopes used for planning.
+    /// Solves never shrink along periodic axes, so their slopes are zero.
+    pub fn planning_slopes(
+        &self,
+        stencil_slopes: &Bounds<GRID_DIMENSION>,
+    ) -> Bounds<GRID_DIMENSION> {
+        let mut result = *stencil_slopes;
+        for d in 0..GRID_DIMENSION {
+            if self.is_periodic(d) {
+                result[(d, 0)] = 0;
+                result[(d, 1)] = 0;
+            }
+        }
+        result
+    }
+
+    /// Boundary condition for the direct solver,
+    /// `bc` on aperiodic faces, wrapping around periodic axes.
+    pub fn axis_bc<BC: BCCheck<GRID_DIMENSION> + 'static>(
+        &self,
+        bc: BC,
+    ) -> FaceBC<GRID_DIMENSION> {
+        let mut result = FaceBC::new(self.aabb, bc);
+        for d in 0..GRID_DIMENSION {
+            if self.is_periodic(d) {
+                result = result.with_axis(d, WrapCheck::new(self.aabb));
+            }
+        }
+        result
+    }
 }
 
 impl<const GRID_DIMENSION: usize> std::default::Default
@@ -46,6 +109,9 @@ impl<const GRID_DIMENSION: usize> std::default::Default
             aabb: AABB::new(Bounds::zeros()),
             task_min: 1,
             task_mult: 1.0,
+            axis_boundaries: [AxisBoundary::Aperiodic; GRID_DIMENSION],
+            plan_cache: None,
+            scratch_dir: None,
         }
     }
 }
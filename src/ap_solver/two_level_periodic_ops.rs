@@ -0,0 +1,120 @@
+use crate::ap_solver::index_types::*;
+use crate::ap_solver::periodic_ops::*;
+use crate::ap_solver::solver_parameters::SolverParameters;
+use crate::fft_solver::PlanType;
+use crate::fft_solver::TwoLevelConvolutionOperation;
+use crate::stencil::*;
+use crate::util::*;
+use fftw::array::*;
+use std::collections::HashMap;
+
+/// Two level counterpart of `ApPeriodicOps`,
+/// stores the companion matrix convolutions in a `TwoLevelSolver`.
+pub struct TwoLevelPeriodicOps {
+    operations: Vec<TwoLevelConvolutionOperation>,
+}
+
+impl TwoLevelPeriodicOps {
+    pub fn new(operations: Vec<TwoLevelConvolutionOperation>) -> Self {
+        TwoLevelPeriodicOps { operations }
+    }
+
+    pub fn get(&self, op: OpId) -> &TwoLevelConvolutionOperation {
+        &self.operations[op]
+    }
+}
+
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+struct ConvolutionDescriptor<const GRID_DIMENSION: usize> {
+    exclusive_bounds: Coord<GRID_DIMENSION>,
+    steps: usize,
+    threads: usize,
+}
+
+/// Used by Planner to create two level convolution operations,
+/// and assign them IDs.
+pub struct TwoLevelPeriodicOpsBuilder<'a, const GRID_DIMENSION: usize> {
+    stencil: &'a TwoLevelStencil<GRID_DIMENSION>,
+    operations: Vec<TwoLevelConvolutionOperation>,
+    real_buffer: AlignedVec<f64>,
+    current_buffer: AlignedVec<c64>,
+    previous_buffer: AlignedVec<c64>,
+    plan_type: PlanType,
+    key_map: HashMap<ConvolutionDescriptor<GRID_DIMENSION>, OpId>,
+}
+
+impl<'a, const GRID_DIMENSION: usize>
+    TwoLevelPeriodicOpsBuilder<'a, GRID_DIMENSION>
+{
+    pub fn new(
+        stencil: &'a TwoLevelStencil<GRID_DIMENSION>,
+        params: &SolverParameters<GRID_DIMENSION>,
+    ) -> Self {
+        let max_real_size = params.aabb.buffer_size();
+        let real_buffer = AlignedVec::new(max_real_size);
+        let max_complex_size = params.aabb.complex_buffer_size();
+        let current_buffer = AlignedVec::new(max_complex_size);
+        let previous_buffer = AlignedVec::new(max_complex_size);
+
+        TwoLevelPeriodicOpsBuilder {
+            stencil,
+            operations: Vec::new(),
+            real_buffer,
+            current_buffer,
+            previous_buffer,
+            plan_type: params.plan_type,
+            key_map: HashMap::new(),
+        }
+    }
+
+    pub fn get_op(
+        &mut self,
+        exclusive_bounds: Coord<GRID_DIMENSION>,
+        steps: usize,
+        threads: usize,
+    ) -> OpId {
+        let key = ConvolutionDescriptor {
+            exclusive_bounds,
+            steps,
+            threads,
+        };
+        *self.key_map.entry(key).or_insert_with(|| {
+            let result = self.operations.len();
+            self.operations.push(TwoLevelConvolutionOperation::create(
+                self.stencil,
+                &mut self.real_buffer,
+                &mut self.current_buffer,
+                &mut self.previous_buffer,
+                &exclusive_bounds,
+                steps,
+                self.plan_type,
+                threads,
+            ));
+            result
+        })
+    }
+
+    pub fn finish(self) -> TwoLevelPeriodicOps {
+        TwoLevelPeriodicOps::new(self.operations)
+    }
+}
+
+impl<const GRID_DIMENSION: usize>
+    PeriodicOpsBuilder<GRID_DIMENSION, TwoLevelPeriodicOps>
+    for TwoLevelPeriodicOpsBuilder<'_, GRID_DIMENSION>
+{
+    fn get_op_id(
+        &mut self,
+        descriptor: PeriodicOpDescriptor<GRID_DIMENSION>,
+    ) -> OpId {
+        self.get_op(
+            descriptor.exclusive_bounds,
+            descriptor.steps,
+            descriptor.threads,
+        )
+    }
+
+    fn finish(self) -> TwoLevelPeriodicOps {
+        self.finish()
+    }
+}
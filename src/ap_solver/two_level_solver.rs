@@ -0,0 +1,559 @@
+use crate::ap_solver::index_types::*;
+use crate::ap_solver::plan::*;
+use crate::ap_solver::planner::*;
+use crate::ap_solver::scratch::*;
+use crate::ap_solver::scratch_builder::*;
+use crate::ap_solver::solver_parameters::*;
+use crate::ap_solver::two_level_periodic_ops::*;
+use crate::direct_solver::*;
+use crate::TwoLevelSolverInterface;
+
+use crate::domain::*;
+
+use crate::mem_fmt::*;
+use crate::util::*;
+
+impl<
+        const GRID_DIMENSION: usize,
+        DirectSolverType: TwoLevelDirectSolverInterface<GRID_DIMENSION>,
+    > TwoLevelSolverInterface<GRID_DIMENSION>
+    for TwoLevelSolver<GRID_DIMENSION, DirectSolverType>
+{
+    fn apply<'a>(
+        &mut self,
+        input_previous: &mut SliceDomain<'a, GRID_DIMENSION>,
+        input_current: &mut SliceDomain<'a, GRID_DIMENSION>,
+        output_previous: &mut SliceDomain<'a, GRID_DIMENSION>,
+        output_current: &mut SliceDomain<'a, GRID_DIMENSION>,
+        global_time: usize,
+    ) {
+        self.apply(
+            input_previous,
+            input_current,
+            output_previous,
+            output_current,
+            global_time,
+        );
+    }
+
+    fn print_report(&self) {
+        self.print_report();
+    }
+
+    fn to_dot_file<P: AsRef<std::path::Path>>(&self, path: &P) {
+        self.plan.to_dot_file(path);
+    }
+}
+
+/// AP solver for two level stencils.
+///
+/// Each time level is carried through the same plan as `Solver`,
+/// the state of a solve is the pair of levels `(previous, current)`.
+/// Periodic solves advance both levels with
+/// `TwoLevelConvolutionOperation`,
+/// and direct solves with a `TwoLevelDirectSolverInterface`.
+///
+/// Node scratch descriptors are shared by both levels,
+/// `previous_scratch` holds the buffers for the previous level
+/// and `current_scratch` those for the current level.
+pub struct TwoLevelSolver<
+    const GRID_DIMENSION: usize,
+    DirectSolverType: TwoLevelDirectSolverInterface<GRID_DIMENSION>,
+> {
+    pub direct_solver: DirectSolverType,
+    pub periodic_ops: TwoLevelPeriodicOps,
+    pub remainder_periodic_ops: TwoLevelPeriodicOps,
+    pub plan: Plan<GRID_DIMENSION>,
+    pub node_scratch_descriptors: Vec<ScratchDescriptor>,
+    pub previous_scratch: Scratch,
+    pub current_scratch: Scratch,
+    pub chunk_size: usize,
+}
+
+/// Input and output domains for both time levels of a solve.
+struct TwoLevelDomains<'b, const GRID_DIMENSION: usize> {
+    input_previous: SliceDomain<'b, GRID_DIMENSION>,
+    input_current: SliceDomain<'b, GRID_DIMENSION>,
+    output_previous: SliceDomain<'b, GRID_DIMENSION>,
+    output_current: SliceDomain<'b, GRID_DIMENSION>,
+}
+
+impl<
+        'a,
+        const GRID_DIMENSION: usize,
+        DirectSolverType: TwoLevelDirectSolverInterface<GRID_DIMENSION>,
+    > TwoLevelSolver<GRID_DIMENSION, DirectSolverType>
+{
+    pub fn new(
+        direct_solver: DirectSolverType,
+        params: &SolverParameters<GRID_DIMENSION>,
+        planner_result: PlannerResult<GRID_DIMENSION, TwoLevelPeriodicOps>,
+    ) -> Self {
+        profiling::scope!("two_level_solver::new");
+
+        let complex_buffer_type = ComplexBufferType::DomainOnly;
+        let (node_scratch_descriptors, previous_scratch, current_scratch) =
+            ScratchBuilder::build_double(
+                &planner_result.plan,
+                complex_buffer_type,
+                params.scratch_dir.as_deref(),
+            );
+
+        TwoLevelSolver {
+            direct_solver,
+            periodic_ops: planner_result.periodic_ops,
+            remainder_periodic_ops: planner_result.remainder_periodic_ops,
+            plan: planner_result.plan,
+            node_scratch_descriptors,
+            previous_scratch,
+            current_scratch,
+            chunk_size: params.chunk_size,
+        }
+    }
+
+    pub fn print_report(&self) {
+        println!("Two Level AP Solver Report:");
+        println!("  - plan size: {}", self.plan.len());
+        println!(
+            "  - scratch size: {}",
+            human_readable_bytes(
+                self.previous_scratch.size + self.current_scratch.size
+            )
+        );
+    }
+
+    pub fn apply(
+        &mut self,
+        input_previous: &mut SliceDomain<'a, GRID_DIMENSION>,
+        input_current: &mut SliceDomain<'a, GRID_DIMENSION>,
+        output_previous: &mut SliceDomain<'a, GRID_DIMENSION>,
+        output_current: &mut SliceDomain<'a, GRID_DIMENSION>,
+        global_time: usize,
+    ) {
+        profiling::scope!("two_level_solver::apply");
+        self.solve_root(
+            input_previous,
+            input_current,
+            output_previous,
+            output_current,
+            global_time,
+        );
+    }
+
+    pub fn to_dot_file<P: AsRef<std::path::Path>>(&self, path: &P) {
+        self.plan.to_dot_file(path);
+    }
+
+    fn get_domains<'b>(
+        &'b self,
+        node_id: usize,
+        aabb: &AABB<GRID_DIMENSION>,
+    ) -> TwoLevelDomains<'b, GRID_DIMENSION> {
+        let scratch_descriptor = &self.node_scratch_descriptors[node_id];
+        let get = |scratch: &'b Scratch, offset: usize| {
+            let buffer = scratch
+                .unsafe_get_buffer(offset, scratch_descriptor.real_buffer_size);
+            debug_assert!(buffer.len() >= aabb.buffer_size());
+            SliceDomain::new(*aabb, buffer)
+        };
+        TwoLevelDomains {
+            input_previous: get(
+                &self.previous_scratch,
+                scratch_descriptor.input_offset,
+            ),
+            input_current: get(
+                &self.current_scratch,
+                scratch_descriptor.input_offset,
+            ),
+            output_previous: get(
+                &self.previous_scratch,
+                scratch_descriptor.output_offset,
+            ),
+            output_current: get(
+                &self.current_scratch,
+                scratch_descriptor.output_offset,
+            ),
+        }
+    }
+
+    fn get_complex(&self, node_id: usize) -> (&mut [c64], &mut [c64]) {
+        let scratch_descriptor = &self.node_scratch_descriptors[node_id];
+        let previous = self.previous_scratch.unsafe_get_buffer(
+            scratch_descriptor.complex_offset,
+            scratch_descriptor.complex_buffer_size,
+        );
+        let current = self.current_scratch.unsafe_get_buffer(
+            scratch_descriptor.complex_offset,
+            scratch_descriptor.complex_buffer_size,
+        );
+        (previous, current)
+    }
+
+    pub fn solve_root(
+        &mut self,
+        input_previous: &mut SliceDomain<'a, GRID_DIMENSION>,
+        input_current: &mut SliceDomain<'a, GRID_DIMENSION>,
+        output_previous: &mut SliceDomain<'a, GRID_DIMENSION>,
+        output_current: &mut SliceDomain<'a, GRID_DIMENSION>,
+        mut global_time: usize,
+    ) {
+        let repeat_solve = self.plan.unwrap_repeat_node(self.plan.root);
+        let repeat_steps = self.central_steps(repeat_solve.node);
+
+        for _ in 0..repeat_solve.n {
+            self.central_solve(
+                repeat_solve.node,
+                input_previous,
+                input_current,
+                output_previous,
+                output_current,
+                global_time,
+            );
+            global_time += repeat_steps;
+            std::mem::swap(input_previous, output_previous);
+            std::mem::swap(input_current, output_current);
+            profiling::finish_frame!();
+        }
+        if let Some(next) = repeat_solve.next {
+            std::mem::swap(
+                &mut self.periodic_ops,
+                &mut self.remainder_periodic_ops,
+            );
+            self.central_solve(
+                next,
+                input_previous,
+                input_current,
+                output_previous,
+                output_current,
+                global_time,
+            );
+            std::mem::swap(
+                &mut self.periodic_ops,
+                &mut self.remainder_periodic_ops,
+            );
+            profiling::finish_frame!();
+        } else {
+            std::mem::swap(input_previous, output_previous);
+            std::mem::swap(input_current, output_current);
+        }
+    }
+
+    /// Steps taken by a central solve node.
+    fn central_steps(&self, node_id: NodeId) -> usize {
+        match self.plan.get_node(node_id) {
+            PlanNode::PeriodicSolve(periodic_solve) => periodic_solve.steps,
+            PlanNode::DirectSolve(direct_solve) => direct_solve.steps,
+            _ => panic!("ERROR: Unexpected central node, {node_id}"),
+        }
+    }
+
+    /// Central solves are periodic solves over the whole domain,
+    /// unless the planner found no periodic solve
+    /// and fell back to directly solving the whole domain.
+    fn central_solve(
+        &self,
+        node_id: NodeId,
+        input_previous: &mut SliceDomain<'a, GRID_DIMENSION>,
+        input_current: &mut SliceDomain<'a, GRID_DIMENSION>,
+        output_previous: &mut SliceDomain<'a, GRID_DIMENSION>,
+        output_current: &mut SliceDomain<'a, GRID_DIMENSION>,
+        global_time: usize,
+    ) {
+        match self.plan.get_node(node_id) {
+            PlanNode::PeriodicSolve(_) => {
+                self.periodic_solve(
+                    node_id,
+                    input_previous,
+                    input_current,
+                    output_previous,
+                    output_current,
+                    global_time,
+                );
+            }
+            PlanNode::DirectSolve(direct_solve) => {
+                self.direct_solver.apply(
+                    input_previous,
+                    input_current,
+                    output_previous,
+                    output_current,
+                    &direct_solve.sloped_sides,
+                    direct_solve.steps,
+                    global_time,
+                    direct_solve.threads,
+                );
+            }
+            _ => panic!("ERROR: Unexpected central node, {node_id}"),
+        }
+    }
+
+    fn unknown_solve_allocate_io<'b>(
+        &self,
+        node_id: NodeId,
+        input_previous: &SliceDomain<'b, GRID_DIMENSION>,
+        input_current: &SliceDomain<'b, GRID_DIMENSION>,
+        output_previous: &mut SliceDomain<'b, GRID_DIMENSION>,
+        output_current: &mut SliceDomain<'b, GRID_DIMENSION>,
+        global_time: usize,
+    ) {
+        match self.plan.get_node(node_id) {
+            PlanNode::DirectSolve(direct_solve) => {
+                let mut domains =
+                    self.get_domains(node_id, &direct_solve.input_aabb);
+                self.copy_input(&mut domains, input_previous, input_current);
+                self.direct_solve_preallocated_io(
+                    node_id,
+                    &mut domains,
+                    global_time,
+                );
+                self.copy_output(&domains, output_previous, output_current);
+            }
+            PlanNode::PeriodicSolve(periodic_solve) => {
+                let mut domains =
+                    self.get_domains(node_id, &periodic_solve.input_aabb);
+                self.copy_input(&mut domains, input_previous, input_current);
+                self.periodic_solve_preallocated_io(
+                    node_id,
+                    &mut domains,
+                    global_time,
+                );
+                self.copy_output(&domains, output_previous, output_current);
+            }
+            PlanNode::Repeat(_) => {
+                panic!("ERROR: Not expecting repeat node");
+            }
+            PlanNode::Range(_) => {
+                panic!("ERROR: Not expecting range node");
+            }
+        }
+    }
+
+    fn unknown_solve_preallocated_io<'b>(
+        &self,
+        node_id: NodeId,
+        domains: &mut TwoLevelDomains<'b, GRID_DIMENSION>,
+        global_time: usize,
+    ) {
+        match self.plan.get_node(node_id) {
+            PlanNode::DirectSolve(_) => {
+                self.direct_solve_preallocated_io(
+                    node_id,
+                    domains,
+                    global_time,
+                );
+            }
+            PlanNode::PeriodicSolve(_) => {
+                self.periodic_solve_preallocated_io(
+                    node_id,
+                    domains,
+                    global_time,
+                );
+            }
+            PlanNode::Repeat(_) => {
+                panic!("ERROR: Not expecting repeat node");
+            }
+            PlanNode::Range(_) => {
+                panic!("ERROR: Not expecting range node");
+            }
+        }
+    }
+
+    fn copy_input(
+        &self,
+        domains: &mut TwoLevelDomains<'_, GRID_DIMENSION>,
+        input_previous: &SliceDomain<'_, GRID_DIMENSION>,
+        input_current: &SliceDomain<'_, GRID_DIMENSION>,
+    ) {
+        domains
+            .input_previous
+            .par_from_superset(input_previous, self.chunk_size);
+        domains
+            .input_current
+            .par_from_superset(input_current, self.chunk_size);
+    }
+
+    fn copy_output(
+        &self,
+        domains: &TwoLevelDomains<'_, GRID_DIMENSION>,
+        output_previous: &mut SliceDomain<'_, GRID_DIMENSION>,
+        output_current: &mut SliceDomain<'_, GRID_DIMENSION>,
+    ) {
+        output_previous
+            .par_set_subdomain(&domains.output_previous, self.chunk_size);
+        output_current
+            .par_set_subdomain(&domains.output_current, self.chunk_size);
+    }
+
+    /// Swap inputs and outputs, then shrink the inputs to `aabb`.
+    /// Used before a solve, when the outputs of
+    /// the previous solve contain `aabb`.
+    fn swap_and_shrink_inputs<'b>(
+        &self,
+        domains: &mut TwoLevelDomains<'b, GRID_DIMENSION>,
+        aabb: AABB<GRID_DIMENSION>,
+    ) {
+        let chunk_size = self.chunk_size;
+        for (input, output) in [
+            (&mut domains.input_previous, &mut domains.output_previous),
+            (&mut domains.input_current, &mut domains.output_current),
+        ] {
+            std::mem::swap(input, output);
+            input.set_aabb(aabb);
+            input.par_from_superset(output, chunk_size);
+            output.set_aabb(aabb);
+        }
+    }
+
+    /// Shrink the outputs of a solve to `aabb`,
+    /// using the inputs as scratch.
+    fn shrink_outputs<'b>(
+        &self,
+        domains: &mut TwoLevelDomains<'b, GRID_DIMENSION>,
+        aabb: AABB<GRID_DIMENSION>,
+    ) {
+        let chunk_size = self.chunk_size;
+        for (input, output) in [
+            (&mut domains.input_previous, &mut domains.output_previous),
+            (&mut domains.input_current, &mut domains.output_current),
+        ] {
+            input.set_aabb(aabb);
+            input.par_from_superset(output, chunk_size);
+            std::mem::swap(input, output);
+        }
+    }
+
+    fn swap_io<'b>(&self, domains: &mut TwoLevelDomains<'b, GRID_DIMENSION>) {
+        std::mem::swap(
+            &mut domains.input_previous,
+            &mut domains.output_previous,
+        );
+        std::mem::swap(&mut domains.input_current, &mut domains.output_current);
+    }
+
+    /// Periodic solve with io buffers provided by the caller.
+    /// The inputs must contain the solve's input AABB,
+    /// and on exit the outputs cover its output AABB.
+    fn periodic_solve_preallocated_io<'b>(
+        &self,
+        node_id: NodeId,
+        domains: &mut TwoLevelDomains<'b, GRID_DIMENSION>,
+        mut global_time: usize,
+    ) {
+        profiling::scope!("two_level_solver::periodic_solve_preallocated_io");
+        let periodic_solve = self.plan.unwrap_periodic_node(node_id);
+        self.swap_and_shrink_inputs(domains, periodic_solve.input_aabb);
+
+        self.periodic_solve(
+            node_id,
+            &mut domains.input_previous,
+            &mut domains.input_current,
+            &mut domains.output_previous,
+            &mut domains.output_current,
+            global_time,
+        );
+
+        self.shrink_outputs(domains, periodic_solve.output_aabb);
+        domains.input_previous.set_aabb(periodic_solve.output_aabb);
+        domains.input_current.set_aabb(periodic_solve.output_aabb);
+
+        // call time cut if needed
+        if let Some(next_id) = periodic_solve.time_cut {
+            global_time += periodic_solve.steps;
+            self.swap_io(domains);
+            self.unknown_solve_preallocated_io(next_id, domains, global_time);
+        }
+    }
+
+    /// Applies the companion matrix convolution to the whole input,
+    /// then fills in the boundary of the output with boundary solves.
+    fn periodic_solve<'b>(
+        &self,
+        node_id: NodeId,
+        input_previous: &mut SliceDomain<'b, GRID_DIMENSION>,
+        input_current: &mut SliceDomain<'b, GRID_DIMENSION>,
+        output_previous: &mut SliceDomain<'b, GRID_DIMENSION>,
+        output_current: &mut SliceDomain<'b, GRID_DIMENSION>,
+        global_time: usize,
+    ) {
+        profiling::scope!("two_level_solver::periodic_solve");
+        let periodic_solve = self.plan.unwrap_periodic_node(node_id);
+
+        // Apply convolution
+        let (previous_buffer, current_buffer) = self.get_complex(node_id);
+        self.periodic_ops.get(periodic_solve.convolution_id).apply(
+            input_previous,
+            input_current,
+            output_previous,
+            output_current,
+            previous_buffer,
+            current_buffer,
+            self.chunk_size,
+        );
+
+        // Boundary
+        // Same as `Solver::periodic_solve`,
+        // boundary nodes have mutually exclusive access to the outputs
+        {
+            let input_previous_const: &SliceDomain<'b, GRID_DIMENSION> =
+                input_previous;
+            let input_current_const: &SliceDomain<'b, GRID_DIMENSION> =
+                input_current;
+            rayon::scope(|s| {
+                for node_id in periodic_solve.boundary_nodes.clone() {
+                    let mut node_output_previous =
+                        output_previous.unsafe_mut_access();
+                    let mut node_output_current =
+                        output_current.unsafe_mut_access();
+                    s.spawn(move |_| {
+                        self.unknown_solve_allocate_io(
+                            node_id,
+                            input_previous_const,
+                            input_current_const,
+                            &mut node_output_previous,
+                            &mut node_output_current,
+                            global_time,
+                        );
+                    });
+                }
+            });
+        }
+    }
+
+    /// Direct solve with io buffers provided by the caller.
+    /// The inputs must contain the solve's input AABB,
+    /// and on exit the outputs cover its output AABB.
+    fn direct_solve_preallocated_io<'b>(
+        &self,
+        node_id: NodeId,
+        domains: &mut TwoLevelDomains<'b, GRID_DIMENSION>,
+        global_time: usize,
+    ) {
+        profiling::scope!("two_level_solver::direct_solve_preallocated_io");
+        let direct_solve = self.plan.unwrap_direct_node(node_id);
+
+        debug_assert!(domains
+            .input_current
+            .aabb()
+            .contains_aabb(&direct_solve.input_aabb));
+        self.swap_and_shrink_inputs(domains, direct_solve.input_aabb);
+
+        // invoke direct solver
+        self.direct_solver.apply(
+            &mut domains.input_previous,
+            &mut domains.input_current,
+            &mut domains.output_previous,
+            &mut domains.output_current,
+            &direct_solve.sloped_sides,
+            direct_solve.steps,
+            global_time,
+            direct_solve.threads,
+        );
+
+        self.shrink_outputs(domains, direct_solve.output_aabb);
+
+        debug_assert_eq!(
+            direct_solve.output_aabb,
+            *domains.output_current.aabb(),
+            "ERROR: n_id: {node_id}, Unexpected solve output"
+        );
+    }
+}
@@ -0,0 +1,126 @@
+use crate::domain::*;
+use crate::par_stencil;
+use crate::stencil::*;
+
+/// Global time doesn't matter for periodic solves
+/// since its only used for boundary conditions
+const GLOBAL_TIME: usize = 0;
+
+/// Block stencil counterpart of `box_apply`,
+/// `bcs` has a boundary condition for each field.
+pub fn block_box_apply<BC, const GRID_DIMENSION: usize>(
+    bcs: &[BC],
+    stencil: &BlockStencil<GRID_DIMENSION>,
+    input: &mut MultiFieldDomain<GRID_DIMENSION>,
+    output: &mut MultiFieldDomain<GRID_DIMENSION>,
+    steps: usize,
+    mut global_time: usize,
+    chunk_size: usize,
+) where
+    BC: BCCheck<GRID_DIMENSION>,
+{
+    debug_assert_eq!(input.aabb(), output.aabb());
+    for _ in 0..steps - 1 {
+        global_time += 1;
+        par_stencil::apply_block(
+            bcs,
+            stencil,
+            input,
+            output,
+            global_time,
+            chunk_size,
+        );
+        std::mem::swap(input, output);
+    }
+    global_time += 1;
+    par_stencil::apply_block(
+        bcs,
+        stencil,
+        input,
+        output,
+        global_time,
+        chunk_size,
+    );
+}
+
+/// Block stencil counterpart of `direct_periodic_apply`.
+pub fn block_direct_periodic_apply<const GRID_DIMENSION: usize>(
+    stencil: &BlockStencil<GRID_DIMENSION>,
+    input: &mut MultiFieldDomain<GRID_DIMENSION>,
+    output: &mut MultiFieldDomain<GRID_DIMENSION>,
+    steps: usize,
+    chunk_size: usize,
+) {
+    debug_assert_eq!(input.aabb(), output.aabb());
+    for _ in 0..steps - 1 {
+        {
+            let bcs: Vec<_> =
+                input.fields().iter().map(PeriodicCheck::new).collect();
+            par_stencil::apply_block(
+                &bcs,
+                stencil,
+                input,
+                output,
+                GLOBAL_TIME,
+                chunk_size,
+            );
+        }
+        std::mem::swap(input, output);
+    }
+
+    let bcs: Vec<_> = input.fields().iter().map(PeriodicCheck::new).collect();
+    par_stencil::apply_block(
+        &bcs,
+        stencil,
+        input,
+        output,
+        GLOBAL_TIME,
+        chunk_size,
+    );
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+    use crate::standard_stencils::*;
+    use crate::util::*;
+    use float_cmp::assert_approx_eq;
+    use nalgebra::{dmatrix, matrix};
+
+    #[test]
+    fn conserves_total() {
+        // Diffusion conserves each field, and the coupling moves mass
+        // between fields without changing the total.
+        let chunk_size = 7;
+        let mut stencil = BlockStencil::uncoupled(&[
+            heat_2d(1.0, 1.0, 1.0, 0.2, 0.2),
+            heat_2d(1.0, 1.0, 1.0, 0.1, 0.05),
+        ]);
+        stencil.add_coupling(&dmatrix![-0.1, 0.05; 0.1, -0.05]);
+
+        let bound = AABB::new(matrix![0, 19; 0, 29]);
+        let mut input = MultiFieldDomain::new(bound, 2);
+        let mut output = MultiFieldDomain::new(bound, 2);
+        input.field_mut(0).par_set_values(
+            |coord: Coord<2>| (coord[0] * coord[1]) as f64,
+            chunk_size,
+        );
+        input.field_mut(1).par_set_values(|_| 1.0, chunk_size);
+        let total = |d: &MultiFieldDomain<2>| -> f64 {
+            d.fields()
+                .iter()
+                .map(|f| f.buffer().iter().sum::<f64>())
+                .sum()
+        };
+        let expected = total(&input);
+
+        block_direct_periodic_apply(
+            &stencil,
+            &mut input,
+            &mut output,
+            10,
+            chunk_size,
+        );
+        assert_approx_eq!(f64, total(&output), expected, epsilon = 0.000001);
+    }
+}
@@ -0,0 +1,181 @@
+use crate::domain::*;
+use crate::stencil::offset_slopes;
+use crate::util::*;
+
+/// The optimized direct solvers treat everything outside of their input
+/// as zero. This adds what `bc` contributes through neighbors outside of
+/// the output AABB, so they can support any boundary condition.
+/// Only the boundary cells are visited, each one once.
+/// Obstacles are handled afterwards, see `apply_solid_cells`.
+pub fn add_boundary_contributions<
+    BC,
+    const GRID_DIMENSION: usize,
+    const NEIGHBORHOOD_SIZE: usize,
+    T: Scalar<Weight = f64>,
+    DomainType: DomainView<GRID_DIMENSION, T>,
+>(
+    bc: &BC,
+    offsets: &[Coord<GRID_DIMENSION>; NEIGHBORHOOD_SIZE],
+    weights: &Values<NEIGHBORHOOD_SIZE>,
+    input: &DomainType,
+    output: &mut DomainType,
+    global_time: usize,
+) where
+    BC: BCCheck<GRID_DIMENSION>,
+{
+    let aabb = *output.aabb();
+    let on_boundary = |coord: &Coord<GRID_DIMENSION>, d: usize| {
+        coord[d] == aabb.bounds[(d, 0)] || coord[d] == aabb.bounds[(d, 1)]
+    };
+    for d in 0..GRID_DIMENSION {
+        for side in 0..2 {
+            // Degenerate axis, both sides are the same face
+            if side == 1 && aabb.bounds[(d, 0)] == aabb.bounds[(d, 1)] {
+                continue;
+            }
+            let mut face = aabb;
+            face.bounds[(d, 0)] = aabb.bounds[(d, side)];
+            face.bounds[(d, 1)] = aabb.bounds[(d, side)];
+            for coord in face.coord_iter() {
+                // Edges and corners belong to their lowest axis
+                if (0..d).any(|e| on_boundary(&coord, e)) {
+                    continue;
+                }
+                let mut contribution = 0.0;
+                for (offset, weight) in offsets.iter().zip(weights.iter()) {
+                    let neighbor = coord + offset;
+                    if aabb.contains(&neighbor) {
+                        continue;
+                    }
+                    if let Some(value) = bc.check(&neighbor, global_time) {
+                        contribution += weight * value;
+                    } else if let Some(value) =
+                        bc.check_input(&neighbor, global_time, input)
+                    {
+                        contribution += weight * value;
+                    } else if let Some((coord, factor)) =
+                        bc.remap(&neighbor, global_time)
+                    {
+                        contribution +=
+                            weight * factor * input.view(&coord).as_f64();
+                    }
+                }
+                if contribution != 0.0 {
+                    let i = aabb.coord_to_linear(&coord);
+                    let value = output.buffer()[i] + T::cast(contribution);
+                    output.buffer_mut()[i] = value;
+                }
+            }
+        }
+    }
+
+    apply_solid_cells(bc, offsets, weights, input, output, global_time);
+}
+
+/// Obstacles, like those of `MaskedCheck`, are inside the domain,
+/// so the optimized kernels read straight through them.
+/// Solid cells are set from `check_solid`, and cells reading
+/// a neighbor that `check_neighbor` handles are recomputed,
+/// matching `par_stencil::apply`.
+/// Only cells within reach of `bc.solid_aabb()` are visited.
+fn apply_solid_cells<
+    BC,
+    const GRID_DIMENSION: usize,
+    const NEIGHBORHOOD_SIZE: usize,
+    T: Scalar,
+    DomainType: DomainView<GRID_DIMENSION, T>,
+>(
+    bc: &BC,
+    offsets: &[Coord<GRID_DIMENSION>; NEIGHBORHOOD_SIZE],
+    weights: &Values<NEIGHBORHOOD_SIZE>,
+    input: &DomainType,
+    output: &mut DomainType,
+    global_time: usize,
+) where
+    BC: BCCheck<GRID_DIMENSION>,
+{
+    let Some(solid_aabb) = bc.solid_aabb() else {
+        return;
+    };
+    let aabb = *output.aabb();
+
+    // Cells that read a solid neighbor are up to a slope away
+    let slopes = offset_slopes(offsets);
+    let mut region =
+        solid_aabb.add_bounds_diff(slopes_to_outward_diff(&slopes));
+    if !region.intersects(&aabb) {
+        return;
+    }
+    region.trim_to_aabb(&aabb);
+
+    for coord in region.coord_iter() {
+        let i = aabb.coord_to_linear(&coord);
+        if let Some(value) = bc.check_solid(&coord, global_time) {
+            output.buffer_mut()[i] = T::cast(value);
+            continue;
+        }
+
+        let mut touches_solid = false;
+        let mut result = 0.0;
+        for (offset, weight) in offsets.iter().zip(weights.iter()) {
+            let neighbor = coord + offset;
+            let value = if let Some(value) =
+                bc.check_neighbor(&coord, &neighbor, global_time, input)
+            {
+                touches_solid = true;
+                value
+            } else if aabb.contains(&neighbor) {
+                input.view(&neighbor).as_f64()
+            } else if let Some(value) = bc.check(&neighbor, global_time) {
+                value
+            } else if let Some(value) =
+                bc.check_input(&neighbor, global_time, input)
+            {
+                value
+            } else if let Some((coord, factor)) =
+                bc.remap(&neighbor, global_time)
+            {
+                factor * input.view(&coord).as_f64()
+            } else {
+                0.0
+            };
+            result += weight * value;
+        }
+        if touches_solid {
+            output.buffer_mut()[i] = T::cast(result);
+        }
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+    use float_cmp::assert_approx_eq;
+    use nalgebra::{matrix, vector};
+
+    #[test]
+    fn boundary_contributions() {
+        let aabb = AABB::new(matrix![0, 4; 0, 3]);
+        let input = OwnedDomain::new(aabb);
+        let mut output = OwnedDomain::new(aabb);
+        let bc = ConstantCheck::new(1.0, aabb);
+        let offsets =
+            [vector![1, 0], vector![0, -1], vector![-1, 0], vector![0, 1]];
+        let weights = vector![1.0, 2.0, 4.0, 8.0];
+        add_boundary_contributions(
+            &bc,
+            &offsets,
+            &weights,
+            &input,
+            &mut output,
+            0,
+        );
+
+        assert_approx_eq!(f64, output.view(&vector![2, 2]), 0.0);
+        assert_approx_eq!(f64, output.view(&vector![0, 0]), 6.0);
+        assert_approx_eq!(f64, output.view(&vector![4, 3]), 9.0);
+        assert_approx_eq!(f64, output.view(&vector![0, 2]), 4.0);
+        assert_approx_eq!(f64, output.view(&vector![2, 3]), 8.0);
+        assert_approx_eq!(f64, output.buffer().iter().sum::<f64>(), 70.0);
+    }
+}
@@ -2,29 +2,32 @@ use crate::domain::*;
 use crate::par_stencil;
 use crate::solver_interface::*;
 use crate::stencil::*;
+use crate::util::Scalar;
 
 pub struct GeneralDirectBoxSolver<
     'a,
     BC,
     const GRID_DIMENSION: usize,
-    const NEIGHBORHOOD_SIZE: usize,
+    StencilType,
 > where
     BC: BCCheck<GRID_DIMENSION>,
+    StencilType: TIStencil<GRID_DIMENSION>,
 {
     bc: &'a BC,
-    stencil: &'a Stencil<GRID_DIMENSION, NEIGHBORHOOD_SIZE>,
+    stencil: &'a StencilType,
     steps: usize,
     chunk_size: usize,
 }
 
-impl<'a, BC, const GRID_DIMENSION: usize, const NEIGHBORHOOD_SIZE: usize>
-    GeneralDirectBoxSolver<'a, BC, GRID_DIMENSION, NEIGHBORHOOD_SIZE>
+impl<'a, BC, const GRID_DIMENSION: usize, StencilType>
+    GeneralDirectBoxSolver<'a, BC, GRID_DIMENSION, StencilType>
 where
     BC: BCCheck<GRID_DIMENSION>,
+    StencilType: TIStencil<GRID_DIMENSION>,
 {
     pub fn new(
         bc: &'a BC,
-        stencil: &'a Stencil<GRID_DIMENSION, NEIGHBORHOOD_SIZE>,
+        stencil: &'a StencilType,
         steps: usize,
         chunk_size: usize,
     ) -> Self {
@@ -37,11 +40,12 @@ where
     }
 }
 
-impl<'a, BC, const GRID_DIMENSION: usize, const NEIGHBORHOOD_SIZE: usize>
+impl<'a, BC, const GRID_DIMENSION: usize, StencilType>
     SolverInterface<GRID_DIMENSION>
-    for GeneralDirectBoxSolver<'a, BC, GRID_DIMENSION, NEIGHBORHOOD_SIZE>
+    for GeneralDirectBoxSolver<'a, BC, GRID_DIMENSION, StencilType>
 where
     BC: BCCheck<GRID_DIMENSION>,
+    StencilType: TIStencil<GRID_DIMENSION>,
 {
     fn apply<'b>(
         &mut self,
@@ -85,11 +89,12 @@ where
 pub fn box_apply<
     BC,
     const GRID_DIMENSION: usize,
-    const NEIGHBORHOOD_SIZE: usize,
-    DomainType: DomainView<GRID_DIMENSION>,
+    StencilType: TIStencil<GRID_DIMENSION, T::Weight>,
+    T: Scalar,
+    DomainType: DomainView<GRID_DIMENSION, T>,
 >(
     bc: &BC,
-    stencil: &Stencil<GRID_DIMENSION, NEIGHBORHOOD_SIZE>,
+    stencil: &StencilType,
     input: &mut DomainType,
     output: &mut DomainType,
     steps: usize,
@@ -5,9 +5,15 @@ use crate::util::*;
 use crate::SolverInterface;
 
 /// Optimized direct solver for 3pt 1D stencil.
-/// Implements a constant zero boundary condition.
-pub struct DirectSolver3Pt1DOpt<'a, StencilType: TVStencil<1, 3>> {
+/// Implements a constant zero boundary condition,
+/// unless another one is given with `with_bc`.
+pub struct DirectSolver3Pt1DOpt<
+    'a,
+    StencilType: TVStencil<1, 3>,
+    BC: BCCheck<1> = ConstantCheck<1>,
+> {
     stencil: &'a StencilType,
+    bc: Option<&'a BC>,
     chunk_size: usize,
 }
 
@@ -21,11 +27,31 @@ impl<'a, StencilType: TVStencil<1, 3>> DirectSolver3Pt1DOpt<'a, StencilType> {
         assert_eq!(&expected_offsets, stencil.offsets());
         DirectSolver3Pt1DOpt {
             stencil,
+            bc: None,
             chunk_size,
         }
     }
+}
+
+impl<'a, StencilType: TVStencil<1, 3>, BC: BCCheck<1>>
+    DirectSolver3Pt1DOpt<'a, StencilType, BC>
+{
+    /// Use `bc` for coordinates outside of the input domain.
+    pub fn with_bc<NewBC: BCCheck<1>>(
+        self,
+        bc: &'a NewBC,
+    ) -> DirectSolver3Pt1DOpt<'a, StencilType, NewBC> {
+        DirectSolver3Pt1DOpt {
+            stencil: self.stencil,
+            bc: Some(bc),
+            chunk_size: self.chunk_size,
+        }
+    }
 
-    fn apply_step<DomainType: DomainView<1> + Send>(
+    fn apply_step<
+        T: Scalar<Weight = f64>,
+        DomainType: DomainView<1, T> + Send,
+    >(
         &self,
         input: &mut DomainType,
         output: &mut DomainType,
@@ -33,7 +59,8 @@ impl<'a, StencilType: TVStencil<1, 3>> DirectSolver3Pt1DOpt<'a, StencilType> {
         global_time: usize,
         n_r: usize,
     ) {
-        let w = self.stencil.weights(global_time);
+        let w64 = self.stencil.weights(global_time);
+        let w: [T; 3] = std::array::from_fn(|i| T::cast(w64[i]));
         let ib = input.buffer();
 
         unsafe {
@@ -41,16 +68,16 @@ impl<'a, StencilType: TVStencil<1, 3>> DirectSolver3Pt1DOpt<'a, StencilType> {
             {
                 let linear_index: usize = 0;
                 *output.buffer_mut().get_unchecked_mut(linear_index) =
-                    w.get_unchecked(0) * ib.get_unchecked(linear_index + 1)
-                        + w.get_unchecked(2) * ib.get_unchecked(linear_index);
+                    *w.get_unchecked(0) * *ib.get_unchecked(linear_index + 1)
+                        + *w.get_unchecked(2) * *ib.get_unchecked(linear_index);
             }
 
             // Max
             {
                 let linear_index: usize = n_r - 1;
                 *output.buffer_mut().get_unchecked_mut(linear_index) =
-                    w.get_unchecked(1) * ib.get_unchecked(linear_index - 1)
-                        + w.get_unchecked(2) * ib.get_unchecked(linear_index);
+                    *w.get_unchecked(1) * *ib.get_unchecked(linear_index - 1)
+                        + *w.get_unchecked(2) * *ib.get_unchecked(linear_index);
             }
 
             let const_output: &DomainType = output;
@@ -63,27 +90,40 @@ impl<'a, StencilType: TVStencil<1, 3>> DirectSolver3Pt1DOpt<'a, StencilType> {
                         profiling::scope!("direct_solver: Thread Callback");
                         let mut o = const_output.unsafe_mut_access();
                         for i in start..end {
-                            *o.buffer_mut().get_unchecked_mut(i) = w
+                            *o.buffer_mut().get_unchecked_mut(i) = *w
                                 .get_unchecked(0)
-                                * ib.get_unchecked(i + 1)
-                                + w.get_unchecked(1) * ib.get_unchecked(i - 1)
-                                + w.get_unchecked(2) * ib.get_unchecked(i);
+                                * *ib.get_unchecked(i + 1)
+                                + *w.get_unchecked(1)
+                                    * *ib.get_unchecked(i - 1)
+                                + *w.get_unchecked(2) * *ib.get_unchecked(i);
                         }
                     });
                     start += chunk_size;
                 }
             });
         }
+
+        // Like par_stencil, boundaries for this step are read at global_time + 1
+        if let Some(bc) = self.bc {
+            add_boundary_contributions(
+                bc,
+                self.stencil.offsets(),
+                &w64,
+                input,
+                output,
+                global_time + 1,
+            );
+        }
     }
 }
 
-impl<StencilType: TVStencil<1, 3>> DirectSolverInterface<1>
-    for DirectSolver3Pt1DOpt<'_, StencilType>
+impl<StencilType: TVStencil<1, 3>, BC: BCCheck<1>, T: Scalar<Weight = f64>>
+    DirectSolverInterface<1, T> for DirectSolver3Pt1DOpt<'_, StencilType, BC>
 {
     fn apply<'b>(
         &self,
-        input: &mut SliceDomain<'b, 1>,
-        output: &mut SliceDomain<'b, 1>,
+        input: &mut SliceDomain<'b, 1, T>,
+        output: &mut SliceDomain<'b, 1, T>,
         _sloped_sides: &Bounds<1>,
         steps: usize,
         mut global_time: usize,
@@ -101,8 +141,12 @@ impl<StencilType: TVStencil<1, 3>> DirectSolverInterface<1>
     }
 }
 
-pub struct Direct3Pt1DSolver<'a, StencilType: TVStencil<1, 3>> {
-    solver: DirectSolver3Pt1DOpt<'a, StencilType>,
+pub struct Direct3Pt1DSolver<
+    'a,
+    StencilType: TVStencil<1, 3>,
+    BC: BCCheck<1> = ConstantCheck<1>,
+> {
+    solver: DirectSolver3Pt1DOpt<'a, StencilType, BC>,
     steps: usize,
     threads: usize,
 }
@@ -117,6 +161,7 @@ impl<'a, StencilType: TVStencil<1, 3>> Direct3Pt1DSolver<'a, StencilType> {
         Direct3Pt1DSolver {
             solver: DirectSolver3Pt1DOpt {
                 stencil,
+                bc: None,
                 chunk_size,
             },
             steps,
@@ -125,8 +170,24 @@ impl<'a, StencilType: TVStencil<1, 3>> Direct3Pt1DSolver<'a, StencilType> {
     }
 }
 
-impl<'a, StencilType: TVStencil<1, 3>> SolverInterface<1>
-    for Direct3Pt1DSolver<'a, StencilType>
+impl<'a, StencilType: TVStencil<1, 3>, BC: BCCheck<1>>
+    Direct3Pt1DSolver<'a, StencilType, BC>
+{
+    /// Use `bc` for coordinates outside of the domain.
+    pub fn with_bc<NewBC: BCCheck<1>>(
+        self,
+        bc: &'a NewBC,
+    ) -> Direct3Pt1DSolver<'a, StencilType, NewBC> {
+        Direct3Pt1DSolver {
+            solver: self.solver.with_bc(bc),
+            steps: self.steps,
+            threads: self.threads,
+        }
+    }
+}
+
+impl<'a, StencilType: TVStencil<1, 3>, BC: BCCheck<1>> SolverInterface<1>
+    for Direct3Pt1DSolver<'a, StencilType, BC>
 {
     fn apply<'b>(
         &mut self,
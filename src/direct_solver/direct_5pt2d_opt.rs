@@ -5,9 +5,15 @@ use crate::util::*;
 use crate::SolverInterface;
 
 /// Optimized direct solver for 5pt 2D stencil.
-/// Implements a constant zero boundary condition.
-pub struct DirectSolver5Pt2DOpt<'a, StencilType: TVStencil<2, 5>> {
+/// Implements a constant zero boundary condition,
+/// unless another one is given with `with_bc`.
+pub struct DirectSolver5Pt2DOpt<
+    'a,
+    StencilType: TVStencil<2, 5>,
+    BC: BCCheck<2> = ConstantCheck<2>,
+> {
     stencil: &'a StencilType,
+    bc: Option<&'a BC>,
 }
 
 impl<'a, StencilType: TVStencil<2, 5>> DirectSolver5Pt2DOpt<'a, StencilType> {
@@ -20,10 +26,28 @@ impl<'a, StencilType: TVStencil<2, 5>> DirectSolver5Pt2DOpt<'a, StencilType> {
             vector![0, 0],  // 4
         ];
         assert_eq!(&expected_offsets, stencil.offsets());
-        DirectSolver5Pt2DOpt { stencil }
+        DirectSolver5Pt2DOpt { stencil, bc: None }
+    }
+}
+
+impl<'a, StencilType: TVStencil<2, 5>, BC: BCCheck<2>>
+    DirectSolver5Pt2DOpt<'a, StencilType, BC>
+{
+    /// Use `bc` for coordinates outside of the input domain.
+    pub fn with_bc<NewBC: BCCheck<2>>(
+        self,
+        bc: &'a NewBC,
+    ) -> DirectSolver5Pt2DOpt<'a, StencilType, NewBC> {
+        DirectSolver5Pt2DOpt {
+            stencil: self.stencil,
+            bc: Some(bc),
+        }
     }
 
-    fn apply_step<DomainType: DomainView<2> + Send>(
+    fn apply_step<
+        T: Scalar<Weight = f64>,
+        DomainType: DomainView<2, T> + Send,
+    >(
         &self,
         input: &mut DomainType,
         output: &mut DomainType,
@@ -32,7 +56,8 @@ impl<'a, StencilType: TVStencil<2, 5>> DirectSolver5Pt2DOpt<'a, StencilType> {
         offsets: [usize; 5],
         exclusive_bounds: Coord<2>,
     ) {
-        let w = self.stencil.weights(global_time);
+        let w64 = self.stencil.weights(global_time);
+        let w: [T; 5] = std::array::from_fn(|i| T::cast(w64[i]));
         let ib = input.buffer();
         let const_output: &DomainType = output;
         unsafe {
@@ -44,17 +69,17 @@ impl<'a, StencilType: TVStencil<2, 5>> DirectSolver5Pt2DOpt<'a, StencilType> {
                     // (min, min)
                     {
                         let linear_index: usize = 0;
-                        *o.buffer_mut().get_unchecked_mut(linear_index) = w
+                        *o.buffer_mut().get_unchecked_mut(linear_index) = *w
                             .get_unchecked(0)
-                            * ib.get_unchecked(
+                            * *ib.get_unchecked(
                                 linear_index + offsets.get_unchecked(0),
                             )
-                            + w.get_unchecked(3)
-                                * ib.get_unchecked(
+                            + *w.get_unchecked(3)
+                                * *ib.get_unchecked(
                                     linear_index + offsets.get_unchecked(3),
                                 )
-                            + w.get_unchecked(4)
-                                * ib.get_unchecked(linear_index);
+                            + *w.get_unchecked(4)
+                                * *ib.get_unchecked(linear_index);
                     }
 
                     // (max, min)
@@ -64,17 +89,17 @@ impl<'a, StencilType: TVStencil<2, 5>> DirectSolver5Pt2DOpt<'a, StencilType> {
                                 * exclusive_bounds.get_unchecked(1))
                                 as usize;
 
-                        *o.buffer_mut().get_unchecked_mut(linear_index) = w
+                        *o.buffer_mut().get_unchecked_mut(linear_index) = *w
                             .get_unchecked(2)
-                            * ib.get_unchecked(
+                            * *ib.get_unchecked(
                                 linear_index - offsets.get_unchecked(2),
                             )
-                            + w.get_unchecked(3)
-                                * ib.get_unchecked(
+                            + *w.get_unchecked(3)
+                                * *ib.get_unchecked(
                                     linear_index + offsets.get_unchecked(3),
                                 )
-                            + w.get_unchecked(4)
-                                * ib.get_unchecked(linear_index);
+                            + *w.get_unchecked(4)
+                                * *ib.get_unchecked(linear_index);
                     }
 
                     // (min, max)
@@ -82,17 +107,17 @@ impl<'a, StencilType: TVStencil<2, 5>> DirectSolver5Pt2DOpt<'a, StencilType> {
                         let linear_index: usize =
                             (exclusive_bounds.get_unchecked(1) - 1) as usize;
 
-                        *o.buffer_mut().get_unchecked_mut(linear_index) = w
+                        *o.buffer_mut().get_unchecked_mut(linear_index) = *w
                             .get_unchecked(0)
-                            * ib.get_unchecked(
+                            * *ib.get_unchecked(
                                 linear_index + offsets.get_unchecked(0),
                             )
-                            + w.get_unchecked(1)
-                                * ib.get_unchecked(
+                            + *w.get_unchecked(1)
+                                * *ib.get_unchecked(
                                     linear_index - offsets.get_unchecked(1),
                                 )
-                            + w.get_unchecked(4)
-                                * ib.get_unchecked(linear_index);
+                            + *w.get_unchecked(4)
+                                * *ib.get_unchecked(linear_index);
                     }
 
                     // (max, max)
@@ -102,17 +127,17 @@ impl<'a, StencilType: TVStencil<2, 5>> DirectSolver5Pt2DOpt<'a, StencilType> {
                                 * exclusive_bounds.get_unchecked(1))
                                 - 1) as usize;
 
-                        *o.buffer_mut().get_unchecked_mut(linear_index) = w
+                        *o.buffer_mut().get_unchecked_mut(linear_index) = *w
                             .get_unchecked(1)
-                            * ib.get_unchecked(
+                            * *ib.get_unchecked(
                                 linear_index - offsets.get_unchecked(1),
                             )
-                            + w.get_unchecked(2)
-                                * ib.get_unchecked(
+                            + *w.get_unchecked(2)
+                                * *ib.get_unchecked(
                                     linear_index - offsets.get_unchecked(2),
                                 )
-                            + w.get_unchecked(4)
-                                * ib.get_unchecked(linear_index);
+                            + *w.get_unchecked(4)
+                                * *ib.get_unchecked(linear_index);
                     }
 
                     // left / right Sides
@@ -122,21 +147,21 @@ impl<'a, StencilType: TVStencil<2, 5>> DirectSolver5Pt2DOpt<'a, StencilType> {
                         {
                             let linear_index: usize = y;
 
-                            *o.buffer_mut().get_unchecked_mut(linear_index) = w
+                            *o.buffer_mut().get_unchecked_mut(linear_index) = *w
                                 .get_unchecked(0)
-                                * ib.get_unchecked(
+                                * *ib.get_unchecked(
                                     linear_index + offsets.get_unchecked(0),
                                 )
-                                + w.get_unchecked(1)
-                                    * ib.get_unchecked(
+                                + *w.get_unchecked(1)
+                                    * *ib.get_unchecked(
                                         linear_index - offsets.get_unchecked(1),
                                     )
-                                + w.get_unchecked(3)
-                                    * ib.get_unchecked(
+                                + *w.get_unchecked(3)
+                                    * *ib.get_unchecked(
                                         linear_index + offsets.get_unchecked(3),
                                     )
-                                + w.get_unchecked(4)
-                                    * ib.get_unchecked(linear_index);
+                                + *w.get_unchecked(4)
+                                    * *ib.get_unchecked(linear_index);
                         }
 
                         // right side
@@ -147,27 +172,30 @@ impl<'a, StencilType: TVStencil<2, 5>> DirectSolver5Pt2DOpt<'a, StencilType> {
                                     + y as i32)
                                     as usize;
 
-                            *o.buffer_mut().get_unchecked_mut(linear_index) = w
+                            *o.buffer_mut().get_unchecked_mut(linear_index) = *w
                                 .get_unchecked(1)
-                                * ib.get_unchecked(
+                                * *ib.get_unchecked(
                                     linear_index - offsets.get_unchecked(1),
                                 )
-                                + w.get_unchecked(2)
-                                    * ib.get_unchecked(
+                                + *w.get_unchecked(2)
+                                    * *ib.get_unchecked(
                                         linear_index - offsets.get_unchecked(2),
                                     )
-                                + w.get_unchecked(3)
-                                    * ib.get_unchecked(
+                                + *w.get_unchecked(3)
+                                    * *ib.get_unchecked(
                                         linear_index + offsets.get_unchecked(3),
                                     )
-                                + w.get_unchecked(4)
-                                    * ib.get_unchecked(linear_index);
+                                + *w.get_unchecked(4)
+                                    * *ib.get_unchecked(linear_index);
                         }
                     }
                 });
 
+                // Boxes thinner than threads still need one row per task
                 let chunk_size =
-                    (*exclusive_bounds.get_unchecked(0) as usize - 2) / threads;
+                    ((*exclusive_bounds.get_unchecked(0) as usize - 2)
+                        / threads)
+                        .max(1);
                 let mut start: usize = 1;
                 while start < (exclusive_bounds.get_unchecked(0) - 1) as usize {
                     let end = (start + chunk_size)
@@ -188,46 +216,46 @@ impl<'a, StencilType: TVStencil<2, 5>> DirectSolver5Pt2DOpt<'a, StencilType> {
                                         as usize
                                     - 1;
                                 *o.buffer_mut()
-                                    .get_unchecked_mut(linear_index) = w
+                                    .get_unchecked_mut(linear_index) = *w
                                     .get_unchecked(0)
-                                    * ib.get_unchecked(
+                                    * *ib.get_unchecked(
                                         linear_index + offsets.get_unchecked(0),
                                     )
-                                    + w.get_unchecked(1)
-                                        * ib.get_unchecked(
+                                    + *w.get_unchecked(1)
+                                        * *ib.get_unchecked(
                                             linear_index
                                                 - offsets.get_unchecked(1),
                                         )
-                                    + w.get_unchecked(2)
-                                        * ib.get_unchecked(
+                                    + *w.get_unchecked(2)
+                                        * *ib.get_unchecked(
                                             linear_index
                                                 - offsets.get_unchecked(2),
                                         )
-                                    + w.get_unchecked(4)
-                                        * ib.get_unchecked(linear_index);
+                                    + *w.get_unchecked(4)
+                                        * *ib.get_unchecked(linear_index);
                             }
 
                             // bottom
                             {
                                 let linear_index: usize = index_base;
                                 *o.buffer_mut()
-                                    .get_unchecked_mut(linear_index) = w
+                                    .get_unchecked_mut(linear_index) = *w
                                     .get_unchecked(0)
-                                    * ib.get_unchecked(
+                                    * *ib.get_unchecked(
                                         linear_index + offsets.get_unchecked(0),
                                     )
-                                    + w.get_unchecked(2)
-                                        * ib.get_unchecked(
+                                    + *w.get_unchecked(2)
+                                        * *ib.get_unchecked(
                                             linear_index
                                                 - offsets.get_unchecked(2),
                                         )
-                                    + w.get_unchecked(3)
-                                        * ib.get_unchecked(
+                                    + *w.get_unchecked(3)
+                                        * *ib.get_unchecked(
                                             linear_index
                                                 + offsets.get_unchecked(3),
                                         )
-                                    + w.get_unchecked(4)
-                                        * ib.get_unchecked(linear_index);
+                                    + *w.get_unchecked(4)
+                                        * *ib.get_unchecked(linear_index);
                             }
 
                             // central
@@ -237,28 +265,28 @@ impl<'a, StencilType: TVStencil<2, 5>> DirectSolver5Pt2DOpt<'a, StencilType> {
                             {
                                 let linear_index: usize = index_base + y;
                                 *o.buffer_mut()
-                                    .get_unchecked_mut(linear_index) = w
+                                    .get_unchecked_mut(linear_index) = *w
                                     .get_unchecked(0)
-                                    * ib.get_unchecked(
+                                    * *ib.get_unchecked(
                                         linear_index + offsets.get_unchecked(0),
                                     )
-                                    + w.get_unchecked(1)
-                                        * ib.get_unchecked(
+                                    + *w.get_unchecked(1)
+                                        * *ib.get_unchecked(
                                             linear_index
                                                 - offsets.get_unchecked(1),
                                         )
-                                    + w.get_unchecked(2)
-                                        * ib.get_unchecked(
+                                    + *w.get_unchecked(2)
+                                        * *ib.get_unchecked(
                                             linear_index
                                                 - offsets.get_unchecked(2),
                                         )
-                                    + w.get_unchecked(3)
-                                        * ib.get_unchecked(
+                                    + *w.get_unchecked(3)
+                                        * *ib.get_unchecked(
                                             linear_index
                                                 + offsets.get_unchecked(3),
                                         )
-                                    + w.get_unchecked(4)
-                                        * ib.get_unchecked(linear_index);
+                                    + *w.get_unchecked(4)
+                                        * *ib.get_unchecked(linear_index);
                             }
                         }
                     });
@@ -266,16 +294,28 @@ impl<'a, StencilType: TVStencil<2, 5>> DirectSolver5Pt2DOpt<'a, StencilType> {
                 }
             });
         }
+
+        // Like par_stencil, boundaries for this step are read at global_time + 1
+        if let Some(bc) = self.bc {
+            add_boundary_contributions(
+                bc,
+                self.stencil.offsets(),
+                &w64,
+                input,
+                output,
+                global_time + 1,
+            );
+        }
     }
 }
 
-impl<StencilType: TVStencil<2, 5>> DirectSolverInterface<2>
-    for DirectSolver5Pt2DOpt<'_, StencilType>
+impl<StencilType: TVStencil<2, 5>, BC: BCCheck<2>, T: Scalar<Weight = f64>>
+    DirectSolverInterface<2, T> for DirectSolver5Pt2DOpt<'_, StencilType, BC>
 {
     fn apply<'b>(
         &self,
-        input: &mut SliceDomain<'b, 2>,
-        output: &mut SliceDomain<'b, 2>,
+        input: &mut SliceDomain<'b, 2, T>,
+        output: &mut SliceDomain<'b, 2, T>,
         _sloped_sides: &Bounds<2>,
         steps: usize,
         mut global_time: usize,
@@ -311,8 +351,12 @@ impl<StencilType: TVStencil<2, 5>> DirectSolverInterface<2>
     }
 }
 
-pub struct Direct5Pt2DSolver<'a, StencilType: TVStencil<2, 5>> {
-    solver: DirectSolver5Pt2DOpt<'a, StencilType>,
+pub struct Direct5Pt2DSolver<
+    'a,
+    StencilType: TVStencil<2, 5>,
+    BC: BCCheck<2> = ConstantCheck<2>,
+> {
+    solver: DirectSolver5Pt2DOpt<'a, StencilType, BC>,
     steps: usize,
     threads: usize,
 }
@@ -325,15 +369,31 @@ impl<'a, StencilType: TVStencil<2, 5>> Direct5Pt2DSolver<'a, StencilType> {
         _chunk_size: usize,
     ) -> Self {
         Direct5Pt2DSolver {
-            solver: DirectSolver5Pt2DOpt { stencil },
+            solver: DirectSolver5Pt2DOpt { stencil, bc: None },
             steps,
             threads,
         }
     }
 }
 
-impl<'a, StencilType: TVStencil<2, 5>> SolverInterface<2>
-    for Direct5Pt2DSolver<'a, StencilType>
+impl<'a, StencilType: TVStencil<2, 5>, BC: BCCheck<2>>
+    Direct5Pt2DSolver<'a, StencilType, BC>
+{
+    /// Use `bc` for coordinates outside of the domain.
+    pub fn with_bc<NewBC: BCCheck<2>>(
+        self,
+        bc: &'a NewBC,
+    ) -> Direct5Pt2DSolver<'a, StencilType, NewBC> {
+        Direct5Pt2DSolver {
+            solver: self.solver.with_bc(bc),
+            steps: self.steps,
+            threads: self.threads,
+        }
+    }
+}
+
+impl<'a, StencilType: TVStencil<2, 5>, BC: BCCheck<2>> SolverInterface<2>
+    for Direct5Pt2DSolver<'a, StencilType, BC>
 {
     fn apply<'b>(
         &mut self,
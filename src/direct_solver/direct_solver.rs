@@ -5,36 +5,42 @@ use crate::stencil::*;
 use crate::util::*;
 
 /// Generic direct solver for time-invariant stencils
-/// in any dimension and of any size.
+/// in any dimension and of any size,
+/// including `DynStencil`s.
 /// You should prefer an optimized direct solver if available.
-/// Supports arbitrary boundary conditions.
+/// Supports arbitrary boundary conditions,
+/// and domains of any `Scalar` type,
+/// as long as the stencil's weights are `T::Weight`,
+/// e.g. `Stencil::new_complex` for `c64` domains.
 pub struct DirectFrustrumSolver<
     'a,
     BC,
     const GRID_DIMENSION: usize,
-    const NEIGHBORHOOD_SIZE: usize,
+    StencilType,
 > where
     BC: BCCheck<GRID_DIMENSION>,
 {
     pub bc: &'a BC,
-    pub stencil: &'a Stencil<GRID_DIMENSION, NEIGHBORHOOD_SIZE>,
+    pub stencil: &'a StencilType,
     pub stencil_slopes: Bounds<GRID_DIMENSION>,
     pub chunk_size: usize,
 }
 
-impl<BC, const GRID_DIMENSION: usize, const NEIGHBORHOOD_SIZE: usize>
-    DirectFrustrumSolver<'_, BC, GRID_DIMENSION, NEIGHBORHOOD_SIZE>
+impl<BC, const GRID_DIMENSION: usize, StencilType>
+    DirectFrustrumSolver<'_, BC, GRID_DIMENSION, StencilType>
 where
     BC: BCCheck<GRID_DIMENSION>,
 {
-    pub fn apply<'b>(
+    pub fn apply<'b, T: Scalar>(
         &self,
-        input_domain: &mut SliceDomain<'b, GRID_DIMENSION>,
-        output_domain: &mut SliceDomain<'b, GRID_DIMENSION>,
+        input_domain: &mut SliceDomain<'b, GRID_DIMENSION, T>,
+        output_domain: &mut SliceDomain<'b, GRID_DIMENSION, T>,
         sloped_sides: &Bounds<GRID_DIMENSION>,
         steps: usize,
         mut global_time: usize,
-    ) {
+    ) where
+        StencilType: TIStencil<GRID_DIMENSION, T::Weight>,
+    {
         assert_eq!(input_domain.aabb(), output_domain.aabb());
 
         let mut trapezoid_slopes =
@@ -67,14 +73,15 @@ where
 impl<
         BC: BCCheck<GRID_DIMENSION>,
         const GRID_DIMENSION: usize,
-        const NEIGHBORHOOD_SIZE: usize,
-    > DirectSolverInterface<GRID_DIMENSION>
-    for DirectFrustrumSolver<'_, BC, GRID_DIMENSION, NEIGHBORHOOD_SIZE>
+        StencilType: TIStencil<GRID_DIMENSION, T::Weight>,
+        T: Scalar,
+    > DirectSolverInterface<GRID_DIMENSION, T>
+    for DirectFrustrumSolver<'_, BC, GRID_DIMENSION, StencilType>
 {
     fn apply<'b>(
         &self,
-        input_domain: &mut SliceDomain<'b, GRID_DIMENSION>,
-        output_domain: &mut SliceDomain<'b, GRID_DIMENSION>,
+        input_domain: &mut SliceDomain<'b, GRID_DIMENSION, T>,
+        output_domain: &mut SliceDomain<'b, GRID_DIMENSION, T>,
         sloped_sides: &Bounds<GRID_DIMENSION>,
         steps: usize,
         global_time: usize,
@@ -9,13 +9,15 @@ use crate::util::*;
 /// * stencil slopes
 /// means that some direct solvers may choose to shrink the domain
 /// each time step, though this is required.
-pub trait DirectSolverInterface<const GRID_DIMENSION: usize>:
+///
+/// `T` is the scalar type of the domains, see `Scalar`.
+pub trait DirectSolverInterface<const GRID_DIMENSION: usize, T: Scalar = f64>:
     Send + Sync
 {
     fn apply<'b>(
         &self,
-        input_domain: &mut SliceDomain<'b, GRID_DIMENSION>,
-        output_domain: &mut SliceDomain<'b, GRID_DIMENSION>,
+        input_domain: &mut SliceDomain<'b, GRID_DIMENSION, T>,
+        output_domain: &mut SliceDomain<'b, GRID_DIMENSION, T>,
         sloped_sides: &Bounds<GRID_DIMENSION>,
         steps: usize,
         global_time: usize,
@@ -4,14 +4,22 @@ pub mod periodic_direct;
 pub use direct::*;
 pub use periodic_direct::*;
 
+mod block_direct;
+mod boundary_contributions;
 mod direct_3pt1d_opt;
 mod direct_5pt2d_opt;
 mod direct_solver;
 mod direct_solver_interface;
+mod spatial_direct_solver;
 mod tv_direct_solver;
+mod two_level_direct;
 
+pub use block_direct::*;
+pub use boundary_contributions::*;
 pub use direct_3pt1d_opt::*;
 pub use direct_5pt2d_opt::*;
 pub use direct_solver::*;
 pub use direct_solver_interface::*;
+pub use spatial_direct_solver::*;
 pub use tv_direct_solver::*;
+pub use two_level_direct::*;
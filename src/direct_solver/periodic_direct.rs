@@ -10,18 +10,21 @@ const GLOBAL_TIME: usize = 0;
 pub struct GeneralDirectPeriodicBoxSolver<
     'a,
     const GRID_DIMENSION: usize,
-    const NEIGHBORHOOD_SIZE: usize,
+    StencilType: TIStencil<GRID_DIMENSION>,
 > {
-    stencil: &'a Stencil<GRID_DIMENSION, NEIGHBORHOOD_SIZE>,
+    stencil: &'a StencilType,
     steps: usize,
     chunk_size: usize,
 }
 
-impl<'a, const GRID_DIMENSION: usize, const NEIGHBORHOOD_SIZE: usize>
-    GeneralDirectPeriodicBoxSolver<'a, GRID_DIMENSION, NEIGHBORHOOD_SIZE>
+impl<
+        'a,
+        const GRID_DIMENSION: usize,
+        StencilType: TIStencil<GRID_DIMENSION>,
+    > GeneralDirectPeriodicBoxSolver<'a, GRID_DIMENSION, StencilType>
 {
     pub fn new(
-        stencil: &'a Stencil<GRID_DIMENSION, NEIGHBORHOOD_SIZE>,
+        stencil: &'a StencilType,
         steps: usize,
         chunk_size: usize,
     ) -> Self {
@@ -33,9 +36,12 @@ impl<'a, const GRID_DIMENSION: usize, const NEIGHBORHOOD_SIZE: usize>
     }
 }
 
-impl<'a, const GRID_DIMENSION: usize, const NEIGHBORHOOD_SIZE: usize>
-    SolverInterface<GRID_DIMENSION>
-    for GeneralDirectPeriodicBoxSolver<'a, GRID_DIMENSION, NEIGHBORHOOD_SIZE>
+impl<
+        'a,
+        const GRID_DIMENSION: usize,
+        StencilType: TIStencil<GRID_DIMENSION>,
+    > SolverInterface<GRID_DIMENSION>
+    for GeneralDirectPeriodicBoxSolver<'a, GRID_DIMENSION, StencilType>
 {
     fn apply<'b>(
         &mut self,
@@ -83,10 +89,10 @@ impl<'a, const GRID_DIMENSION: usize, const NEIGHBORHOOD_SIZE: usize>
 
 pub fn direct_periodic_apply<
     const GRID_DIMENSION: usize,
-    const NEIGHBORHOOD_SIZE: usize,
+    StencilType: TIStencil<GRID_DIMENSION>,
     DomainType: DomainView<GRID_DIMENSION>,
 >(
-    stencil: &Stencil<GRID_DIMENSION, NEIGHBORHOOD_SIZE>,
+    stencil: &StencilType,
     input: &mut DomainType,
     output: &mut DomainType,
     steps: usize,
@@ -0,0 +1,118 @@
+use crate::direct_solver::*;
+use crate::domain::*;
+use crate::par_stencil;
+use crate::stencil::SpatialStencil;
+use crate::util::*;
+
+/// Generic direct solver for spatially varying stencils
+/// in any dimension and of any size.
+/// Supports arbitrary boundary conditions.
+pub struct SpatialDirectFrustrumSolver<
+    'a,
+    BC,
+    const GRID_DIMENSION: usize,
+    const NEIGHBORHOOD_SIZE: usize,
+    StencilType: SpatialStencil<GRID_DIMENSION, NEIGHBORHOOD_SIZE>,
+> where
+    BC: BCCheck<GRID_DIMENSION>,
+{
+    pub bc: &'a BC,
+    pub stencil: &'a StencilType,
+    pub stencil_slopes: Bounds<GRID_DIMENSION>,
+    pub chunk_size: usize,
+}
+
+impl<
+        'a,
+        BC: BCCheck<GRID_DIMENSION>,
+        const GRID_DIMENSION: usize,
+        const NEIGHBORHOOD_SIZE: usize,
+        StencilType: SpatialStencil<GRID_DIMENSION, NEIGHBORHOOD_SIZE>,
+    > DirectSolverInterface<GRID_DIMENSION>
+    for SpatialDirectFrustrumSolver<
+        'a,
+        BC,
+        GRID_DIMENSION,
+        NEIGHBORHOOD_SIZE,
+        StencilType,
+    >
+{
+    fn apply<'b>(
+        &self,
+        input_domain: &mut SliceDomain<'b, GRID_DIMENSION>,
+        output_domain: &mut SliceDomain<'b, GRID_DIMENSION>,
+        sloped_sides: &Bounds<GRID_DIMENSION>,
+        steps: usize,
+        mut global_time: usize,
+        _threads: usize,
+    ) {
+        assert_eq!(input_domain.aabb(), output_domain.aabb());
+
+        let mut trapezoid_slopes =
+            self.stencil_slopes.component_mul(sloped_sides);
+        let negative_slopes = -1 * trapezoid_slopes.column(1);
+        trapezoid_slopes.set_column(1, &negative_slopes);
+
+        let mut output_box = *input_domain.aabb();
+        for _ in 0..steps {
+            global_time += 1;
+            output_box = output_box.add_bounds_diff(trapezoid_slopes);
+            debug_assert!(
+                input_domain.aabb().buffer_size() >= output_box.buffer_size()
+            );
+            output_domain.set_aabb(output_box);
+            par_stencil::apply_spatial(
+                self.bc,
+                self.stencil,
+                input_domain,
+                output_domain,
+                global_time,
+                self.chunk_size,
+            );
+            std::mem::swap(input_domain, output_domain);
+        }
+        std::mem::swap(input_domain, output_domain);
+    }
+}
+
+/// Spatially varying counterpart of `box_apply`.
+pub fn spatial_box_apply<
+    BC,
+    const GRID_DIMENSION: usize,
+    const NEIGHBORHOOD_SIZE: usize,
+    StencilType: SpatialStencil<GRID_DIMENSION, NEIGHBORHOOD_SIZE>,
+    DomainType: DomainView<GRID_DIMENSION>,
+>(
+    bc: &BC,
+    stencil: &StencilType,
+    input: &mut DomainType,
+    output: &mut DomainType,
+    steps: usize,
+    mut global_time: usize,
+    chunk_size: usize,
+) where
+    BC: BCCheck<GRID_DIMENSION>,
+{
+    debug_assert_eq!(input.aabb(), output.aabb());
+    for _ in 0..steps - 1 {
+        global_time += 1;
+        par_stencil::apply_spatial(
+            bc,
+            stencil,
+            input,
+            output,
+            global_time,
+            chunk_size,
+        );
+        std::mem::swap(input, output);
+    }
+    global_time += 1;
+    par_stencil::apply_spatial(
+        bc,
+        stencil,
+        input,
+        output,
+        global_time,
+        chunk_size,
+    );
+}
@@ -0,0 +1,299 @@
+use crate::domain::*;
+use crate::par_stencil;
+use crate::solver_interface::*;
+use crate::stencil::*;
+use crate::util::*;
+
+/// Global time doesn't matter for periodic solves
+/// since its only used for boundary conditions
+const GLOBAL_TIME: usize = 0;
+
+/// Direct solver for two level stencils over a box,
+/// using the same boundary condition for both time levels.
+pub struct TwoLevelDirectBoxSolver<'a, BC, const GRID_DIMENSION: usize>
+where
+    BC: BCCheck<GRID_DIMENSION>,
+{
+    bc: &'a BC,
+    stencil: &'a TwoLevelStencil<GRID_DIMENSION>,
+    steps: usize,
+    chunk_size: usize,
+}
+
+impl<'a, BC, const GRID_DIMENSION: usize>
+    TwoLevelDirectBoxSolver<'a, BC, GRID_DIMENSION>
+where
+    BC: BCCheck<GRID_DIMENSION>,
+{
+    pub fn new(
+        bc: &'a BC,
+        stencil: &'a TwoLevelStencil<GRID_DIMENSION>,
+        steps: usize,
+        chunk_size: usize,
+    ) -> Self {
+        TwoLevelDirectBoxSolver {
+            bc,
+            stencil,
+            steps,
+            chunk_size,
+        }
+    }
+}
+
+impl<'a, BC, const GRID_DIMENSION: usize>
+    TwoLevelSolverInterface<GRID_DIMENSION>
+    for TwoLevelDirectBoxSolver<'a, BC, GRID_DIMENSION>
+where
+    BC: BCCheck<GRID_DIMENSION>,
+{
+    fn apply<'b>(
+        &mut self,
+        input_previous: &mut SliceDomain<'b, GRID_DIMENSION>,
+        input_current: &mut SliceDomain<'b, GRID_DIMENSION>,
+        output_previous: &mut SliceDomain<'b, GRID_DIMENSION>,
+        output_current: &mut SliceDomain<'b, GRID_DIMENSION>,
+        global_time: usize,
+    ) {
+        two_level_box_apply(
+            self.bc,
+            self.stencil,
+            input_previous,
+            input_current,
+            output_current,
+            self.steps,
+            global_time,
+            self.chunk_size,
+        );
+        std::mem::swap(input_previous, output_previous);
+        std::mem::swap(input_current, output_current);
+    }
+
+    fn print_report(&self) {
+        println!("TwoLevelDirectBoxSolver: No Report");
+    }
+
+    fn to_dot_file<P: AsRef<std::path::Path>>(&self, _path: &P) {
+        eprintln!("WARNING: TwoLevelDirectBoxSolver cannot save to dot file");
+    }
+}
+
+/// Two level counterpart of `DirectSolverInterface`,
+/// used for the direct solves in `TwoLevelSolver`.
+/// The inputs hold the levels at `global_time - 1` and `global_time`,
+/// on exit the outputs hold the last two levels computed.
+/// Like `DirectSolverInterface`, the box may shrink each step
+/// along the sloped sides.
+pub trait TwoLevelDirectSolverInterface<const GRID_DIMENSION: usize>:
+    Send + Sync
+{
+    #[allow(clippy::too_many_arguments)]
+    fn apply<'b>(
+        &self,
+        input_previous: &mut SliceDomain<'b, GRID_DIMENSION>,
+        input_current: &mut SliceDomain<'b, GRID_DIMENSION>,
+        output_previous: &mut SliceDomain<'b, GRID_DIMENSION>,
+        output_current: &mut SliceDomain<'b, GRID_DIMENSION>,
+        sloped_sides: &Bounds<GRID_DIMENSION>,
+        steps: usize,
+        global_time: usize,
+        threads: usize,
+    );
+}
+
+/// Two level counterpart of `DirectFrustrumSolver`,
+/// using the same boundary condition for both time levels.
+pub struct TwoLevelDirectFrustrumSolver<'a, BC, const GRID_DIMENSION: usize>
+where
+    BC: BCCheck<GRID_DIMENSION>,
+{
+    pub bc: &'a BC,
+    pub stencil: &'a TwoLevelStencil<GRID_DIMENSION>,
+    pub stencil_slopes: Bounds<GRID_DIMENSION>,
+    pub chunk_size: usize,
+}
+
+impl<BC, const GRID_DIMENSION: usize>
+    TwoLevelDirectSolverInterface<GRID_DIMENSION>
+    for TwoLevelDirectFrustrumSolver<'_, BC, GRID_DIMENSION>
+where
+    BC: BCCheck<GRID_DIMENSION>,
+{
+    fn apply<'b>(
+        &self,
+        input_previous: &mut SliceDomain<'b, GRID_DIMENSION>,
+        input_current: &mut SliceDomain<'b, GRID_DIMENSION>,
+        output_previous: &mut SliceDomain<'b, GRID_DIMENSION>,
+        output_current: &mut SliceDomain<'b, GRID_DIMENSION>,
+        sloped_sides: &Bounds<GRID_DIMENSION>,
+        steps: usize,
+        mut global_time: usize,
+        _threads: usize,
+    ) {
+        assert_eq!(input_previous.aabb(), input_current.aabb());
+        debug_assert!(steps > 0);
+
+        let mut trapezoid_slopes =
+            self.stencil_slopes.component_mul(sloped_sides);
+        let negative_slopes = -1 * trapezoid_slopes.column(1);
+        trapezoid_slopes.set_column(1, &negative_slopes);
+
+        // Rotate through input_previous, input_current and output_current,
+        // each level is computed on a box shrunk from the one before
+        let mut output_box = *input_current.aabb();
+        for _ in 0..steps {
+            global_time += 1;
+            output_box = output_box.add_bounds_diff(trapezoid_slopes);
+            output_current.set_aabb(output_box);
+            par_stencil::apply_two_level(
+                self.bc,
+                self.bc,
+                self.stencil,
+                input_previous,
+                input_current,
+                output_current,
+                global_time,
+                self.chunk_size,
+            );
+            std::mem::swap(input_previous, input_current);
+            std::mem::swap(input_current, output_current);
+        }
+
+        // The previous level still covers the box from the step before
+        output_previous.set_aabb(output_box);
+        output_previous.par_from_superset(input_previous, self.chunk_size);
+        std::mem::swap(input_current, output_current);
+    }
+}
+
+/// Two level counterpart of `box_apply`.
+/// The domains are rotated each step, so on exit
+/// `previous` and `current` hold the last two levels computed.
+#[allow(clippy::too_many_arguments)]
+pub fn two_level_box_apply<
+    BC,
+    const GRID_DIMENSION: usize,
+    DomainType: DomainView<GRID_DIMENSION>,
+>(
+    bc: &BC,
+    stencil: &TwoLevelStencil<GRID_DIMENSION>,
+    previous: &mut DomainType,
+    current: &mut DomainType,
+    scratch: &mut DomainType,
+    steps: usize,
+    mut global_time: usize,
+    chunk_size: usize,
+) where
+    BC: BCCheck<GRID_DIMENSION>,
+{
+    debug_assert_eq!(previous.aabb(), current.aabb());
+    debug_assert_eq!(current.aabb(), scratch.aabb());
+    for _ in 0..steps {
+        global_time += 1;
+        par_stencil::apply_two_level(
+            bc,
+            bc,
+            stencil,
+            previous,
+            current,
+            scratch,
+            global_time,
+            chunk_size,
+        );
+        std::mem::swap(previous, current);
+        std::mem::swap(current, scratch);
+    }
+}
+
+/// Two level counterpart of `direct_periodic_apply`.
+pub fn two_level_direct_periodic_apply<
+    const GRID_DIMENSION: usize,
+    DomainType: DomainView<GRID_DIMENSION>,
+>(
+    stencil: &TwoLevelStencil<GRID_DIMENSION>,
+    previous: &mut DomainType,
+    current: &mut DomainType,
+    scratch: &mut DomainType,
+    steps: usize,
+    chunk_size: usize,
+) {
+    debug_assert_eq!(previous.aabb(), current.aabb());
+    debug_assert_eq!(current.aabb(), scratch.aabb());
+    for _ in 0..steps {
+        {
+            let current_bc = PeriodicCheck::new(current);
+            let previous_bc = PeriodicCheck::new(previous);
+            par_stencil::apply_two_level(
+                &current_bc,
+                &previous_bc,
+                stencil,
+                previous,
+                current,
+                scratch,
+                GLOBAL_TIME,
+                chunk_size,
+            );
+        }
+        std::mem::swap(previous, current);
+        std::mem::swap(current, scratch);
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+    use crate::standard_stencils::*;
+    use float_cmp::assert_approx_eq;
+    use nalgebra::matrix;
+
+    #[test]
+    fn constant_solution() {
+        // u = 1 at every level is an exact solution of the wave equation
+        let chunk_size = 3;
+        let stencil = wave_2d(0.5, 1.0, 1.0, 1.0);
+        let bound = AABB::new(matrix![0, 20; 0, 30]);
+        let mut previous = OwnedDomain::new(bound);
+        let mut current = OwnedDomain::new(bound);
+        let mut scratch = OwnedDomain::new(bound);
+        previous.par_set_values(|_| 1.0, chunk_size);
+        current.par_set_values(|_| 1.0, chunk_size);
+        two_level_direct_periodic_apply(
+            &stencil,
+            &mut previous,
+            &mut current,
+            &mut scratch,
+            7,
+            chunk_size,
+        );
+        for x in previous.buffer().iter().chain(current.buffer()) {
+            assert_approx_eq!(f64, *x, 1.0);
+        }
+    }
+
+    #[test]
+    fn linear_in_time() {
+        // u = t is also exact, since the Laplacian of a constant is zero
+        let chunk_size = 4;
+        let stencil = wave_1d(0.5, 1.0, 1.0);
+        let bound = AABB::new(matrix![0, 40]);
+        let bc = ConstantCheck::new(-100.0, bound);
+        let mut previous = OwnedDomain::new(bound);
+        let mut current = OwnedDomain::new(bound);
+        let mut scratch = OwnedDomain::new(bound);
+        current.par_set_values(|_| 1.0, chunk_size);
+        let steps = 5;
+        two_level_box_apply(
+            &bc,
+            &stencil,
+            &mut previous,
+            &mut current,
+            &mut scratch,
+            steps,
+            0,
+            chunk_size,
+        );
+        // Away from the boundary the solution is untouched
+        let x = 20;
+        assert_approx_eq!(f64, previous.buffer()[x], (steps - 1) as f64 + 1.0);
+        assert_approx_eq!(f64, current.buffer()[x], steps as f64 + 1.0);
+    }
+}
@@ -0,0 +1,281 @@
+use crate::domain::bc::BCCheck;
+use crate::domain::DomainRead;
+use crate::util::*;
+
+/// How a coordinate outside of the domain resolves.
+enum Resolution<const GRID_DIMENSION: usize> {
+    Value(f64),
+    Read(Coord<GRID_DIMENSION>, f64),
+    /// A face needs to look at the input, see `check_input`.
+    NeedsInput,
+}
+
+/// Composite boundary condition with its own `BCCheck` per face,
+/// e.g. Dirichlet at x min, Neumann at x max, and periodic in y.
+///
+/// Each face starts out with the default boundary condition.
+/// At edges and corners the face with the highest priority decides,
+/// faces set later have higher priority.
+/// Faces that remap, like `WrapCheck` or `ReflectCheck`,
+/// only move the coordinate along their own axis,
+/// after which any faces it still lies beyond are resolved the same way.
+/// Faces that read their input, like `RobinCheck`, see it through
+/// the `FaceBC`, so their reads beyond other faces resolve as well.
+/// Every face boundary condition should use the same bound as the `FaceBC`.
+pub struct FaceBC<const GRID_DIMENSION: usize> {
+    bound: AABB<GRID_DIMENSION>,
+    bcs: Vec<Box<dyn BCCheck<GRID_DIMENSION>>>,
+    faces: [[usize; 2]; GRID_DIMENSION],
+    priorities: [[usize; 2]; GRID_DIMENSION],
+}
+
+impl<const GRID_DIMENSION: usize> FaceBC<GRID_DIMENSION> {
+    pub fn new<BC: BCCheck<GRID_DIMENSION> + 'static>(
+        bound: AABB<GRID_DIMENSION>,
+        default: BC,
+    ) -> Self {
+        FaceBC {
+            bound,
+            bcs: vec![Box::new(default)],
+            faces: [[0; 2]; GRID_DIMENSION],
+            priorities: [[0; 2]; GRID_DIMENSION],
+        }
+    }
+
+    /// Use `bc` for the face at `side` (0 for min, 1 for max) of `axis`.
+    pub fn with_face<BC: BCCheck<GRID_DIMENSION> + 'static>(
+        mut self,
+        axis: usize,
+        side: usize,
+        bc: BC,
+    ) -> Self {
+        self.bcs.push(Box::new(bc));
+        self.faces[axis][side] = self.bcs.len() - 1;
+        self.priorities[axis][side] = self.bcs.len() - 1;
+        self
+    }
+
+    /// Use `bc` for both faces of `axis`, e.g. for a `WrapCheck`.
+    pub fn with_axis<BC: BCCheck<GRID_DIMENSION> + 'static>(
+        mut self,
+        axis: usize,
+        bc: BC,
+    ) -> Self {
+        self.bcs.push(Box::new(bc));
+        self.faces[axis] = [self.bcs.len() - 1; 2];
+        self.priorities[axis] = [self.bcs.len() - 1; 2];
+        self
+    }
+
+    /// The boundary condition handling a face.
+    pub fn face(
+        &self,
+        axis: usize,
+        side: usize,
+    ) -> &dyn BCCheck<GRID_DIMENSION> {
+        self.bcs[self.faces[axis][side]].as_ref()
+    }
+
+    fn resolve(
+        &self,
+        world_coord: &Coord<GRID_DIMENSION>,
+        global_time: usize,
+        input: Option<&dyn DomainRead<GRID_DIMENSION>>,
+    ) -> Resolution<GRID_DIMENSION> {
+        let mut coord = *world_coord;
+        let mut factor = 1.0;
+        // Each pass moves the coord inside along one axis
+        for _ in 0..GRID_DIMENSION {
+            let Some((axis, side)) = self
+                .bound
+                .outside_faces(&coord)
+                .max_by_key(|(axis, side)| self.priorities[*axis][*side])
+            else {
+                break;
+            };
+            let bc = self.face(axis, side);
+            if let Some(value) = bc.check(&coord, global_time) {
+                return Resolution::Value(factor * value);
+            }
+
+            // Only ask about this face
+            let mut probe = self.bound.clamp_coord(&coord);
+            probe[axis] = coord[axis];
+            if let Some(input) = input {
+                let reader = FaceReader {
+                    face_bc: self,
+                    input,
+                    shift: coord - probe,
+                    global_time,
+                };
+                if let Some(value) =
+                    bc.check_input(&probe, global_time, &reader)
+                {
+                    return Resolution::Value(factor * value);
+                }
+            }
+            let Some((remapped, f)) = bc.remap(&probe, global_time) else {
+                debug_assert!(
+                    input.is_none(),
+                    "face boundary condition must handle its face"
+                );
+                return Resolution::NeedsInput;
+            };
+            coord[axis] = remapped[axis];
+            factor *= f;
+        }
+        debug_assert!(self.bound.contains(&coord));
+        Resolution::Read(coord, factor)
+    }
+}
+
+/// What a face that reads its input sees.
+/// Its reads are relative to the probe coordinate it was asked about,
+/// so they are shifted back and resolved through the whole `FaceBC`.
+struct FaceReader<'a, const GRID_DIMENSION: usize> {
+    face_bc: &'a FaceBC<GRID_DIMENSION>,
+    input: &'a dyn DomainRead<GRID_DIMENSION>,
+    shift: Coord<GRID_DIMENSION>,
+    global_time: usize,
+}
+
+impl<const GRID_DIMENSION: usize> DomainRead<GRID_DIMENSION>
+    for FaceReader<'_, GRID_DIMENSION>
+{
+    fn read(&self, world_coord: &Coord<GRID_DIMENSION>) -> f64 {
+        let coord = world_coord + self.shift;
+        if self.face_bc.bound.contains(&coord) {
+            return self.input.read(&coord);
+        }
+        match self
+            .face_bc
+            .resolve(&coord, self.global_time, Some(self.input))
+        {
+            Resolution::Value(value) => value,
+            Resolution::Read(coord, factor) => factor * self.input.read(&coord),
+            Resolution::NeedsInput => unreachable!(),
+        }
+    }
+}
+
+impl<const GRID_DIMENSION: usize> BCCheck<GRID_DIMENSION>
+    for FaceBC<GRID_DIMENSION>
+{
+    fn check(
+        &self,
+        world_coord: &Coord<GRID_DIMENSION>,
+        global_time: usize,
+    ) -> Option<f64> {
+        if self.bound.contains(world_coord) {
+            return None;
+        }
+        match self.resolve(world_coord, global_time, None) {
+            Resolution::Value(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    fn check_input(
+        &self,
+        world_coord: &Coord<GRID_DIMENSION>,
+        global_time: usize,
+        input: &dyn DomainRead<GRID_DIMENSION>,
+    ) -> Option<f64> {
+        if self.bound.contains(world_coord) {
+            return None;
+        }
+        match self.resolve(world_coord, global_time, Some(input)) {
+            Resolution::Value(value) => Some(value),
+            // Left to `remap`, so the input is only read when a face has to
+            _ => None,
+        }
+    }
+
+    fn remap(
+        &self,
+        world_coord: &Coord<GRID_DIMENSION>,
+        global_time: usize,
+    ) -> Option<(Coord<GRID_DIMENSION>, f64)> {
+        if self.bound.contains(world_coord) {
+            return None;
+        }
+        match self.resolve(world_coord, global_time, None) {
+            Resolution::Read(coord, factor) => Some((coord, factor)),
+            _ => None,
+        }
+    }
+
+    fn reads_input(&self) -> bool {
+        self.bcs.iter().any(|bc| bc.reads_input())
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+    use crate::domain::*;
+    use float_cmp::assert_approx_eq;
+    use nalgebra::{matrix, vector};
+
+    #[test]
+    fn face_bc_test() {
+        let bound = AABB::new(matrix![0, 9; 0, 4]);
+        let mut domain = OwnedDomain::new(bound);
+        domain.par_set_values(
+            |coord: Coord<2>| (10 * coord[0] + coord[1]) as f64,
+            1,
+        );
+
+        // Dirichlet at x min, reflect at x max, periodic in y
+        let bc = FaceBC::new(bound, ConstantCheck::new(-1.0, bound))
+            .with_axis(1, WrapCheck::new(bound))
+            .with_face(1, 1, AntiReflectCheck::new(bound))
+            .with_face(0, 1, ReflectCheck::new(bound));
+        let value = |x: i32, y: i32| bc_value(&bc, &domain, &vector![x, y], 0);
+
+        assert_approx_eq!(f64, value(3, 2), 32.0);
+        assert_approx_eq!(f64, value(-1, 2), -1.0);
+        assert_approx_eq!(f64, value(10, 2), 92.0);
+        assert_approx_eq!(f64, value(11, 3), 83.0);
+        assert_approx_eq!(f64, value(3, -1), 34.0);
+        assert_approx_eq!(f64, value(3, 5), -34.0);
+
+        // x max has the highest priority, then y max
+        assert_approx_eq!(f64, value(10, 5), -94.0);
+        assert_approx_eq!(f64, value(10, -1), 94.0);
+
+        // y max beats x min, then x min is Dirichlet
+        assert_approx_eq!(f64, value(-1, 5), 1.0);
+        assert_approx_eq!(f64, value(-1, -1), -1.0);
+        assert!(!bc.reads_input());
+    }
+
+    #[test]
+    fn face_bc_input_test() {
+        let bound = AABB::new(matrix![0, 9; 0, 4]);
+        let mut domain = OwnedDomain::new(bound);
+        domain.par_set_values(
+            |coord: Coord<2>| (10 * coord[0] + coord[1]) as f64,
+            1,
+        );
+
+        // Dirichlet at x min, Neumann Robin at x max, periodic in y
+        let bc = FaceBC::new(bound, ConstantCheck::new(-1.0, bound))
+            .with_axis(1, WrapCheck::new(bound))
+            .with_face(0, 1, RobinCheck::new(0.0, 2.0, 3.0, 0.5, bound));
+        let value = |x: i32, y: i32| bc_value(&bc, &domain, &vector![x, y], 0);
+
+        // Robin faces can't be resolved without the input
+        assert!(bc.check(&vector![10, 2], 0).is_none());
+        assert!(bc.remap(&vector![10, 2], 0).is_none());
+        assert!(bc.reads_input());
+
+        assert_approx_eq!(f64, value(10, 2), 92.75);
+        assert_approx_eq!(f64, value(3, -1), 34.0);
+
+        // Robin reads past the periodic faces
+        assert_approx_eq!(f64, value(10, -1), 94.75);
+        assert_approx_eq!(f64, value(10, 5), 90.75);
+        assert_approx_eq!(f64, value(-1, -1), -1.0);
+    }
+}
@@ -0,0 +1,63 @@
+use crate::domain::bc::BCCheck;
+use crate::util::*;
+
+/// Dirichlet boundary condition given by a closure,
+/// evaluated as `f(world_coord, global_time)` outside of `bound`.
+/// Useful for boundaries that vary in space or time,
+/// e.g. a heated wall or an oscillating inflow.
+pub struct FnCheck<const GRID_DIMENSION: usize, F>
+where
+    F: Fn(&Coord<GRID_DIMENSION>, usize) -> f64 + Sync,
+{
+    f: F,
+    bound: AABB<GRID_DIMENSION>,
+}
+
+impl<const GRID_DIMENSION: usize, F> FnCheck<GRID_DIMENSION, F>
+where
+    F: Fn(&Coord<GRID_DIMENSION>, usize) -> f64 + Sync,
+{
+    pub fn new(f: F, bound: AABB<GRID_DIMENSION>) -> Self {
+        FnCheck { f, bound }
+    }
+}
+
+impl<const GRID_DIMENSION: usize, F> BCCheck<GRID_DIMENSION>
+    for FnCheck<GRID_DIMENSION, F>
+where
+    F: Fn(&Coord<GRID_DIMENSION>, usize) -> f64 + Sync,
+{
+    fn check(
+        &self,
+        coord: &Coord<GRID_DIMENSION>,
+        global_time: usize,
+    ) -> Option<f64> {
+        if self.bound.contains(coord) {
+            return None;
+        }
+        Some((self.f)(coord, global_time))
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+    use float_cmp::assert_approx_eq;
+    use nalgebra::{matrix, vector};
+
+    #[test]
+    fn fn_check_test() {
+        let bound = AABB::new(matrix![0, 10; 0, 5]);
+        let bc = FnCheck::new(
+            |coord: &Coord<2>, t: usize| {
+                (coord[0] + coord[1]) as f64 + t as f64
+            },
+            bound,
+        );
+        for coord in bound.coord_iter() {
+            assert_eq!(bc.check(&coord, 3), None);
+        }
+        assert_approx_eq!(f64, bc.check(&vector![-1, 2], 0).unwrap(), 1.0);
+        assert_approx_eq!(f64, bc.check(&vector![11, 6], 4).unwrap(), 21.0);
+    }
+}
@@ -0,0 +1,189 @@
+use crate::domain::bc::BCCheck;
+use crate::domain::{DomainRead, Mask};
+use crate::util::*;
+
+/// Boundary condition on the faces of solid cells.
+#[derive(Copy, Clone, Debug)]
+pub enum ObstacleBC {
+    /// Solid cells hold a fixed value.
+    Dirichlet(f64),
+
+    /// No flux through solid faces,
+    /// solid neighbors read as the cell reading them.
+    /// Solid cells themselves are set to zero,
+    /// so stencils that conserve mass still do.
+    ZeroFlux,
+}
+
+/// Adds the obstacles of a `Mask` to another boundary condition,
+/// which still handles coordinates outside of the domain.
+/// Solid cells are not solved for, and stencils reading them
+/// see `obstacle` instead, see `BCCheck::check_solid`
+/// and `BCCheck::check_neighbor`.
+/// These are honored by `par_stencil` and the solvers built on it,
+/// and by the optimized direct solvers through `with_bc`,
+/// see `add_boundary_contributions`.
+pub struct MaskedCheck<'a, const GRID_DIMENSION: usize, BC>
+where
+    BC: BCCheck<GRID_DIMENSION>,
+{
+    bc: BC,
+    mask: &'a Mask<GRID_DIMENSION>,
+    obstacle: ObstacleBC,
+}
+
+impl<'a, const GRID_DIMENSION: usize, BC> MaskedCheck<'a, GRID_DIMENSION, BC>
+where
+    BC: BCCheck<GRID_DIMENSION>,
+{
+    pub fn new(
+        bc: BC,
+        mask: &'a Mask<GRID_DIMENSION>,
+        obstacle: ObstacleBC,
+    ) -> Self {
+        MaskedCheck { bc, mask, obstacle }
+    }
+
+    pub fn mask(&self) -> &Mask<GRID_DIMENSION> {
+        self.mask
+    }
+}
+
+impl<const GRID_DIMENSION: usize, BC> BCCheck<GRID_DIMENSION>
+    for MaskedCheck<'_, GRID_DIMENSION, BC>
+where
+    BC: BCCheck<GRID_DIMENSION>,
+{
+    fn check(
+        &self,
+        world_coord: &Coord<GRID_DIMENSION>,
+        global_time: usize,
+    ) -> Option<f64> {
+        self.bc.check(world_coord, global_time)
+    }
+
+    fn check_input(
+        &self,
+        world_coord: &Coord<GRID_DIMENSION>,
+        global_time: usize,
+        input: &dyn DomainRead<GRID_DIMENSION>,
+    ) -> Option<f64> {
+        self.bc.check_input(world_coord, global_time, input)
+    }
+
+    fn remap(
+        &self,
+        world_coord: &Coord<GRID_DIMENSION>,
+        global_time: usize,
+    ) -> Option<(Coord<GRID_DIMENSION>, f64)> {
+        self.bc.remap(world_coord, global_time)
+    }
+
+    fn check_solid(
+        &self,
+        world_coord: &Coord<GRID_DIMENSION>,
+        global_time: usize,
+    ) -> Option<f64> {
+        if !self.mask.is_solid(world_coord) {
+            return self.bc.check_solid(world_coord, global_time);
+        }
+        match self.obstacle {
+            ObstacleBC::Dirichlet(value) => Some(value),
+            ObstacleBC::ZeroFlux => Some(0.0),
+        }
+    }
+
+    fn check_neighbor(
+        &self,
+        center_coord: &Coord<GRID_DIMENSION>,
+        neighbor_coord: &Coord<GRID_DIMENSION>,
+        global_time: usize,
+        input: &dyn DomainRead<GRID_DIMENSION>,
+    ) -> Option<f64> {
+        if !self.mask.is_solid(neighbor_coord) {
+            return self.bc.check_neighbor(
+                center_coord,
+                neighbor_coord,
+                global_time,
+                input,
+            );
+        }
+        match self.obstacle {
+            ObstacleBC::Dirichlet(value) => Some(value),
+            ObstacleBC::ZeroFlux => Some(input.read(center_coord)),
+        }
+    }
+
+    fn reads_input(&self) -> bool {
+        matches!(self.obstacle, ObstacleBC::ZeroFlux) || self.bc.reads_input()
+    }
+
+    fn solid_aabb(&self) -> Option<AABB<GRID_DIMENSION>> {
+        let inner = self.bc.solid_aabb();
+        let Some(own) = self.mask.solid_bounds() else {
+            return inner;
+        };
+        Some(match inner {
+            Some(inner) => AABB::from_mm(
+                own.min().inf(&inner.min()),
+                own.max().sup(&inner.max()),
+            ),
+            None => own,
+        })
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+    use crate::domain::*;
+    use crate::stencil::*;
+    use float_cmp::assert_approx_eq;
+    use nalgebra::{matrix, vector};
+
+    #[test]
+    fn masked_check_test() {
+        let bound = AABB::new(matrix![0, 9; 0, 9]);
+        let mut domain = OwnedDomain::new(bound);
+        domain.par_set_values(
+            |coord: Coord<2>| (10 * coord[0] + coord[1]) as f64,
+            1,
+        );
+        let mask = Mask::from_aabbs(bound, &[AABB::new(matrix![4, 5; 4, 5])]);
+        let stencil = Stencil::new(
+            [[0, -1], [0, 1], [1, 0], [-1, 0], [0, 0]],
+            |_: &[f64; 5]| -1.0,
+        );
+
+        let bc = MaskedCheck::new(
+            ConstantCheck::new(-4.0, bound),
+            &mask,
+            ObstacleBC::ZeroFlux,
+        );
+        assert_eq!(bc.check_solid(&vector![4, 5], 0), Some(0.0));
+        assert_eq!(bc.check_solid(&vector![3, 5], 0), None);
+        let r = gather_args(&stencil, &bc, &domain, &vector![3, 4], 0);
+        let e = [33.0, 35.0, 34.0, 24.0, 34.0];
+        for n in 0..r.len() {
+            assert_approx_eq!(f64, r[n], e[n]);
+        }
+
+        // Still uses the outer boundary condition
+        let bc = MaskedCheck::new(
+            ConstantCheck::new(-4.0, bound),
+            &mask,
+            ObstacleBC::Dirichlet(7.0),
+        );
+        assert_eq!(bc.check_solid(&vector![5, 5], 0), Some(7.0));
+        let r = gather_args(&stencil, &bc, &domain, &vector![0, 5], 0);
+        let e = [4.0, 6.0, 15.0, -4.0, 5.0];
+        for n in 0..r.len() {
+            assert_approx_eq!(f64, r[n], e[n]);
+        }
+        let r = gather_args(&stencil, &bc, &domain, &vector![5, 6], 0);
+        let e = [7.0, 57.0, 66.0, 46.0, 56.0];
+        for n in 0..r.len() {
+            assert_approx_eq!(f64, r[n], e[n]);
+        }
+    }
+}
@@ -1,9 +1,20 @@
 mod constant;
+mod face_bc;
+mod fn_check;
+mod masked;
 mod periodic;
+mod reflect;
+mod robin;
 
 pub use constant::*;
+pub use face_bc::*;
+pub use fn_check::*;
+pub use masked::*;
 pub use periodic::*;
+pub use reflect::*;
+pub use robin::*;
 
+use crate::domain::{DomainRead, DomainView};
 use crate::util::*;
 
 pub trait BCCheck<const GRID_DIMENSION: usize>: Sync {
@@ -12,4 +23,101 @@ pub trait BCCheck<const GRID_DIMENSION: usize>: Sync {
         world_coord: &Coord<GRID_DIMENSION>,
         global_time: usize,
     ) -> Option<f64>;
+
+    /// For boundary conditions whose values are computed from the input,
+    /// like `RobinCheck`, where ghost values depend on interior neighbors.
+    /// Only used when `check` returns `None`.
+    fn check_input(
+        &self,
+        _world_coord: &Coord<GRID_DIMENSION>,
+        _global_time: usize,
+        _input: &dyn DomainRead<GRID_DIMENSION>,
+    ) -> Option<f64> {
+        None
+    }
+
+    /// For boundary conditions that depend on the input itself,
+    /// like `ReflectCheck`, map a coordinate outside of the domain
+    /// to the coordinate to read instead, and a factor to scale it by.
+    /// Only used when both `check` and `check_input` return `None`.
+    fn remap(
+        &self,
+        _world_coord: &Coord<GRID_DIMENSION>,
+        _global_time: usize,
+    ) -> Option<(Coord<GRID_DIMENSION>, f64)> {
+        None
+    }
+
+    /// For boundaries inside the domain, like the obstacles of `MaskedCheck`.
+    /// Cells this returns `Some` for aren't solved for,
+    /// they are set to the returned value instead.
+    fn check_solid(
+        &self,
+        _world_coord: &Coord<GRID_DIMENSION>,
+        _global_time: usize,
+    ) -> Option<f64> {
+        None
+    }
+
+    /// For boundaries inside the domain, where the value of a neighbor
+    /// depends on the cell reading it, e.g. zero flux obstacle faces.
+    /// Used before any of the other checks.
+    fn check_neighbor(
+        &self,
+        _center_coord: &Coord<GRID_DIMENSION>,
+        _neighbor_coord: &Coord<GRID_DIMENSION>,
+        _global_time: usize,
+        _input: &dyn DomainRead<GRID_DIMENSION>,
+    ) -> Option<f64> {
+        None
+    }
+
+    /// Bounds every coordinate `check_solid` or `check_neighbor`
+    /// may return `Some` for, `None` if there are none.
+    /// Lets the optimized direct solvers only visit cells near obstacles.
+    fn solid_aabb(&self) -> Option<AABB<GRID_DIMENSION>> {
+        None
+    }
+
+    /// Whether `check_input` or `check_neighbor` read their input.
+    /// Those reads go through `DomainRead`, which only sees the real part
+    /// of complex domains, so solving these for `c64` is rejected.
+    fn reads_input(&self) -> bool {
+        false
+    }
+}
+
+/// Resolve the value at `world_coord`,
+/// either from `bc` or by reading `input`.
+/// Boundary values are real,
+/// `bc` must not read its input for complex domains,
+/// see `BCCheck::reads_input`.
+#[inline]
+pub fn bc_value<
+    BC,
+    const GRID_DIMENSION: usize,
+    T: Scalar,
+    DomainType: DomainView<GRID_DIMENSION, T>,
+>(
+    bc: &BC,
+    input: &DomainType,
+    world_coord: &Coord<GRID_DIMENSION>,
+    global_time: usize,
+) -> T::Weight
+where
+    BC: BCCheck<GRID_DIMENSION>,
+{
+    debug_assert!(!T::COMPLEX || !bc.reads_input());
+    if let Some(value) = bc.check(world_coord, global_time) {
+        return value.into();
+    }
+    if let Some(value) = bc.check_input(world_coord, global_time, input) {
+        return value.into();
+    }
+    match bc.remap(world_coord, global_time) {
+        Some((coord, factor)) => {
+            T::Weight::from(factor) * input.view(&coord).to_weight()
+        }
+        None => input.view(world_coord).to_weight(),
+    }
 }
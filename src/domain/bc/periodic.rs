@@ -36,6 +36,43 @@ impl<const GRID_DIMENSION: usize, DomainType: DomainView<GRID_DIMENSION>>
     }
 }
 
+/// Periodic boundary condition that doesn't hold on to a domain,
+/// coordinates outside of `bound` are wrapped around instead.
+/// Unlike `PeriodicCheck` this can be shared between time steps,
+/// e.g. by `GeneralDirectBoxSolver`, or used for some faces of a `FaceBC`.
+pub struct WrapCheck<const GRID_DIMENSION: usize> {
+    bound: AABB<GRID_DIMENSION>,
+}
+
+impl<const GRID_DIMENSION: usize> WrapCheck<GRID_DIMENSION> {
+    pub fn new(bound: AABB<GRID_DIMENSION>) -> Self {
+        WrapCheck { bound }
+    }
+}
+
+impl<const GRID_DIMENSION: usize> BCCheck<GRID_DIMENSION>
+    for WrapCheck<GRID_DIMENSION>
+{
+    fn check(
+        &self,
+        _world_coord: &Coord<GRID_DIMENSION>,
+        _global_time: usize,
+    ) -> Option<f64> {
+        None
+    }
+
+    fn remap(
+        &self,
+        world_coord: &Coord<GRID_DIMENSION>,
+        _global_time: usize,
+    ) -> Option<(Coord<GRID_DIMENSION>, f64)> {
+        if self.bound.contains(world_coord) {
+            return None;
+        }
+        Some((self.bound.periodic_coord(world_coord), 1.0))
+    }
+}
+
 #[cfg(test)]
 mod unit_tests {
     use super::*;
@@ -68,4 +105,15 @@ mod unit_tests {
             }
         }
     }
+
+    #[test]
+    fn wrap_check_test() {
+        let aabb = AABB::new(matrix![0, 10]);
+        let mut domain = OwnedDomain::new(aabb);
+        domain.par_set_values(|coord| coord[0] as f64, 1);
+        let bc = WrapCheck::new(aabb);
+        assert_eq!(bc.remap(&vector![3], 0), None);
+        assert_approx_eq!(f64, bc_value(&bc, &domain, &vector![-1], 0), 10.0);
+        assert_approx_eq!(f64, bc_value(&bc, &domain, &vector![11], 0), 0.0);
+    }
 }
@@ -0,0 +1,146 @@
+use crate::domain::bc::BCCheck;
+use crate::util::*;
+
+/// Mirror a coordinate across the faces of `bound`.
+/// The mirror sits half a cell outside the boundary,
+/// so `min - 1` maps to `min`, `min - 2` to `min + 1`, and so on.
+/// Assumes that coords are no more than one box away!
+pub fn reflect_coord<const GRID_DIMENSION: usize>(
+    bound: &AABB<GRID_DIMENSION>,
+    coord: &Coord<GRID_DIMENSION>,
+) -> Coord<GRID_DIMENSION> {
+    let mut result = *coord;
+    for d in 0..GRID_DIMENSION {
+        let min = bound.bounds[(d, 0)];
+        let max = bound.bounds[(d, 1)];
+        if coord[d] < min {
+            result[d] = 2 * min - 1 - coord[d];
+        } else if coord[d] > max {
+            result[d] = 2 * max + 1 - coord[d];
+        }
+    }
+    result
+}
+
+/// Zero flux (homogeneous Neumann) boundary condition.
+/// Values outside of `bound` mirror those inside,
+/// so stencils that conserve mass still do.
+pub struct ReflectCheck<const GRID_DIMENSION: usize> {
+    bound: AABB<GRID_DIMENSION>,
+}
+
+impl<const GRID_DIMENSION: usize> ReflectCheck<GRID_DIMENSION> {
+    pub fn new(bound: AABB<GRID_DIMENSION>) -> Self {
+        ReflectCheck { bound }
+    }
+}
+
+impl<const GRID_DIMENSION: usize> BCCheck<GRID_DIMENSION>
+    for ReflectCheck<GRID_DIMENSION>
+{
+    fn check(
+        &self,
+        _coord: &Coord<GRID_DIMENSION>,
+        _global_time: usize,
+    ) -> Option<f64> {
+        None
+    }
+
+    fn remap(
+        &self,
+        coord: &Coord<GRID_DIMENSION>,
+        _global_time: usize,
+    ) -> Option<(Coord<GRID_DIMENSION>, f64)> {
+        if self.bound.contains(coord) {
+            return None;
+        }
+        Some((reflect_coord(&self.bound, coord), 1.0))
+    }
+}
+
+/// Odd symmetry counterpart of `ReflectCheck`,
+/// values outside of `bound` are the negated mirror of those inside.
+/// This puts a zero Dirichlet boundary halfway between cells.
+pub struct AntiReflectCheck<const GRID_DIMENSION: usize> {
+    bound: AABB<GRID_DIMENSION>,
+}
+
+impl<const GRID_DIMENSION: usize> AntiReflectCheck<GRID_DIMENSION> {
+    pub fn new(bound: AABB<GRID_DIMENSION>) -> Self {
+        AntiReflectCheck { bound }
+    }
+}
+
+impl<const GRID_DIMENSION: usize> BCCheck<GRID_DIMENSION>
+    for AntiReflectCheck<GRID_DIMENSION>
+{
+    fn check(
+        &self,
+        _coord: &Coord<GRID_DIMENSION>,
+        _global_time: usize,
+    ) -> Option<f64> {
+        None
+    }
+
+    fn remap(
+        &self,
+        coord: &Coord<GRID_DIMENSION>,
+        _global_time: usize,
+    ) -> Option<(Coord<GRID_DIMENSION>, f64)> {
+        if self.bound.contains(coord) {
+            return None;
+        }
+        let reflected = reflect_coord(&self.bound, coord);
+        // Odd in each axis we reflected across
+        let mut factor = 1.0;
+        for d in 0..GRID_DIMENSION {
+            if reflected[d] != coord[d] {
+                factor = -factor;
+            }
+        }
+        Some((reflected, factor))
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+    use crate::domain::*;
+    use float_cmp::assert_approx_eq;
+    use nalgebra::{matrix, vector};
+
+    #[test]
+    fn reflect_coord_test() {
+        let bound = AABB::new(matrix![0, 9; 5, 7]);
+        assert_eq!(reflect_coord(&bound, &vector![3, 6]), vector![3, 6]);
+        assert_eq!(reflect_coord(&bound, &vector![-1, 6]), vector![0, 6]);
+        assert_eq!(reflect_coord(&bound, &vector![-2, 4]), vector![1, 5]);
+        assert_eq!(reflect_coord(&bound, &vector![10, 9]), vector![9, 6]);
+    }
+
+    #[test]
+    fn reflect_check_test() {
+        let bound = AABB::new(matrix![0, 10]);
+        let mut domain = OwnedDomain::new(bound);
+        domain.par_set_values(|coord| coord[0] as f64 + 1.0, 1);
+
+        let bc = ReflectCheck::new(bound);
+        assert_eq!(bc.remap(&vector![4], 0), None);
+        assert_approx_eq!(f64, bc_value(&bc, &domain, &vector![4], 0), 5.0);
+        assert_approx_eq!(f64, bc_value(&bc, &domain, &vector![-1], 0), 1.0);
+        assert_approx_eq!(f64, bc_value(&bc, &domain, &vector![12], 0), 10.0);
+
+        let bc = AntiReflectCheck::new(bound);
+        assert_approx_eq!(f64, bc_value(&bc, &domain, &vector![4], 0), 5.0);
+        assert_approx_eq!(f64, bc_value(&bc, &domain, &vector![-1], 0), -1.0);
+        assert_approx_eq!(f64, bc_value(&bc, &domain, &vector![11], 0), -11.0);
+    }
+
+    #[test]
+    fn anti_reflect_corner() {
+        let bound = AABB::new(matrix![0, 3; 0, 3]);
+        let bc = AntiReflectCheck::new(bound);
+        assert_eq!(bc.remap(&vector![-1, -1], 0), Some((vector![0, 0], 1.0)));
+        assert_eq!(bc.remap(&vector![-1, 2], 0), Some((vector![0, 2], -1.0)));
+    }
+}
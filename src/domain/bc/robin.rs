@@ -0,0 +1,146 @@
+use crate::domain::bc::BCCheck;
+use crate::domain::DomainRead;
+use crate::util::*;
+
+/// Robin boundary condition, `a * u + b * du/dn = g` on the faces of `bound`,
+/// where `n` is the outward normal, e.g. convective heat transfer
+/// `h * u + k * du/dn = h * u_ambient`.
+///
+/// Like `ReflectCheck` the boundary sits half a cell outside of `bound`.
+/// Ghost values are found from their mirror image inside the domain
+/// by making the boundary condition hold between the two of them,
+/// `b = 0` gives Dirichlet and `a = 0` Neumann boundaries.
+/// Edges and corners apply this one axis at a time.
+pub struct RobinCheck<const GRID_DIMENSION: usize> {
+    a: f64,
+    b: f64,
+    g: f64,
+    dx: f64,
+    bound: AABB<GRID_DIMENSION>,
+}
+
+impl<const GRID_DIMENSION: usize> RobinCheck<GRID_DIMENSION> {
+    /// `a` and `b` should be non-negative, and not both zero.
+    /// `dx` is the grid spacing.
+    pub fn new(
+        a: f64,
+        b: f64,
+        g: f64,
+        dx: f64,
+        bound: AABB<GRID_DIMENSION>,
+    ) -> Self {
+        debug_assert!(a >= 0.0 && b >= 0.0 && a + b > 0.0);
+        debug_assert!(dx > 0.0);
+        RobinCheck { a, b, g, dx, bound }
+    }
+
+    /// The ghost value `depth` cells outside of the domain is
+    /// `beta + alpha * u_mirror`, returns `(alpha, beta)`.
+    fn ghost_coefficients(&self, depth: i32) -> (f64, f64) {
+        // Distance between the ghost and its mirror image
+        let gap = (2 * depth - 1) as f64 * self.dx;
+        let denominator = 0.5 * self.a + self.b / gap;
+        let alpha = (self.b / gap - 0.5 * self.a) / denominator;
+        let beta = self.g / denominator;
+        (alpha, beta)
+    }
+}
+
+impl<const GRID_DIMENSION: usize> BCCheck<GRID_DIMENSION>
+    for RobinCheck<GRID_DIMENSION>
+{
+    fn check(
+        &self,
+        _world_coord: &Coord<GRID_DIMENSION>,
+        _global_time: usize,
+    ) -> Option<f64> {
+        None
+    }
+
+    fn check_input(
+        &self,
+        world_coord: &Coord<GRID_DIMENSION>,
+        _global_time: usize,
+        input: &dyn DomainRead<GRID_DIMENSION>,
+    ) -> Option<f64> {
+        if self.bound.contains(world_coord) {
+            return None;
+        }
+
+        // Ghost value is shift + scale * input at the mirror coordinate
+        let mut mirror = *world_coord;
+        let mut shift = 0.0;
+        let mut scale = 1.0;
+        for d in 0..GRID_DIMENSION {
+            let min = self.bound.bounds[(d, 0)];
+            let max = self.bound.bounds[(d, 1)];
+            let depth = if mirror[d] < min {
+                mirror[d] = 2 * min - 1 - mirror[d];
+                min - world_coord[d]
+            } else if mirror[d] > max {
+                mirror[d] = 2 * max + 1 - mirror[d];
+                world_coord[d] - max
+            } else {
+                continue;
+            };
+            let (alpha, beta) = self.ghost_coefficients(depth);
+            shift += scale * beta;
+            scale *= alpha;
+        }
+        Some(shift + scale * input.read(&mirror))
+    }
+
+    fn reads_input(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+    use crate::domain::*;
+    use float_cmp::assert_approx_eq;
+    use nalgebra::{matrix, vector};
+
+    #[test]
+    fn robin_check_test() {
+        let bound = AABB::new(matrix![0, 9; 0, 4]);
+        let mut domain = OwnedDomain::new(bound);
+        domain.par_set_values(
+            |coord: Coord<2>| (10 * coord[0] + coord[1]) as f64,
+            1,
+        );
+        let value = |bc: &RobinCheck<2>, x: i32, y: i32| {
+            bc_value(bc, &domain, &vector![x, y], 0)
+        };
+
+        // Inside is left alone
+        let bc = RobinCheck::new(1.0, 1.0, 1.0, 0.5, bound);
+        assert_approx_eq!(f64, value(&bc, 3, 2), 32.0);
+
+        // Dirichlet, the boundary is the average of ghost and mirror
+        let bc = RobinCheck::new(2.0, 0.0, 3.0, 0.5, bound);
+        assert_approx_eq!(f64, value(&bc, -1, 2), 3.0 - 2.0);
+        assert_approx_eq!(f64, value(&bc, 10, 2), 3.0 - 92.0);
+        assert_approx_eq!(f64, value(&bc, 3, -2), 3.0 - 31.0);
+
+        // Neumann, the outward slope is g / b
+        let bc = RobinCheck::new(0.0, 2.0, 3.0, 0.5, bound);
+        assert_approx_eq!(f64, value(&bc, -1, 2), 2.0 + 1.5 * 0.5);
+        assert_approx_eq!(f64, value(&bc, 10, 2), 92.0 + 1.5 * 0.5);
+        assert_approx_eq!(f64, value(&bc, 11, 2), 82.0 + 1.5 * 1.5);
+        assert_approx_eq!(f64, value(&bc, 10, 5), 94.0 + 1.5);
+
+        // Mixed, a * (u_g + u_m) / 2 + b * (u_g - u_m) / dx = g
+        let (a, b, g, dx) = (2.0, 1.0, 5.0, 0.5);
+        let bc = RobinCheck::new(a, b, g, dx, bound);
+        let ghost = value(&bc, 10, 3);
+        let mirror = 93.0;
+        assert_approx_eq!(
+            f64,
+            a * 0.5 * (ghost + mirror) + b * (ghost - mirror) / dx,
+            g,
+            epsilon = 1e-12
+        );
+    }
+}
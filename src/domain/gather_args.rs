@@ -1,5 +1,5 @@
 use crate::domain::*;
-use crate::stencil::TVStencil;
+use crate::stencil::{SpatialStencil, TVStencil};
 use crate::util::*;
 
 pub fn gather_args<
@@ -18,14 +18,76 @@ pub fn gather_args<
 where
     BC: BCCheck<GRID_DIMENSION>,
 {
-    let mut result = Values::zero();
-    for (i, n_i) in stencil.offsets().iter().enumerate() {
+    let mut result: Values<NEIGHBORHOOD_SIZE> = Values::zero();
+    gather_args_into(
+        stencil.offsets(),
+        bc,
+        input,
+        world_coord,
+        global_time,
+        result.as_mut_slice(),
+    );
+    result
+}
+
+/// Same as `gather_args`, but for spatially varying stencils.
+pub fn gather_spatial_args<
+    BC,
+    const GRID_DIMENSION: usize,
+    const NEIGHBORHOOD_SIZE: usize,
+    DomainType: DomainView<GRID_DIMENSION>,
+    StencilType: SpatialStencil<GRID_DIMENSION, NEIGHBORHOOD_SIZE>,
+>(
+    stencil: &StencilType,
+    bc: &BC,
+    input: &DomainType,
+    world_coord: &Coord<GRID_DIMENSION>,
+    global_time: usize,
+) -> Values<NEIGHBORHOOD_SIZE>
+where
+    BC: BCCheck<GRID_DIMENSION>,
+{
+    let mut result: Values<NEIGHBORHOOD_SIZE> = Values::zero();
+    gather_args_into(
+        stencil.offsets(),
+        bc,
+        input,
+        world_coord,
+        global_time,
+        result.as_mut_slice(),
+    );
+    result
+}
+
+/// Same as `gather_args`, but for neighborhoods whose size
+/// is only known at runtime, e.g. `DynStencil`.
+/// `result` must be the same length as `offsets`.
+/// Values are gathered in terms of `T::Weight`, see `Scalar`.
+pub fn gather_args_into<
+    BC,
+    const GRID_DIMENSION: usize,
+    T: Scalar,
+    DomainType: DomainView<GRID_DIMENSION, T>,
+>(
+    offsets: &[Coord<GRID_DIMENSION>],
+    bc: &BC,
+    input: &DomainType,
+    world_coord: &Coord<GRID_DIMENSION>,
+    global_time: usize,
+    result: &mut [T::Weight],
+) where
+    BC: BCCheck<GRID_DIMENSION>,
+{
+    debug_assert_eq!(offsets.len(), result.len());
+    for (i, n_i) in offsets.iter().enumerate() {
         let n_world_coord = world_coord + n_i;
         result[i] = bc
-            .check(&n_world_coord, global_time)
-            .unwrap_or_else(|| input.view(&n_world_coord));
+            .check_neighbor(world_coord, &n_world_coord, global_time, input)
+            .map(T::Weight::from)
+            .unwrap_or_else(|| {
+                bc_value(bc, input, &n_world_coord, global_time)
+            });
     }
-    result
 }
 
 #[cfg(test)]
@@ -89,4 +151,30 @@ mod unit_tests {
             assert_approx_eq!(f64, r[n], e[n]);
         }
     }
+
+    #[test]
+    fn gather_args_into_test_dyn() {
+        let bound = AABB::new(matrix![0, 9; 0, 9]);
+        let mut domain = OwnedDomain::new(bound);
+        domain.par_set_values(
+            |coord: Coord<2>| (coord[0] + 3 * coord[1]) as f64,
+            1,
+        );
+        let bc = ConstantCheck::new(-4.0, bound);
+        let stencil =
+            DynStencil::new(&[[0, -1], [2, 0], [0, 0]], |_: &[f64]| -1.0);
+        let mut r = vec![0.0; stencil.len()];
+        gather_args_into(
+            stencil.offsets(),
+            &bc,
+            &domain,
+            &vector![8, 9],
+            0,
+            &mut r,
+        );
+        let e = [(8 + 3 * 8) as f64, -4.0, (8 + 3 * 9) as f64];
+        for n in 0..r.len() {
+            assert_approx_eq!(f64, r[n], e[n]);
+        }
+    }
 }
@@ -0,0 +1,175 @@
+use crate::util::*;
+
+/// Marks cells of a domain as solid, e.g. obstacles inside a
+/// rectangular grid. See `MaskedCheck` for how solvers treat them.
+///
+/// Keeps a table of solid cell counts so that asking
+/// whether a sub-AABB is obstacle free is cheap,
+/// which the AP planner does a lot.
+pub struct Mask<const GRID_DIMENSION: usize> {
+    aabb: AABB<GRID_DIMENSION>,
+    solid: Vec<bool>,
+
+    /// Number of solid cells between the min corner and each coordinate,
+    /// with an extra row of zeros in front along each axis.
+    counts_aabb: AABB<GRID_DIMENSION>,
+    counts: Vec<usize>,
+
+    /// Smallest AABB holding every solid cell.
+    solid_bounds: Option<AABB<GRID_DIMENSION>>,
+}
+
+impl<const GRID_DIMENSION: usize> Mask<GRID_DIMENSION> {
+    /// Every cell is solid where `is_solid` returns true.
+    pub fn from_fn<F: Fn(Coord<GRID_DIMENSION>) -> bool>(
+        aabb: AABB<GRID_DIMENSION>,
+        is_solid: F,
+    ) -> Self {
+        let solid: Vec<bool> = aabb.coord_iter().map(is_solid).collect();
+
+        let counts_aabb =
+            AABB::from_exclusive_bounds(&aabb.exclusive_bounds().add_scalar(1));
+        let mut counts = vec![0; counts_aabb.buffer_size()];
+        let mut solid_bounds: Option<AABB<GRID_DIMENSION>> = None;
+        for (i, coord) in aabb.coord_iter().enumerate() {
+            let count_coord = coord - aabb.min() + Coord::repeat(1);
+            counts[counts_aabb.coord_to_linear(&count_coord)] =
+                solid[i] as usize;
+            if solid[i] {
+                solid_bounds = Some(match solid_bounds {
+                    Some(b) => {
+                        AABB::from_mm(b.min().inf(&coord), b.max().sup(&coord))
+                    }
+                    None => AABB::from_mm(coord, coord),
+                });
+            }
+        }
+
+        // Prefix sums along each axis in turn,
+        // in linear order the previous coord is always done already
+        for d in 0..GRID_DIMENSION {
+            for i in 0..counts.len() {
+                let mut coord = counts_aabb.linear_to_coord(i);
+                if coord[d] == 0 {
+                    continue;
+                }
+                coord[d] -= 1;
+                counts[i] += counts[counts_aabb.coord_to_linear(&coord)];
+            }
+        }
+
+        Mask {
+            aabb,
+            solid,
+            counts_aabb,
+            counts,
+            solid_bounds,
+        }
+    }
+
+    /// Every cell inside one of the `obstacles` is solid.
+    pub fn from_aabbs(
+        aabb: AABB<GRID_DIMENSION>,
+        obstacles: &[AABB<GRID_DIMENSION>],
+    ) -> Self {
+        Self::from_fn(aabb, |coord| {
+            obstacles.iter().any(|obstacle| obstacle.contains(&coord))
+        })
+    }
+
+    pub fn aabb(&self) -> &AABB<GRID_DIMENSION> {
+        &self.aabb
+    }
+
+    /// Smallest AABB holding every solid cell, `None` if there are none.
+    pub fn solid_bounds(&self) -> Option<AABB<GRID_DIMENSION>> {
+        self.solid_bounds
+    }
+
+    /// Coordinates outside of the mask are never solid.
+    pub fn is_solid(&self, world_coord: &Coord<GRID_DIMENSION>) -> bool {
+        self.aabb.contains(world_coord)
+            && self.solid[self.aabb.coord_to_linear(world_coord)]
+    }
+
+    /// Number of solid cells in `aabb`.
+    pub fn solid_count(&self, aabb: &AABB<GRID_DIMENSION>) -> usize {
+        if !self.aabb.intersects(aabb) {
+            return 0;
+        }
+        let mut clipped = *aabb;
+        clipped.trim_to_aabb(&self.aabb);
+        let lower = clipped.min() - self.aabb.min();
+        let upper = clipped.max() - self.aabb.min() + Coord::repeat(1);
+
+        // Inclusion-exclusion over the corners
+        let mut added = 0;
+        let mut removed = 0;
+        for corner in 0..(1 << GRID_DIMENSION) {
+            let mut coord = upper;
+            let mut n_lower = 0;
+            for d in 0..GRID_DIMENSION {
+                if corner & (1 << d) != 0 {
+                    coord[d] = lower[d];
+                    n_lower += 1;
+                }
+            }
+            let count = self.counts[self.counts_aabb.coord_to_linear(&coord)];
+            if n_lower & 1 == 0 {
+                added += count;
+            } else {
+                removed += count;
+            }
+        }
+        added - removed
+    }
+
+    /// Check whether `aabb` is free of obstacles.
+    pub fn is_clear(&self, aabb: &AABB<GRID_DIMENSION>) -> bool {
+        self.solid_count(aabb) == 0
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn solid_count_test() {
+        let aabb = AABB::new(matrix![-3, 9; 2, 11]);
+        let is_solid = |coord: Coord<2>| (coord[0] * 7 + coord[1] * 3) % 5 == 1;
+        let mask = Mask::from_fn(aabb, is_solid);
+        let queries = [
+            aabb,
+            AABB::new(matrix![-3, -3; 2, 2]),
+            AABB::new(matrix![0, 4; 5, 11]),
+            AABB::new(matrix![-10, 2; 8, 20]),
+            AABB::new(matrix![20, 30; 2, 11]),
+        ];
+        for query in queries {
+            let expected = aabb
+                .coord_iter()
+                .filter(|coord| query.contains(coord) && is_solid(*coord))
+                .count();
+            assert_eq!(mask.solid_count(&query), expected);
+        }
+        assert!(!mask.is_solid(&vector![-4, 2]));
+    }
+
+    #[test]
+    fn is_clear_test() {
+        let aabb = AABB::new(matrix![0, 19; 0, 19; 0, 9]);
+        let obstacle = AABB::new(matrix![5, 7; 10, 12; 3, 3]);
+        let mask = Mask::from_aabbs(aabb, &[obstacle]);
+        assert_eq!(mask.solid_count(&aabb), 9);
+        assert!(mask.is_solid(&vector![6, 11, 3]));
+        assert!(!mask.is_solid(&vector![6, 11, 4]));
+        assert!(mask.is_clear(&AABB::new(matrix![0, 19; 0, 9; 0, 9])));
+        assert!(mask.is_clear(&AABB::new(matrix![0, 19; 0, 19; 4, 9])));
+        assert!(!mask.is_clear(&AABB::new(matrix![7, 8; 12, 13; 0, 3])));
+        assert_eq!(mask.solid_bounds(), Some(obstacle));
+
+        let mask = Mask::from_aabbs(aabb, &[]);
+        assert_eq!(mask.solid_bounds(), None);
+    }
+}
@@ -7,8 +7,12 @@
 
 mod bc;
 mod gather_args;
+mod mask;
+mod multi_field;
 mod view;
 
 pub use bc::*;
 pub use gather_args::*;
+pub use mask::*;
+pub use multi_field::*;
 pub use view::*;
@@ -0,0 +1,77 @@
+use crate::domain::*;
+use crate::util::*;
+
+/// Domain for systems of coupled fields,
+/// one `OwnedDomain` per field over the same AABB.
+pub struct MultiFieldDomain<const GRID_DIMENSION: usize> {
+    fields: Vec<OwnedDomain<GRID_DIMENSION>>,
+}
+
+impl<const GRID_DIMENSION: usize> MultiFieldDomain<GRID_DIMENSION> {
+    pub fn new(aabb: AABB<GRID_DIMENSION>, n_fields: usize) -> Self {
+        assert!(n_fields > 0);
+        MultiFieldDomain {
+            fields: (0..n_fields).map(|_| OwnedDomain::new(aabb)).collect(),
+        }
+    }
+
+    pub fn aabb(&self) -> &AABB<GRID_DIMENSION> {
+        self.fields[0].aabb()
+    }
+
+    /// Set the AABB of every field,
+    /// current buffers must be large enough!
+    pub fn set_aabb(&mut self, aabb: AABB<GRID_DIMENSION>) {
+        for field in self.fields.iter_mut() {
+            field.set_aabb(aabb);
+        }
+    }
+
+    pub fn n_fields(&self) -> usize {
+        self.fields.len()
+    }
+
+    pub fn field(&self, i: usize) -> &OwnedDomain<GRID_DIMENSION> {
+        &self.fields[i]
+    }
+
+    pub fn field_mut(&mut self, i: usize) -> &mut OwnedDomain<GRID_DIMENSION> {
+        &mut self.fields[i]
+    }
+
+    pub fn fields(&self) -> &[OwnedDomain<GRID_DIMENSION>] {
+        &self.fields
+    }
+
+    pub fn fields_mut(&mut self) -> &mut [OwnedDomain<GRID_DIMENSION>] {
+        &mut self.fields
+    }
+
+    /// Values of every field at the given world coord.
+    pub fn view(&self, world_coord: &Coord<GRID_DIMENSION>) -> Vec<f64> {
+        self.fields.iter().map(|f| f.view(world_coord)).collect()
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+    use nalgebra::{matrix, vector};
+
+    #[test]
+    fn multi_field() {
+        let aabb = AABB::new(matrix![0, 9; 0, 4]);
+        let mut domain = MultiFieldDomain::new(aabb, 3);
+        assert_eq!(domain.n_fields(), 3);
+        assert_eq!(domain.aabb(), &aabb);
+        for (i, field) in domain.fields_mut().iter_mut().enumerate() {
+            field.par_set_values(move |_| i as f64, 4);
+        }
+        assert_eq!(domain.view(&vector![3, 2]), vec![0.0, 1.0, 2.0]);
+
+        let smaller = AABB::new(matrix![0, 4; 0, 4]);
+        domain.set_aabb(smaller);
+        assert_eq!(domain.field(2).aabb(), &smaller);
+        assert_eq!(domain.field_mut(1).buffer().len(), 25);
+    }
+}
@@ -1,16 +1,18 @@
 use crate::util::*;
 
-pub struct DomainChunk<'a, const GRID_DIMENSION: usize> {
+pub struct DomainChunk<'a, const GRID_DIMENSION: usize, T: Scalar = f64> {
     offset: usize,
     aabb: &'a AABB<GRID_DIMENSION>,
-    buffer: &'a mut [f64],
+    buffer: &'a mut [T],
 }
 
-impl<'a, const GRID_DIMENSION: usize> DomainChunk<'a, GRID_DIMENSION> {
+impl<'a, const GRID_DIMENSION: usize, T: Scalar>
+    DomainChunk<'a, GRID_DIMENSION, T>
+{
     pub fn new(
         offset: usize,
         aabb: &'a AABB<GRID_DIMENSION>,
-        buffer: &'a mut [f64],
+        buffer: &'a mut [T],
     ) -> Self {
         DomainChunk {
             offset,
@@ -21,11 +23,11 @@ impl<'a, const GRID_DIMENSION: usize> DomainChunk<'a, GRID_DIMENSION> {
 
     pub fn coord_iter_mut(
         &mut self,
-    ) -> impl Iterator<Item = (Coord<GRID_DIMENSION>, &mut f64)> {
+    ) -> impl Iterator<Item = (Coord<GRID_DIMENSION>, &mut T)> {
         self.buffer
             .iter_mut()
             .enumerate()
-            .map(|(i, v): (usize, &mut f64)| {
+            .map(|(i, v): (usize, &mut T)| {
                 let linear_index = self.offset + i;
                 let coord = self.aabb.linear_to_coord(linear_index);
                 (coord, v)
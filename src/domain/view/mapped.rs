@@ -0,0 +1,293 @@
+use super::*;
+use crate::ap_solver::scratch::Scratch;
+use crate::mem_fmt::human_readable_bytes;
+use crate::util::*;
+use std::fs::{File, OpenOptions};
+use std::marker::PhantomData;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Used to name the files of `create_in`.
+static MAPPED_FILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Bytes of `map` currently held in RAM.
+pub fn resident_bytes(map: &memmap2::MmapMut) -> usize {
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+    let len = map.len();
+    if len == 0 {
+        return 0;
+    }
+    let mut residency = vec![0u8; len.div_ceil(page_size)];
+    let result = unsafe {
+        libc::mincore(
+            map.as_ptr() as *mut libc::c_void,
+            len,
+            residency.as_mut_ptr() as _,
+        )
+    };
+    assert_eq!(result, 0, "ERROR: mincore failed");
+    let resident_pages = residency.iter().filter(|p| *p & 1 == 1).count();
+    (resident_pages * page_size).min(len)
+}
+
+/// Report lines shared by everything memory-mapped.
+pub fn print_residency(mapped: usize, resident: usize) {
+    println!("  - mapped size: {}", human_readable_bytes(mapped));
+    println!(
+        "  - resident: {} ({:.1}%)",
+        human_readable_bytes(resident),
+        100.0 * resident as f64 / mapped.max(1) as f64
+    );
+}
+
+/// Domain backed by a memory-mapped file, for grids that don't fit in RAM.
+/// The OS pages values in and out as they are accessed,
+/// so solves over these stream from disk.
+/// Use `as_slice_domain` to hand them to solvers.
+///
+/// Like any memory-mapped file, changes made to the file by
+/// other processes while it is mapped are undefined behavior.
+pub struct MappedDomain<const GRID_DIMENSION: usize, T: Scalar = f64> {
+    aabb: AABB<GRID_DIMENSION>,
+    map: memmap2::MmapMut,
+    scalar_marker: PhantomData<T>,
+}
+
+impl<const GRID_DIMENSION: usize> MappedDomain<GRID_DIMENSION> {
+    /// Creates the file at `path`, or truncates it, sized for `aabb`.
+    pub fn create<P: AsRef<Path>>(
+        path: P,
+        aabb: AABB<GRID_DIMENSION>,
+    ) -> std::io::Result<Self> {
+        Self::create_scalar(path, aabb)
+    }
+
+    /// Maps an existing file, e.g. one written by an earlier run.
+    /// Fails if the file isn't sized for `aabb`.
+    pub fn open<P: AsRef<Path>>(
+        path: P,
+        aabb: AABB<GRID_DIMENSION>,
+    ) -> std::io::Result<Self> {
+        Self::open_scalar(path, aabb)
+    }
+
+    /// Maps a new file in `dir`, which is removed right away,
+    /// the space is freed once the domain is dropped.
+    pub fn create_in<P: AsRef<Path>>(
+        dir: P,
+        aabb: AABB<GRID_DIMENSION>,
+    ) -> std::io::Result<Self> {
+        Self::create_in_scalar(dir, aabb)
+    }
+}
+
+impl<const GRID_DIMENSION: usize, T: Scalar> MappedDomain<GRID_DIMENSION, T> {
+    /// Same as `create`, for domains that don't store `f64`.
+    pub fn create_scalar<P: AsRef<Path>>(
+        path: P,
+        aabb: AABB<GRID_DIMENSION>,
+    ) -> std::io::Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        file.set_len(Self::file_size(&aabb))?;
+        Self::map(file, aabb)
+    }
+
+    /// Same as `open`, for domains that don't store `f64`.
+    pub fn open_scalar<P: AsRef<Path>>(
+        path: P,
+        aabb: AABB<GRID_DIMENSION>,
+    ) -> std::io::Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).open(&path)?;
+        let expected = Self::file_size(&aabb);
+        let actual = file.metadata()?.len();
+        if actual != expected {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "{:?} has {actual} bytes, expected {expected} for {aabb:?}",
+                    path.as_ref()
+                ),
+            ));
+        }
+        Self::map(file, aabb)
+    }
+
+    /// Same as `create_in`, for domains that don't store `f64`.
+    pub fn create_in_scalar<P: AsRef<Path>>(
+        dir: P,
+        aabb: AABB<GRID_DIMENSION>,
+    ) -> std::io::Result<Self> {
+        let id = MAPPED_FILE_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let path = dir
+            .as_ref()
+            .join(format!("nhls_{}_{id}.domain", std::process::id()));
+        let result = Self::create_scalar(&path, aabb)?;
+        // The mapping keeps the data alive
+        std::fs::remove_file(&path)?;
+        Ok(result)
+    }
+
+    fn file_size(aabb: &AABB<GRID_DIMENSION>) -> u64 {
+        (aabb.buffer_size() * std::mem::size_of::<T>()) as u64
+    }
+
+    fn map(file: File, aabb: AABB<GRID_DIMENSION>) -> std::io::Result<Self> {
+        // Maps are page aligned, which satisfies both `T` and FFTW
+        let map = unsafe { memmap2::MmapMut::map_mut(&file)? };
+        debug_assert_eq!(map.len() as u64, Self::file_size(&aabb));
+        Ok(MappedDomain {
+            aabb,
+            map,
+            scalar_marker: PhantomData,
+        })
+    }
+
+    pub fn as_slice_domain(&mut self) -> SliceDomain<'_, GRID_DIMENSION, T> {
+        SliceDomain::new(self.aabb, self.values_mut())
+    }
+
+    /// Write changes back to the file.
+    /// The OS does this eventually anyway, this blocks until it has.
+    pub fn flush(&self) -> std::io::Result<()> {
+        self.map.flush()
+    }
+
+    pub fn mapped_bytes(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Bytes of the mapping currently held in RAM.
+    pub fn resident_bytes(&self) -> usize {
+        resident_bytes(&self.map)
+    }
+
+    /// The solver's scratch space is part of the working set too,
+    /// pass it in to report on it as well,
+    /// see `SolverParameters::scratch_dir`.
+    pub fn print_report(&self, name: &str, scratch: Option<&Scratch>) {
+        println!("Mapped Domain Report ({name}):");
+        print_residency(self.mapped_bytes(), self.resident_bytes());
+        if let Some(scratch) = scratch {
+            scratch.print_report();
+        }
+    }
+
+    fn values(&self) -> &[T] {
+        bytemuck::cast_slice(&self.map[..])
+    }
+
+    fn values_mut(&mut self) -> &mut [T] {
+        bytemuck::cast_slice_mut(&mut self.map[..])
+    }
+}
+
+impl<const GRID_DIMENSION: usize, T: Scalar> DomainRead<GRID_DIMENSION>
+    for MappedDomain<GRID_DIMENSION, T>
+{
+    fn read(&self, world_coord: &Coord<GRID_DIMENSION>) -> f64 {
+        self.view(world_coord).as_f64()
+    }
+}
+
+impl<const GRID_DIMENSION: usize, T: Scalar> DomainView<GRID_DIMENSION, T>
+    for MappedDomain<GRID_DIMENSION, T>
+{
+    fn aabb(&self) -> &AABB<GRID_DIMENSION> {
+        &self.aabb
+    }
+
+    fn set_aabb(&mut self, aabb: AABB<GRID_DIMENSION>) {
+        debug_assert!(aabb.buffer_size() <= self.values().len());
+        self.aabb = aabb;
+    }
+
+    fn buffer(&self) -> &[T] {
+        let range = 0..self.aabb().buffer_size();
+        &self.values()[range]
+    }
+
+    fn buffer_mut(&mut self) -> &mut [T] {
+        let range = 0..self.aabb().buffer_size();
+        &mut self.values_mut()[range]
+    }
+
+    fn aabb_buffer_mut(&mut self) -> (&AABB<GRID_DIMENSION>, &mut [T]) {
+        let range = 0..self.aabb.buffer_size();
+        (
+            &self.aabb,
+            &mut bytemuck::cast_slice_mut(&mut self.map[..])[range],
+        )
+    }
+
+    #[track_caller]
+    fn view(&self, world_coord: &Coord<GRID_DIMENSION>) -> T {
+        debug_assert!(
+            self.aabb.contains(world_coord),
+            "{:?} does not contain {:?}",
+            self.aabb,
+            world_coord
+        );
+        let index = self.aabb.coord_to_linear(world_coord);
+        self.values()[index]
+    }
+
+    #[track_caller]
+    fn set_coord(&mut self, world_coord: &Coord<GRID_DIMENSION>, value: T) {
+        debug_assert!(
+            self.aabb.contains(world_coord),
+            "{:?} does not contain {:?}",
+            self.aabb,
+            world_coord
+        );
+        let index = self.aabb.coord_to_linear(world_coord);
+        self.values_mut()[index] = value;
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let aabb = AABB::new(matrix![0, 9; 0, 19]);
+        let path = std::env::temp_dir()
+            .join(format!("nhls_round_trip_{}.domain", std::process::id()));
+        {
+            let mut domain = MappedDomain::create(&path, aabb).unwrap();
+            assert_eq!(domain.mapped_bytes(), 200 * 8);
+            domain.par_set_values(|c: Coord<2>| (c[0] + 10 * c[1]) as f64, 7);
+            domain.flush().unwrap();
+        }
+        {
+            let domain = MappedDomain::open(&path, aabb).unwrap();
+            assert_eq!(domain.view(&vector![3, 4]), 43.0);
+            assert_eq!(domain.read(&vector![9, 19]), 199.0);
+            assert!(domain.resident_bytes() <= domain.mapped_bytes());
+        }
+        let wrong_size = AABB::new(matrix![0, 9; 0, 9]);
+        assert!(MappedDomain::open(&path, wrong_size).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn create_in() {
+        let aabb = AABB::new(matrix![0, 99]);
+        let dir = std::env::temp_dir();
+        let mut domain =
+            MappedDomain::<1, f32>::create_in_scalar(&dir, aabb).unwrap();
+        domain.par_set_values(|c: Coord<1>| c[0] as f32, 10);
+        let mut slice = domain.as_slice_domain();
+        slice.set_aabb(AABB::new(matrix![0, 9]));
+        assert_eq!(slice.buffer().len(), 10);
+        assert_eq!(slice.view(&vector![9]), 9.0);
+
+        // Everything was just written, so it should all be resident
+        assert_eq!(domain.resident_bytes(), domain.mapped_bytes());
+    }
+}
@@ -1,17 +1,24 @@
 mod chunk;
 mod debug_io;
+mod mapped;
 mod owned;
 mod slice;
 
 pub use chunk::*;
 pub use debug_io::*;
+pub use mapped::*;
 pub use owned::*;
 pub use slice::*;
 
 use crate::util::*;
 use rayon::prelude::*;
 
-pub trait DomainView<const GRID_DIMENSION: usize>: Sync {
+/// Domains store their values as `T`, see `Scalar`.
+/// Every domain can also be read as `f64` through `DomainRead`,
+/// which is the real part for complex domains.
+pub trait DomainView<const GRID_DIMENSION: usize, T: Scalar = f64>:
+    DomainRead<GRID_DIMENSION>
+{
     /// Get the AABB for this domain
     fn aabb(&self) -> &AABB<GRID_DIMENSION>;
 
@@ -20,40 +27,40 @@ pub trait DomainView<const GRID_DIMENSION: usize>: Sync {
     fn set_aabb(&mut self, aabb: AABB<GRID_DIMENSION>);
 
     /// Get the buffer, this will be sliced to the right size for the aabb.
-    fn buffer(&self) -> &[f64];
+    fn buffer(&self) -> &[T];
 
     /// Get mutable access to the buffer,
     /// this will be sliced to the right size for the aabb.
-    fn buffer_mut(&mut self) -> &mut [f64];
+    fn buffer_mut(&mut self) -> &mut [T];
 
     /// Get both a const reference to the aabb
     /// and a mutable reference to the buffer.
-    fn aabb_buffer_mut(&mut self) -> (&AABB<GRID_DIMENSION>, &mut [f64]);
+    fn aabb_buffer_mut(&mut self) -> (&AABB<GRID_DIMENSION>, &mut [T]);
 
     /// Access the value at tbe given world coord.
-    fn view(&self, world_coord: &Coord<GRID_DIMENSION>) -> f64;
+    fn view(&self, world_coord: &Coord<GRID_DIMENSION>) -> T;
 
     /// Set the value at a given coordinate.
     /// When setting all values in a domain, use par_set_values instead.
-    fn set_coord(&mut self, world_coord: &Coord<GRID_DIMENSION>, value: f64);
+    fn set_coord(&mut self, world_coord: &Coord<GRID_DIMENSION>, value: T);
 
     fn par_modify_access(
         &mut self,
         chunk_size: usize,
-    ) -> impl ParallelIterator<Item = DomainChunk<'_, GRID_DIMENSION>> {
+    ) -> impl ParallelIterator<Item = DomainChunk<'_, GRID_DIMENSION, T>> {
         let (aabb, buffer) = self.aabb_buffer_mut();
         par_modify_access_impl(buffer, aabb, chunk_size)
     }
 
     fn par_set_values<
-        F: FnOnce(Coord<GRID_DIMENSION>) -> f64 + Send + Sync + Copy,
+        F: FnOnce(Coord<GRID_DIMENSION>) -> T + Send + Sync + Copy,
     >(
         &mut self,
         f: F,
         chunk_size: usize,
     ) {
         self.par_modify_access(chunk_size).for_each(
-            |mut d: DomainChunk<'_, GRID_DIMENSION>| {
+            |mut d: DomainChunk<'_, GRID_DIMENSION, T>| {
                 profiling::scope!("domain::par_set_values Thread Callback");
                 d.coord_iter_mut().for_each(|(world_coord, value_mut)| {
                     *value_mut = f(world_coord);
@@ -62,7 +69,7 @@ pub trait DomainView<const GRID_DIMENSION: usize>: Sync {
         );
     }
 
-    fn par_set_from<DomainType: DomainView<GRID_DIMENSION>>(
+    fn par_set_from<DomainType: DomainView<GRID_DIMENSION, T>>(
         &mut self,
         other: &DomainType,
         aabb: &AABB<GRID_DIMENSION>,
@@ -76,7 +83,7 @@ pub trait DomainView<const GRID_DIMENSION: usize>: Sync {
     }
 
     /// Copy other domain into self
-    fn par_set_subdomain<DomainType: DomainView<GRID_DIMENSION>>(
+    fn par_set_subdomain<DomainType: DomainView<GRID_DIMENSION, T>>(
         &mut self,
         other: &DomainType,
         chunk_size: usize,
@@ -86,7 +93,7 @@ pub trait DomainView<const GRID_DIMENSION: usize>: Sync {
         other.buffer()[0..other.aabb().buffer_size()]
             .par_chunks(chunk_size)
             .enumerate()
-            .for_each(move |(i, buffer_chunk): (usize, &[f64])| {
+            .for_each(move |(i, buffer_chunk): (usize, &[T])| {
                 profiling::scope!("domain::par_set_subdomain Thread Callback");
                 let self_ptr = const_self_ref as *const Self;
                 let mut_self_ref: &mut Self =
@@ -105,7 +112,7 @@ pub trait DomainView<const GRID_DIMENSION: usize>: Sync {
     }
 
     /// Copy self coords from other into self
-    fn par_from_superset<DomainType: DomainView<GRID_DIMENSION>>(
+    fn par_from_superset<DomainType: DomainView<GRID_DIMENSION, T>>(
         &mut self,
         other: &DomainType,
         chunk_size: usize,
@@ -119,30 +126,38 @@ pub trait DomainView<const GRID_DIMENSION: usize>: Sync {
     /// In parallel situations, if you can gaurentee that threads are accessing
     /// mutually exclusive coords, then use this as an escape hatch.
     /// DO NOT modify aabb in parallel.
-    fn unsafe_mut_access(&self) -> SliceDomain<'_, GRID_DIMENSION> {
+    fn unsafe_mut_access(&self) -> SliceDomain<'_, GRID_DIMENSION, T> {
         let buffer = self.buffer();
         let len = buffer.len();
         let buffer_ptr = buffer.as_ptr();
-        let buffer_ptr_mut = buffer_ptr as *mut f64;
+        let buffer_ptr_mut = buffer_ptr as *mut T;
         let unsafe_buffer =
             unsafe { std::slice::from_raw_parts_mut(buffer_ptr_mut, len) };
         SliceDomain::new(*self.aabb(), unsafe_buffer)
     }
 }
 
+/// Read only access to a domain that, unlike `DomainView`,
+/// can be used as a trait object.
+/// Boundary conditions that need to look at their input get one of these.
+pub trait DomainRead<const GRID_DIMENSION: usize>: Sync {
+    /// Access the value at the given world coord.
+    fn read(&self, world_coord: &Coord<GRID_DIMENSION>) -> f64;
+}
+
 /// Why not just put this into Domain::par_modify_access?
 /// Rust compiler can't figure out how to borrow aabb and buffer
 /// at the same time in this way.
 /// By putting their borrows into one function call first we work around it.
-fn par_modify_access_impl<'a, const GRID_DIMENSION: usize>(
-    buffer: &'a mut [f64],
+fn par_modify_access_impl<'a, const GRID_DIMENSION: usize, T: Scalar>(
+    buffer: &'a mut [T],
     aabb: &'a AABB<GRID_DIMENSION>,
     chunk_size: usize,
-) -> impl ParallelIterator<Item = DomainChunk<'a, GRID_DIMENSION>> + 'a {
+) -> impl ParallelIterator<Item = DomainChunk<'a, GRID_DIMENSION, T>> + 'a {
     buffer[0..aabb.buffer_size()]
         .par_chunks_mut(chunk_size)
         .enumerate()
-        .map(move |(i, buffer_chunk): (usize, &mut [f64])| {
+        .map(move |(i, buffer_chunk): (usize, &mut [T])| {
             let offset = i * chunk_size;
             DomainChunk::new(offset, aabb, buffer_chunk)
         })
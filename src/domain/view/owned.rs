@@ -2,24 +2,40 @@ use super::*;
 use crate::util::*;
 use fftw::array::*;
 
-pub struct OwnedDomain<const GRID_DIMENSION: usize> {
+pub struct OwnedDomain<const GRID_DIMENSION: usize, T: Scalar = f64> {
     aabb: AABB<GRID_DIMENSION>,
-    buffer: AlignedVec<f64>,
+    buffer: AlignedVec<T>,
 }
 
 impl<const GRID_DIMENSION: usize> OwnedDomain<GRID_DIMENSION> {
     pub fn new(aabb: AABB<GRID_DIMENSION>) -> Self {
+        Self::new_scalar(aabb)
+    }
+}
+
+impl<const GRID_DIMENSION: usize, T: Scalar> OwnedDomain<GRID_DIMENSION, T> {
+    /// Same as `new`, for domains that don't store `f64`,
+    /// e.g. `OwnedDomain::<3, f32>::new_scalar(aabb)`.
+    pub fn new_scalar(aabb: AABB<GRID_DIMENSION>) -> Self {
         let buffer = AlignedVec::new(aabb.buffer_size());
         OwnedDomain { aabb, buffer }
     }
 
-    pub fn as_slice_domain(&mut self) -> SliceDomain<'_, GRID_DIMENSION> {
+    pub fn as_slice_domain(&mut self) -> SliceDomain<'_, GRID_DIMENSION, T> {
         SliceDomain::new(self.aabb, &mut self.buffer)
     }
 }
 
-impl<const GRID_DIMENSION: usize> DomainView<GRID_DIMENSION>
-    for OwnedDomain<GRID_DIMENSION>
+impl<const GRID_DIMENSION: usize, T: Scalar> DomainRead<GRID_DIMENSION>
+    for OwnedDomain<GRID_DIMENSION, T>
+{
+    fn read(&self, world_coord: &Coord<GRID_DIMENSION>) -> f64 {
+        self.view(world_coord).as_f64()
+    }
+}
+
+impl<const GRID_DIMENSION: usize, T: Scalar> DomainView<GRID_DIMENSION, T>
+    for OwnedDomain<GRID_DIMENSION, T>
 {
     fn aabb(&self) -> &AABB<GRID_DIMENSION> {
         &self.aabb
@@ -31,23 +47,23 @@ impl<const GRID_DIMENSION: usize> DomainView<GRID_DIMENSION>
         self.aabb = aabb;
     }
 
-    fn buffer(&self) -> &[f64] {
+    fn buffer(&self) -> &[T] {
         let range = 0..self.aabb().buffer_size();
         &self.buffer[range]
     }
 
-    fn buffer_mut(&mut self) -> &mut [f64] {
+    fn buffer_mut(&mut self) -> &mut [T] {
         let range = 0..self.aabb().buffer_size();
         &mut self.buffer[range]
     }
 
-    fn aabb_buffer_mut(&mut self) -> (&AABB<GRID_DIMENSION>, &mut [f64]) {
+    fn aabb_buffer_mut(&mut self) -> (&AABB<GRID_DIMENSION>, &mut [T]) {
         let range = 0..self.aabb().buffer_size();
         (&self.aabb, &mut self.buffer[range])
     }
 
     #[track_caller]
-    fn view(&self, world_coord: &Coord<GRID_DIMENSION>) -> f64 {
+    fn view(&self, world_coord: &Coord<GRID_DIMENSION>) -> T {
         debug_assert!(
             self.aabb.contains(world_coord),
             "{:?} does not contain {:?}",
@@ -59,7 +75,7 @@ impl<const GRID_DIMENSION: usize> DomainView<GRID_DIMENSION>
     }
 
     #[track_caller]
-    fn set_coord(&mut self, world_coord: &Coord<GRID_DIMENSION>, value: f64) {
+    fn set_coord(&mut self, world_coord: &Coord<GRID_DIMENSION>, value: T) {
         debug_assert!(
             self.aabb.contains(world_coord),
             "{:?} does not contain {:?}",
@@ -1,20 +1,30 @@
 use super::*;
 use crate::util::*;
 
-pub struct SliceDomain<'a, const GRID_DIMENSION: usize> {
+pub struct SliceDomain<'a, const GRID_DIMENSION: usize, T: Scalar = f64> {
     aabb: AABB<GRID_DIMENSION>,
-    buffer: &'a mut [f64],
+    buffer: &'a mut [T],
 }
 
-impl<'a, const GRID_DIMENSION: usize> SliceDomain<'a, GRID_DIMENSION> {
-    pub fn new(aabb: AABB<GRID_DIMENSION>, buffer: &'a mut [f64]) -> Self {
+impl<'a, const GRID_DIMENSION: usize, T: Scalar>
+    SliceDomain<'a, GRID_DIMENSION, T>
+{
+    pub fn new(aabb: AABB<GRID_DIMENSION>, buffer: &'a mut [T]) -> Self {
         debug_assert!(buffer.len() >= aabb.buffer_size());
         SliceDomain { aabb, buffer }
     }
 }
 
-impl<'a, const GRID_DIMENSION: usize> DomainView<GRID_DIMENSION>
-    for SliceDomain<'a, GRID_DIMENSION>
+impl<const GRID_DIMENSION: usize, T: Scalar> DomainRead<GRID_DIMENSION>
+    for SliceDomain<'_, GRID_DIMENSION, T>
+{
+    fn read(&self, world_coord: &Coord<GRID_DIMENSION>) -> f64 {
+        self.view(world_coord).as_f64()
+    }
+}
+
+impl<'a, const GRID_DIMENSION: usize, T: Scalar> DomainView<GRID_DIMENSION, T>
+    for SliceDomain<'a, GRID_DIMENSION, T>
 {
     fn aabb(&self) -> &AABB<GRID_DIMENSION> {
         &self.aabb
@@ -25,23 +35,23 @@ impl<'a, const GRID_DIMENSION: usize> DomainView<GRID_DIMENSION>
         self.aabb = aabb;
     }
 
-    fn buffer(&self) -> &[f64] {
+    fn buffer(&self) -> &[T] {
         let range = 0..self.aabb().buffer_size();
         &self.buffer[range]
     }
 
-    fn buffer_mut(&mut self) -> &mut [f64] {
+    fn buffer_mut(&mut self) -> &mut [T] {
         let range = 0..self.aabb().buffer_size();
         &mut self.buffer[range]
     }
 
-    fn aabb_buffer_mut(&mut self) -> (&AABB<GRID_DIMENSION>, &mut [f64]) {
+    fn aabb_buffer_mut(&mut self) -> (&AABB<GRID_DIMENSION>, &mut [T]) {
         let range = 0..self.aabb().buffer_size();
         (&self.aabb, &mut self.buffer[range])
     }
 
     #[track_caller]
-    fn view(&self, world_coord: &Coord<GRID_DIMENSION>) -> f64 {
+    fn view(&self, world_coord: &Coord<GRID_DIMENSION>) -> T {
         debug_assert!(
             self.aabb.contains(world_coord),
             "{:?} does not contain {:?}",
@@ -53,7 +63,7 @@ impl<'a, const GRID_DIMENSION: usize> DomainView<GRID_DIMENSION>
     }
 
     #[track_caller]
-    fn set_coord(&mut self, world_coord: &Coord<GRID_DIMENSION>, value: f64) {
+    fn set_coord(&mut self, world_coord: &Coord<GRID_DIMENSION>, value: T) {
         debug_assert!(
             self.aabb.contains(world_coord),
             "{:?} does not contain {:?}",
@@ -0,0 +1,168 @@
+use crate::domain::*;
+use crate::fft_solver::*;
+use crate::par_slice;
+use crate::stencil::*;
+use crate::util::indexing::*;
+use crate::util::*;
+use fftw::plan::*;
+use nalgebra::DMatrix;
+use rayon::prelude::*;
+
+/// Raises a square matrix to the power `n`
+/// with the repeated square algorithm.
+pub fn matrix_power(mut x: DMatrix<c64>, n: usize) -> DMatrix<c64> {
+    debug_assert!(x.is_square());
+    let mut result = DMatrix::identity(x.nrows(), x.ncols());
+    let mut exp = n;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = &result * &x;
+        }
+        exp >>= 1;
+        x = &x * &x;
+    }
+    result
+}
+
+/// Periodic solves for block stencils.
+/// Each frequency has a `fields` x `fields` matrix,
+/// which we raise to the step power.
+/// The entries are stored as one buffer per (output, input) field pair,
+/// `convolutions[output * fields + input]`.
+pub struct BlockConvolutionOperation {
+    pub forward_plan: fftw::plan::Plan<f64, c64, fftw::plan::Plan64>,
+    pub backward_plan: fftw::plan::Plan<c64, f64, fftw::plan::Plan64>,
+    pub fields: usize,
+    pub convolutions: Vec<AlignedVec<c64>>,
+}
+
+impl BlockConvolutionOperation {
+    pub fn create<const GRID_DIMENSION: usize>(
+        stencil: &BlockStencil<GRID_DIMENSION>,
+        real_buffer: &mut [f64],
+        exclusive_bounds: &Coord<GRID_DIMENSION>,
+        steps: usize,
+        plan_type: PlanType,
+        threads: usize,
+    ) -> Self {
+        fftw::threading::plan_with_nthreads_f64(threads);
+        let plan_size = exclusive_bounds.try_cast::<usize>().unwrap();
+        let forward_plan = fftw::plan::R2CPlan64::aligned(
+            plan_size.as_slice(),
+            plan_type.to_fftw3_flag(),
+        )
+        .unwrap();
+        let backward_plan = fftw::plan::C2RPlan64::aligned(
+            plan_size.as_slice(),
+            plan_type.to_fftw3_flag(),
+        )
+        .unwrap();
+
+        let fields = stencil.fields();
+        let n_c = complex_buffer_size(exclusive_bounds);
+        let mut convolutions: Vec<AlignedVec<c64>> =
+            (0..fields * fields).map(|_| AlignedVec::new(n_c)).collect();
+        for f in 0..fields {
+            for g in 0..fields {
+                stencil_frequencies(
+                    &stencil.component(f, g),
+                    &forward_plan,
+                    real_buffer,
+                    &mut convolutions[f * fields + g],
+                    exclusive_bounds,
+                );
+            }
+        }
+
+        // Apply power calculation to each frequency
+        let powers: Vec<DMatrix<c64>> = (0..n_c)
+            .into_par_iter()
+            .map(|i| {
+                let matrix = DMatrix::from_fn(fields, fields, |f, g| {
+                    convolutions[f * fields + g][i]
+                });
+                matrix_power(matrix, steps)
+            })
+            .collect();
+        convolutions.par_iter_mut().enumerate().for_each(
+            |(fg, convolution)| {
+                let (f, g) = (fg / fields, fg % fields);
+                for (i, c) in convolution.iter_mut().enumerate() {
+                    *c = powers[i][(f, g)];
+                }
+            },
+        );
+
+        BlockConvolutionOperation {
+            forward_plan,
+            backward_plan,
+            fields,
+            convolutions,
+        }
+    }
+
+    /// `input_buffers` and `output_buffers` need a complex buffer
+    /// per field. The input domain is clobbered.
+    pub fn apply<const GRID_DIMENSION: usize>(
+        &self,
+        input: &mut MultiFieldDomain<GRID_DIMENSION>,
+        output: &mut MultiFieldDomain<GRID_DIMENSION>,
+        input_buffers: &mut [AlignedVec<c64>],
+        output_buffers: &mut [AlignedVec<c64>],
+        chunk_size: usize,
+    ) {
+        profiling::scope!("block_convolution_op::apply");
+        debug_assert_eq!(input.n_fields(), self.fields);
+        debug_assert_eq!(output.n_fields(), self.fields);
+        let n_r = input.aabb().buffer_size();
+        let n_c = input.aabb().complex_buffer_size();
+        for (field, buffer) in
+            input.fields_mut().iter_mut().zip(input_buffers.iter_mut())
+        {
+            self.forward_plan
+                .r2c(field.buffer_mut(), &mut buffer[0..n_c])
+                .unwrap();
+        }
+        for (f, (field, buffer)) in output
+            .fields_mut()
+            .iter_mut()
+            .zip(output_buffers.iter_mut())
+            .enumerate()
+        {
+            par_slice::set_value(&mut buffer[0..n_c], c64::zero(), chunk_size);
+            for (g, input_buffer) in input_buffers.iter().enumerate() {
+                par_slice::multiply_add(
+                    &mut buffer[0..n_c],
+                    &self.convolutions[f * self.fields + g][0..n_c],
+                    &input_buffer[0..n_c],
+                    chunk_size,
+                );
+            }
+            self.backward_plan
+                .c2r(&mut buffer[0..n_c], field.buffer_mut())
+                .unwrap();
+            par_slice::div(field.buffer_mut(), n_r as f64, chunk_size);
+        }
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+    use float_cmp::assert_approx_eq;
+    use nalgebra::dmatrix;
+
+    #[test]
+    fn matrix_power_test() {
+        let m = dmatrix![
+            c64::new(1.0, 0.0), c64::new(1.0, 0.0);
+            c64::new(1.0, 0.0), c64::new(0.0, 0.0)
+        ];
+        let p = matrix_power(m.clone(), 10);
+        assert_approx_eq!(f64, p[(0, 0)].re, 89.0);
+        assert_approx_eq!(f64, p[(0, 1)].re, 55.0);
+        assert_approx_eq!(f64, p[(1, 1)].re, 34.0);
+        assert_eq!(matrix_power(m.clone(), 0), DMatrix::identity(2, 2));
+        assert_eq!(matrix_power(m.clone(), 1), m);
+    }
+}
@@ -0,0 +1,58 @@
+use crate::domain::*;
+use crate::fft_solver::*;
+use crate::stencil::*;
+use crate::util::*;
+use fftw::array::*;
+use indexing::complex_buffer_size;
+
+/// Periodic solver for block stencils over multi-field domains.
+pub struct BlockPeriodicSolver {
+    operation: BlockConvolutionOperation,
+    input_buffers: Vec<AlignedVec<c64>>,
+    output_buffers: Vec<AlignedVec<c64>>,
+    chunk_size: usize,
+}
+
+impl BlockPeriodicSolver {
+    pub fn create<const GRID_DIMENSION: usize>(
+        stencil: &BlockStencil<GRID_DIMENSION>,
+        real_buffer: &mut [f64],
+        aabb: &AABB<GRID_DIMENSION>,
+        steps: usize,
+        plan_type: PlanType,
+        chunk_size: usize,
+        threads: usize,
+    ) -> Self {
+        let exclusive_bounds = aabb.exclusive_bounds();
+        let n_c = complex_buffer_size(&exclusive_bounds);
+        let operation = BlockConvolutionOperation::create(
+            stencil,
+            real_buffer,
+            &exclusive_bounds,
+            steps,
+            plan_type,
+            threads,
+        );
+        let fields = stencil.fields();
+        BlockPeriodicSolver {
+            operation,
+            input_buffers: (0..fields).map(|_| AlignedVec::new(n_c)).collect(),
+            output_buffers: (0..fields).map(|_| AlignedVec::new(n_c)).collect(),
+            chunk_size,
+        }
+    }
+
+    pub fn apply<const GRID_DIMENSION: usize>(
+        &mut self,
+        input: &mut MultiFieldDomain<GRID_DIMENSION>,
+        output: &mut MultiFieldDomain<GRID_DIMENSION>,
+    ) {
+        self.operation.apply(
+            input,
+            output,
+            &mut self.input_buffers,
+            &mut self.output_buffers,
+            self.chunk_size,
+        );
+    }
+}
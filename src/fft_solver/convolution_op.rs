@@ -2,31 +2,69 @@ use crate::domain::*;
 use crate::fft_solver::*;
 use crate::par_slice;
 use crate::stencil::*;
-use crate::util::indexing::*;
 use crate::util::*;
-use fftw::plan::*;
 use float_cmp::assert_approx_eq;
 
+/// Writes the frequency domain representation of `stencil` into
+/// `frequency_buffer`. `real_buffer` must be zeroed, and is left that way.
+pub fn stencil_frequencies<
+    const GRID_DIMENSION: usize,
+    StencilType: TIStencil<GRID_DIMENSION, T::Weight>,
+    T: Scalar,
+>(
+    stencil: &StencilType,
+    forward_plan: &T::ForwardPlan,
+    real_buffer: &mut [T],
+    frequency_buffer: &mut [T::Complex],
+    exclusive_bounds: &Coord<GRID_DIMENSION>,
+) {
+    let domain_aabb = AABB::from_exclusive_bounds(exclusive_bounds);
+    let mut stencil_domain = SliceDomain::new(domain_aabb, real_buffer);
+
+    // Place offsets in real buffer
+    let offsets = stencil.offset_slice();
+    let weights = stencil.weight_slice();
+    for n_i in 0..offsets.len() {
+        // I don't understand why, but we found that this mirroring operation
+        // was necessary. I think it was in the paper.
+        // TODO: Why is this the case?
+        let rn_i: Coord<GRID_DIMENSION> = offsets[n_i] * -1;
+        let periodic_coord = domain_aabb.periodic_coord(&rn_i);
+        stencil_domain.set_coord(&periodic_coord, T::from_weight(weights[n_i]));
+    }
+
+    forward_plan.forward(stencil_domain.buffer_mut(), frequency_buffer);
+
+    // Clean up real buffer
+    for n_i in 0..offsets.len() {
+        let rn_i: Coord<GRID_DIMENSION> = offsets[n_i] * -1;
+        let periodic_coord = domain_aabb.periodic_coord(&rn_i);
+        stencil_domain.set_coord(&periodic_coord, T::zero());
+    }
+}
+
 /// The backbone of our periodic solves.
 /// This has the FFTW plans we need, as well
 /// as the stencil operation in the frequency domain to some
 /// power.
-pub struct ConvolutionOperation {
-    pub forward_plan: fftw::plan::Plan<f64, c64, fftw::plan::Plan64>,
-    pub backward_plan: fftw::plan::Plan<c64, f64, fftw::plan::Plan64>,
-    pub convolution: AlignedVec<c64>,
+/// Plans and buffers are in terms of `T`, see `Scalar`,
+/// i.e. R2C / C2R for real types and C2C for complex ones.
+pub struct ConvolutionOperation<T: Scalar = f64> {
+    pub forward_plan: T::ForwardPlan,
+    pub backward_plan: T::BackwardPlan,
+    pub convolution: AlignedVec<T::Complex>,
 }
 
-impl ConvolutionOperation {
+impl<T: Scalar> ConvolutionOperation<T> {
     #[inline]
     #[allow(clippy::too_many_arguments)]
     pub fn create<
         const GRID_DIMENSION: usize,
-        const NEIGHBORHOOD_SIZE: usize,
+        StencilType: TIStencil<GRID_DIMENSION, T::Weight>,
     >(
-        stencil: &Stencil<GRID_DIMENSION, NEIGHBORHOOD_SIZE>,
-        real_buffer: &mut [f64],
-        convolution_buffer: &mut [c64],
+        stencil: &StencilType,
+        real_buffer: &mut [T],
+        convolution_buffer: &mut [T::Complex],
         exclusive_bounds: &Coord<GRID_DIMENSION>,
         steps: usize,
         plan_type: PlanType,
@@ -34,52 +72,38 @@ impl ConvolutionOperation {
         threads: usize,
     ) -> Self {
         {
-            let b: &[f64] = real_buffer;
+            let b: &[T] = real_buffer;
             for v in b {
-                assert_approx_eq!(f64, *v, 0.0, epsilon = 0.0000000000001);
+                assert_approx_eq!(
+                    f64,
+                    v.as_f64(),
+                    0.0,
+                    epsilon = 0.0000000000001
+                );
             }
         }
-        fftw::threading::plan_with_nthreads_f64(threads);
+        T::plan_with_nthreads(threads);
         let plan_size = exclusive_bounds.try_cast::<usize>().unwrap();
-        let forward_plan = fftw::plan::R2CPlan64::aligned(
+        let forward_plan = T::ForwardPlan::plan(
             plan_size.as_slice(),
             plan_type.to_fftw3_flag(),
-        )
-        .unwrap();
-        let backward_plan = fftw::plan::C2RPlan64::aligned(
+        );
+        let backward_plan = T::BackwardPlan::plan(
             plan_size.as_slice(),
             plan_type.to_fftw3_flag(),
-        )
-        .unwrap();
-
-        let domain_aabb = AABB::from_exclusive_bounds(exclusive_bounds);
-        let mut stencil_domain = SliceDomain::new(domain_aabb, real_buffer);
-
-        // Place offsets in real buffer
-        let offsets = stencil.offsets();
-        let weights = stencil.weights();
-        for n_i in 0..NEIGHBORHOOD_SIZE {
-            // I don't understand why, but we found that this mirroring operation
-            // was necessary. I think it was in the paper.
-            // TODO: Why is this the case?
-            let rn_i: Coord<GRID_DIMENSION> = offsets[n_i] * -1;
-            let periodic_coord = domain_aabb.periodic_coord(&rn_i);
-            stencil_domain.set_coord(&periodic_coord, weights[n_i]);
-        }
+        );
 
         // Calculate convolution of stencil
-        let n_c = complex_buffer_size(exclusive_bounds);
-        forward_plan
-            .r2c(stencil_domain.buffer_mut(), &mut convolution_buffer[0..n_c])
-            .unwrap();
-
-        // Clean up real buffer
-        //stencil_domain.par_set_values(|_| 0.0, chunk_size);
-        for n_i in 0..NEIGHBORHOOD_SIZE {
-            let rn_i: Coord<GRID_DIMENSION> = offsets[n_i] * -1;
-            let periodic_coord = domain_aabb.periodic_coord(&rn_i);
-            stencil_domain.set_coord(&periodic_coord, 0.0);
-        }
+        let n_c = T::frequency_buffer_size(&AABB::from_exclusive_bounds(
+            exclusive_bounds,
+        ));
+        stencil_frequencies::<GRID_DIMENSION, StencilType, T>(
+            stencil,
+            &forward_plan,
+            real_buffer,
+            &mut convolution_buffer[0..n_c],
+            exclusive_bounds,
+        );
 
         // Apply power calculation to convolution
         let mut result_buffer = fftw::array::AlignedVec::new(n_c);
@@ -93,7 +117,7 @@ impl ConvolutionOperation {
         // Clear convoluton_buffer
         par_slice::set_value(
             &mut convolution_buffer[0..n_c],
-            c64::zero(),
+            T::Complex::zero(),
             chunk_size,
         );
 
@@ -107,28 +131,26 @@ impl ConvolutionOperation {
     #[inline]
     pub fn apply<
         const GRID_DIMENSION: usize,
-        DomainType: DomainView<GRID_DIMENSION>,
+        DomainType: DomainView<GRID_DIMENSION, T>,
     >(
         &self,
         input: &mut DomainType,
         output: &mut DomainType,
-        complex_buffer: &mut [c64],
+        complex_buffer: &mut [T::Complex],
         chunk_size: usize,
     ) {
         profiling::scope!("convolution_op::apply");
         let n_r = input.aabb().buffer_size();
-        let n_c = input.aabb().complex_buffer_size();
+        let n_c = T::frequency_buffer_size(input.aabb());
         self.forward_plan
-            .r2c(input.buffer_mut(), &mut complex_buffer[0..n_c])
-            .unwrap();
+            .forward(input.buffer_mut(), &mut complex_buffer[0..n_c]);
         par_slice::multiply_by(
             &mut complex_buffer[0..n_c],
             self.convolution.as_slice(),
             chunk_size,
         );
         self.backward_plan
-            .c2r(&mut complex_buffer[0..n_c], output.buffer_mut())
-            .unwrap();
-        par_slice::div(output.buffer_mut(), n_r as f64, chunk_size);
+            .backward(&mut complex_buffer[0..n_c], output.buffer_mut());
+        par_slice::div(output.buffer_mut(), T::cast(n_r as f64), chunk_size);
     }
 }
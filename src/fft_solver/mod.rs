@@ -3,10 +3,18 @@ pub type NodeId = usize;
 
 pub const MIN_ALIGNMENT: usize = 128;
 
+mod block_convolution_op;
+mod block_periodic_solver;
 mod convolution_op;
 mod periodic_solver;
 mod plan_type;
+mod two_level_convolution_op;
+mod two_level_periodic_solver;
 
+pub use block_convolution_op::*;
+pub use block_periodic_solver::*;
 pub use convolution_op::*;
 pub use periodic_solver::*;
 pub use plan_type::*;
+pub use two_level_convolution_op::*;
+pub use two_level_periodic_solver::*;
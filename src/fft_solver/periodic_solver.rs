@@ -15,9 +15,9 @@ pub struct PeriodicSolver {
 impl PeriodicSolver {
     pub fn create<
         const GRID_DIMENSION: usize,
-        const NEIGHBORHOOD_SIZE: usize,
+        StencilType: TIStencil<GRID_DIMENSION>,
     >(
-        stencil: &Stencil<GRID_DIMENSION, NEIGHBORHOOD_SIZE>,
+        stencil: &StencilType,
         real_buffer: &mut [f64],
         aabb: &AABB<GRID_DIMENSION>,
         steps: usize,
@@ -0,0 +1,180 @@
+use crate::domain::*;
+use crate::fft_solver::*;
+use crate::par_slice;
+use crate::stencil::*;
+use crate::util::indexing::*;
+use crate::util::*;
+use fftw::plan::*;
+use rayon::prelude::*;
+
+/// 2x2 complex matrix in row major order.
+pub type Companion = [c64; 4];
+
+fn companion_mul(a: &Companion, b: &Companion) -> Companion {
+    [
+        a[0] * b[0] + a[1] * b[2],
+        a[0] * b[1] + a[1] * b[3],
+        a[2] * b[0] + a[3] * b[2],
+        a[2] * b[1] + a[3] * b[3],
+    ]
+}
+
+/// Raises the companion matrix [[current, previous], [1, 0]]
+/// to the power `n` with the repeated square algorithm.
+pub fn companion_power(current: c64, previous: c64, n: usize) -> Companion {
+    let mut x = [current, previous, c64::one(), c64::zero()];
+    let mut result = [c64::one(), c64::zero(), c64::zero(), c64::one()];
+    let mut exp = n;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = companion_mul(&result, &x);
+        }
+        exp >>= 1;
+        x = companion_mul(&x, &x);
+    }
+    result
+}
+
+/// Periodic solves for two level stencils.
+///
+/// In the frequency domain each step is
+///
+///   [u^{t+1}, u^t] = [[S1, S0], [1, 0]] [u^t, u^{t-1}]
+///
+/// so we store the power of that companion matrix for every frequency.
+pub struct TwoLevelConvolutionOperation {
+    pub forward_plan: fftw::plan::Plan<f64, c64, fftw::plan::Plan64>,
+    pub backward_plan: fftw::plan::Plan<c64, f64, fftw::plan::Plan64>,
+    pub companions: Vec<Companion>,
+}
+
+impl TwoLevelConvolutionOperation {
+    /// `steps` must be at least one.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create<const GRID_DIMENSION: usize>(
+        stencil: &TwoLevelStencil<GRID_DIMENSION>,
+        real_buffer: &mut [f64],
+        current_buffer: &mut [c64],
+        previous_buffer: &mut [c64],
+        exclusive_bounds: &Coord<GRID_DIMENSION>,
+        steps: usize,
+        plan_type: PlanType,
+        threads: usize,
+    ) -> Self {
+        debug_assert!(steps > 0);
+        fftw::threading::plan_with_nthreads_f64(threads);
+        let plan_size = exclusive_bounds.try_cast::<usize>().unwrap();
+        let forward_plan = fftw::plan::R2CPlan64::aligned(
+            plan_size.as_slice(),
+            plan_type.to_fftw3_flag(),
+        )
+        .unwrap();
+        let backward_plan = fftw::plan::C2RPlan64::aligned(
+            plan_size.as_slice(),
+            plan_type.to_fftw3_flag(),
+        )
+        .unwrap();
+
+        let n_c = complex_buffer_size(exclusive_bounds);
+        stencil_frequencies(
+            stencil.current(),
+            &forward_plan,
+            real_buffer,
+            &mut current_buffer[0..n_c],
+            exclusive_bounds,
+        );
+        stencil_frequencies(
+            stencil.previous(),
+            &forward_plan,
+            real_buffer,
+            &mut previous_buffer[0..n_c],
+            exclusive_bounds,
+        );
+
+        let companions = current_buffer[0..n_c]
+            .par_iter()
+            .zip(previous_buffer[0..n_c].par_iter())
+            .map(|(c, p)| companion_power(*c, *p, steps))
+            .collect();
+
+        TwoLevelConvolutionOperation {
+            forward_plan,
+            backward_plan,
+            companions,
+        }
+    }
+
+    /// Advance `input_previous` and `input_current` by `steps`,
+    /// writing the results to `output_previous` and `output_current`.
+    /// Inputs are left alone, like `ConvolutionOperation::apply`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn apply<
+        const GRID_DIMENSION: usize,
+        DomainType: DomainView<GRID_DIMENSION>,
+    >(
+        &self,
+        input_previous: &mut DomainType,
+        input_current: &mut DomainType,
+        output_previous: &mut DomainType,
+        output_current: &mut DomainType,
+        previous_buffer: &mut [c64],
+        current_buffer: &mut [c64],
+        chunk_size: usize,
+    ) {
+        profiling::scope!("two_level_convolution_op::apply");
+        debug_assert_eq!(input_previous.aabb(), input_current.aabb());
+        debug_assert_eq!(output_previous.aabb(), output_current.aabb());
+        let n_r = input_current.aabb().buffer_size();
+        let n_c = input_current.aabb().complex_buffer_size();
+        self.forward_plan
+            .r2c(input_previous.buffer_mut(), &mut previous_buffer[0..n_c])
+            .unwrap();
+        self.forward_plan
+            .r2c(input_current.buffer_mut(), &mut current_buffer[0..n_c])
+            .unwrap();
+        previous_buffer[0..n_c]
+            .par_chunks_mut(chunk_size)
+            .zip(current_buffer[0..n_c].par_chunks_mut(chunk_size))
+            .zip(self.companions.par_chunks(chunk_size))
+            .for_each(|((p_chunk, c_chunk), m_chunk)| {
+                for ((p, c), m) in
+                    p_chunk.iter_mut().zip(c_chunk.iter_mut()).zip(m_chunk)
+                {
+                    let new_c = m[0] * *c + m[1] * *p;
+                    let new_p = m[2] * *c + m[3] * *p;
+                    *c = new_c;
+                    *p = new_p;
+                }
+            });
+        self.backward_plan
+            .c2r(&mut previous_buffer[0..n_c], output_previous.buffer_mut())
+            .unwrap();
+        self.backward_plan
+            .c2r(&mut current_buffer[0..n_c], output_current.buffer_mut())
+            .unwrap();
+        par_slice::div(output_previous.buffer_mut(), n_r as f64, chunk_size);
+        par_slice::div(output_current.buffer_mut(), n_r as f64, chunk_size);
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+    use float_cmp::assert_approx_eq;
+
+    #[test]
+    fn companion_power_test() {
+        // Fibonacci numbers
+        let m = companion_power(c64::one(), c64::one(), 10);
+        assert_approx_eq!(f64, m[0].re, 89.0);
+        assert_approx_eq!(f64, m[1].re, 55.0);
+        assert_approx_eq!(f64, m[2].re, 55.0);
+        assert_approx_eq!(f64, m[3].re, 34.0);
+
+        let m = companion_power(c64::new(0.5, 0.0), c64::new(2.0, 0.0), 1);
+        assert_approx_eq!(f64, m[0].re, 0.5);
+        assert_approx_eq!(f64, m[1].re, 2.0);
+        assert_approx_eq!(f64, m[2].re, 1.0);
+        assert_approx_eq!(f64, m[3].re, 0.0);
+    }
+}
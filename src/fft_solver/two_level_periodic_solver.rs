@@ -0,0 +1,138 @@
+use crate::domain::*;
+use crate::fft_solver::*;
+use crate::stencil::*;
+use crate::util::*;
+use crate::TwoLevelSolverInterface;
+use fftw::array::*;
+use indexing::complex_buffer_size;
+
+/// Periodic solver for two level stencils,
+/// advancing both time levels by `steps`.
+pub struct TwoLevelPeriodicSolver {
+    operation: TwoLevelConvolutionOperation,
+    previous_buffer: AlignedVec<c64>,
+    current_buffer: AlignedVec<c64>,
+    chunk_size: usize,
+}
+
+impl TwoLevelPeriodicSolver {
+    pub fn create<const GRID_DIMENSION: usize>(
+        stencil: &TwoLevelStencil<GRID_DIMENSION>,
+        real_buffer: &mut [f64],
+        aabb: &AABB<GRID_DIMENSION>,
+        steps: usize,
+        plan_type: PlanType,
+        chunk_size: usize,
+        threads: usize,
+    ) -> Self {
+        let exclusive_bounds = aabb.exclusive_bounds();
+        let n_c = complex_buffer_size(&exclusive_bounds);
+        let mut previous_buffer = AlignedVec::new(n_c);
+        let mut current_buffer = AlignedVec::new(n_c);
+        let operation = TwoLevelConvolutionOperation::create(
+            stencil,
+            real_buffer,
+            &mut current_buffer,
+            &mut previous_buffer,
+            &exclusive_bounds,
+            steps,
+            plan_type,
+            threads,
+        );
+
+        TwoLevelPeriodicSolver {
+            operation,
+            previous_buffer,
+            current_buffer,
+            chunk_size,
+        }
+    }
+
+    pub fn apply<
+        const GRID_DIMENSION: usize,
+        DomainType: DomainView<GRID_DIMENSION>,
+    >(
+        &mut self,
+        input_previous: &mut DomainType,
+        input_current: &mut DomainType,
+        output_previous: &mut DomainType,
+        output_current: &mut DomainType,
+    ) {
+        self.operation.apply(
+            input_previous,
+            input_current,
+            output_previous,
+            output_current,
+            &mut self.previous_buffer,
+            &mut self.current_buffer,
+            self.chunk_size,
+        );
+    }
+}
+
+impl<const GRID_DIMENSION: usize> TwoLevelSolverInterface<GRID_DIMENSION>
+    for TwoLevelPeriodicSolver
+{
+    fn apply<'a>(
+        &mut self,
+        input_previous: &mut SliceDomain<'a, GRID_DIMENSION>,
+        input_current: &mut SliceDomain<'a, GRID_DIMENSION>,
+        output_previous: &mut SliceDomain<'a, GRID_DIMENSION>,
+        output_current: &mut SliceDomain<'a, GRID_DIMENSION>,
+        _global_time: usize,
+    ) {
+        self.apply(
+            input_previous,
+            input_current,
+            output_previous,
+            output_current,
+        );
+    }
+
+    fn print_report(&self) {
+        println!("TwoLevelPeriodicSolver: No Report");
+    }
+
+    fn to_dot_file<P: AsRef<std::path::Path>>(&self, _path: &P) {
+        eprintln!("WARNING: TwoLevelPeriodicSolver cannot save to dot file");
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+    use crate::standard_stencils::*;
+    use float_cmp::assert_approx_eq;
+    use nalgebra::matrix;
+
+    #[test]
+    fn constant_solution() {
+        let chunk_size = 3;
+        let stencil = wave_2d(0.5, 1.0, 1.0, 1.0);
+        let aabb = AABB::new(matrix![0, 19; 0, 29]);
+        let mut previous = OwnedDomain::new(aabb);
+        let mut current = OwnedDomain::new(aabb);
+        let mut solver = TwoLevelPeriodicSolver::create(
+            &stencil,
+            previous.buffer_mut(),
+            &aabb,
+            13,
+            PlanType::Estimate,
+            chunk_size,
+            1,
+        );
+        let mut next_previous = OwnedDomain::new(aabb);
+        let mut next_current = OwnedDomain::new(aabb);
+        previous.par_set_values(|_| 1.0, chunk_size);
+        current.par_set_values(|_| 1.0, chunk_size);
+        solver.apply(
+            &mut previous,
+            &mut current,
+            &mut next_previous,
+            &mut next_current,
+        );
+        for x in next_previous.buffer().iter().chain(next_current.buffer()) {
+            assert_approx_eq!(f64, *x, 1.0);
+        }
+    }
+}
@@ -1,10 +1,16 @@
-use crate::ap_solver::SolverParameters;
+use crate::ap_solver::plan_report::generate_plan_report;
+use crate::ap_solver::scratch_builder::ComplexBufferType;
+use crate::ap_solver::{AxisBoundary, SolverParameters};
 use crate::build_info;
 use crate::domain::*;
 use crate::fft_solver::PlanType;
 use crate::image::*;
 use crate::image_example_util::*;
 use crate::initial_conditions::*;
+use crate::stencil::{
+    load_stencil_file, parse_param_override, DynStencil, TIStencil, TVStencil,
+};
+use crate::symbol_analysis::*;
 use crate::util::*;
 use crate::SolverInterface;
 use clap::Parser;
@@ -78,6 +84,11 @@ pub struct Args {
     #[arg(long)]
     pub gen_only: bool,
 
+    /// Report on the AP plan and quit.
+    /// Unlike --gen-only, no FFTW plans are created.
+    #[arg(long)]
+    pub dry_run: bool,
+
     /// Print build information and quit
     #[arg(long)]
     pub build_info: bool,
@@ -94,6 +105,23 @@ pub struct Args {
     #[arg(long)]
     pub timings_file: Option<PathBuf>,
 
+    /// Record per node runtimes of the AP solver,
+    /// written to <INSTRUMENT>.json and <INSTRUMENT>.csv.
+    /// With --write-dot, the dot file is rewritten after solving,
+    /// annotated with the runtimes.
+    #[arg(long)]
+    pub instrument: Option<PathBuf>,
+
+    /// Load the stencil from a definition file instead of using
+    /// the example's built in one, see stencils/ for the format.
+    /// Only used by examples that accept runtime stencils.
+    #[arg(long)]
+    pub stencil_file: Option<PathBuf>,
+
+    /// Override a param of --stencil-file, e.g. --stencil-param k=0.3
+    #[arg(long, value_parser = parse_param_override)]
+    pub stencil_param: Vec<(String, f64)>,
+
     /// Puffin Viewer url
     #[cfg(feature = "profile-with-puffin")]
     #[arg(long, default_value = "127.0.0.1:8585")]
@@ -113,6 +141,9 @@ impl Args {
             aabb: grid_bound,
             task_min: self.task_min,
             task_mult: self.task_mult,
+            axis_boundaries: [AxisBoundary::Aperiodic; 1],
+            plan_cache: None,
+            scratch_dir: None,
         }
     }
 
@@ -144,6 +175,9 @@ impl Args {
         output_domain: &mut SliceDomain<'a, 1>,
         solver: &mut SolverType,
     ) {
+        self.reject_stencil_file();
+        self.reject_dry_run();
+
         // Solver Diagnostics
         solver.print_report();
         if let Some(dot_path) = self.write_dot.as_ref() {
@@ -176,6 +210,10 @@ impl Args {
             timings_writer = Some(writer);
         }
 
+        if self.instrument.is_some() {
+            solver.enable_instrumentation();
+        }
+
         // Main solver loop
         let mut global_time = 0;
         for t in 1..self.lines as u32 {
@@ -206,6 +244,14 @@ impl Args {
             i.write(&image_path);
         }
 
+        // Write instrumentation (maybe)
+        if let Some(instrument_path) = self.instrument.as_ref() {
+            solver.write_instrumentation(&instrument_path);
+            if let Some(dot_path) = self.write_dot.as_ref() {
+                solver.to_dot_file(&dot_path);
+            }
+        }
+
         self.finish();
     }
 
@@ -256,9 +302,133 @@ impl Args {
             ensure_dir_exists(&parent_path);
         }
 
+        if let Some(ref instrument_path) = args.instrument {
+            let parent_path = instrument_path.parent().unwrap();
+            ensure_dir_exists(&parent_path);
+        }
+
         args
     }
 
+    /// The stencil from --stencil-file, if one was given.
+    /// Exits with an error message if it fails to load.
+    /// Consumes the flags, `run_solver` rejects any that are left over.
+    pub fn stencil_from_file(&mut self) -> Option<DynStencil<1>> {
+        let params = std::mem::take(&mut self.stencil_param);
+        let Some(path) = self.stencil_file.take() else {
+            if !params.is_empty() {
+                eprintln!("ERROR: --stencil-param requires --stencil-file");
+                std::process::exit(1);
+            }
+            return None;
+        };
+        match load_stencil_file(&path, &params) {
+            Ok(stencil) => Some(stencil),
+            Err(e) => {
+                eprintln!("ERROR: {e}");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    /// Exits with an error if --stencil-file was passed to an example
+    /// that doesn't load it with `stencil_from_file`.
+    pub fn reject_stencil_file(&self) {
+        if self.stencil_file.is_some() || !self.stencil_param.is_empty() {
+            eprintln!(
+                "ERROR: --stencil-file is only supported by file_*_ap_fft examples"
+            );
+            std::process::exit(1);
+        }
+    }
+
+    /// Exits with an error if --dry-run was passed to an example
+    /// that doesn't report on its plan with `dry_run`.
+    pub fn reject_dry_run(&self) {
+        if self.dry_run {
+            eprintln!("ERROR: --dry-run is not supported by this example");
+            std::process::exit(1);
+        }
+    }
+
+    /// Frequencies per axis used for stability checks.
+    pub fn symbol_resolution(&self) -> usize {
+        self.domain_size.clamp(2, DEFAULT_SYMBOL_RESOLUTION)
+    }
+
+    /// Warn if the stencil amplifies any Fourier mode,
+    /// otherwise unstable parameters only show up after many steps.
+    pub fn check_stability<StencilType: TIStencil<1>>(
+        &self,
+        stencil: &StencilType,
+    ) {
+        analyze_stencil(stencil, self.symbol_resolution()).print_warnings();
+    }
+
+    /// Like `check_stability`, but checks a sample of the time steps
+    /// this run will take.
+    pub fn check_tv_stability<
+        const NEIGHBORHOOD_SIZE: usize,
+        StencilType: TVStencil<1, NEIGHBORHOOD_SIZE>,
+    >(
+        &self,
+        stencil: &StencilType,
+    ) {
+        let total_steps = (self.lines * self.steps_per_line).max(1);
+        let stride = total_steps.div_ceil(DEFAULT_TV_SAMPLES);
+        analyze_tv_stencil(
+            stencil,
+            (0..total_steps).step_by(stride),
+            self.symbol_resolution(),
+        )
+        .print_warnings();
+    }
+
+    /// With --dry-run, report on the AP plan for `stencil` and exit.
+    /// Call this before creating the solver.
+    pub fn dry_run<StencilType: TIStencil<1>>(&self, stencil: &StencilType) {
+        self.dry_run_report(
+            stencil.slopes(),
+            stencil.neighborhood_size(),
+            ComplexBufferType::DomainOnly,
+        );
+    }
+
+    /// Like `dry_run`, for time-varying stencils.
+    pub fn tv_dry_run<
+        const NEIGHBORHOOD_SIZE: usize,
+        StencilType: TVStencil<1, NEIGHBORHOOD_SIZE>,
+    >(
+        &self,
+        stencil: &StencilType,
+    ) {
+        self.dry_run_report(
+            stencil.slopes(),
+            NEIGHBORHOOD_SIZE,
+            ComplexBufferType::DomainAndOp,
+        );
+    }
+
+    fn dry_run_report(
+        &self,
+        stencil_slopes: Bounds<1>,
+        neighborhood_size: usize,
+        complex_buffer_type: ComplexBufferType,
+    ) {
+        if !self.dry_run {
+            return;
+        }
+        let report = generate_plan_report::<1, f64>(
+            stencil_slopes,
+            neighborhood_size,
+            &self.solver_parameters(),
+            complex_buffer_type,
+        );
+        report.print_report();
+        self.finish();
+        std::process::exit(0);
+    }
+
     pub fn grid_bounds(&self) -> AABB<1> {
         AABB::new(matrix![0, self.domain_size as i32 - 1])
     }
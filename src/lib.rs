@@ -22,6 +22,7 @@ pub mod par_slice;
 pub mod par_stencil;
 pub mod solver_interface;
 pub mod stencil;
+pub mod symbol_analysis;
 pub mod time_varying;
 pub mod util;
 pub mod vtk;
@@ -40,9 +41,11 @@ pub fn init_threads(threads: usize) {
         .build_global()
         .unwrap();
     fftw::threading::init_threads_f64().unwrap();
+    fftw::threading::init_threads_f32().unwrap();
 
     // This is setting a default value,
     // Not strictly necessary,
     // i.e. we should set this explicitly whenever planning
     fftw::threading::plan_with_nthreads_f64(threads);
+    fftw::threading::plan_with_nthreads_f32(threads);
 }
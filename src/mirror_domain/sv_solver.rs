@@ -38,13 +38,18 @@ impl<
         solver: SolverType,
     ) -> Self {
         let create_ops_builder = || ApPeriodicOpsBuilder::new(stencil, params);
-        let planner_result = generate_plan(stencil, create_ops_builder, params);
+        let planner_result =
+            generate_plan(stencil.slopes(), create_ops_builder, params);
 
         // Create our plan and convolution_store
         let plan = planner_result.plan;
         let complex_buffer_type = ComplexBufferType::DomainOnly;
         let (node_scratch_descriptors, scratch_space_1, scratch_space_2) =
-            ScratchBuilder::build_double(&plan, complex_buffer_type);
+            ScratchBuilder::build_double(
+                &plan,
+                complex_buffer_type,
+                params.scratch_dir.as_deref(),
+            );
 
         SVSolver {
             direct_frustrum_solver: solver,
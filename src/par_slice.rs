@@ -48,6 +48,58 @@ pub fn multiply_by<NumType: NumTrait>(
         });
 }
 
+/// Implements a = a + b * c over slice elements.
+pub fn multiply_add<NumType: NumTrait>(
+    a_slice: &mut [NumType],
+    b_slice: &[NumType],
+    c_slice: &[NumType],
+    chunk_size: usize,
+) {
+    a_slice
+        .par_chunks_mut(chunk_size)
+        .zip(b_slice.par_chunks(chunk_size))
+        .zip(c_slice.par_chunks(chunk_size))
+        .for_each(|((a_chunk, b_chunk), c_chunk)| {
+            profiling::scope!("par_slice::multiply_add Thread Callback");
+            for ((a, b), c) in
+                a_chunk.iter_mut().zip(b_chunk.iter()).zip(c_chunk.iter())
+            {
+                *a = *a + *b * *c;
+            }
+        });
+}
+
+/// Implements a = a + c over slice elements.
+pub fn add_value<NumType: NumTrait>(
+    a_slice: &mut [NumType],
+    c: NumType,
+    chunk_size: usize,
+) {
+    a_slice.par_chunks_mut(chunk_size).for_each(|a_chunk| {
+        profiling::scope!("par_slice::add_value Thread Callback");
+        for a in a_chunk.iter_mut() {
+            *a = *a + c;
+        }
+    });
+}
+
+/// Implements a = a + b over slice elements.
+pub fn add<NumType: NumTrait>(
+    a_slice: &mut [NumType],
+    b_slice: &[NumType],
+    chunk_size: usize,
+) {
+    a_slice
+        .par_chunks_mut(chunk_size)
+        .zip(b_slice.par_chunks(chunk_size))
+        .for_each(|(a_chunk, b_chunk)| {
+            profiling::scope!("par_slice::add Thread Callback");
+            for (a, b) in a_chunk.iter_mut().zip(b_chunk.iter()) {
+                *a = *a + *b;
+            }
+        });
+}
+
 /// Implements a = a / c over slice elements.
 pub fn div<NumType: NumTrait>(
     a_slice: &mut [NumType],
@@ -171,6 +223,28 @@ mod unit_tests {
         }
     }
 
+    #[test]
+    fn multiply_add_test() {
+        let mut a = vec![1, 2, 3, 4, 5];
+        let b = vec![6, 7, 8, 9, 10];
+        let c = vec![2, 2, 2, 2, 2];
+        multiply_add(&mut a, &b, &c, 2);
+        for (i, x) in a.iter().enumerate() {
+            assert_eq!(*x, (i + 1) + 2 * (i + 6));
+        }
+    }
+
+    #[test]
+    fn add_test() {
+        let mut a = vec![1, 2, 3, 4, 5];
+        let b = vec![6, 7, 8, 9, 10];
+        add(&mut a, &b, 2);
+        add_value(&mut a, 3, 2);
+        for (i, x) in a.iter().enumerate() {
+            assert_eq!(*x, (i + 1) + (i + 6) + 3);
+        }
+    }
+
     #[test]
     fn power_test() {
         {
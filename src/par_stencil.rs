@@ -3,14 +3,68 @@ use crate::stencil::*;
 use crate::util::*;
 use rayon::prelude::*;
 
+/// Values are computed in `T::Weight` and stored as `T`,
+/// see `Scalar`.
+/// Each value is computed with `TIStencil::gather_apply`.
+/// Panics for complex `T` if `bc` reads its input,
+/// see `BCCheck::reads_input`.
 pub fn apply<
+    BC,
+    const GRID_DIMENSION: usize,
+    StencilType: TIStencil<GRID_DIMENSION, T::Weight>,
+    T: Scalar,
+    DomainType: DomainView<GRID_DIMENSION, T>,
+>(
+    bc: &BC,
+    stencil: &StencilType,
+    input: &DomainType,
+    output: &mut DomainType,
+    global_time: usize,
+    chunk_size: usize,
+) where
+    BC: BCCheck<GRID_DIMENSION>,
+{
+    debug_assert!(input.aabb().contains_aabb(output.aabb()));
+    assert!(
+        !T::COMPLEX || !bc.reads_input(),
+        "boundary conditions that read their input don't support {}",
+        T::NAME
+    );
+    output.par_modify_access(chunk_size).for_each(
+        |mut d: DomainChunk<'_, GRID_DIMENSION, T>| {
+            let mut args = Vec::new();
+            d.coord_iter_mut().for_each(
+                |(world_coord, value_mut): (Coord<GRID_DIMENSION>, &mut T)| {
+                    if let Some(value) =
+                        bc.check_solid(&world_coord, global_time)
+                    {
+                        *value_mut = T::cast(value);
+                        return;
+                    }
+                    let result = stencil.gather_apply(
+                        bc,
+                        input,
+                        &world_coord,
+                        global_time,
+                        &mut args,
+                    );
+                    *value_mut = T::from_weight(result);
+                },
+            )
+        },
+    )
+}
+
+/// Same as `apply`, but the weights depend on the output coordinate.
+pub fn apply_spatial<
     BC,
     const GRID_DIMENSION: usize,
     const NEIGHBORHOOD_SIZE: usize,
+    StencilType: SpatialStencil<GRID_DIMENSION, NEIGHBORHOOD_SIZE>,
     DomainType: DomainView<GRID_DIMENSION>,
 >(
     bc: &BC,
-    stencil: &Stencil<GRID_DIMENSION, NEIGHBORHOOD_SIZE>,
+    stencil: &StencilType,
     input: &DomainType,
     output: &mut DomainType,
     global_time: usize,
@@ -26,21 +80,142 @@ pub fn apply<
                     Coord<GRID_DIMENSION>,
                     &mut f64,
                 )| {
-                    let args = gather_args(
+                    if let Some(value) =
+                        bc.check_solid(&world_coord, global_time)
+                    {
+                        *value_mut = value;
+                        return;
+                    }
+                    let args = gather_spatial_args(
                         stencil,
                         bc,
                         input,
                         &world_coord,
                         global_time,
                     );
-                    let result = stencil.apply(&args);
-                    *value_mut = result;
+                    *value_mut =
+                        stencil.apply(&args, &world_coord, global_time);
                 },
             )
         },
     )
 }
 
+/// Computes one step of a two level stencil,
+/// `output = current_stencil(current) + previous_stencil(previous)`.
+/// Boundary conditions for the previous level are checked
+/// one step earlier than those for the current level.
+#[allow(clippy::too_many_arguments)]
+pub fn apply_two_level<
+    CurrentBC,
+    PreviousBC,
+    const GRID_DIMENSION: usize,
+    DomainType: DomainView<GRID_DIMENSION>,
+>(
+    current_bc: &CurrentBC,
+    previous_bc: &PreviousBC,
+    stencil: &TwoLevelStencil<GRID_DIMENSION>,
+    previous: &DomainType,
+    current: &DomainType,
+    output: &mut DomainType,
+    global_time: usize,
+    chunk_size: usize,
+) where
+    CurrentBC: BCCheck<GRID_DIMENSION>,
+    PreviousBC: BCCheck<GRID_DIMENSION>,
+{
+    debug_assert!(current.aabb().contains_aabb(output.aabb()));
+    debug_assert!(previous.aabb().contains_aabb(output.aabb()));
+    let current_stencil = stencil.current();
+    let previous_stencil = stencil.previous();
+    output.par_modify_access(chunk_size).for_each(
+        |mut d: DomainChunk<'_, GRID_DIMENSION>| {
+            let mut current_args = vec![0.0; current_stencil.len()];
+            let mut previous_args = vec![0.0; previous_stencil.len()];
+            d.coord_iter_mut().for_each(
+                |(world_coord, value_mut): (
+                    Coord<GRID_DIMENSION>,
+                    &mut f64,
+                )| {
+                    if let Some(value) =
+                        current_bc.check_solid(&world_coord, global_time)
+                    {
+                        *value_mut = value;
+                        return;
+                    }
+                    gather_args_into(
+                        &current_stencil.offsets,
+                        current_bc,
+                        current,
+                        &world_coord,
+                        global_time,
+                        &mut current_args,
+                    );
+                    gather_args_into(
+                        &previous_stencil.offsets,
+                        previous_bc,
+                        previous,
+                        &world_coord,
+                        global_time.saturating_sub(1),
+                        &mut previous_args,
+                    );
+                    *value_mut = current_stencil.apply(&current_args)
+                        + previous_stencil.apply(&previous_args);
+                },
+            )
+        },
+    )
+}
+
+/// Computes one step of a block stencil over every field.
+/// `bcs` has a boundary condition for each input field.
+pub fn apply_block<BC, const GRID_DIMENSION: usize>(
+    bcs: &[BC],
+    stencil: &BlockStencil<GRID_DIMENSION>,
+    input: &MultiFieldDomain<GRID_DIMENSION>,
+    output: &mut MultiFieldDomain<GRID_DIMENSION>,
+    global_time: usize,
+    chunk_size: usize,
+) where
+    BC: BCCheck<GRID_DIMENSION>,
+{
+    let fields = stencil.fields();
+    debug_assert_eq!(bcs.len(), fields);
+    debug_assert_eq!(input.n_fields(), fields);
+    debug_assert_eq!(output.n_fields(), fields);
+    debug_assert!(input.aabb().contains_aabb(output.aabb()));
+    let offsets = stencil.offsets();
+    for (f, output_field) in output.fields_mut().iter_mut().enumerate() {
+        let components: Vec<DynStencil<GRID_DIMENSION>> =
+            (0..fields).map(|g| stencil.component(f, g)).collect();
+        output_field.par_modify_access(chunk_size).for_each(
+            |mut d: DomainChunk<'_, GRID_DIMENSION>| {
+                let mut args = vec![0.0; offsets.len()];
+                d.coord_iter_mut().for_each(
+                    |(world_coord, value_mut): (
+                        Coord<GRID_DIMENSION>,
+                        &mut f64,
+                    )| {
+                        let mut result = 0.0;
+                        for (g, component) in components.iter().enumerate() {
+                            gather_args_into(
+                                offsets,
+                                &bcs[g],
+                                input.field(g),
+                                &world_coord,
+                                global_time,
+                                &mut args,
+                            );
+                            result += component.apply(&args);
+                        }
+                        *value_mut = result;
+                    },
+                )
+            },
+        )
+    }
+}
+
 #[cfg(test)]
 mod unit_test {
     use super::*;
@@ -123,4 +298,116 @@ mod unit_test {
             assert_approx_eq!(f64, *i, 1.0);
         }
     }
+
+    #[test]
+    fn par_stencil_dyn_matches_static() {
+        let chunk_size = 7;
+        let stencil = Stencil::new(
+            [[0, -1], [0, 1], [1, 0], [-1, 0], [0, 0]],
+            |args: &[f64; 5]| {
+                0.1 * args[0] + 0.2 * args[1] + 0.3 * args[2] + 0.15 * args[3]
+                    - 0.5 * args[4]
+            },
+        );
+        let dyn_stencil = DynStencil::from(&stencil);
+        let bound = AABB::new(matrix![0, 19; 0, 12]);
+        let mut input_domain = OwnedDomain::new(bound);
+        let mut static_output = OwnedDomain::new(bound);
+        let mut dyn_output = OwnedDomain::new(bound);
+        input_domain.par_set_values(
+            |coord: Coord<2>| (coord[0] * 2 + coord[1]) as f64,
+            chunk_size,
+        );
+        let bc = ConstantCheck::new(3.0, bound);
+        apply(
+            &bc,
+            &stencil,
+            &input_domain,
+            &mut static_output,
+            0,
+            chunk_size,
+        );
+        apply(
+            &bc,
+            &dyn_stencil,
+            &input_domain,
+            &mut dyn_output,
+            0,
+            chunk_size,
+        );
+        for (s, d) in static_output.buffer().iter().zip(dyn_output.buffer()) {
+            assert_approx_eq!(f64, *s, *d);
+        }
+    }
+
+    #[test]
+    fn par_stencil_spatial_homogeneous_matches_apply() {
+        let chunk_size = 5;
+        let stencil =
+            crate::standard_stencils::heat_2d(1.0, 1.0, 1.0, 0.2, 0.1);
+        let mut spatial = RegionStencil::new(
+            crate::standard_stencils::heat_2d(1.0, 1.0, 1.0, 0.2, 0.1),
+        );
+        let inclusion =
+            crate::standard_stencils::heat_2d(1.0, 1.0, 1.0, 0.05, 0.05);
+        let bound = AABB::new(matrix![0, 19; 0, 12]);
+        let mut input_domain = OwnedDomain::new(bound);
+        let mut expected = OwnedDomain::new(bound);
+        let mut spatial_output = OwnedDomain::new(bound);
+        input_domain.par_set_values(
+            |coord: Coord<2>| (coord[0] * 2 + coord[1]) as f64,
+            chunk_size,
+        );
+        let bc = ConstantCheck::new(3.0, bound);
+        apply(&bc, &stencil, &input_domain, &mut expected, 0, chunk_size);
+
+        // Background only matches the plain stencil
+        apply_spatial(
+            &bc,
+            &spatial,
+            &input_domain,
+            &mut spatial_output,
+            0,
+            chunk_size,
+        );
+        for (e, s) in expected.buffer().iter().zip(spatial_output.buffer()) {
+            assert_approx_eq!(f64, *e, *s);
+        }
+
+        // Inclusion only changes values inside of it
+        let region = AABB::new(matrix![4, 8; 3, 6]);
+        spatial.add_region(region, &inclusion);
+        apply_spatial(
+            &bc,
+            &spatial,
+            &input_domain,
+            &mut spatial_output,
+            0,
+            chunk_size,
+        );
+        for coord in bound.coord_iter() {
+            let s = spatial_output.view(&coord);
+            if region.contains(&coord) {
+                let args =
+                    gather_args(&inclusion, &bc, &input_domain, &coord, 0);
+                assert_approx_eq!(f64, s, inclusion.apply(&args));
+            } else {
+                assert_approx_eq!(f64, s, expected.view(&coord));
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn par_stencil_complex_rejects_input_reads() {
+        let stencil =
+            Stencil::new_complex([[-1], [0], [1]], |args: &[c64; 3]| {
+                args[0] + args[1] + args[2]
+            });
+        let bound = AABB::new(matrix![0, 9]);
+        let input_domain = OwnedDomain::<1, c64>::new_scalar(bound);
+        let mut output_domain = OwnedDomain::<1, c64>::new_scalar(bound);
+        let bc = RobinCheck::new(1.0, 1.0, 0.0, 1.0, bound);
+        apply(&bc, &stencil, &input_domain, &mut output_domain, 0, 4);
+    }
 }
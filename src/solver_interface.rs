@@ -1,11 +1,44 @@
 use crate::domain::*;
+use crate::util::Scalar;
 
-/// All solvers should adhere implement this interface
-pub trait SolverInterface<const GRID_DIMENSION: usize> {
+/// All solvers should adhere implement this interface.
+/// Solvers that support other scalar types implement it for each `T`.
+pub trait SolverInterface<const GRID_DIMENSION: usize, T: Scalar = f64> {
     fn apply<'a>(
         &mut self,
-        input_domain: &mut SliceDomain<'a, GRID_DIMENSION>,
-        output_domain: &mut SliceDomain<'a, GRID_DIMENSION>,
+        input_domain: &mut SliceDomain<'a, GRID_DIMENSION, T>,
+        output_domain: &mut SliceDomain<'a, GRID_DIMENSION, T>,
+        global_time: usize,
+    );
+
+    fn print_report(&self);
+
+    fn to_dot_file<P: AsRef<std::path::Path>>(&self, path: &P);
+
+    /// Record per node runtimes in later applies,
+    /// only AP solvers support this.
+    fn enable_instrumentation(&mut self) {
+        eprintln!("WARNING: Solver does not support instrumentation");
+    }
+
+    /// Write out the records from `enable_instrumentation`.
+    fn write_instrumentation<P: AsRef<std::path::Path>>(&self, _path: &P) {}
+}
+
+/// Interface for solvers of two level stencils,
+/// where each step depends on the last two time levels.
+///
+/// On entry the input domains hold the levels at
+/// `global_time - 1` and `global_time`.
+/// On exit the output domains hold the last two levels computed,
+/// and the input domains have been clobbered.
+pub trait TwoLevelSolverInterface<const GRID_DIMENSION: usize> {
+    fn apply<'a>(
+        &mut self,
+        input_previous: &mut SliceDomain<'a, GRID_DIMENSION>,
+        input_current: &mut SliceDomain<'a, GRID_DIMENSION>,
+        output_previous: &mut SliceDomain<'a, GRID_DIMENSION>,
+        output_current: &mut SliceDomain<'a, GRID_DIMENSION>,
         global_time: usize,
     );
 
@@ -0,0 +1,135 @@
+use crate::stencil::*;
+use crate::util::*;
+use nalgebra::DMatrix;
+
+/// Linear stencils for systems of coupled fields,
+/// e.g. linear reaction-diffusion with several species.
+/// Each offset carries a `fields` x `fields` matrix of weights,
+/// where entry (f, g) is how much field g at that offset
+/// contributes to field f at the output coordinate.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlockStencil<const GRID_DIMENSION: usize> {
+    pub fields: usize,
+    pub offsets: Vec<Coord<GRID_DIMENSION>>,
+    pub weights: Vec<DMatrix<f64>>,
+}
+
+impl<const GRID_DIMENSION: usize> BlockStencil<GRID_DIMENSION> {
+    /// A stencil with no terms, i.e. that maps everything to zero.
+    pub fn new(fields: usize) -> Self {
+        assert!(fields > 0);
+        BlockStencil {
+            fields,
+            offsets: Vec::new(),
+            weights: Vec::new(),
+        }
+    }
+
+    /// Fields that don't interact, each updated by their own stencil.
+    pub fn uncoupled<StencilType: TIStencil<GRID_DIMENSION>>(
+        stencils: &[StencilType],
+    ) -> Self {
+        let fields = stencils.len();
+        let mut result = Self::new(fields);
+        for (f, stencil) in stencils.iter().enumerate() {
+            for (offset, weight) in
+                stencil.offset_slice().iter().zip(stencil.weight_slice())
+            {
+                let mut matrix = DMatrix::zeros(fields, fields);
+                matrix[(f, f)] = *weight;
+                result.add_term(offset, &matrix);
+            }
+        }
+        result
+    }
+
+    /// Adds `matrix` to the weights at `offset`.
+    pub fn add_term(
+        &mut self,
+        offset: &Coord<GRID_DIMENSION>,
+        matrix: &DMatrix<f64>,
+    ) {
+        assert_eq!(matrix.shape(), (self.fields, self.fields));
+        match self.offsets.iter().position(|o| o == offset) {
+            Some(i) => self.weights[i] += matrix,
+            None => {
+                self.offsets.push(*offset);
+                self.weights.push(matrix.clone());
+            }
+        }
+    }
+
+    /// Adds a pointwise coupling between fields,
+    /// e.g. the linear reaction term of a reaction-diffusion system.
+    pub fn add_coupling(&mut self, matrix: &DMatrix<f64>) {
+        self.add_term(&Coord::zeros(), matrix);
+    }
+
+    pub fn fields(&self) -> usize {
+        self.fields
+    }
+
+    pub fn offsets(&self) -> &[Coord<GRID_DIMENSION>] {
+        &self.offsets
+    }
+
+    pub fn weights(&self) -> &[DMatrix<f64>] {
+        &self.weights
+    }
+
+    pub fn slopes(&self) -> Bounds<GRID_DIMENSION> {
+        offset_slopes(&self.offsets)
+    }
+
+    /// The scalar stencil describing how field `input_field`
+    /// contributes to field `output_field`.
+    pub fn component(
+        &self,
+        output_field: usize,
+        input_field: usize,
+    ) -> DynStencil<GRID_DIMENSION> {
+        DynStencil::from_offset_weights(
+            self.offsets.clone(),
+            self.weights
+                .iter()
+                .map(|w| w[(output_field, input_field)])
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+    use crate::standard_stencils::*;
+    use nalgebra::{dmatrix, matrix, vector};
+
+    #[test]
+    fn block_stencil() {
+        let a = heat_1d(1.0, 1.0, 0.2);
+        let b = heat_1d(1.0, 1.0, 0.1);
+        let mut stencil = BlockStencil::uncoupled(&[
+            heat_1d(1.0, 1.0, 0.2),
+            heat_1d(1.0, 1.0, 0.1),
+        ]);
+        assert_eq!(stencil.fields(), 2);
+        assert_eq!(stencil.offsets().len(), 3);
+        assert_eq!(stencil.slopes(), matrix![1, 1]);
+        assert_eq!(stencil.component(0, 0).weights(), a.weights().as_slice());
+        assert_eq!(stencil.component(1, 1).weights(), b.weights().as_slice());
+        assert_eq!(stencil.component(0, 1).weights(), &[0.0, 0.0, 0.0]);
+
+        stencil.add_coupling(&dmatrix![-0.1, 0.05; 0.1, -0.05]);
+        assert_eq!(stencil.offsets().len(), 3);
+        let center = stencil
+            .offsets()
+            .iter()
+            .position(|o| *o == vector![0])
+            .unwrap();
+        assert_eq!(stencil.component(0, 1).weights()[center], 0.05);
+        assert_eq!(
+            stencil.component(0, 0).weights()[center],
+            a.weights()[center] - 0.1
+        );
+    }
+}
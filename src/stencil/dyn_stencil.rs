@@ -0,0 +1,217 @@
+use crate::stencil::*;
+use crate::util::*;
+
+/// Runtime sized counterpart of `extract_weights`.
+pub fn extract_dyn_weights<F: Fn(&[f64]) -> f64>(
+    neighborhood_size: usize,
+    f: F,
+) -> Vec<f64> {
+    let mut weights = vec![0.0; neighborhood_size];
+    let mut arg_buffer = vec![0.0; neighborhood_size];
+    for n in 0..neighborhood_size {
+        arg_buffer[n] = 1.0;
+        weights[n] = f(&arg_buffer);
+        arg_buffer[n] = 0.0;
+    }
+    weights
+}
+
+/// A linear stencil whose neighborhood size is only known at runtime,
+/// e.g. one that was loaded from a file or built by combining stencils.
+/// Accepted anywhere a `TIStencil` is.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DynStencil<const GRID_DIMENSION: usize> {
+    pub weights: Vec<f64>,
+    pub offsets: Vec<Coord<GRID_DIMENSION>>,
+}
+
+impl<const GRID_DIMENSION: usize> DynStencil<GRID_DIMENSION> {
+    pub fn new<F: Fn(&[f64]) -> f64>(
+        offsets: &[[i32; GRID_DIMENSION]],
+        operation: F,
+    ) -> Self {
+        let weights = extract_dyn_weights(offsets.len(), operation);
+        DynStencil {
+            offsets: offsets
+                .iter()
+                .map(|o| Coord::from_column_slice(o))
+                .collect(),
+            weights,
+        }
+    }
+
+    /// Checked version of `new`.
+    /// Rejects duplicate offsets, and operations that aren't linear.
+    pub fn try_new<F: Fn(&[f64]) -> f64>(
+        offsets: &[[i32; GRID_DIMENSION]],
+        operation: F,
+    ) -> Result<Self, StencilError> {
+        let result = Self::new(offsets, &operation);
+        check_unique_offsets(&result.offsets)?;
+        check_linearity(&result.weights, operation)?;
+        Ok(result)
+    }
+
+    pub fn from_offset_weights(
+        offsets: Vec<Coord<GRID_DIMENSION>>,
+        weights: Vec<f64>,
+    ) -> Self {
+        assert_eq!(offsets.len(), weights.len());
+        DynStencil { offsets, weights }
+    }
+
+    pub fn weights(&self) -> &[f64] {
+        &self.weights
+    }
+
+    pub fn offsets(&self) -> &[Coord<GRID_DIMENSION>] {
+        &self.offsets
+    }
+
+    pub fn len(&self) -> usize {
+        self.offsets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.offsets.is_empty()
+    }
+
+    pub fn slopes(&self) -> Bounds<GRID_DIMENSION> {
+        offset_slopes(&self.offsets)
+    }
+
+    pub fn apply(&self, args: &[f64]) -> f64 {
+        debug_assert_eq!(args.len(), self.weights.len());
+        self.weights
+            .iter()
+            .zip(args.iter())
+            .map(|(w, a)| w * a)
+            .sum()
+    }
+}
+
+impl<const GRID_DIMENSION: usize, const NEIGHBORHOOD_SIZE: usize>
+    From<&Stencil<GRID_DIMENSION, NEIGHBORHOOD_SIZE>>
+    for DynStencil<GRID_DIMENSION>
+{
+    fn from(stencil: &Stencil<GRID_DIMENSION, NEIGHBORHOOD_SIZE>) -> Self {
+        DynStencil {
+            offsets: stencil.offsets.to_vec(),
+            weights: stencil.weights.as_slice().to_vec(),
+        }
+    }
+}
+
+/// For solvers that need the neighborhood size at compile time,
+/// e.g. `Stencil::try_from(&a.compose(&b))`.
+impl<const GRID_DIMENSION: usize, const NEIGHBORHOOD_SIZE: usize>
+    TryFrom<&DynStencil<GRID_DIMENSION>>
+    for Stencil<GRID_DIMENSION, NEIGHBORHOOD_SIZE>
+{
+    type Error = StencilError;
+
+    fn try_from(
+        stencil: &DynStencil<GRID_DIMENSION>,
+    ) -> Result<Self, Self::Error> {
+        if stencil.len() != NEIGHBORHOOD_SIZE {
+            return Err(StencilError::NeighborhoodSize {
+                expected: NEIGHBORHOOD_SIZE,
+                actual: stencil.len(),
+            });
+        }
+        Ok(Stencil {
+            offsets: std::array::from_fn(|i| stencil.offsets[i]),
+            weights: Values::from_column_slice(&stencil.weights),
+        })
+    }
+}
+
+impl<const GRID_DIMENSION: usize> TIStencil<GRID_DIMENSION>
+    for DynStencil<GRID_DIMENSION>
+{
+    fn offset_slice(&self) -> &[Coord<GRID_DIMENSION>] {
+        &self.offsets
+    }
+
+    fn weight_slice(&self) -> &[f64] {
+        &self.weights
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+    use float_cmp::assert_approx_eq;
+    use nalgebra::matrix;
+
+    #[test]
+    fn extract_weights() {
+        let s = DynStencil::new(&[[1], [2], [3]], |args: &[f64]| {
+            2.0 * args[0] + 3.0 * args[1] + 5.0 * args[2]
+        });
+        assert_eq!(s.len(), 3);
+        let w = s.weights();
+        assert_approx_eq!(f64, w[0], 2.0, ulps = 1);
+        assert_approx_eq!(f64, w[1], 3.0, ulps = 1);
+        assert_approx_eq!(f64, w[2], 5.0, ulps = 1);
+        assert_approx_eq!(f64, s.apply(&[1.0, 1.0, 1.0]), 10.0);
+    }
+
+    #[test]
+    fn try_new() {
+        let s = DynStencil::try_new(&[[1, 0], [0, 0]], |args: &[f64]| {
+            0.5 * args[0] + 0.5 * args[1]
+        })
+        .unwrap();
+        assert_eq!(s.len(), 2);
+
+        let duplicate =
+            DynStencil::try_new(&[[1, 0], [1, 0]], |args: &[f64]| {
+                args[0] + args[1]
+            });
+        assert!(matches!(
+            duplicate,
+            Err(StencilError::DuplicateOffset { .. })
+        ));
+
+        let nonlinear =
+            DynStencil::try_new(&[[1, 0], [0, 0]], |args: &[f64]| {
+                (args[0] * args[1]).sin() + args[0]
+            });
+        assert!(matches!(nonlinear, Err(StencilError::Nonlinear { .. })));
+    }
+
+    #[test]
+    fn slopes() {
+        let s = DynStencil::new(
+            &[[-1, 0], [0, 0], [1, 0], [0, 2], [0, -3]],
+            |args: &[f64]| 2.0 * args[0] + args[1],
+        );
+        assert_eq!(s.slopes(), matrix![1, 1; 3, 2]);
+        assert_eq!(TIStencil::slopes(&s), matrix![1, 1; 3, 2]);
+    }
+
+    #[test]
+    fn from_stencil() {
+        let stencil = Stencil::new(
+            [[-1, 0], [0, 0], [1, 0], [0, 2], [0, -3]],
+            |args: &[f64; 5]| {
+                args[0] + 2.0 * args[1] + 3.0 * args[2] + 4.0 * args[3]
+                    - args[4]
+            },
+        );
+        let dyn_stencil = DynStencil::from(&stencil);
+        assert_eq!(dyn_stencil.len(), 5);
+        assert_eq!(dyn_stencil.slopes(), stencil.slopes());
+        for n in 0..5 {
+            assert_eq!(dyn_stencil.offsets()[n], stencil.offsets()[n]);
+            assert_approx_eq!(
+                f64,
+                dyn_stencil.weights()[n],
+                stencil.weights()[n]
+            );
+        }
+        assert_eq!(stencil.offset_slice(), dyn_stencil.offset_slice());
+        assert_eq!(stencil.weight_slice(), dyn_stencil.weight_slice());
+    }
+}
@@ -2,11 +2,26 @@
 /// Artifact I haven't wanted to address yet
 mod stencil;
 
+mod block_stencil;
 mod circ_stencil;
+mod dyn_stencil;
+mod spatial_stencil;
+mod stencil_file;
+mod ti_stencil;
 mod tv_stencil;
+mod two_level_stencil;
+mod validation;
 
 pub mod standard_stencils;
+pub mod stencil_algebra;
 
+pub use block_stencil::*;
 pub use circ_stencil::*;
+pub use dyn_stencil::*;
+pub use spatial_stencil::*;
 pub use stencil::*;
+pub use stencil_file::*;
+pub use ti_stencil::*;
 pub use tv_stencil::*;
+pub use two_level_stencil::*;
+pub use validation::*;
@@ -0,0 +1,170 @@
+use crate::stencil::*;
+use crate::util::*;
+
+/// Linear stencils whose weights depend on where they are applied,
+/// e.g. diffusion through a heterogeneous material.
+///
+/// The AP solver can only use periodic (FFT) solves over regions
+/// where the weights match `homogeneous_weights`,
+/// so implementations must be able to answer `is_homogeneous`
+/// for any AABB cheaply.
+pub trait SpatialStencil<
+    const GRID_DIMENSION: usize,
+    const NEIGHBORHOOD_SIZE: usize,
+>: Send + Sync
+{
+    fn offsets(&self) -> &[Coord<GRID_DIMENSION>; NEIGHBORHOOD_SIZE];
+
+    /// Weights for computing the value at `world_coord`
+    /// for the step ending at `global_time`.
+    fn weights(
+        &self,
+        world_coord: &Coord<GRID_DIMENSION>,
+        global_time: usize,
+    ) -> Values<NEIGHBORHOOD_SIZE>;
+
+    /// Background weights, used for periodic solves.
+    fn homogeneous_weights(&self) -> Values<NEIGHBORHOOD_SIZE>;
+
+    /// True if `weights` equals `homogeneous_weights`
+    /// at every coordinate of `aabb` for all time.
+    /// Returning false is always safe, but forces direct solves.
+    fn is_homogeneous(&self, aabb: &AABB<GRID_DIMENSION>) -> bool;
+
+    fn slopes(&self) -> Bounds<GRID_DIMENSION> {
+        offset_slopes(self.offsets())
+    }
+
+    fn apply(
+        &self,
+        args: &Values<NEIGHBORHOOD_SIZE>,
+        world_coord: &Coord<GRID_DIMENSION>,
+        global_time: usize,
+    ) -> f64 {
+        self.weights(world_coord, global_time)
+            .component_mul(args)
+            .sum()
+    }
+
+    /// The time invariant stencil matching `homogeneous_weights`.
+    fn homogeneous_stencil(
+        &self,
+    ) -> Stencil<GRID_DIMENSION, NEIGHBORHOOD_SIZE> {
+        Stencil {
+            weights: self.homogeneous_weights(),
+            offsets: *self.offsets(),
+        }
+    }
+}
+
+/// A background stencil with rectangular regions of different weights,
+/// e.g. inclusions of another material.
+/// Regions added later take priority where they overlap.
+pub struct RegionStencil<
+    const GRID_DIMENSION: usize,
+    const NEIGHBORHOOD_SIZE: usize,
+> {
+    background: Stencil<GRID_DIMENSION, NEIGHBORHOOD_SIZE>,
+    regions: Vec<(AABB<GRID_DIMENSION>, Values<NEIGHBORHOOD_SIZE>)>,
+}
+
+impl<const GRID_DIMENSION: usize, const NEIGHBORHOOD_SIZE: usize>
+    RegionStencil<GRID_DIMENSION, NEIGHBORHOOD_SIZE>
+{
+    pub fn new(background: Stencil<GRID_DIMENSION, NEIGHBORHOOD_SIZE>) -> Self {
+        RegionStencil {
+            background,
+            regions: Vec::new(),
+        }
+    }
+
+    /// Use the weights from `stencil` inside of `aabb`.
+    /// The offsets of `stencil` must match the background stencil.
+    pub fn add_region(
+        &mut self,
+        aabb: AABB<GRID_DIMENSION>,
+        stencil: &Stencil<GRID_DIMENSION, NEIGHBORHOOD_SIZE>,
+    ) {
+        assert_eq!(stencil.offsets(), self.background.offsets());
+        self.regions.push((aabb, *stencil.weights()));
+    }
+
+    pub fn regions(
+        &self,
+    ) -> &[(AABB<GRID_DIMENSION>, Values<NEIGHBORHOOD_SIZE>)] {
+        &self.regions
+    }
+}
+
+impl<const GRID_DIMENSION: usize, const NEIGHBORHOOD_SIZE: usize>
+    SpatialStencil<GRID_DIMENSION, NEIGHBORHOOD_SIZE>
+    for RegionStencil<GRID_DIMENSION, NEIGHBORHOOD_SIZE>
+{
+    fn offsets(&self) -> &[Coord<GRID_DIMENSION>; NEIGHBORHOOD_SIZE] {
+        self.background.offsets()
+    }
+
+    fn weights(
+        &self,
+        world_coord: &Coord<GRID_DIMENSION>,
+        _global_time: usize,
+    ) -> Values<NEIGHBORHOOD_SIZE> {
+        self.regions
+            .iter()
+            .rev()
+            .find(|(aabb, _)| aabb.contains(world_coord))
+            .map(|(_, weights)| *weights)
+            .unwrap_or(*self.background.weights())
+    }
+
+    fn homogeneous_weights(&self) -> Values<NEIGHBORHOOD_SIZE> {
+        *self.background.weights()
+    }
+
+    fn is_homogeneous(&self, aabb: &AABB<GRID_DIMENSION>) -> bool {
+        // Walk from the highest priority region down,
+        // a background region covering all of aabb hides the rest
+        for (region, weights) in self.regions.iter().rev() {
+            if !region.intersects(aabb) {
+                continue;
+            }
+            if weights != self.background.weights() {
+                return false;
+            }
+            if region.contains_aabb(aabb) {
+                return true;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+    use crate::standard_stencils::*;
+    use nalgebra::{matrix, vector};
+
+    #[test]
+    fn region_stencil() {
+        let background = heat_2d(1.0, 1.0, 1.0, 0.2, 0.2);
+        let inclusion = heat_2d(1.0, 1.0, 1.0, 0.05, 0.05);
+        let mut stencil = RegionStencil::new(heat_2d(1.0, 1.0, 1.0, 0.2, 0.2));
+        stencil.add_region(AABB::new(matrix![10, 19; 5, 9]), &inclusion);
+
+        assert_eq!(stencil.slopes(), background.slopes());
+        assert_eq!(stencil.weights(&vector![0, 0], 0), *background.weights());
+        assert_eq!(stencil.weights(&vector![10, 9], 3), *inclusion.weights());
+        assert_eq!(stencil.homogeneous_weights(), *background.weights());
+
+        assert!(stencil.is_homogeneous(&AABB::new(matrix![0, 9; 0, 30])));
+        assert!(stencil.is_homogeneous(&AABB::new(matrix![0, 30; 10, 30])));
+        assert!(!stencil.is_homogeneous(&AABB::new(matrix![0, 10; 0, 5])));
+
+        // Regions matching the background don't break homogeneity
+        stencil.add_region(AABB::new(matrix![12, 14; 6, 8]), &background);
+        assert!(stencil.is_homogeneous(&AABB::new(matrix![12, 14; 6, 8])));
+        assert!(!stencil.is_homogeneous(&AABB::new(matrix![12, 15; 6, 8])));
+        assert_eq!(stencil.weights(&vector![13, 7], 0), *background.weights());
+    }
+}
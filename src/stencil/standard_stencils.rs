@@ -62,6 +62,509 @@ pub fn heat_3d(
     )
 }
 
+/// Explicit Euler step u + dt * L(u) for a linear operator L,
+/// given as (offset, weight) terms. Offsets may repeat.
+fn euler_step<const GRID_DIMENSION: usize, const NEIGHBORHOOD_SIZE: usize>(
+    offsets: [[i32; GRID_DIMENSION]; NEIGHBORHOOD_SIZE],
+    terms: &[([i32; GRID_DIMENSION], f64)],
+    dt: f64,
+) -> Stencil<GRID_DIMENSION, NEIGHBORHOOD_SIZE> {
+    let offsets = offsets.map(Coord::from);
+    let mut weights = Values::zeros();
+    let center = offsets
+        .iter()
+        .position(|o| *o == Coord::<GRID_DIMENSION>::zeros())
+        .unwrap();
+    weights[center] = 1.0;
+    for (offset, weight) in terms {
+        let i = offsets
+            .iter()
+            .position(|o| *o == Coord::from(*offset))
+            .unwrap();
+        weights[i] += dt * weight;
+    }
+    Stencil { weights, offsets }
+}
+
+/// Fourth order accurate second derivative along `axis`,
+/// (-1, 16, -30, 16, -1) / 12.
+fn fourth_order_terms<const GRID_DIMENSION: usize>(
+    axis: usize,
+    scale: f64,
+) -> Vec<([i32; GRID_DIMENSION], f64)> {
+    [(-2, -1.0), (-1, 16.0), (0, -30.0), (1, 16.0), (2, -1.0)]
+        .iter()
+        .map(|(d, w)| {
+            let mut offset = [0; GRID_DIMENSION];
+            offset[axis] = *d;
+            (offset, scale * w / 12.0)
+        })
+        .collect()
+}
+
+/// Heat equation with a fourth order accurate Laplacian.
+/// Reaches two neighbors out, so the slopes are 2.
+pub fn heat_1d_4th(dt: f64, dx: f64, k: f64) -> Stencil<1, 5> {
+    euler_step(
+        [[0], [-1], [1], [-2], [2]],
+        &fourth_order_terms(0, k / (dx * dx)),
+        dt,
+    )
+}
+
+pub fn heat_2d_4th(
+    dt: f64,
+    dx: f64,
+    dy: f64,
+    k_x: f64,
+    k_y: f64,
+) -> Stencil<2, 9> {
+    let mut terms = fourth_order_terms(0, k_x / (dx * dx));
+    terms.extend(fourth_order_terms(1, k_y / (dy * dy)));
+    euler_step(
+        [
+            [0, 0],
+            [-1, 0],
+            [1, 0],
+            [0, -1],
+            [0, 1],
+            [-2, 0],
+            [2, 0],
+            [0, -2],
+            [0, 2],
+        ],
+        &terms,
+        dt,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn heat_3d_4th(
+    dt: f64,
+    dx: f64,
+    dy: f64,
+    dz: f64,
+    k_x: f64,
+    k_y: f64,
+    k_z: f64,
+) -> Stencil<3, 13> {
+    let mut terms = fourth_order_terms(0, k_x / (dx * dx));
+    terms.extend(fourth_order_terms(1, k_y / (dy * dy)));
+    terms.extend(fourth_order_terms(2, k_z / (dz * dz)));
+    euler_step(
+        [
+            [0, 0, 0],
+            [-1, 0, 0],
+            [1, 0, 0],
+            [0, -1, 0],
+            [0, 1, 0],
+            [0, 0, -1],
+            [0, 0, 1],
+            [-2, 0, 0],
+            [2, 0, 0],
+            [0, -2, 0],
+            [0, 2, 0],
+            [0, 0, -2],
+            [0, 0, 2],
+        ],
+        &terms,
+        dt,
+    )
+}
+
+/// Offsets of the 3^d box around the center, center first.
+fn box_offsets<const GRID_DIMENSION: usize, const NEIGHBORHOOD_SIZE: usize>(
+) -> [[i32; GRID_DIMENSION]; NEIGHBORHOOD_SIZE] {
+    assert_eq!(NEIGHBORHOOD_SIZE, 3usize.pow(GRID_DIMENSION as u32));
+    let mut offsets = [[0; GRID_DIMENSION]; NEIGHBORHOOD_SIZE];
+    for (i, offset) in offsets.iter_mut().enumerate() {
+        let mut rem = i;
+        for o in offset.iter_mut() {
+            // 0, -1, 1 so that index 0 is the center
+            *o = [0, -1, 1][rem % 3];
+            rem /= 3;
+        }
+    }
+    offsets
+}
+
+/// Heat equation with the isotropic 9 point Laplacian,
+/// (4 * edges + corners - 20 * center) / (6 dx^2).
+/// Its error is rotationally symmetric to leading order.
+pub fn heat_2d_isotropic(dt: f64, dx: f64, k: f64) -> Stencil<2, 9> {
+    let offsets = box_offsets();
+    let scale = k / (6.0 * dx * dx);
+    let terms: Vec<_> = offsets
+        .iter()
+        .map(|o| {
+            let weight = match o.iter().filter(|d| **d != 0).count() {
+                0 => -20.0,
+                1 => 4.0,
+                _ => 1.0,
+            };
+            (*o, scale * weight)
+        })
+        .collect();
+    euler_step(offsets, &terms, dt)
+}
+
+/// Heat equation with the isotropic 27 point Laplacian,
+/// (14 * faces + 3 * edges + corners - 128 * center) / (30 dx^2).
+pub fn heat_3d_isotropic(dt: f64, dx: f64, k: f64) -> Stencil<3, 27> {
+    let offsets = box_offsets();
+    let scale = k / (30.0 * dx * dx);
+    let terms: Vec<_> = offsets
+        .iter()
+        .map(|o| {
+            let weight = match o.iter().filter(|d| **d != 0).count() {
+                0 => -128.0,
+                1 => 14.0,
+                2 => 3.0,
+                _ => 1.0,
+            };
+            (*o, scale * weight)
+        })
+        .collect();
+    euler_step(offsets, &terms, dt)
+}
+
+/// Hyperdiffusion u_t = -k laplace(laplace(u))
+/// with the 13 point biharmonic stencil.
+/// Stable for k dt / dx^4 <= 1 / 32.
+pub fn biharmonic_2d(dt: f64, dx: f64, k: f64) -> Stencil<2, 13> {
+    let scale = -k / dx.powi(4);
+    let offsets = [
+        [0, 0],
+        [-1, 0],
+        [1, 0],
+        [0, -1],
+        [0, 1],
+        [-1, -1],
+        [1, -1],
+        [-1, 1],
+        [1, 1],
+        [-2, 0],
+        [2, 0],
+        [0, -2],
+        [0, 2],
+    ];
+    let terms: Vec<_> = offsets
+        .iter()
+        .map(|o: &[i32; 2]| {
+            let weight = match (o[0].abs(), o[1].abs()) {
+                (0, 0) => 20.0,
+                (1, 0) | (0, 1) => -8.0,
+                (1, 1) => 2.0,
+                _ => 1.0,
+            };
+            (*o, scale * weight)
+        })
+        .collect();
+    euler_step(offsets, &terms, dt)
+}
+
+/// Leapfrog scheme for the 1D wave equation,
+/// u^{t+1} = 2 u^t - u^{t-1} + (c dt / dx)^2 (u_{x-1} - 2 u + u_{x+1}).
+/// Stable for c dt / dx <= 1.
+pub fn wave_1d(dt: f64, dx: f64, c: f64) -> TwoLevelStencil<1> {
+    let r2 = (c * dt / dx).powi(2);
+    let current = Stencil::new([[1], [-1], [0]], move |args: &[f64; 3]| {
+        let left = args[1];
+        let middle = args[2];
+        let right = args[0];
+        2.0 * middle + r2 * (left - 2.0 * middle + right)
+    });
+    let previous = Stencil::new([[0]], |args: &[f64; 1]| -args[0]);
+    TwoLevelStencil::new(&current, &previous)
+}
+
+/// Leapfrog scheme for the 2D wave equation.
+/// Stable for c dt sqrt(1 / dx^2 + 1 / dy^2) <= 1.
+pub fn wave_2d(dt: f64, dx: f64, dy: f64, c: f64) -> TwoLevelStencil<2> {
+    let r2_x = (c * dt / dx).powi(2);
+    let r2_y = (c * dt / dy).powi(2);
+    let current = Stencil::new(
+        [[1, 0], [0, -1], [-1, 0], [0, 1], [0, 0]],
+        move |args: &[f64; 5]| {
+            let middle = args[4];
+            let left = args[2];
+            let right = args[0];
+            let bottom = args[1];
+            let top = args[3];
+            2.0 * middle
+                + r2_x * (left - 2.0 * middle + right)
+                + r2_y * (top - 2.0 * middle + bottom)
+        },
+    );
+    let previous = Stencil::new([[0, 0]], |args: &[f64; 1]| -args[0]);
+    TwoLevelStencil::new(&current, &previous)
+}
+
+/// Leapfrog scheme for the 3D wave equation.
+/// Stable for c dt sqrt(1 / dx^2 + 1 / dy^2 + 1 / dz^2) <= 1.
+pub fn wave_3d(
+    dt: f64,
+    dx: f64,
+    dy: f64,
+    dz: f64,
+    c: f64,
+) -> TwoLevelStencil<3> {
+    let r2_x = (c * dt / dx).powi(2);
+    let r2_y = (c * dt / dy).powi(2);
+    let r2_z = (c * dt / dz).powi(2);
+    let current = Stencil::new(
+        [
+            [0, 0, 0],
+            [-1, 0, 0],
+            [1, 0, 0],
+            [0, -1, 0],
+            [0, 1, 0],
+            [0, 0, -1],
+            [0, 0, 1],
+        ],
+        move |args: &[f64; 7]| {
+            let middle = args[0];
+            let left = args[1];
+            let right = args[2];
+            let bottom = args[3];
+            let top = args[4];
+            let front = args[5];
+            let back = args[6];
+            2.0 * middle
+                + r2_x * (left - 2.0 * middle + right)
+                + r2_y * (top - 2.0 * middle + bottom)
+                + r2_z * (front - 2.0 * middle + back)
+        },
+    );
+    let previous = Stencil::new([[0, 0, 0]], |args: &[f64; 1]| -args[0]);
+    TwoLevelStencil::new(&current, &previous)
+}
+
+/// Offsets for the compact advection-diffusion stencils,
+/// the center followed by the -/+ neighbor along each axis.
+fn advection_diffusion_offsets<
+    const GRID_DIMENSION: usize,
+    const NEIGHBORHOOD_SIZE: usize,
+>() -> [Coord<GRID_DIMENSION>; NEIGHBORHOOD_SIZE] {
+    assert_eq!(NEIGHBORHOOD_SIZE, 2 * GRID_DIMENSION + 1);
+    let mut offsets = [Coord::zeros(); NEIGHBORHOOD_SIZE];
+    for d in 0..GRID_DIMENSION {
+        offsets[2 * d + 1][d] = -1;
+        offsets[2 * d + 2][d] = 1;
+    }
+    offsets
+}
+
+/// Weights for u_t + v . grad(u) = k laplace(u) on a uniform grid,
+/// matching `advection_diffusion_offsets`.
+///
+/// Along each axis we use Lax-Wendroff, which is second order,
+/// when its weights are non-negative (diffusion dominated flow).
+/// Otherwise we fall back to first order upwinding.
+/// Also returns the stability number,
+/// which must not exceed 1 for the scheme to be stable.
+fn advection_diffusion_weights<
+    const GRID_DIMENSION: usize,
+    const NEIGHBORHOOD_SIZE: usize,
+>(
+    velocity: &[f64; GRID_DIMENSION],
+    diffusivity: f64,
+    dt: f64,
+    dx: f64,
+) -> (Values<NEIGHBORHOOD_SIZE>, f64) {
+    assert_eq!(NEIGHBORHOOD_SIZE, 2 * GRID_DIMENSION + 1);
+    let d = diffusivity * dt / (dx * dx);
+    let mut weights = Values::zeros();
+    let mut stability_number = 0.0;
+    for (axis, v) in velocity.iter().enumerate() {
+        let c = v * dt / dx;
+        let lax_wendroff = 2.0 * d + c * c;
+        let (minus, plus, factor) = if lax_wendroff >= c.abs() {
+            (
+                d + 0.5 * c + 0.5 * c * c,
+                d - 0.5 * c + 0.5 * c * c,
+                lax_wendroff,
+            )
+        } else {
+            (d + c.max(0.0), d + (-c).max(0.0), 2.0 * d + c.abs())
+        };
+        weights[2 * axis + 1] = minus;
+        weights[2 * axis + 2] = plus;
+        stability_number += factor;
+    }
+    weights[0] = 1.0 - stability_number;
+    (weights, stability_number)
+}
+
+fn advection_diffusion<
+    const GRID_DIMENSION: usize,
+    const NEIGHBORHOOD_SIZE: usize,
+>(
+    velocity: &[f64; GRID_DIMENSION],
+    diffusivity: f64,
+    dt: f64,
+    dx: f64,
+) -> Result<Stencil<GRID_DIMENSION, NEIGHBORHOOD_SIZE>, StencilError> {
+    let (weights, stability_number) =
+        advection_diffusion_weights(velocity, diffusivity, dt, dx);
+    if stability_number > 1.0 {
+        return Err(StencilError::Cfl { stability_number });
+    }
+    Ok(Stencil {
+        weights,
+        offsets: advection_diffusion_offsets(),
+    })
+}
+
+/// Explicit advection-diffusion with constant velocity,
+/// see `advection_diffusion_weights` for the scheme.
+/// Fails if `dt` violates the CFL condition.
+pub fn advection_diffusion_1d(
+    velocity: f64,
+    diffusivity: f64,
+    dt: f64,
+    dx: f64,
+) -> Result<Stencil<1, 3>, StencilError> {
+    advection_diffusion(&[velocity], diffusivity, dt, dx)
+}
+
+pub fn advection_diffusion_2d(
+    velocity: [f64; 2],
+    diffusivity: f64,
+    dt: f64,
+    dx: f64,
+) -> Result<Stencil<2, 5>, StencilError> {
+    advection_diffusion(&velocity, diffusivity, dt, dx)
+}
+
+pub fn advection_diffusion_3d(
+    velocity: [f64; 3],
+    diffusivity: f64,
+    dt: f64,
+    dx: f64,
+) -> Result<Stencil<3, 7>, StencilError> {
+    advection_diffusion(&velocity, diffusivity, dt, dx)
+}
+
+/// Free particle Schrödinger equation, `i ψ_t = -ψ_xx / 2m` with `ħ = 1`,
+/// using forward Euler.
+/// This isn't norm preserving, each step can grow a mode by
+/// up to `sqrt(1 + 16 r^2)` where `r = dt / (2 m dx^2)`, so keep `r` small.
+/// Paraxial optics has the same form, with `z` in place of `t`.
+pub fn schrodinger_1d(dt: f64, dx: f64, mass: f64) -> Stencil<1, 3, c64> {
+    let r = c64::new(0.0, dt / (2.0 * mass * dx * dx));
+    Stencil::new_complex([[-1], [0], [1]], move |args: &[c64; 3]| {
+        let left = args[0];
+        let middle = args[1];
+        let right = args[2];
+        middle + r * (left - 2.0 * middle + right)
+    })
+}
+
+pub fn schrodinger_2d(
+    dt: f64,
+    dx: f64,
+    dy: f64,
+    mass: f64,
+) -> Stencil<2, 5, c64> {
+    let r_x = c64::new(0.0, dt / (2.0 * mass * dx * dx));
+    let r_y = c64::new(0.0, dt / (2.0 * mass * dy * dy));
+    Stencil::new_complex(
+        [[1, 0], [0, -1], [-1, 0], [0, 1], [0, 0]],
+        move |args: &[c64; 5]| {
+            let middle = args[4];
+            let left = args[2];
+            let right = args[0];
+            let bottom = args[1];
+            let top = args[3];
+            middle
+                + r_x * (left - 2.0 * middle + right)
+                + r_y * (top - 2.0 * middle + bottom)
+        },
+    )
+}
+
+/// Advection-diffusion where the velocity depends on `global_time`.
+/// The scheme is picked per step, as for the constant velocity stencils.
+/// Use `check_cfl` before solving, `weights` doesn't check stability.
+pub struct TVAdvectionDiffusion<
+    const GRID_DIMENSION: usize,
+    const NEIGHBORHOOD_SIZE: usize,
+    VelocityFn: Fn(usize) -> [f64; GRID_DIMENSION] + Send + Sync,
+> {
+    offsets: [Coord<GRID_DIMENSION>; NEIGHBORHOOD_SIZE],
+    velocity: VelocityFn,
+    diffusivity: f64,
+    dt: f64,
+    dx: f64,
+}
+
+impl<
+        const GRID_DIMENSION: usize,
+        const NEIGHBORHOOD_SIZE: usize,
+        VelocityFn: Fn(usize) -> [f64; GRID_DIMENSION] + Send + Sync,
+    > TVAdvectionDiffusion<GRID_DIMENSION, NEIGHBORHOOD_SIZE, VelocityFn>
+{
+    pub fn new(
+        velocity: VelocityFn,
+        diffusivity: f64,
+        dt: f64,
+        dx: f64,
+    ) -> Self {
+        TVAdvectionDiffusion {
+            offsets: advection_diffusion_offsets(),
+            velocity,
+            diffusivity,
+            dt,
+            dx,
+        }
+    }
+
+    /// Check the CFL condition at each of the given times.
+    pub fn check_cfl<I: IntoIterator<Item = usize>>(
+        &self,
+        global_times: I,
+    ) -> Result<(), StencilError> {
+        for global_time in global_times {
+            let (_, stability_number) = advection_diffusion_weights::<
+                GRID_DIMENSION,
+                NEIGHBORHOOD_SIZE,
+            >(
+                &(self.velocity)(global_time),
+                self.diffusivity,
+                self.dt,
+                self.dx,
+            );
+            if stability_number > 1.0 {
+                return Err(StencilError::Cfl { stability_number });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<
+        const GRID_DIMENSION: usize,
+        const NEIGHBORHOOD_SIZE: usize,
+        VelocityFn: Fn(usize) -> [f64; GRID_DIMENSION] + Send + Sync,
+    > TVStencil<GRID_DIMENSION, NEIGHBORHOOD_SIZE>
+    for TVAdvectionDiffusion<GRID_DIMENSION, NEIGHBORHOOD_SIZE, VelocityFn>
+{
+    fn offsets(&self) -> &[Coord<GRID_DIMENSION>; NEIGHBORHOOD_SIZE] {
+        &self.offsets
+    }
+
+    fn weights(&self, global_time: usize) -> Values<NEIGHBORHOOD_SIZE> {
+        advection_diffusion_weights(
+            &(self.velocity)(global_time),
+            self.diffusivity,
+            self.dt,
+            self.dx,
+        )
+        .0
+    }
+}
+
 pub struct RotatingAdvectionStencil {
     offsets: [Coord<2>; 5],
 
@@ -181,3 +684,119 @@ impl TVStencil<2, 5> for TVHeat2D {
         vector![nw, nw, nw, nw, cw]
     }
 }
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+    use float_cmp::assert_approx_eq;
+
+    #[test]
+    fn wide_stencils() {
+        // Every Laplacian is zero on constants, so these conserve mass
+        let stencils: Vec<DynStencil<2>> = vec![
+            (&heat_2d_4th(0.1, 1.0, 2.0, 1.0, 0.5)).into(),
+            (&heat_2d_isotropic(0.1, 1.0, 1.0)).into(),
+            (&biharmonic_2d(0.01, 1.0, 1.0)).into(),
+        ];
+        for stencil in stencils {
+            assert_approx_eq!(f64, stencil.weights().iter().sum::<f64>(), 1.0);
+        }
+        assert_approx_eq!(f64, heat_1d_4th(0.1, 1.0, 1.0).weights().sum(), 1.0);
+        let s = heat_3d_4th(0.1, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0);
+        assert_approx_eq!(f64, s.weights().sum(), 1.0);
+        assert_eq!(s.slopes(), matrix![2, 2; 2, 2; 2, 2]);
+        let s = heat_3d_isotropic(0.1, 1.0, 1.0);
+        assert_approx_eq!(f64, s.weights().sum(), 1.0);
+        assert_approx_eq!(f64, s.weights()[0], 1.0 - 0.1 * 128.0 / 30.0);
+        assert_eq!(s.slopes(), matrix![1, 1; 1, 1; 1, 1]);
+        assert_eq!(check_unique_offsets(s.offsets()), Ok(()));
+
+        // Second derivative of x^2 is exact for the 4th order stencil
+        let s = heat_1d_4th(1.0, 1.0, 1.0);
+        let x_sq: f64 = s
+            .offsets()
+            .iter()
+            .zip(s.weights().iter())
+            .map(|(o, w)| w * (o[0] * o[0]) as f64)
+            .sum();
+        assert_approx_eq!(f64, x_sq, 2.0);
+
+        let s = biharmonic_2d(1.0, 1.0, 1.0);
+        assert_eq!(s.slopes(), matrix![2, 2; 2, 2]);
+        assert_approx_eq!(f64, s.weights()[0], -19.0);
+    }
+
+    #[test]
+    fn schrodinger_test() {
+        // Laplacian is zero on constants
+        let s = schrodinger_2d(0.01, 1.0, 0.5, 1.0);
+        let sum: c64 = s.weights().iter().sum();
+        assert_approx_eq!(f64, sum.re, 1.0);
+        assert_approx_eq!(f64, sum.im, 0.0);
+        assert_approx_eq!(f64, s.weights()[0].im, 0.005);
+        assert_approx_eq!(f64, s.weights()[1].im, 0.02);
+        assert_approx_eq!(f64, s.weights()[4].im, -0.05);
+
+        let s = schrodinger_1d(0.01, 1.0, 0.5);
+        assert_approx_eq!(f64, s.weights()[0].re, 0.0);
+        assert_approx_eq!(f64, s.weights()[0].im, 0.01);
+        assert_eq!(s.slopes(), matrix![1, 1]);
+    }
+
+    #[test]
+    fn advection_diffusion_test() {
+        // Advection dominated, so upwind
+        let stencil = advection_diffusion_1d(1.0, 0.01, 0.5, 1.0).unwrap();
+        assert_eq!(stencil.offsets(), &[vector![0], vector![-1], vector![1]]);
+        assert_approx_eq!(f64, stencil.weights()[1], 0.505);
+        assert_approx_eq!(f64, stencil.weights()[2], 0.005);
+        assert_approx_eq!(f64, stencil.weights().sum(), 1.0);
+
+        // Upwind direction follows the velocity
+        let stencil = advection_diffusion_1d(-1.0, 0.01, 0.5, 1.0).unwrap();
+        assert_approx_eq!(f64, stencil.weights()[1], 0.005);
+        assert_approx_eq!(f64, stencil.weights()[2], 0.505);
+
+        // Diffusion dominated, so Lax-Wendroff
+        let stencil = advection_diffusion_1d(0.2, 1.0, 0.25, 1.0).unwrap();
+        assert_approx_eq!(f64, stencil.weights()[1], 0.25 + 0.025 + 0.00125);
+        assert_approx_eq!(f64, stencil.weights()[2], 0.25 - 0.025 + 0.00125);
+        assert_approx_eq!(f64, stencil.weights().sum(), 1.0);
+
+        let stencil =
+            advection_diffusion_3d([0.3, -0.2, 0.1], 0.05, 0.5, 1.0).unwrap();
+        assert_approx_eq!(f64, stencil.weights().sum(), 1.0);
+        assert!(stencil.weights().iter().all(|w| *w >= 0.0));
+
+        assert!(matches!(
+            advection_diffusion_2d([1.0, 1.0], 0.1, 1.0, 1.0),
+            Err(StencilError::Cfl { .. })
+        ));
+    }
+
+    #[test]
+    fn tv_advection_diffusion_test() {
+        let stencil = TVAdvectionDiffusion::<2, 5, _>::new(
+            |t: usize| [(t as f64 * 0.1).cos(), (t as f64 * 0.1).sin()],
+            0.05,
+            0.4,
+            1.0,
+        );
+        let constant = advection_diffusion_2d([1.0, 0.0], 0.05, 0.4, 1.0);
+        assert_eq!(stencil.offsets(), constant.as_ref().unwrap().offsets());
+        for (a, b) in stencil
+            .weights(0)
+            .iter()
+            .zip(constant.unwrap().weights().iter())
+        {
+            assert_approx_eq!(f64, *a, *b);
+        }
+        assert_eq!(stencil.check_cfl(0..100), Ok(()));
+
+        let fast =
+            TVAdvectionDiffusion::<1, 3, _>::new(|t| [t as f64], 0.0, 0.5, 1.0);
+        // Lax-Wendroff is still stable at a Courant number of 1
+        assert_eq!(fast.check_cfl(0..3), Ok(()));
+        assert!(fast.check_cfl(0..4).is_err());
+    }
+}
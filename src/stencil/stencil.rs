@@ -1,3 +1,7 @@
+use crate::stencil::{
+    check_complex_linearity, check_linearity, check_unique_offsets,
+    offset_slopes, StencilError,
+};
 use crate::util::*;
 
 /// For linear stencils, we can extract the weight for a neighbor
@@ -20,9 +24,14 @@ pub fn extract_weights<
 
 /// For this code base we only deal with linear stencils.
 /// We view linear stencils as a combination of neighbor offsets and weights.
-pub struct Stencil<const GRID_DIMENSION: usize, const NEIGHBORHOOD_SIZE: usize>
-{
-    pub weights: Values<NEIGHBORHOOD_SIZE>,
+/// Weights are `f64` unless `W` says otherwise, e.g. `c64` weights
+/// for discretized Schrödinger equations, see `new_complex`.
+pub struct Stencil<
+    const GRID_DIMENSION: usize,
+    const NEIGHBORHOOD_SIZE: usize,
+    W = f64,
+> {
+    pub weights: nalgebra::SVector<W, NEIGHBORHOOD_SIZE>,
     pub offsets: [Coord<GRID_DIMENSION>; NEIGHBORHOOD_SIZE],
 }
 
@@ -42,7 +51,68 @@ impl<const GRID_DIMENSION: usize, const NEIGHBORHOOD_SIZE: usize>
         }
     }
 
-    pub fn weights(&self) -> &Values<NEIGHBORHOOD_SIZE> {
+    /// Checked version of `new`.
+    /// Rejects duplicate offsets, and operations that aren't linear.
+    pub fn try_new<F: Fn(&[f64; NEIGHBORHOOD_SIZE]) -> f64>(
+        offsets: [[i32; GRID_DIMENSION]; NEIGHBORHOOD_SIZE],
+        operation: F,
+    ) -> Result<Self, StencilError> {
+        let result = Self::new(offsets, &operation);
+        check_unique_offsets(&result.offsets)?;
+        check_linearity(result.weights.as_slice(), |args: &[f64]| {
+            operation(args.try_into().unwrap())
+        })?;
+        Ok(result)
+    }
+}
+
+impl<const GRID_DIMENSION: usize, const NEIGHBORHOOD_SIZE: usize>
+    Stencil<GRID_DIMENSION, NEIGHBORHOOD_SIZE, c64>
+{
+    /// Same as `new`, with complex weights.
+    /// Apply these to `c64` domains.
+    pub fn new_complex<F: Fn(&[c64; NEIGHBORHOOD_SIZE]) -> c64>(
+        offsets: [[i32; GRID_DIMENSION]; NEIGHBORHOOD_SIZE],
+        operation: F,
+    ) -> Self {
+        let mut weights = nalgebra::SVector::from_element(c64::zero());
+        let mut arg_buffer = [c64::zero(); NEIGHBORHOOD_SIZE];
+        for n in 0..NEIGHBORHOOD_SIZE {
+            arg_buffer[n] = c64::one();
+            weights[n] = operation(&arg_buffer);
+            arg_buffer[n] = c64::zero();
+        }
+        Stencil {
+            offsets: std::array::from_fn(|i| {
+                Coord::from_column_slice(&offsets[i])
+            }),
+            weights,
+        }
+    }
+
+    /// Checked version of `new_complex`.
+    /// Rejects duplicate offsets, and operations that aren't linear.
+    pub fn try_new_complex<F: Fn(&[c64; NEIGHBORHOOD_SIZE]) -> c64>(
+        offsets: [[i32; GRID_DIMENSION]; NEIGHBORHOOD_SIZE],
+        operation: F,
+    ) -> Result<Self, StencilError> {
+        let result = Self::new_complex(offsets, &operation);
+        check_unique_offsets(&result.offsets)?;
+        check_complex_linearity(
+            result.weights.as_slice(),
+            |args: &[c64]| operation(args.try_into().unwrap()),
+        )?;
+        Ok(result)
+    }
+}
+
+impl<
+        const GRID_DIMENSION: usize,
+        const NEIGHBORHOOD_SIZE: usize,
+        W: NumTrait + nalgebra::Scalar,
+    > Stencil<GRID_DIMENSION, NEIGHBORHOOD_SIZE, W>
+{
+    pub fn weights(&self) -> &nalgebra::SVector<W, NEIGHBORHOOD_SIZE> {
         &self.weights
     }
 
@@ -51,22 +121,14 @@ impl<const GRID_DIMENSION: usize, const NEIGHBORHOOD_SIZE: usize>
     }
 
     pub fn slopes(&self) -> Bounds<GRID_DIMENSION> {
-        let mut result = Bounds::zero();
-        for neighbor in self.offsets {
-            for d in 0..GRID_DIMENSION {
-                let neighbor_d = neighbor[d];
-                if neighbor_d > 0 {
-                    result[(d, 1)] = result[(d, 1)].max(neighbor_d);
-                } else {
-                    result[(d, 0)] = result[(d, 0)].max(-neighbor_d);
-                }
-            }
-        }
-        result
+        offset_slopes(&self.offsets)
     }
 
-    pub fn apply(&self, args: &Values<NEIGHBORHOOD_SIZE>) -> f64 {
-        self.weights.component_mul(args).sum()
+    pub fn apply(&self, args: &nalgebra::SVector<W, NEIGHBORHOOD_SIZE>) -> W {
+        self.weights
+            .iter()
+            .zip(args.iter())
+            .fold(W::zero(), |acc, (w, a)| acc + *w * *a)
     }
 }
 
@@ -95,6 +157,36 @@ mod unit_tests {
         }
     }
 
+    #[test]
+    fn try_new() {
+        let s = Stencil::try_new([[-1], [0], [1]], |args: &[f64; 3]| {
+            0.25 * args[0] + 0.5 * args[1] + 0.25 * args[2]
+        })
+        .unwrap();
+        assert_approx_eq!(f64, s.weights()[1], 0.5);
+
+        let duplicate =
+            Stencil::try_new([[-1], [0], [-1]], |args: &[f64; 3]| {
+                args[0] + args[1] + args[2]
+            });
+        assert!(matches!(
+            duplicate,
+            Err(StencilError::DuplicateOffset {
+                first: 0,
+                second: 2,
+                ..
+            })
+        ));
+
+        let affine =
+            Stencil::try_new([[0]], |args: &[f64; 1]| 2.0 * args[0] + 0.1);
+        assert!(matches!(affine, Err(StencilError::Affine { .. })));
+
+        let nonlinear =
+            Stencil::try_new([[0]], |args: &[f64; 1]| args[0] * args[0]);
+        assert!(matches!(nonlinear, Err(StencilError::Nonlinear { .. })));
+    }
+
     #[test]
     fn slopes() {
         {
@@ -118,4 +210,37 @@ mod unit_tests {
             assert_eq!(w, matrix![1, 1; 3, 2]);
         }
     }
+
+    #[test]
+    fn complex_weights() {
+        let i = c64::new(0.0, 1.0);
+        let s = Stencil::new_complex([[-1], [0], [1]], |args: &[c64; 3]| {
+            args[1] + i * (args[0] - 2.0 * args[1] + args[2])
+        });
+        assert_eq!(s.weights()[0], i);
+        assert_eq!(s.weights()[1], c64::new(1.0, -2.0));
+        assert_eq!(s.weights()[2], i);
+        assert_eq!(s.slopes(), matrix![1, 1]);
+
+        let args =
+            nalgebra::vector![c64::one(), c64::new(2.0, 1.0), c64::zero()];
+        assert_eq!(s.apply(&args), c64::new(4.0, -2.0));
+
+        let duplicate =
+            Stencil::try_new_complex([[1], [1]], |args: &[c64; 2]| {
+                args[0] + args[1]
+            });
+        assert!(matches!(
+            duplicate,
+            Err(StencilError::DuplicateOffset {
+                first: 0,
+                second: 1,
+                ..
+            })
+        ));
+
+        let conjugate =
+            Stencil::try_new_complex([[0]], |args: &[c64; 1]| args[0].conj());
+        assert!(matches!(conjugate, Err(StencilError::Nonlinear { .. })));
+    }
 }
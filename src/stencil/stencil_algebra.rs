@@ -0,0 +1,372 @@
+//! Building new stencils out of existing ones,
+//! e.g. for operator splitting or multi-step direct kernels.
+//! Results are `DynStencil`s since the merged neighborhood size
+//! isn't known at compile time.
+//! They work with solvers that take a `TIStencil`,
+//! for the others convert with `Stencil::try_from`.
+//! Offsets appearing more than once are merged by summing their weights,
+//! and merged weights that cancel exactly are dropped,
+//! so `slopes()` only reflect neighbors that matter.
+//! When every weight cancels the result is the zero operator,
+//! a single center offset with weight zero.
+
+use crate::stencil::*;
+use crate::util::*;
+use std::collections::HashMap;
+
+/// Sum weights of repeated offsets, preserving first appearance order.
+fn merge_offset_weights<
+    const GRID_DIMENSION: usize,
+    I: IntoIterator<Item = (Coord<GRID_DIMENSION>, f64)>,
+>(
+    offset_weights: I,
+) -> DynStencil<GRID_DIMENSION> {
+    let mut offsets = Vec::new();
+    let mut weights: Vec<f64> = Vec::new();
+    let mut index_map: HashMap<Coord<GRID_DIMENSION>, usize> = HashMap::new();
+    for (offset, weight) in offset_weights {
+        let index = *index_map.entry(offset).or_insert_with(|| {
+            offsets.push(offset);
+            weights.push(0.0);
+            offsets.len() - 1
+        });
+        weights[index] += weight;
+    }
+
+    let (offsets, weights): (Vec<_>, Vec<_>) = offsets
+        .into_iter()
+        .zip(weights)
+        .filter(|(_, w)| *w != 0.0)
+        .unzip();
+    if offsets.is_empty() {
+        return DynStencil::from_offset_weights(vec![Coord::zero()], vec![0.0]);
+    }
+    DynStencil::from_offset_weights(offsets, weights)
+}
+
+/// Stencil equivalent to applying `first`, then `second`.
+pub fn compose<
+    const GRID_DIMENSION: usize,
+    A: TIStencil<GRID_DIMENSION>,
+    B: TIStencil<GRID_DIMENSION>,
+>(
+    first: &A,
+    second: &B,
+) -> DynStencil<GRID_DIMENSION> {
+    let mut offset_weights = Vec::with_capacity(
+        first.neighborhood_size() * second.neighborhood_size(),
+    );
+    for (o_b, w_b) in second.offset_slice().iter().zip(second.weight_slice()) {
+        for (o_a, w_a) in first.offset_slice().iter().zip(first.weight_slice())
+        {
+            offset_weights.push((o_a + o_b, w_a * w_b));
+        }
+    }
+    merge_offset_weights(offset_weights)
+}
+
+/// Stencil computing `a * s_a + b * s_b`.
+pub fn linear_combination<
+    const GRID_DIMENSION: usize,
+    A: TIStencil<GRID_DIMENSION>,
+    B: TIStencil<GRID_DIMENSION>,
+>(
+    a: f64,
+    s_a: &A,
+    b: f64,
+    s_b: &B,
+) -> DynStencil<GRID_DIMENSION> {
+    let a_terms = s_a
+        .offset_slice()
+        .iter()
+        .zip(s_a.weight_slice())
+        .map(|(o, w)| (*o, a * w));
+    let b_terms = s_b
+        .offset_slice()
+        .iter()
+        .zip(s_b.weight_slice())
+        .map(|(o, w)| (*o, b * w));
+    merge_offset_weights(a_terms.chain(b_terms))
+}
+
+pub fn scale<const GRID_DIMENSION: usize, S: TIStencil<GRID_DIMENSION>>(
+    stencil: &S,
+    factor: f64,
+) -> DynStencil<GRID_DIMENSION> {
+    merge_offset_weights(
+        stencil
+            .offset_slice()
+            .iter()
+            .zip(stencil.weight_slice())
+            .map(|(o, w)| (*o, factor * w)),
+    )
+}
+
+/// Stencil equivalent to applying `stencil` `k` times.
+/// The zeroth power is the identity stencil.
+pub fn power<const GRID_DIMENSION: usize, S: TIStencil<GRID_DIMENSION>>(
+    stencil: &S,
+    mut k: usize,
+) -> DynStencil<GRID_DIMENSION> {
+    let mut result =
+        DynStencil::from_offset_weights(vec![Coord::zero()], vec![1.0]);
+    let mut base = scale(stencil, 1.0);
+    while k > 0 {
+        if k % 2 == 1 {
+            result = compose(&result, &base);
+        }
+        k /= 2;
+        if k > 0 {
+            base = compose(&base, &base);
+        }
+    }
+    result
+}
+
+impl<const GRID_DIMENSION: usize, const NEIGHBORHOOD_SIZE: usize>
+    Stencil<GRID_DIMENSION, NEIGHBORHOOD_SIZE>
+{
+    /// Stencil equivalent to applying `self`, then `next`.
+    pub fn compose<S: TIStencil<GRID_DIMENSION>>(
+        &self,
+        next: &S,
+    ) -> DynStencil<GRID_DIMENSION> {
+        compose(self, next)
+    }
+
+    /// Stencil computing `a * self + b * other`.
+    pub fn linear_combination<S: TIStencil<GRID_DIMENSION>>(
+        &self,
+        a: f64,
+        other: &S,
+        b: f64,
+    ) -> DynStencil<GRID_DIMENSION> {
+        linear_combination(a, self, b, other)
+    }
+
+    pub fn scale(&self, factor: f64) -> DynStencil<GRID_DIMENSION> {
+        scale(self, factor)
+    }
+
+    /// Stencil equivalent to applying `self` `k` times.
+    pub fn power(&self, k: usize) -> DynStencil<GRID_DIMENSION> {
+        power(self, k)
+    }
+}
+
+impl<const GRID_DIMENSION: usize> DynStencil<GRID_DIMENSION> {
+    /// Stencil equivalent to applying `self`, then `next`.
+    pub fn compose<S: TIStencil<GRID_DIMENSION>>(
+        &self,
+        next: &S,
+    ) -> DynStencil<GRID_DIMENSION> {
+        compose(self, next)
+    }
+
+    /// Stencil computing `a * self + b * other`.
+    pub fn linear_combination<S: TIStencil<GRID_DIMENSION>>(
+        &self,
+        a: f64,
+        other: &S,
+        b: f64,
+    ) -> DynStencil<GRID_DIMENSION> {
+        linear_combination(a, self, b, other)
+    }
+
+    pub fn scale(&self, factor: f64) -> DynStencil<GRID_DIMENSION> {
+        scale(self, factor)
+    }
+
+    /// Stencil equivalent to applying `self` `k` times.
+    pub fn power(&self, k: usize) -> DynStencil<GRID_DIMENSION> {
+        power(self, k)
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+    use crate::domain::*;
+    use crate::par_stencil;
+    use float_cmp::assert_approx_eq;
+    use nalgebra::{matrix, vector};
+
+    fn weight_of<const GRID_DIMENSION: usize>(
+        stencil: &DynStencil<GRID_DIMENSION>,
+        offset: Coord<GRID_DIMENSION>,
+    ) -> f64 {
+        stencil
+            .offsets()
+            .iter()
+            .position(|o| *o == offset)
+            .map(|i| stencil.weights()[i])
+            .unwrap_or(0.0)
+    }
+
+    /// Apply `stencil` `steps` times with periodic boundaries
+    fn periodic_steps<
+        const GRID_DIMENSION: usize,
+        S: TIStencil<GRID_DIMENSION>,
+    >(
+        stencil: &S,
+        input: &mut OwnedDomain<GRID_DIMENSION>,
+        output: &mut OwnedDomain<GRID_DIMENSION>,
+        steps: usize,
+    ) {
+        for _ in 0..steps {
+            {
+                let bc = PeriodicCheck::new(input);
+                par_stencil::apply(&bc, stencil, input, output, 0, 7);
+            }
+            std::mem::swap(input, output);
+        }
+        std::mem::swap(input, output);
+    }
+
+    fn ic(coord: Coord<2>) -> f64 {
+        ((coord[0] * 7 + coord[1] * 13) % 17) as f64
+    }
+
+    #[test]
+    fn compose_1d() {
+        let a = Stencil::new([[-1], [0], [1]], |args: &[f64; 3]| {
+            0.25 * args[0] + 0.5 * args[1] + 0.25 * args[2]
+        });
+        let b = Stencil::new([[1]], |args: &[f64; 1]| args[0]);
+        let ab = a.compose(&b);
+        assert_eq!(ab.len(), 3);
+        assert_eq!(ab.slopes(), matrix![0, 2]);
+        assert_approx_eq!(f64, weight_of(&ab, vector![0]), 0.25);
+        assert_approx_eq!(f64, weight_of(&ab, vector![1]), 0.5);
+        assert_approx_eq!(f64, weight_of(&ab, vector![2]), 0.25);
+
+        let aa = a.compose(&a);
+        assert_eq!(aa.len(), 5);
+        assert_approx_eq!(f64, weight_of(&aa, vector![0]), 0.375);
+        assert_approx_eq!(f64, weight_of(&aa, vector![-2]), 0.0625);
+        assert_approx_eq!(f64, aa.weights().iter().sum::<f64>(), 1.0);
+    }
+
+    #[test]
+    fn compose_matches_sequential_apply() {
+        let a = standard_stencils::heat_2d(1.0, 1.0, 1.0, 0.2, 0.1);
+        let b = Stencil::new([[1, 1], [0, 0], [-2, 0]], |args: &[f64; 3]| {
+            0.3 * args[0] + 0.5 * args[1] + 0.2 * args[2]
+        });
+        let ab = a.compose(&b);
+        let bound = AABB::new(matrix![0, 19; 0, 23]);
+
+        let mut input = OwnedDomain::new(bound);
+        let mut output = OwnedDomain::new(bound);
+        input.par_set_values(ic, 7);
+        periodic_steps(&a, &mut input, &mut output, 1);
+        std::mem::swap(&mut input, &mut output);
+        periodic_steps(&b, &mut input, &mut output, 1);
+
+        let mut composed_input = OwnedDomain::new(bound);
+        let mut composed_output = OwnedDomain::new(bound);
+        composed_input.par_set_values(ic, 7);
+        periodic_steps(&ab, &mut composed_input, &mut composed_output, 1);
+
+        for (s, c) in output.buffer().iter().zip(composed_output.buffer()) {
+            assert_approx_eq!(f64, *s, *c, epsilon = 1e-12);
+        }
+    }
+
+    #[test]
+    fn power_matches_sequential_apply() {
+        let a = standard_stencils::heat_2d(1.0, 1.0, 1.0, 0.2, 0.1);
+        let bound = AABB::new(matrix![0, 29; 0, 26]);
+        for k in [0, 1, 2, 5] {
+            let a_k = a.power(k);
+            assert_eq!(a_k.slopes(), k as i32 * a.slopes());
+
+            let mut input = OwnedDomain::new(bound);
+            let mut output = OwnedDomain::new(bound);
+            input.par_set_values(ic, 7);
+            if k == 0 {
+                output.par_set_values(ic, 7);
+            } else {
+                periodic_steps(&a, &mut input, &mut output, k);
+            }
+
+            let mut power_input = OwnedDomain::new(bound);
+            let mut power_output = OwnedDomain::new(bound);
+            power_input.par_set_values(ic, 7);
+            periodic_steps(&a_k, &mut power_input, &mut power_output, 1);
+
+            for (s, p) in output.buffer().iter().zip(power_output.buffer()) {
+                assert_approx_eq!(f64, *s, *p, epsilon = 1e-10);
+            }
+        }
+    }
+
+    #[test]
+    fn linear_combination_and_scale() {
+        let a = standard_stencils::heat_1d(1.0, 1.0, 0.25);
+        let identity = DynStencil::new(&[[0]], |args: &[f64]| args[0]);
+
+        // Laplacian part only, center cancels with the identity
+        let lap = a.linear_combination(4.0, &identity, -4.0);
+        assert_eq!(lap.len(), 3);
+        assert_approx_eq!(f64, weight_of(&lap, vector![1]), 1.0);
+        assert_approx_eq!(f64, weight_of(&lap, vector![0]), -2.0);
+
+        let half = a.scale(0.5);
+        for (o, w) in a.offsets().iter().zip(a.weights().iter()) {
+            assert_approx_eq!(f64, weight_of(&half, *o), 0.5 * w);
+        }
+    }
+
+    fn assert_zero_operator<const GRID_DIMENSION: usize>(
+        stencil: &DynStencil<GRID_DIMENSION>,
+    ) {
+        assert_eq!(stencil.offsets(), &[Coord::<GRID_DIMENSION>::zero()]);
+        assert_eq!(stencil.weights(), &[0.0]);
+        assert_eq!(stencil.slopes(), Bounds::<GRID_DIMENSION>::zeros());
+    }
+
+    #[test]
+    fn linear_combination_cancels() {
+        let a = standard_stencils::heat_1d(1.0, 1.0, 0.25);
+        let zero = a.linear_combination(1.0, &a, -1.0);
+        assert_zero_operator(&zero);
+
+        // The zero operator composes like any other stencil
+        assert_zero_operator(&a.compose(&zero));
+        let again = zero.linear_combination(1.0, &a, 2.0);
+        for (o, w) in a.offsets().iter().zip(a.weights().iter()) {
+            assert_approx_eq!(f64, weight_of(&again, *o), 2.0 * w);
+        }
+    }
+
+    #[test]
+    fn scale_by_zero() {
+        let a = standard_stencils::heat_2d(1.0, 1.0, 1.0, 0.2, 0.1);
+        let zero = a.scale(0.0);
+        assert_zero_operator(&zero);
+
+        let bound = AABB::new(matrix![0, 9; 0, 8]);
+        let mut input = OwnedDomain::new(bound);
+        let mut output = OwnedDomain::new(bound);
+        input.par_set_values(ic, 7);
+        periodic_steps(&zero, &mut input, &mut output, 1);
+        assert!(output.buffer().iter().all(|v| *v == 0.0));
+    }
+
+    #[test]
+    fn convert_to_stencil() {
+        let a = standard_stencils::heat_1d(1.0, 1.0, 0.25);
+        let aa = a.compose(&a);
+        let stencil: Stencil<1, 5> = Stencil::try_from(&aa).unwrap();
+        assert_eq!(stencil.offsets().as_slice(), aa.offsets());
+        assert_eq!(stencil.weights().as_slice(), aa.weights());
+        assert!(matches!(
+            Stencil::<1, 3>::try_from(&aa),
+            Err(StencilError::NeighborhoodSize {
+                expected: 3,
+                actual: 5
+            })
+        ));
+    }
+}
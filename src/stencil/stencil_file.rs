@@ -0,0 +1,537 @@
+//! Plain-text stencil definitions, so new discretizations can be tried
+//! without recompiling.
+//!
+//! ```text
+//! # Lines starting with '#' are comments
+//! dimension 2
+//!
+//! # Parameters have defaults, which can be overridden when loading
+//! param dt = 1.0
+//! param dx = 1.0
+//! param k = 0.2
+//!
+//! # Derived values, evaluated in order
+//! let r = k * dt / dx^2
+//!
+//! # One neighbor per line, the offset then the weight
+//! offset  1  0 = r
+//! offset -1  0 = r
+//! offset  0  1 = r
+//! offset  0 -1 = r
+//! offset  0  0 = 1 - 4 * r
+//! ```
+//!
+//! Weight expressions support `+ - * / ^`, parentheses,
+//! the constant `pi`, and the functions
+//! `sqrt`, `exp`, `ln`, `sin`, `cos` and `abs`.
+
+use crate::stencil::*;
+use crate::util::*;
+use std::collections::HashMap;
+use std::path::Path;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct StencilFileError {
+    /// 1-based line number, 0 if the error isn't tied to a line.
+    pub line: usize,
+    pub message: String,
+}
+
+impl StencilFileError {
+    fn new(line: usize, message: String) -> Self {
+        StencilFileError { line, message }
+    }
+}
+
+impl std::fmt::Display for StencilFileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.line == 0 {
+            write!(f, "stencil file: {}", self.message)
+        } else {
+            write!(f, "stencil file line {}: {}", self.line, self.message)
+        }
+    }
+}
+
+impl std::error::Error for StencilFileError {}
+
+/// Parses `name=value` parameter overrides,
+/// usable as a clap value parser.
+pub fn parse_param_override(s: &str) -> Result<(String, f64), String> {
+    let (name, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected name=value, found '{s}'"))?;
+    let value = value
+        .trim()
+        .parse::<f64>()
+        .map_err(|e| format!("invalid value for '{}': {e}", name.trim()))?;
+    Ok((name.trim().to_string(), value))
+}
+
+pub fn load_stencil_file<const GRID_DIMENSION: usize, P: AsRef<Path>>(
+    path: &P,
+    overrides: &[(String, f64)],
+) -> Result<DynStencil<GRID_DIMENSION>, StencilFileError> {
+    let source = std::fs::read_to_string(path).map_err(|e| {
+        StencilFileError::new(
+            0,
+            format!("failed to read {:?}: {e}", path.as_ref()),
+        )
+    })?;
+    parse_stencil(&source, overrides)
+}
+
+/// Parse a stencil definition, see module documentation for the format.
+/// Every override must name a `param` declared in the source.
+pub fn parse_stencil<const GRID_DIMENSION: usize>(
+    source: &str,
+    overrides: &[(String, f64)],
+) -> Result<DynStencil<GRID_DIMENSION>, StencilFileError> {
+    let mut variables: HashMap<String, f64> = HashMap::new();
+    let mut declared_params = Vec::new();
+    let mut dimension = None;
+    let mut offsets: Vec<Coord<GRID_DIMENSION>> = Vec::new();
+    let mut weights = Vec::new();
+
+    for (line_index, raw_line) in source.lines().enumerate() {
+        let line_number = line_index + 1;
+        let err = |message: String| StencilFileError::new(line_number, message);
+        let line = match raw_line.split_once('#') {
+            Some((content, _)) => content.trim(),
+            None => raw_line.trim(),
+        };
+        if line.is_empty() {
+            continue;
+        }
+        let (keyword, rest) =
+            line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim();
+
+        if keyword != "dimension" && dimension.is_none() {
+            return Err(err(
+                "'dimension' must be declared before anything else".to_string(),
+            ));
+        }
+
+        match keyword {
+            "dimension" => {
+                if dimension.is_some() {
+                    return Err(err("dimension declared twice".to_string()));
+                }
+                let d = rest
+                    .parse::<usize>()
+                    .map_err(|_| err(format!("invalid dimension '{rest}'")))?;
+                if d != GRID_DIMENSION {
+                    return Err(err(format!(
+                        "expected a {GRID_DIMENSION}D stencil, found {d}D"
+                    )));
+                }
+                dimension = Some(d);
+            }
+            "param" | "let" => {
+                let (name, expression) =
+                    split_assignment(rest).ok_or_else(|| {
+                        err(format!("expected '{keyword} <name> = <value>'"))
+                    })?;
+                if !is_identifier(name) || name == "pi" {
+                    return Err(err(format!("invalid name '{name}'")));
+                }
+                if variables.contains_key(name) {
+                    return Err(err(format!("'{name}' declared twice")));
+                }
+                let mut value =
+                    evaluate_expression(expression, &variables).map_err(err)?;
+                if keyword == "param" {
+                    declared_params.push(name.to_string());
+                    if let Some((_, v)) =
+                        overrides.iter().rev().find(|(n, _)| n == name)
+                    {
+                        value = *v;
+                    }
+                }
+                variables.insert(name.to_string(), value);
+            }
+            "offset" => {
+                let (coords, expression) =
+                    rest.split_once('=').ok_or_else(|| {
+                        err("expected 'offset <coords> = <weight>'".to_string())
+                    })?;
+                let coords = coords
+                    .split_whitespace()
+                    .map(|c| c.parse::<i32>())
+                    .collect::<Result<Vec<i32>, _>>()
+                    .map_err(|_| {
+                        err(format!("invalid offset '{}'", coords.trim()))
+                    })?;
+                if coords.len() != GRID_DIMENSION {
+                    return Err(err(format!(
+                        "offset has {} coordinates, expected {GRID_DIMENSION}",
+                        coords.len()
+                    )));
+                }
+                let offset = Coord::from_column_slice(&coords);
+                if offsets.contains(&offset) {
+                    return Err(err(format!(
+                        "duplicate offset {:?}",
+                        offset.as_slice()
+                    )));
+                }
+                let weight =
+                    evaluate_expression(expression, &variables).map_err(err)?;
+                offsets.push(offset);
+                weights.push(weight);
+            }
+            _ => {
+                return Err(err(format!("unknown keyword '{keyword}'")));
+            }
+        }
+    }
+
+    if dimension.is_none() {
+        return Err(StencilFileError::new(
+            0,
+            "missing 'dimension'".to_string(),
+        ));
+    }
+    if offsets.is_empty() {
+        return Err(StencilFileError::new(0, "no offsets".to_string()));
+    }
+    for (name, _) in overrides {
+        if !declared_params.contains(name) {
+            return Err(StencilFileError::new(
+                0,
+                format!("override for undeclared param '{name}'"),
+            ));
+        }
+    }
+
+    Ok(DynStencil::from_offset_weights(offsets, weights))
+}
+
+fn split_assignment(s: &str) -> Option<(&str, &str)> {
+    let (name, expression) = s.split_once('=')?;
+    Some((name.trim(), expression))
+}
+
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        _ => false,
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    Identifier(String),
+    Symbol(char),
+}
+
+fn tokenize(expression: &str) -> Result<Vec<Token>, String> {
+    let mut result = Vec::new();
+    let chars: Vec<char> = expression.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || c == '.' {
+            let start = i;
+            while i < chars.len()
+                && (chars[i].is_ascii_digit()
+                    || chars[i] == '.'
+                    || chars[i] == 'e'
+                    || chars[i] == 'E'
+                    || ((chars[i] == '-' || chars[i] == '+')
+                        && (chars[i - 1] == 'e' || chars[i - 1] == 'E')))
+            {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            let value = text
+                .parse::<f64>()
+                .map_err(|_| format!("invalid number '{text}'"))?;
+            result.push(Token::Number(value));
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len()
+                && (chars[i].is_ascii_alphanumeric() || chars[i] == '_')
+            {
+                i += 1;
+            }
+            result.push(Token::Identifier(chars[start..i].iter().collect()));
+        } else if "+-*/^()".contains(c) {
+            result.push(Token::Symbol(c));
+            i += 1;
+        } else {
+            return Err(format!("unexpected character '{c}'"));
+        }
+    }
+    Ok(result)
+}
+
+/// Recursive descent evaluator,
+/// expressions are small enough that we never build a tree.
+struct ExpressionEvaluator<'a> {
+    tokens: Vec<Token>,
+    position: usize,
+    variables: &'a HashMap<String, f64>,
+}
+
+impl ExpressionEvaluator<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next_symbol_is(&self, symbol: char) -> bool {
+        self.peek() == Some(&Token::Symbol(symbol))
+    }
+
+    fn expect_symbol(&mut self, symbol: char) -> Result<(), String> {
+        if self.next_symbol_is(symbol) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(format!("expected '{symbol}'"))
+        }
+    }
+
+    // expression := term (('+' | '-') term)*
+    fn expression(&mut self) -> Result<f64, String> {
+        let mut result = self.term()?;
+        loop {
+            if self.next_symbol_is('+') {
+                self.position += 1;
+                result += self.term()?;
+            } else if self.next_symbol_is('-') {
+                self.position += 1;
+                result -= self.term()?;
+            } else {
+                return Ok(result);
+            }
+        }
+    }
+
+    // term := unary (('*' | '/') unary)*
+    fn term(&mut self) -> Result<f64, String> {
+        let mut result = self.unary()?;
+        loop {
+            if self.next_symbol_is('*') {
+                self.position += 1;
+                result *= self.unary()?;
+            } else if self.next_symbol_is('/') {
+                self.position += 1;
+                result /= self.unary()?;
+            } else {
+                return Ok(result);
+            }
+        }
+    }
+
+    // unary := ('-' | '+') unary | power
+    fn unary(&mut self) -> Result<f64, String> {
+        if self.next_symbol_is('-') {
+            self.position += 1;
+            Ok(-self.unary()?)
+        } else if self.next_symbol_is('+') {
+            self.position += 1;
+            self.unary()
+        } else {
+            self.power()
+        }
+    }
+
+    // power := atom ('^' unary)?
+    fn power(&mut self) -> Result<f64, String> {
+        let base = self.atom()?;
+        if self.next_symbol_is('^') {
+            self.position += 1;
+            let exponent = self.unary()?;
+            Ok(base.powf(exponent))
+        } else {
+            Ok(base)
+        }
+    }
+
+    // atom := number | name | function '(' expression ')' | '(' expression ')'
+    fn atom(&mut self) -> Result<f64, String> {
+        let token = self
+            .peek()
+            .cloned()
+            .ok_or_else(|| "unexpected end of expression".to_string())?;
+        self.position += 1;
+        match token {
+            Token::Number(value) => Ok(value),
+            Token::Symbol('(') => {
+                let result = self.expression()?;
+                self.expect_symbol(')')?;
+                Ok(result)
+            }
+            Token::Identifier(name) => {
+                if self.next_symbol_is('(') {
+                    self.position += 1;
+                    let arg = self.expression()?;
+                    self.expect_symbol(')')?;
+                    apply_function(&name, arg)
+                } else if name == "pi" {
+                    Ok(std::f64::consts::PI)
+                } else {
+                    self.variables
+                        .get(&name)
+                        .copied()
+                        .ok_or_else(|| format!("unknown name '{name}'"))
+                }
+            }
+            Token::Symbol(c) => Err(format!("unexpected '{c}'")),
+        }
+    }
+}
+
+fn apply_function(name: &str, arg: f64) -> Result<f64, String> {
+    match name {
+        "sqrt" => Ok(arg.sqrt()),
+        "exp" => Ok(arg.exp()),
+        "ln" => Ok(arg.ln()),
+        "sin" => Ok(arg.sin()),
+        "cos" => Ok(arg.cos()),
+        "abs" => Ok(arg.abs()),
+        _ => Err(format!("unknown function '{name}'")),
+    }
+}
+
+fn evaluate_expression(
+    expression: &str,
+    variables: &HashMap<String, f64>,
+) -> Result<f64, String> {
+    let tokens = tokenize(expression)?;
+    if tokens.is_empty() {
+        return Err("empty expression".to_string());
+    }
+    let mut evaluator = ExpressionEvaluator {
+        tokens,
+        position: 0,
+        variables,
+    };
+    let result = evaluator.expression()?;
+    if evaluator.position != evaluator.tokens.len() {
+        return Err("unexpected trailing input in expression".to_string());
+    }
+    if !result.is_finite() {
+        return Err(format!("expression evaluated to {result}"));
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+    use float_cmp::assert_approx_eq;
+    use nalgebra::matrix;
+
+    fn eval(s: &str) -> f64 {
+        let mut variables = HashMap::new();
+        variables.insert("k".to_string(), 0.5);
+        evaluate_expression(s, &variables).unwrap()
+    }
+
+    #[test]
+    fn expressions() {
+        assert_approx_eq!(f64, eval("1 + 2 * 3"), 7.0);
+        assert_approx_eq!(f64, eval("(1 + 2) * 3"), 9.0);
+        assert_approx_eq!(f64, eval("-2^2"), -4.0);
+        assert_approx_eq!(f64, eval("2^3^2"), 512.0);
+        assert_approx_eq!(f64, eval("8 / 4 / 2"), 1.0);
+        assert_approx_eq!(f64, eval("1 - 4 * k"), -1.0);
+        assert_approx_eq!(f64, eval("1.5e-1 * 2"), 0.3);
+        assert_approx_eq!(f64, eval("sqrt(4) + cos(0)"), 3.0);
+        assert_approx_eq!(f64, eval("sin(pi / 2)"), 1.0);
+        let variables = HashMap::new();
+        assert!(evaluate_expression("1 +", &variables).is_err());
+        assert!(evaluate_expression("(1", &variables).is_err());
+        assert!(evaluate_expression("1 2", &variables).is_err());
+        assert!(evaluate_expression("q", &variables).is_err());
+        assert!(evaluate_expression("foo(1)", &variables).is_err());
+        assert!(evaluate_expression("1 / 0", &variables).is_err());
+    }
+
+    #[test]
+    fn heat_2d_matches_standard() {
+        let source = include_str!("../../stencils/heat_2d.stencil");
+        let stencil = parse_stencil::<2>(source, &[]).unwrap();
+        let expected = standard_stencils::heat_2d(1.0, 1.0, 1.0, 0.2, 0.2);
+        assert_eq!(stencil.len(), 5);
+        assert_eq!(stencil.slopes(), matrix![1, 1; 1, 1]);
+        for (o, w) in stencil.offsets().iter().zip(stencil.weights()) {
+            let i = expected.offsets().iter().position(|e| e == o).unwrap();
+            assert_approx_eq!(f64, *w, expected.weights()[i], ulps = 2);
+        }
+    }
+
+    #[test]
+    fn shipped_files_parse() {
+        parse_stencil::<1>(include_str!("../../stencils/heat_1d.stencil"), &[])
+            .unwrap();
+        parse_stencil::<3>(include_str!("../../stencils/heat_3d.stencil"), &[])
+            .unwrap();
+    }
+
+    #[test]
+    fn overrides() {
+        let source = "
+            dimension 1
+            param k = 0.1 # diffusion
+            let r = k / 2
+            offset -1 = r
+            offset 1 = r
+            offset 0 = 1 - 2 * r
+        ";
+        let stencil =
+            parse_stencil::<1>(source, &[("k".to_string(), 0.4)]).unwrap();
+        assert_approx_eq!(f64, stencil.weights()[0], 0.2);
+        assert_approx_eq!(f64, stencil.weights()[2], 0.6);
+
+        let err = parse_stencil::<1>(source, &[("dt".to_string(), 0.4)]);
+        assert!(err.is_err());
+
+        assert_eq!(
+            parse_param_override(" k = 0.25"),
+            Ok(("k".to_string(), 0.25))
+        );
+        assert!(parse_param_override("k").is_err());
+        assert!(parse_param_override("k=abc").is_err());
+    }
+
+    #[test]
+    fn errors() {
+        let err =
+            parse_stencil::<2>("dimension 1\noffset 0 = 1", &[]).unwrap_err();
+        assert_eq!(err.line, 1);
+
+        let err = parse_stencil::<1>("offset 0 = 1", &[]).unwrap_err();
+        assert_eq!(err.line, 1);
+
+        let err = parse_stencil::<1>(
+            "dimension 1\noffset 0 = 1\n\noffset 0 = 2",
+            &[],
+        )
+        .unwrap_err();
+        assert_eq!(err.line, 4);
+
+        let err =
+            parse_stencil::<2>("dimension 2\noffset 0 = 1", &[]).unwrap_err();
+        assert_eq!(err.line, 2);
+
+        let err =
+            parse_stencil::<1>("dimension 1\noffset 1 = r", &[]).unwrap_err();
+        assert_eq!(err.line, 2);
+
+        let err =
+            parse_stencil::<1>("dimension 1\nweight 1 = 1", &[]).unwrap_err();
+        assert_eq!(err.line, 2);
+
+        assert!(parse_stencil::<1>("dimension 1", &[]).is_err());
+    }
+}
@@ -0,0 +1,118 @@
+use crate::domain::*;
+use crate::stencil::*;
+use crate::util::*;
+
+/// Slopes of the neighborhood described by `offsets`,
+/// i.e. how far the stencil reaches in each direction.
+pub fn offset_slopes<const GRID_DIMENSION: usize>(
+    offsets: &[Coord<GRID_DIMENSION>],
+) -> Bounds<GRID_DIMENSION> {
+    let mut result = Bounds::zero();
+    for neighbor in offsets {
+        for d in 0..GRID_DIMENSION {
+            let neighbor_d = neighbor[d];
+            if neighbor_d > 0 {
+                result[(d, 1)] = result[(d, 1)].max(neighbor_d);
+            } else {
+                result[(d, 0)] = result[(d, 0)].max(-neighbor_d);
+            }
+        }
+    }
+    result
+}
+
+/// Time-invariant linear stencils, regardless of whether the neighborhood
+/// size is known at compile time.
+/// Both `Stencil` and `DynStencil` implement this,
+/// so solvers written against it accept either.
+/// `W` is the weight type, e.g. `c64` for `Stencil::new_complex`.
+pub trait TIStencil<const GRID_DIMENSION: usize, W: NumTrait = f64>:
+    Send + Sync
+{
+    fn offset_slice(&self) -> &[Coord<GRID_DIMENSION>];
+
+    /// Same length and order as `offset_slice`.
+    fn weight_slice(&self) -> &[W];
+
+    fn neighborhood_size(&self) -> usize {
+        self.offset_slice().len()
+    }
+
+    fn slopes(&self) -> Bounds<GRID_DIMENSION> {
+        offset_slopes(self.offset_slice())
+    }
+
+    /// Gather the neighborhood of `world_coord` and apply the stencil.
+    /// Runtime sized stencils gather into `args`,
+    /// which is resized on first use and can be reused between calls.
+    /// Stencils with a compile time neighborhood size leave it empty.
+    fn gather_apply<BC, T, DomainType>(
+        &self,
+        bc: &BC,
+        input: &DomainType,
+        world_coord: &Coord<GRID_DIMENSION>,
+        global_time: usize,
+        args: &mut Vec<W>,
+    ) -> W
+    where
+        BC: BCCheck<GRID_DIMENSION>,
+        T: Scalar<Weight = W>,
+        DomainType: DomainView<GRID_DIMENSION, T>,
+    {
+        args.resize(self.neighborhood_size(), W::zero());
+        gather_args_into(
+            self.offset_slice(),
+            bc,
+            input,
+            world_coord,
+            global_time,
+            args,
+        );
+        self.weight_slice()
+            .iter()
+            .zip(args.iter())
+            .fold(W::zero(), |acc, (w, a)| acc + *w * *a)
+    }
+}
+
+impl<
+        const GRID_DIMENSION: usize,
+        const NEIGHBORHOOD_SIZE: usize,
+        W: NumTrait + nalgebra::Scalar,
+    > TIStencil<GRID_DIMENSION, W>
+    for Stencil<GRID_DIMENSION, NEIGHBORHOOD_SIZE, W>
+{
+    fn offset_slice(&self) -> &[Coord<GRID_DIMENSION>] {
+        &self.offsets
+    }
+
+    fn weight_slice(&self) -> &[W] {
+        self.weights.as_slice()
+    }
+
+    fn gather_apply<BC, T, DomainType>(
+        &self,
+        bc: &BC,
+        input: &DomainType,
+        world_coord: &Coord<GRID_DIMENSION>,
+        global_time: usize,
+        _args: &mut Vec<W>,
+    ) -> W
+    where
+        BC: BCCheck<GRID_DIMENSION>,
+        T: Scalar<Weight = W>,
+        DomainType: DomainView<GRID_DIMENSION, T>,
+    {
+        let mut args: nalgebra::SVector<W, NEIGHBORHOOD_SIZE> =
+            nalgebra::SVector::from_element(W::zero());
+        gather_args_into(
+            &self.offsets,
+            bc,
+            input,
+            world_coord,
+            global_time,
+            args.as_mut_slice(),
+        );
+        self.apply(&args)
+    }
+}
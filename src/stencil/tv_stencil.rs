@@ -9,18 +9,7 @@ pub trait TVStencil<const GRID_DIMENSION: usize, const NEIGHBORHOOD_SIZE: usize>
     fn offsets(&self) -> &[Coord<GRID_DIMENSION>; NEIGHBORHOOD_SIZE];
 
     fn slopes(&self) -> Bounds<GRID_DIMENSION> {
-        let mut result = Bounds::zero();
-        for neighbor in self.offsets() {
-            for d in 0..GRID_DIMENSION {
-                let neighbor_d = neighbor[d];
-                if neighbor_d > 0 {
-                    result[(d, 1)] = result[(d, 1)].max(neighbor_d);
-                } else {
-                    result[(d, 0)] = result[(d, 0)].max(-neighbor_d);
-                }
-            }
-        }
-        result
+        offset_slopes(self.offsets())
     }
 
     fn apply(
@@ -0,0 +1,72 @@
+use crate::stencil::*;
+use crate::util::*;
+
+/// Linear stencils that are second order in time,
+/// e.g. leapfrog schemes for the wave equation:
+///
+///   u^{t+1} = S1(u^t) + S0(u^{t-1})
+///
+/// `current` is S1, applied to the most recent time level,
+/// and `previous` is S0, applied to the one before it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TwoLevelStencil<const GRID_DIMENSION: usize> {
+    pub current: DynStencil<GRID_DIMENSION>,
+    pub previous: DynStencil<GRID_DIMENSION>,
+}
+
+impl<const GRID_DIMENSION: usize> TwoLevelStencil<GRID_DIMENSION> {
+    pub fn new<
+        CurrentType: TIStencil<GRID_DIMENSION>,
+        PreviousType: TIStencil<GRID_DIMENSION>,
+    >(
+        current: &CurrentType,
+        previous: &PreviousType,
+    ) -> Self {
+        TwoLevelStencil {
+            current: DynStencil::from_offset_weights(
+                current.offset_slice().to_vec(),
+                current.weight_slice().to_vec(),
+            ),
+            previous: DynStencil::from_offset_weights(
+                previous.offset_slice().to_vec(),
+                previous.weight_slice().to_vec(),
+            ),
+        }
+    }
+
+    pub fn current(&self) -> &DynStencil<GRID_DIMENSION> {
+        &self.current
+    }
+
+    pub fn previous(&self) -> &DynStencil<GRID_DIMENSION> {
+        &self.previous
+    }
+
+    /// Slopes covering both time levels,
+    /// i.e. how far information can travel in one step.
+    pub fn slopes(&self) -> Bounds<GRID_DIMENSION> {
+        self.current.slopes().sup(&self.previous.slopes())
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+    use nalgebra::matrix;
+
+    #[test]
+    fn slopes() {
+        let current =
+            Stencil::new([[-1, 0], [0, 0], [0, 2]], |args: &[f64; 3]| {
+                args[0] + args[1] + args[2]
+            });
+        let previous = Stencil::new([[2, 0], [0, -1]], |args: &[f64; 2]| {
+            args[0] - args[1]
+        });
+        let stencil = TwoLevelStencil::new(&current, &previous);
+        assert_eq!(stencil.current().slopes(), matrix![1, 0; 0, 2]);
+        assert_eq!(stencil.previous().slopes(), matrix![0, 2; 1, 0]);
+        assert_eq!(stencil.slopes(), matrix![1, 2; 1, 2]);
+        assert_eq!(stencil.previous().weights(), &[1.0, -1.0]);
+    }
+}
@@ -0,0 +1,260 @@
+//! Checks behind the `try_new` stencil constructors.
+//!
+//! `extract_weights` probes the operation with unit vectors,
+//! which is only correct if the operation is linear.
+//! Affine or nonlinear operations, and repeated offsets,
+//! would otherwise silently produce the wrong stencil.
+
+use crate::util::*;
+use rand::prelude::*;
+
+/// Random inputs used to probe for linearity.
+const LINEARITY_PROBES: usize = 8;
+
+/// Relative tolerance for comparing the operation against its weights.
+const LINEARITY_EPSILON: f64 = 1e-9;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum StencilError {
+    /// Two neighbors share an offset.
+    DuplicateOffset {
+        first: usize,
+        second: usize,
+        offset: Vec<i32>,
+    },
+
+    /// The operation returns `constant` when every neighbor is zero.
+    Affine { constant: f64 },
+
+    /// The operation doesn't match its extracted weights
+    /// for some input, i.e. it isn't linear.
+    Nonlinear { expected: f64, actual: f64 },
+
+    /// Extracting the weight for a neighbor gave NaN or infinity.
+    NonFiniteWeight { index: usize, weight: f64 },
+
+    /// The time step is too large for the explicit scheme,
+    /// i.e. the summed Courant and diffusion numbers exceed one.
+    Cfl { stability_number: f64 },
+
+    /// Converting into a `Stencil` with `expected` neighbors,
+    /// but the stencil has `actual` neighbors.
+    NeighborhoodSize { expected: usize, actual: usize },
+}
+
+impl std::fmt::Display for StencilError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StencilError::DuplicateOffset {
+                first,
+                second,
+                offset,
+            } => write!(
+                f,
+                "neighbors {first} and {second} share offset {offset:?}"
+            ),
+            StencilError::Affine { constant } => write!(
+                f,
+                "operation is affine, it returns {constant} for all zero inputs"
+            ),
+            StencilError::Nonlinear { expected, actual } => write!(
+                f,
+                "operation is not linear, \
+                 weights predict {expected} but operation returned {actual}"
+            ),
+            StencilError::NonFiniteWeight { index, weight } => {
+                write!(f, "neighbor {index} has non-finite weight {weight}")
+            }
+            StencilError::Cfl { stability_number } => write!(
+                f,
+                "time step violates the CFL condition, \
+                 stability number {stability_number} exceeds 1"
+            ),
+            StencilError::NeighborhoodSize { expected, actual } => write!(
+                f,
+                "expected a neighborhood of {expected}, stencil has {actual}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for StencilError {}
+
+pub fn check_unique_offsets<const GRID_DIMENSION: usize>(
+    offsets: &[Coord<GRID_DIMENSION>],
+) -> Result<(), StencilError> {
+    for second in 0..offsets.len() {
+        for first in 0..second {
+            if offsets[first] == offsets[second] {
+                return Err(StencilError::DuplicateOffset {
+                    first,
+                    second,
+                    offset: offsets[second].iter().copied().collect(),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Verify that `operation` is the linear map given by `weights`.
+/// We check the zero input for a constant term,
+/// then compare against the weights on random inputs.
+pub fn check_linearity<F: Fn(&[f64]) -> f64>(
+    weights: &[f64],
+    operation: F,
+) -> Result<(), StencilError> {
+    for (index, weight) in weights.iter().enumerate() {
+        if !weight.is_finite() {
+            return Err(StencilError::NonFiniteWeight {
+                index,
+                weight: *weight,
+            });
+        }
+    }
+
+    let mut args = vec![0.0; weights.len()];
+    let constant = operation(&args);
+    if constant != 0.0 {
+        return Err(StencilError::Affine { constant });
+    }
+
+    // Seeded so a given stencil is always accepted or always rejected
+    let mut rng = StdRng::seed_from_u64(0x6e686c73);
+    for _ in 0..LINEARITY_PROBES {
+        for a in args.iter_mut() {
+            *a = rng.gen_range(-1.0..1.0);
+        }
+        let actual = operation(&args);
+        let mut expected = 0.0;
+        let mut magnitude = 0.0;
+        for (w, a) in weights.iter().zip(args.iter()) {
+            expected += w * a;
+            magnitude += (w * a).abs();
+        }
+        let tolerance = LINEARITY_EPSILON * magnitude.max(1.0);
+        if !actual.is_finite() || (actual - expected).abs() > tolerance {
+            return Err(StencilError::Nonlinear { expected, actual });
+        }
+    }
+    Ok(())
+}
+
+/// Complex version of `check_linearity`.
+/// A `c64` operation is linear exactly when the real and imaginary parts
+/// of its result are real linear maps of the real and imaginary parts
+/// of its input, so we check both of those.
+/// This also rejects operations that are only real linear,
+/// like complex conjugation.
+pub fn check_complex_linearity<F: Fn(&[c64]) -> c64>(
+    weights: &[c64],
+    operation: F,
+) -> Result<(), StencilError> {
+    for (index, w) in weights.iter().enumerate() {
+        for weight in [w.re, w.im] {
+            if !weight.is_finite() {
+                return Err(StencilError::NonFiniteWeight { index, weight });
+            }
+        }
+    }
+
+    // Real inputs are the real parts followed by the imaginary parts
+    let n = weights.len();
+    let complex_args = |parts: &[f64]| -> Vec<c64> {
+        (0..n).map(|i| c64::new(parts[i], parts[n + i])).collect()
+    };
+    let re_weights: Vec<f64> = weights
+        .iter()
+        .map(|w| w.re)
+        .chain(weights.iter().map(|w| -w.im))
+        .collect();
+    check_linearity(&re_weights, |parts: &[f64]| {
+        operation(&complex_args(parts)).re
+    })?;
+    let im_weights: Vec<f64> = weights
+        .iter()
+        .map(|w| w.im)
+        .chain(weights.iter().map(|w| w.re))
+        .collect();
+    check_linearity(&im_weights, |parts: &[f64]| {
+        operation(&complex_args(parts)).im
+    })
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+    use nalgebra::vector;
+
+    #[test]
+    fn unique_offsets() {
+        assert_eq!(
+            check_unique_offsets(&[vector![0, 1], vector![1, 0]]),
+            Ok(())
+        );
+        assert_eq!(
+            check_unique_offsets(&[
+                vector![0, 1],
+                vector![1, 0],
+                vector![0, 1]
+            ]),
+            Err(StencilError::DuplicateOffset {
+                first: 0,
+                second: 2,
+                offset: vec![0, 1]
+            })
+        );
+    }
+
+    #[test]
+    fn linearity() {
+        let linear = |a: &[f64]| 0.5 * a[0] - 2.0 * a[1];
+        assert_eq!(check_linearity(&[0.5, -2.0], linear), Ok(()));
+
+        let affine = |a: &[f64]| 0.5 * a[0] - 2.0 * a[1] + 1.0;
+        assert_eq!(
+            check_linearity(&[0.5, -2.0], affine),
+            Err(StencilError::Affine { constant: 1.0 })
+        );
+
+        // Unit vectors can't see the cross term
+        let nonlinear = |a: &[f64]| a[0] + a[1] + a[0] * a[1];
+        assert!(matches!(
+            check_linearity(&[1.0, 1.0], nonlinear),
+            Err(StencilError::Nonlinear { .. })
+        ));
+
+        let non_finite = |a: &[f64]| a[0] / 0.0;
+        assert!(matches!(
+            check_linearity(&[f64::INFINITY], non_finite),
+            Err(StencilError::NonFiniteWeight { index: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn complex_linearity() {
+        let i = c64::new(0.0, 1.0);
+        let weights = [c64::new(0.5, 1.0), c64::new(-2.0, 0.0)];
+        let linear = |a: &[c64]| weights[0] * a[0] + weights[1] * a[1];
+        assert_eq!(check_complex_linearity(&weights, linear), Ok(()));
+
+        let affine = |a: &[c64]| a[0] + i;
+        assert_eq!(
+            check_complex_linearity(&[c64::one()], affine),
+            Err(StencilError::Affine { constant: 1.0 })
+        );
+
+        // Agrees with its weights on real inputs, but not on complex ones
+        let conjugate = |a: &[c64]| a[0].conj();
+        assert!(matches!(
+            check_complex_linearity(&[c64::one()], conjugate),
+            Err(StencilError::Nonlinear { .. })
+        ));
+
+        let non_finite = [c64::new(1.0, f64::NAN)];
+        assert!(matches!(
+            check_complex_linearity(&non_finite, |a: &[c64]| a[0]),
+            Err(StencilError::NonFiniteWeight { index: 0, .. })
+        ));
+    }
+}
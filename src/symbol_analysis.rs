@@ -0,0 +1,292 @@
+//! Von Neumann analysis of stencils.
+//!
+//! A linear stencil acts on a Fourier mode `exp(i k x)`
+//! by multiplying it with the stencil's symbol,
+//! `S(k) = sum_j w_j exp(i k o_j)`.
+//! If `|S(k)| > 1` for any frequency, that mode grows every step,
+//! and the simulation will eventually blow up.
+//! We sample the symbol on a periodic grid of frequencies
+//! using the same R2C FFT path as `ConvolutionOperation::create`.
+
+use crate::domain::*;
+use crate::stencil::*;
+use crate::util::indexing::*;
+use crate::util::*;
+use fftw::plan::*;
+
+/// Frequencies sampled along each axis when none are given.
+pub const DEFAULT_SYMBOL_RESOLUTION: usize = 64;
+
+/// Time steps sampled when checking a time varying stencil.
+pub const DEFAULT_TV_SAMPLES: usize = 64;
+
+/// Tolerance used for mass conservation and stability checks.
+pub const SYMBOL_EPSILON: f64 = 1e-10;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SymbolReport {
+    /// Maximum of |S(k)| over all sampled frequencies.
+    pub max_amplification: f64,
+
+    /// Sum of the weights, i.e. S(0).
+    /// For time varying stencils this is the sum furthest from 1.
+    pub weight_sum: f64,
+
+    /// Do the weights sum to 1?
+    pub mass_conserving: bool,
+
+    /// Are all weights non-negative?
+    pub positive: bool,
+
+    /// The smallest weight seen.
+    pub min_weight: f64,
+}
+
+impl SymbolReport {
+    fn from_weights(max_amplification: f64, weights: &[f64]) -> Self {
+        let weight_sum: f64 = weights.iter().sum();
+        let min_weight = weights.iter().copied().fold(f64::INFINITY, f64::min);
+        SymbolReport {
+            max_amplification,
+            weight_sum,
+            mass_conserving: (weight_sum - 1.0).abs() <= SYMBOL_EPSILON,
+            positive: min_weight >= 0.0,
+            min_weight,
+        }
+    }
+
+    /// Combine reports, keeping the worst case of each property.
+    pub fn worst(&self, other: &Self) -> Self {
+        let weight_sum =
+            if (other.weight_sum - 1.0).abs() > (self.weight_sum - 1.0).abs() {
+                other.weight_sum
+            } else {
+                self.weight_sum
+            };
+        SymbolReport {
+            max_amplification: self
+                .max_amplification
+                .max(other.max_amplification),
+            weight_sum,
+            mass_conserving: self.mass_conserving && other.mass_conserving,
+            positive: self.positive && other.positive,
+            min_weight: self.min_weight.min(other.min_weight),
+        }
+    }
+
+    /// No sampled Fourier mode grows.
+    pub fn is_stable(&self) -> bool {
+        self.max_amplification <= 1.0 + SYMBOL_EPSILON
+    }
+
+    pub fn print_report(&self) {
+        println!("Symbol Report:");
+        println!("  max |S(k)|: {}", self.max_amplification);
+        println!("  stable: {}", self.is_stable());
+        println!(
+            "  weight sum: {}, mass conserving: {}",
+            self.weight_sum, self.mass_conserving
+        );
+        println!(
+            "  min weight: {}, positive: {}",
+            self.min_weight, self.positive
+        );
+    }
+
+    /// Print warnings for properties that will likely
+    /// cause trouble in a long run.
+    pub fn print_warnings(&self) {
+        if !self.is_stable() {
+            eprintln!(
+                "WARNING: stencil is unstable, max |S(k)| = {} > 1. \
+                 Solutions will blow up, try a smaller dt.",
+                self.max_amplification
+            );
+        }
+        if !self.positive {
+            eprintln!(
+                "WARNING: stencil has negative weight {}, \
+                 solutions may oscillate.",
+                self.min_weight
+            );
+        }
+    }
+}
+
+/// Sample the symbol of the stencil given by `offsets` and `weights`
+/// on a periodic grid with `resolution` frequencies along each axis.
+/// The result uses FFTW3's R2C layout,
+/// i.e. the last axis only has `resolution / 2 + 1` entries.
+/// Since weights are real, the remaining frequencies are conjugates.
+pub fn sample_symbol<const GRID_DIMENSION: usize>(
+    offsets: &[Coord<GRID_DIMENSION>],
+    weights: &[f64],
+    resolution: usize,
+) -> AlignedVec<c64> {
+    debug_assert_eq!(offsets.len(), weights.len());
+    assert!(resolution > 0);
+
+    let exclusive_bounds =
+        Coord::<GRID_DIMENSION>::from_element(resolution as i32);
+    let domain_aabb = AABB::from_exclusive_bounds(&exclusive_bounds);
+    let mut real_buffer = AlignedVec::new(domain_aabb.buffer_size());
+    let mut symbol = AlignedVec::new(complex_buffer_size(&exclusive_bounds));
+
+    // Only planned once per analysis so keep it cheap,
+    // ESTIMATE also leaves the input buffer alone.
+    let plan_size = exclusive_bounds.try_cast::<usize>().unwrap();
+    let forward_plan = fftw::plan::R2CPlan64::aligned(
+        plan_size.as_slice(),
+        fftw::types::Flag::ESTIMATE,
+    )
+    .unwrap();
+
+    {
+        // Mirror offsets to match ConvolutionOperation.
+        // Accumulate rather than set, on small grids
+        // several offsets can wrap to the same coordinate.
+        let mut stencil_domain =
+            SliceDomain::new(domain_aabb, &mut real_buffer);
+        for (offset, weight) in offsets.iter().zip(weights) {
+            let rn_i: Coord<GRID_DIMENSION> = offset * -1;
+            let periodic_coord = domain_aabb.periodic_coord(&rn_i);
+            let value = stencil_domain.view(&periodic_coord) + weight;
+            stencil_domain.set_coord(&periodic_coord, value);
+        }
+    }
+
+    forward_plan.r2c(&mut real_buffer, &mut symbol).unwrap();
+    symbol
+}
+
+fn analyze_offset_weights<const GRID_DIMENSION: usize>(
+    offsets: &[Coord<GRID_DIMENSION>],
+    weights: &[f64],
+    resolution: usize,
+) -> SymbolReport {
+    let symbol = sample_symbol(offsets, weights, resolution);
+    let max_amplification = symbol.iter().map(|s| s.norm()).fold(0.0, f64::max);
+    SymbolReport::from_weights(max_amplification, weights)
+}
+
+/// Von Neumann analysis of a time invariant stencil.
+pub fn analyze_stencil<
+    const GRID_DIMENSION: usize,
+    StencilType: TIStencil<GRID_DIMENSION>,
+>(
+    stencil: &StencilType,
+    resolution: usize,
+) -> SymbolReport {
+    analyze_offset_weights(
+        stencil.offset_slice(),
+        stencil.weight_slice(),
+        resolution,
+    )
+}
+
+/// Von Neumann analysis of a time varying stencil,
+/// reporting the worst case over `global_times`.
+/// Panics if `global_times` is empty.
+pub fn analyze_tv_stencil<
+    const GRID_DIMENSION: usize,
+    const NEIGHBORHOOD_SIZE: usize,
+    StencilType: TVStencil<GRID_DIMENSION, NEIGHBORHOOD_SIZE>,
+    TimeIter: IntoIterator<Item = usize>,
+>(
+    stencil: &StencilType,
+    global_times: TimeIter,
+    resolution: usize,
+) -> SymbolReport {
+    let offsets = TVStencil::offsets(stencil);
+    global_times
+        .into_iter()
+        .map(|t| {
+            let weights = TVStencil::weights(stencil, t);
+            analyze_offset_weights(offsets, weights.as_slice(), resolution)
+        })
+        .reduce(|a, b| a.worst(&b))
+        .expect("analyze_tv_stencil requires at least one time")
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+    use crate::standard_stencils::*;
+    use float_cmp::assert_approx_eq;
+
+    #[test]
+    fn heat_1d_stability() {
+        // r = a * dt / dx^2, explicit heat is stable for r <= 1/2
+        let stable = heat_1d(0.5, 1.0, 1.0);
+        let report = analyze_stencil(&stable, DEFAULT_SYMBOL_RESOLUTION);
+        assert!(report.is_stable());
+        assert!(report.mass_conserving);
+        assert!(report.positive);
+        assert_approx_eq!(f64, report.weight_sum, 1.0);
+        assert_approx_eq!(f64, report.max_amplification, 1.0);
+
+        let unstable = heat_1d(0.6, 1.0, 1.0);
+        let report = analyze_stencil(&unstable, DEFAULT_SYMBOL_RESOLUTION);
+        assert!(!report.is_stable());
+        assert!(report.mass_conserving);
+        assert!(!report.positive);
+        // Highest frequency mode, 1 - 4r
+        assert_approx_eq!(f64, report.max_amplification, 1.4, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn heat_2d_stability() {
+        let stable = heat_2d(0.2, 1.0, 1.0, 1.0, 1.0);
+        let report = analyze_stencil(&stable, 32);
+        assert!(report.is_stable());
+        assert!(report.positive);
+
+        let unstable = heat_2d(0.3, 1.0, 1.0, 1.0, 1.0);
+        let report = analyze_stencil(&unstable, 32);
+        assert!(!report.is_stable());
+    }
+
+    #[test]
+    fn symbol_matches_direct_sum() {
+        let stencil =
+            DynStencil::new(&[[0, 0], [1, 0], [0, 2]], |a: &[f64]| {
+                0.5 * a[0] + 0.3 * a[1] - 0.1 * a[2]
+            });
+        let n = 8;
+        let symbol = sample_symbol(stencil.offsets(), stencil.weights(), n);
+        let bounds = vector![n as i32, n as i32 / 2 + 1];
+        for i in 0..symbol.len() {
+            let k = linear_to_coord(i, &bounds);
+            let mut expected = c64::new(0.0, 0.0);
+            for (o, w) in stencil.offsets().iter().zip(stencil.weights()) {
+                let phase =
+                    2.0 * std::f64::consts::PI * (k.dot(o) as f64) / n as f64;
+                expected += c64::new(phase.cos(), phase.sin()) * *w;
+            }
+            assert_approx_eq!(f64, symbol[i].re, expected.re, epsilon = 1e-12);
+            assert_approx_eq!(f64, symbol[i].im, expected.im, epsilon = 1e-12);
+        }
+    }
+
+    #[test]
+    fn wide_stencil_small_grid() {
+        // Offsets wrap onto each other, weights must accumulate
+        let stencil = DynStencil::new(&[[-3], [0], [3]], |a: &[f64]| {
+            0.25 * a[0] + 0.5 * a[1] + 0.25 * a[2]
+        });
+        let symbol = sample_symbol(stencil.offsets(), stencil.weights(), 3);
+        for s in symbol.iter() {
+            assert_approx_eq!(f64, s.re, 1.0, epsilon = 1e-12);
+            assert_approx_eq!(f64, s.im, 0.0, epsilon = 1e-12);
+        }
+    }
+
+    #[test]
+    fn tv_worst_case() {
+        let stencil = TVHeat1D::new();
+        let report = analyze_tv_stencil(&stencil, 0..10, 16);
+        let single = analyze_tv_stencil(&stencil, [3], 16);
+        assert!(report.max_amplification >= single.max_amplification);
+        assert!(report.min_weight <= single.min_weight);
+    }
+}
@@ -92,6 +92,46 @@ impl<const DIMENSION: usize> AABB<DIMENSION> {
         true
     }
 
+    /// Check whether the instance and another AABB share any coordinates.
+    pub fn intersects(&self, other: &Self) -> bool {
+        for d in 0..DIMENSION {
+            if other.bounds[(d, 1)] < self.bounds[(d, 0)]
+                || other.bounds[(d, 0)] > self.bounds[(d, 1)]
+            {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Faces of the instance that a coordinate lies beyond,
+    /// as (axis, side) pairs where side 0 is min and side 1 is max.
+    pub fn outside_faces(
+        &self,
+        coord: &Coord<DIMENSION>,
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let coord = *coord;
+        (0..DIMENSION).filter_map(move |d| {
+            if coord[d] < self.bounds[(d, 0)] {
+                Some((d, 0))
+            } else if coord[d] > self.bounds[(d, 1)] {
+                Some((d, 1))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Closest coordinate within the instance.
+    pub fn clamp_coord(&self, coord: &Coord<DIMENSION>) -> Coord<DIMENSION> {
+        let mut result = *coord;
+        for d in 0..DIMENSION {
+            result[d] =
+                coord[d].clamp(self.bounds[(d, 0)], self.bounds[(d, 1)]);
+        }
+        result
+    }
+
     pub fn trim_to_aabb(&mut self, other: &Self) {
         for d in 0..DIMENSION {
             self.bounds[(d, 0)] = self.bounds[(d, 0)].max(other.bounds[(d, 0)]);
@@ -156,6 +196,8 @@ impl<const DIMENSION: usize> AABB<DIMENSION> {
     /// Given a bounding box within self,
     /// return decomposition of remaining coordinate space.
     /// Used for recursion during aperiodic algorithm.
+    /// Where `center` touches a face, the box on that side is empty,
+    /// i.e. it fails `check_validity`.
     /// Until generic_const_exprs is stabilized,
     /// we need to return a nested array.
     pub fn decomposition(
@@ -167,11 +209,14 @@ impl<const DIMENSION: usize> AABB<DIMENSION> {
         for d in 0..DIMENSION {
             result[d][0] = remaining_bounds;
             result[d][0].bounds[(d, 1)] = center.bounds[(d, 0)] - 1;
-            debug_assert!(result[d][0].check_validity());
 
             result[d][1] = remaining_bounds;
             result[d][1].bounds[(d, 0)] = center.bounds[(d, 1)] + 1;
-            debug_assert!(result[d][1].check_validity());
+
+            let touches_min = center.bounds[(d, 0)] == self.bounds[(d, 0)];
+            let touches_max = center.bounds[(d, 1)] == self.bounds[(d, 1)];
+            debug_assert!(touches_min || result[d][0].check_validity());
+            debug_assert!(touches_max || result[d][1].check_validity());
 
             remaining_bounds.bounds[(d, 0)] = center.bounds[(d, 0)];
             remaining_bounds.bounds[(d, 1)] = center.bounds[(d, 1)];
@@ -197,19 +242,43 @@ impl<const DIMENSION: usize> AABB<DIMENSION> {
     ) -> (usize, AABB<DIMENSION>) {
         debug_assert!(ratio < 1.0);
         let inclusive_sides = self.bounds.column(1) - self.bounds.column(0);
-        let (min_side_d, min_side_len) = inclusive_sides.argmin();
-        let scaled_side_len = ((min_side_len as f64) * ratio) as i32;
-        let diff = min_side_len - scaled_side_len;
-        let min_side_slope = slopes[(min_side_d, 0)] + slopes[(min_side_d, 1)];
 
-        // Round down?
-        let mut steps = diff / min_side_slope;
+        // Only sides we shrink along limit the steps
+        let mut min_side: Option<(i32, i32)> = None;
+        for d in 0..DIMENSION {
+            let slope = slopes[(d, 0)] + slopes[(d, 1)];
+            if slope == 0 {
+                continue;
+            }
+            if min_side.is_none_or(|(len, _)| inclusive_sides[d] < len) {
+                min_side = Some((inclusive_sides[d], slope));
+            }
+        }
+
+        let mut steps = match min_side {
+            Some((min_side_len, min_side_slope)) => {
+                let scaled_side_len = ((min_side_len as f64) * ratio) as i32;
+                let diff = min_side_len - scaled_side_len;
+
+                // Round down?
+                diff / min_side_slope
+            }
+            None => max_steps.unwrap_or(0) as i32,
+        };
         if let Some(max) = max_steps {
             if steps > max as i32 {
                 steps = max as i32;
             }
         }
 
+        // Other sides may slope faster, they can't shrink past empty
+        for d in 0..DIMENSION {
+            let slope = slopes[(d, 0)] + slopes[(d, 1)];
+            if slope != 0 {
+                steps = steps.min(inclusive_sides[d] / slope);
+            }
+        }
+
         // Okay now we can construct center
         let new_min = self.bounds.column(0) + steps * slopes.column(0);
         let new_max = self.bounds.column(1) - steps * slopes.column(1);
@@ -326,6 +395,17 @@ mod unit_tests {
         }
     }
 
+    #[test]
+    fn outside_faces_test() {
+        let bound = AABB::new(matrix![0, 10; 5, 10; -3, 3]);
+        let faces: Vec<_> = bound.outside_faces(&vector![3, 7, 0]).collect();
+        assert!(faces.is_empty());
+        let faces: Vec<_> = bound.outside_faces(&vector![-1, 7, 4]).collect();
+        assert_eq!(faces, vec![(0, 0), (2, 1)]);
+        assert_eq!(bound.clamp_coord(&vector![-1, 7, 4]), vector![0, 7, 3]);
+        assert_eq!(bound.clamp_coord(&vector![3, 11, -9]), vector![3, 10, -3]);
+    }
+
     #[test]
     fn periodic_coord_test() {
         {
@@ -405,6 +485,15 @@ mod unit_tests {
         }
     }
 
+    #[test]
+    fn intersects_test() {
+        let a = AABB::new(matrix![0, 9; 0, 9]);
+        assert!(a.intersects(&AABB::new(matrix![9, 12; -3, 0])));
+        assert!(a.intersects(&AABB::new(matrix![-5, 20; 2, 3])));
+        assert!(!a.intersects(&AABB::new(matrix![10, 12; 0, 9])));
+        assert!(!a.intersects(&AABB::new(matrix![0, 9; -4, -1])));
+    }
+
     #[test]
     fn check_validity_test() {
         {
@@ -519,6 +608,22 @@ mod unit_tests {
                 (max_steps, AABB::new(matrix![24, 80; 16, 88; 8, 36]))
             );
         }
+        {
+            // The shortest side isn't always the one to empty first
+            let b = AABB::new(matrix![0, 30; 0, 20]);
+            let slopes = matrix![10, 10; 1, 1];
+            let c = b.shrink(0.5, slopes, None);
+            assert_eq!(c, (1, AABB::new(matrix![10, 20; 1, 19])));
+        }
+        {
+            // Axes without slopes, e.g. periodic ones, don't shrink
+            let b = AABB::new(matrix![0, 100; 0, 40]);
+            let slopes = matrix![1, 1; 0, 0];
+            let c = b.shrink(0.5, slopes, None);
+            assert_eq!(c, (25, AABB::new(matrix![25, 75; 0, 40])));
+            let c = b.shrink(0.5, Bounds::zeros(), Some(7));
+            assert_eq!(c, (7, b));
+        }
     }
 
     #[test]
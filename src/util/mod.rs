@@ -1,9 +1,11 @@
 pub mod indexing;
 
 mod aabb;
+mod scalar;
 pub use aabb::*;
 pub use fftw::array::AlignedVec;
 pub use nalgebra::{matrix, vector};
+pub use scalar::*;
 
 pub use num_traits::{Num, One, Zero};
 
@@ -0,0 +1,307 @@
+use super::{NumTrait, AABB};
+use fftw::array::AlignedAllocable;
+use fftw::plan::*;
+use fftw::types::*;
+
+/// Types that domains and solvers can store values as.
+/// Boundary conditions stay `f64`,
+/// values are converted when they are read and written.
+/// Stencils are applied in terms of `Weight`,
+/// `f64` for real types, `c64` for complex ones.
+pub trait Scalar:
+    NumTrait
+    + AlignedAllocable
+    + bytemuck::NoUninit
+    + bytemuck::AnyBitPattern
+    + Default
+    + std::fmt::Debug
+    + std::fmt::Display
+    + 'static
+{
+    /// Complex type used in the frequency domain.
+    type Complex: NumTrait
+        + AlignedAllocable
+        + bytemuck::NoUninit
+        + bytemuck::AnyBitPattern
+        + std::fmt::Debug;
+
+    /// Type of stencil weights, and the type stencils are applied in.
+    type Weight: NumTrait + From<f64> + std::fmt::Debug;
+
+    /// FFTW plan into the frequency domain,
+    /// R2C for real types, C2C for complex ones.
+    type ForwardPlan: ForwardFft<Self>;
+
+    /// FFTW plan out of the frequency domain,
+    /// C2R for real types, C2C for complex ones.
+    type BackwardPlan: BackwardFft<Self>;
+
+    /// Used in reports
+    const NAME: &'static str;
+
+    /// `as_f64` drops part of the value,
+    /// see `BCCheck::reads_input`.
+    const COMPLEX: bool;
+
+    fn cast(value: f64) -> Self;
+
+    /// Real part for complex types.
+    fn as_f64(self) -> f64;
+
+    fn to_weight(self) -> Self::Weight;
+
+    fn from_weight(weight: Self::Weight) -> Self;
+
+    /// Number of `Complex` values the frequency domain
+    /// representation of `aabb` needs.
+    fn frequency_buffer_size<const GRID_DIMENSION: usize>(
+        aabb: &AABB<GRID_DIMENSION>,
+    ) -> usize;
+
+    /// Number of threads FFTW uses for plans created after this call.
+    fn plan_with_nthreads(threads: usize);
+}
+
+pub trait ForwardFft<T: Scalar>: Send + Sync + Sized {
+    fn plan(shape: &[usize], flag: Flag) -> Self;
+
+    fn forward(&self, input: &mut [T], output: &mut [T::Complex]);
+}
+
+pub trait BackwardFft<T: Scalar>: Send + Sync + Sized {
+    fn plan(shape: &[usize], flag: Flag) -> Self;
+
+    /// Unnormalized, i.e. `backward(forward(x))` is `x` scaled
+    /// by the number of values.
+    fn backward(&self, input: &mut [T::Complex], output: &mut [T]);
+}
+
+impl Scalar for f64 {
+    type Complex = c64;
+    type Weight = f64;
+    type ForwardPlan = R2CPlan64;
+    type BackwardPlan = C2RPlan64;
+
+    const NAME: &'static str = "f64";
+    const COMPLEX: bool = false;
+
+    #[inline]
+    fn cast(value: f64) -> Self {
+        value
+    }
+
+    #[inline]
+    fn as_f64(self) -> f64 {
+        self
+    }
+
+    #[inline]
+    fn to_weight(self) -> f64 {
+        self
+    }
+
+    #[inline]
+    fn from_weight(weight: f64) -> Self {
+        weight
+    }
+
+    fn frequency_buffer_size<const GRID_DIMENSION: usize>(
+        aabb: &AABB<GRID_DIMENSION>,
+    ) -> usize {
+        aabb.complex_buffer_size()
+    }
+
+    fn plan_with_nthreads(threads: usize) {
+        fftw::threading::plan_with_nthreads_f64(threads);
+    }
+}
+
+impl Scalar for f32 {
+    type Complex = c32;
+    type Weight = f64;
+    type ForwardPlan = R2CPlan32;
+    type BackwardPlan = C2RPlan32;
+
+    const NAME: &'static str = "f32";
+    const COMPLEX: bool = false;
+
+    #[inline]
+    fn cast(value: f64) -> Self {
+        value as f32
+    }
+
+    #[inline]
+    fn as_f64(self) -> f64 {
+        self as f64
+    }
+
+    #[inline]
+    fn to_weight(self) -> f64 {
+        self as f64
+    }
+
+    #[inline]
+    fn from_weight(weight: f64) -> Self {
+        weight as f32
+    }
+
+    fn frequency_buffer_size<const GRID_DIMENSION: usize>(
+        aabb: &AABB<GRID_DIMENSION>,
+    ) -> usize {
+        aabb.complex_buffer_size()
+    }
+
+    fn plan_with_nthreads(threads: usize) {
+        fftw::threading::plan_with_nthreads_f32(threads);
+    }
+}
+
+/// Complex fields, e.g. for Schrödinger type equations.
+/// There is no Hermitian symmetry to exploit,
+/// so the frequency domain is as large as the domain itself.
+impl Scalar for c64 {
+    type Complex = c64;
+    type Weight = c64;
+    type ForwardPlan = C2CForward;
+    type BackwardPlan = C2CBackward;
+
+    const NAME: &'static str = "c64";
+    const COMPLEX: bool = true;
+
+    #[inline]
+    fn cast(value: f64) -> Self {
+        c64::new(value, 0.0)
+    }
+
+    #[inline]
+    fn as_f64(self) -> f64 {
+        self.re
+    }
+
+    #[inline]
+    fn to_weight(self) -> c64 {
+        self
+    }
+
+    #[inline]
+    fn from_weight(weight: c64) -> Self {
+        weight
+    }
+
+    fn frequency_buffer_size<const GRID_DIMENSION: usize>(
+        aabb: &AABB<GRID_DIMENSION>,
+    ) -> usize {
+        aabb.buffer_size()
+    }
+
+    fn plan_with_nthreads(threads: usize) {
+        fftw::threading::plan_with_nthreads_f64(threads);
+    }
+}
+
+macro_rules! impl_r2c_fft {
+    ($real:ty, $forward:ty, $backward:ty) => {
+        impl ForwardFft<$real> for $forward {
+            fn plan(shape: &[usize], flag: Flag) -> Self {
+                <$forward as R2CPlan>::aligned(shape, flag).unwrap()
+            }
+
+            fn forward(
+                &self,
+                input: &mut [$real],
+                output: &mut [<$real as Scalar>::Complex],
+            ) {
+                self.r2c(input, output).unwrap();
+            }
+        }
+
+        impl BackwardFft<$real> for $backward {
+            fn plan(shape: &[usize], flag: Flag) -> Self {
+                <$backward as C2RPlan>::aligned(shape, flag).unwrap()
+            }
+
+            fn backward(
+                &self,
+                input: &mut [<$real as Scalar>::Complex],
+                output: &mut [$real],
+            ) {
+                self.c2r(input, output).unwrap();
+            }
+        }
+    };
+}
+
+impl_r2c_fft!(f64, R2CPlan64, C2RPlan64);
+impl_r2c_fft!(f32, R2CPlan32, C2RPlan32);
+
+/// C2C plans fix their direction when they are created.
+pub struct C2CForward(C2CPlan64);
+
+pub struct C2CBackward(C2CPlan64);
+
+impl ForwardFft<c64> for C2CForward {
+    fn plan(shape: &[usize], flag: Flag) -> Self {
+        C2CForward(C2CPlan64::aligned(shape, Sign::Forward, flag).unwrap())
+    }
+
+    fn forward(&self, input: &mut [c64], output: &mut [c64]) {
+        self.0.c2c(input, output).unwrap();
+    }
+}
+
+impl BackwardFft<c64> for C2CBackward {
+    fn plan(shape: &[usize], flag: Flag) -> Self {
+        C2CBackward(C2CPlan64::aligned(shape, Sign::Backward, flag).unwrap())
+    }
+
+    fn backward(&self, input: &mut [c64], output: &mut [c64]) {
+        self.0.c2c(input, output).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::*;
+    use nalgebra::matrix;
+
+    #[test]
+    fn cast_test() {
+        assert_eq!(f64::cast(0.1), 0.1);
+        assert_eq!(f32::cast(0.1), 0.1f32);
+        assert_eq!(f32::cast(0.5).as_f64(), 0.5);
+        assert_eq!(
+            std::mem::size_of::<<f32 as Scalar>::Complex>(),
+            2 * std::mem::size_of::<f32>()
+        );
+        assert_eq!(c64::cast(0.5), c64::new(0.5, 0.0));
+        assert_eq!(c64::new(0.5, 2.0).as_f64(), 0.5);
+    }
+
+    #[test]
+    fn frequency_buffer_size_test() {
+        let aabb = AABB::new(matrix![0, 9; 0, 9]);
+        assert_eq!(f64::frequency_buffer_size(&aabb), 10 * 6);
+        assert_eq!(f32::frequency_buffer_size(&aabb), 10 * 6);
+        assert_eq!(c64::frequency_buffer_size(&aabb), 10 * 10);
+    }
+
+    #[test]
+    fn c2c_round_trip() {
+        let n = 16;
+        let forward = C2CForward::plan(&[n], Flag::ESTIMATE);
+        let backward = C2CBackward::plan(&[n], Flag::ESTIMATE);
+        let mut input = fftw::array::AlignedVec::new(n);
+        for i in 0..n {
+            input[i] = c64::new(i as f64, 1.0 - i as f64);
+        }
+        let expected = input.clone();
+        let mut frequencies = fftw::array::AlignedVec::new(n);
+        let mut output = fftw::array::AlignedVec::new(n);
+        forward.forward(&mut input, &mut frequencies);
+        backward.backward(&mut frequencies, &mut output);
+        for i in 0..n {
+            let value = output[i] / n as f64;
+            assert!((value - expected[i]).norm() < 1e-10);
+        }
+    }
+}
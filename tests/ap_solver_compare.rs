@@ -3,6 +3,7 @@ use nhls::ap_solver::*;
 use nhls::direct_solver::*;
 use nhls::domain::*;
 use nhls::initial_conditions::normal_impulse::*;
+use nhls::stencil::*;
 use nhls::util::*;
 use nhls::SolverInterface;
 
@@ -141,3 +142,267 @@ fn heat_2d_ap_compare() {
         );
     }
 }
+
+#[test]
+fn dyn_2d_ap_compare() {
+    // Grid size
+    let grid_bound = AABB::new(matrix![0, 80; 0, 70]);
+
+    let n_steps = 300;
+
+    let chunk_size = 100;
+
+    // Neighborhood size is only known at runtime
+    let offsets = vec![
+        [0, 0],
+        [1, 0],
+        [-1, 0],
+        [0, 1],
+        [0, -1],
+        [1, 1],
+        [-1, -1],
+        [1, -1],
+        [-1, 1],
+    ];
+    let stencil = DynStencil::new(&offsets, |args: &[f64]| {
+        0.4 * args[0]
+            + 0.1 * (args[1] + args[2] + args[3] + args[4])
+            + 0.05 * (args[5] + args[6] + args[7] + args[8])
+    });
+
+    // Create domains
+    let buffer_size = grid_bound.buffer_size();
+    let mut direct_input_domain = OwnedDomain::new(grid_bound);
+    let mut direct_output_domain = OwnedDomain::new(grid_bound);
+    let mut fft_buffer_1 = OwnedDomain::new(grid_bound);
+    let mut fft_buffer_2 = OwnedDomain::new(grid_bound);
+    let mut fft_input_domain = fft_buffer_1.as_slice_domain();
+    let mut fft_output_domain = fft_buffer_2.as_slice_domain();
+
+    // Fill in with IC values (use normal dist for spike in the middle)
+    normal_ic_2d(&mut direct_input_domain, 25.0, chunk_size);
+    normal_ic_2d(&mut fft_input_domain, 25.0, chunk_size);
+
+    // Create BC
+    let bc = ConstantCheck::new(1.0, grid_bound);
+
+    // Create AP Solver
+    let solver_params = SolverParameters {
+        cutoff: 20,
+        chunk_size,
+        threads: TEST_SOLVE_THREADS,
+        aabb: grid_bound,
+        steps: n_steps,
+        ..Default::default()
+    };
+    let direct_solver = DirectFrustrumSolver {
+        bc: &bc,
+        stencil: &stencil,
+        stencil_slopes: stencil.slopes(),
+        chunk_size,
+    };
+
+    let mut fft_solver =
+        generate_ap_solver(&stencil, direct_solver, &solver_params);
+    fft_solver.apply(&mut fft_input_domain, &mut fft_output_domain, 0);
+
+    box_apply(
+        &bc,
+        &stencil,
+        &mut direct_input_domain,
+        &mut direct_output_domain,
+        n_steps,
+        0,
+        chunk_size,
+    );
+
+    for i in 0..buffer_size {
+        assert_approx_eq!(
+            f64,
+            fft_output_domain.buffer()[i],
+            direct_output_domain.buffer()[i],
+            epsilon = 0.000001
+        );
+    }
+}
+
+/// Compare AP solver against `GeneralDirectBoxSolver`
+fn wide_ap_compare<
+    const GRID_DIMENSION: usize,
+    const NEIGHBORHOOD_SIZE: usize,
+>(
+    grid_bound: AABB<GRID_DIMENSION>,
+    stencil: &Stencil<GRID_DIMENSION, NEIGHBORHOOD_SIZE>,
+    n_steps: usize,
+    cutoff: i32,
+    ic: fn(&mut OwnedDomain<GRID_DIMENSION>, f64, usize),
+) {
+    let chunk_size = 100;
+
+    // Create domains
+    let buffer_size = grid_bound.buffer_size();
+    let mut direct_buffer_1 = OwnedDomain::new(grid_bound);
+    let mut direct_buffer_2 = OwnedDomain::new(grid_bound);
+    let mut fft_buffer_1 = OwnedDomain::new(grid_bound);
+    let mut fft_buffer_2 = OwnedDomain::new(grid_bound);
+
+    // Fill in with IC values (use normal dist for spike in the middle)
+    ic(&mut direct_buffer_1, 25.0, chunk_size);
+    ic(&mut fft_buffer_1, 25.0, chunk_size);
+
+    let mut direct_input_domain = direct_buffer_1.as_slice_domain();
+    let mut direct_output_domain = direct_buffer_2.as_slice_domain();
+    let mut fft_input_domain = fft_buffer_1.as_slice_domain();
+    let mut fft_output_domain = fft_buffer_2.as_slice_domain();
+
+    // Create BC
+    let bc = ConstantCheck::new(1.0, grid_bound);
+
+    // Create AP Solver
+    let solver_params = SolverParameters {
+        cutoff,
+        chunk_size,
+        threads: TEST_SOLVE_THREADS,
+        aabb: grid_bound,
+        steps: n_steps,
+        ..Default::default()
+    };
+    let direct_solver = DirectFrustrumSolver {
+        bc: &bc,
+        stencil,
+        stencil_slopes: stencil.slopes(),
+        chunk_size,
+    };
+    let mut fft_solver =
+        generate_ap_solver(stencil, direct_solver, &solver_params);
+    fft_solver.apply(&mut fft_input_domain, &mut fft_output_domain, 0);
+
+    let mut box_solver =
+        GeneralDirectBoxSolver::new(&bc, stencil, n_steps, chunk_size);
+    box_solver.apply(&mut direct_input_domain, &mut direct_output_domain, 0);
+
+    for i in 0..buffer_size {
+        assert_approx_eq!(
+            f64,
+            fft_output_domain.buffer()[i],
+            direct_output_domain.buffer()[i],
+            epsilon = 0.000001
+        );
+    }
+}
+
+#[test]
+fn heat_1d_4th_ap_compare() {
+    let grid_bound = AABB::new(matrix![0, 999]);
+    let stencil = nhls::standard_stencils::heat_1d_4th(1.0, 1.0, 0.2);
+    wide_ap_compare(grid_bound, &stencil, 300, 40, normal_ic_1d);
+}
+
+#[test]
+fn heat_2d_4th_ap_compare() {
+    let grid_bound = AABB::new(matrix![0, 119; 0, 99]);
+    let stencil =
+        nhls::standard_stencils::heat_2d_4th(1.0, 1.0, 1.0, 0.15, 0.1);
+    wide_ap_compare(grid_bound, &stencil, 120, 20, normal_ic_2d);
+}
+
+#[test]
+fn heat_3d_4th_ap_compare() {
+    let grid_bound = AABB::new(matrix![0, 39; 0, 39; 0, 39]);
+    let stencil =
+        nhls::standard_stencils::heat_3d_4th(1.0, 1.0, 1.0, 1.0, 0.1, 0.1, 0.1);
+    wide_ap_compare(grid_bound, &stencil, 30, 10, normal_ic_3d);
+}
+
+#[test]
+fn heat_2d_isotropic_ap_compare() {
+    let grid_bound = AABB::new(matrix![0, 99; 0, 89]);
+    let stencil = nhls::standard_stencils::heat_2d_isotropic(1.0, 1.0, 0.2);
+    wide_ap_compare(grid_bound, &stencil, 200, 20, normal_ic_2d);
+}
+
+#[test]
+fn heat_3d_isotropic_ap_compare() {
+    let grid_bound = AABB::new(matrix![0, 39; 0, 39; 0, 39]);
+    let stencil = nhls::standard_stencils::heat_3d_isotropic(1.0, 1.0, 0.15);
+    wide_ap_compare(grid_bound, &stencil, 40, 10, normal_ic_3d);
+}
+
+#[test]
+fn biharmonic_2d_ap_compare() {
+    let grid_bound = AABB::new(matrix![0, 99; 0, 99]);
+    let stencil = nhls::standard_stencils::biharmonic_2d(1.0, 1.0, 0.02);
+    wide_ap_compare(grid_bound, &stencil, 100, 20, normal_ic_2d);
+}
+
+/// AP solver with the zero boundary optimized direct solver,
+/// wrapped to support a constant boundary,
+/// against `GeneralDirectBoxSolver` with `ConstantCheck`.
+fn constant_bc_ap_compare(stencil: &Stencil<2, 5>, value: f64) {
+    let grid_bound = AABB::new(matrix![0, 119; 10, 109]);
+    let n_steps = 300;
+    let chunk_size = 100;
+
+    // Create domains
+    let buffer_size = grid_bound.buffer_size();
+    let mut direct_buffer_1 = OwnedDomain::new(grid_bound);
+    let mut direct_buffer_2 = OwnedDomain::new(grid_bound);
+    let mut fft_buffer_1 = OwnedDomain::new(grid_bound);
+    let mut fft_buffer_2 = OwnedDomain::new(grid_bound);
+    let mut direct_input_domain = direct_buffer_1.as_slice_domain();
+    let mut direct_output_domain = direct_buffer_2.as_slice_domain();
+    let mut fft_input_domain = fft_buffer_1.as_slice_domain();
+    let mut fft_output_domain = fft_buffer_2.as_slice_domain();
+    normal_ic_2d(&mut direct_input_domain, 25.0, chunk_size);
+    normal_ic_2d(&mut fft_input_domain, 25.0, chunk_size);
+
+    // Create AP Solver
+    let solver_params = SolverParameters {
+        cutoff: 40,
+        chunk_size,
+        threads: TEST_SOLVE_THREADS,
+        aabb: grid_bound,
+        steps: n_steps,
+        ..Default::default()
+    };
+    let direct_solver = DirectSolver5Pt2DOpt::new(stencil);
+    let ap_solver = generate_ap_solver(stencil, direct_solver, &solver_params);
+    let mut fft_solver = ConstantBCSolver::new(
+        ap_solver, stencil, value, grid_bound, n_steps, chunk_size,
+    );
+    fft_solver.apply(&mut fft_input_domain, &mut fft_output_domain, 0);
+
+    let bc = ConstantCheck::new(value, grid_bound);
+    let mut box_solver =
+        GeneralDirectBoxSolver::new(&bc, stencil, n_steps, chunk_size);
+    box_solver.apply(&mut direct_input_domain, &mut direct_output_domain, 0);
+
+    for i in 0..buffer_size {
+        assert_approx_eq!(
+            f64,
+            fft_output_domain.buffer()[i],
+            direct_output_domain.buffer()[i],
+            epsilon = 0.00000001
+        );
+    }
+}
+
+#[test]
+fn heat_2d_constant_bc_ap_compare() {
+    let stencil = nhls::standard_stencils::heat_2d(1.0, 1.0, 1.0, 0.2, 0.2);
+    constant_bc_ap_compare(&stencil, 1.0);
+    constant_bc_ap_compare(&stencil, -2.5);
+}
+
+#[test]
+fn decay_2d_constant_bc_ap_compare() {
+    // Weights don't sum to one, so this needs the correction term
+    let stencil = Stencil::new(
+        [[1, 0], [0, -1], [-1, 0], [0, 1], [0, 0]],
+        |args: &[f64; 5]| {
+            0.2 * (args[0] + args[1] + args[2] + args[3]) + 0.15 * args[4]
+        },
+    );
+    constant_bc_ap_compare(&stencil, 1.0);
+    constant_bc_ap_compare(&stencil, 3.0);
+}
@@ -0,0 +1,76 @@
+use nhls::ap_solver::autotune::*;
+use nhls::ap_solver::*;
+use nhls::direct_solver::*;
+use nhls::domain::*;
+use nhls::stencil::*;
+use nhls::util::*;
+
+pub const TEST_SOLVE_THREADS: usize = 8;
+
+/// Trials build real solvers, the tuned database entry is reused.
+#[test]
+fn heat_2d_autotune() {
+    let grid_bound = AABB::new(matrix![0, 299; 0, 199]);
+    let stencil = nhls::standard_stencils::heat_2d(1.0, 1.0, 1.0, 0.2, 0.2);
+    let bc = ConstantCheck::new(1.0, grid_bound);
+    let chunk_size = 100;
+    let params = SolverParameters {
+        chunk_size,
+        threads: TEST_SOLVE_THREADS,
+        aabb: grid_bound,
+        steps: 100,
+        ..Default::default()
+    };
+    let autotuner = Autotuner {
+        ratios: vec![0.4, 0.6],
+        cutoffs: vec![20, 60],
+        task_mins: vec![1, 4],
+        task_mults: vec![1.0],
+        trials: 2,
+        ..Default::default()
+    };
+    let trial = |candidate: &SolverParameters<2>| {
+        let direct_solver = DirectFrustrumSolver {
+            bc: &bc,
+            stencil: &stencil,
+            stencil_slopes: stencil.slopes(),
+            chunk_size,
+        };
+        let mut solver = generate_ap_solver(&stencil, direct_solver, candidate);
+        time_apply(&mut solver, grid_bound, chunk_size)
+    };
+
+    let path = std::env::temp_dir()
+        .join(format!("nhls_autotune_{}.db", std::process::id()));
+    let tuned = autotuner
+        .tune_cached_with_trials(
+            &path,
+            stencil.slopes(),
+            stencil.neighborhood_size(),
+            &params,
+            trial,
+        )
+        .unwrap();
+    let tuned_values = TunedParameters::from_params(&tuned);
+    assert!(autotuner.candidates().contains(&tuned_values));
+    assert_eq!(tuned.steps, params.steps);
+    assert_eq!(tuned.aabb, params.aabb);
+
+    let database = TuningDatabase::load(&path).unwrap();
+    let key =
+        tuning_key(&stencil.slopes(), stencil.neighborhood_size(), &params);
+    assert_eq!(database.get(&key), Some(tuned_values));
+
+    // Second lookup hits the database, the trial must not run
+    let cached = autotuner
+        .tune_cached_with_trials(
+            &path,
+            stencil.slopes(),
+            stencil.neighborhood_size(),
+            &params,
+            |_: &SolverParameters<2>| -> f64 { panic!("trial ran") },
+        )
+        .unwrap();
+    assert_eq!(TunedParameters::from_params(&cached), tuned_values);
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,92 @@
+use float_cmp::assert_approx_eq;
+use nalgebra::dmatrix;
+use nhls::direct_solver::*;
+use nhls::domain::*;
+use nhls::fft_solver::*;
+use nhls::initial_conditions::normal_impulse::*;
+use nhls::stencil::*;
+use nhls::util::*;
+
+/// Compare periodic FFT solve against direct periodic solve
+/// for a coupled system.
+fn block_compare<const GRID_DIMENSION: usize>(
+    grid_bound: AABB<GRID_DIMENSION>,
+    stencil: &BlockStencil<GRID_DIMENSION>,
+    n_steps: usize,
+    ic: fn(&mut OwnedDomain<GRID_DIMENSION>, f64, usize),
+) {
+    let chunk_size = 100;
+    let fields = stencil.fields();
+
+    // Create domains
+    let buffer_size = grid_bound.buffer_size();
+    let mut direct_input = MultiFieldDomain::new(grid_bound, fields);
+    let mut direct_output = MultiFieldDomain::new(grid_bound, fields);
+    let mut fft_input = MultiFieldDomain::new(grid_bound, fields);
+    let mut fft_output = MultiFieldDomain::new(grid_bound, fields);
+
+    let plan_type = PlanType::Estimate;
+    let mut periodic_solver = BlockPeriodicSolver::create(
+        stencil,
+        fft_output.field_mut(0).buffer_mut(),
+        &grid_bound,
+        n_steps,
+        plan_type,
+        chunk_size,
+        8,
+    );
+
+    // Each species starts with a differently sized spike
+    for f in 0..fields {
+        let variance = 10.0 * (f + 1) as f64;
+        ic(direct_input.field_mut(f), variance, chunk_size);
+        ic(fft_input.field_mut(f), variance, chunk_size);
+    }
+
+    periodic_solver.apply(&mut fft_input, &mut fft_output);
+    block_direct_periodic_apply(
+        stencil,
+        &mut direct_input,
+        &mut direct_output,
+        n_steps,
+        chunk_size,
+    );
+
+    for f in 0..fields {
+        for i in 0..buffer_size {
+            assert_approx_eq!(
+                f64,
+                fft_output.field(f).buffer()[i],
+                direct_output.field(f).buffer()[i],
+                epsilon = 0.000000001
+            );
+        }
+    }
+}
+
+#[test]
+fn reaction_diffusion_1d_p_compare() {
+    let grid_bound = AABB::new(matrix![0, 499]);
+    let mut stencil = BlockStencil::uncoupled(&[
+        nhls::standard_stencils::heat_1d(1.0, 1.0, 0.4),
+        nhls::standard_stencils::heat_1d(1.0, 1.0, 0.2),
+        nhls::standard_stencils::heat_1d(1.0, 1.0, 0.1),
+    ]);
+    stencil.add_coupling(&dmatrix![
+        -0.02, 0.0, 0.01;
+        0.02, -0.01, 0.0;
+        0.0, 0.01, -0.01
+    ]);
+    block_compare(grid_bound, &stencil, 200, normal_ic_1d);
+}
+
+#[test]
+fn reaction_diffusion_2d_p_compare() {
+    let grid_bound = AABB::new(matrix![0, 59; 0, 79]);
+    let mut stencil = BlockStencil::uncoupled(&[
+        nhls::standard_stencils::heat_2d(1.0, 1.0, 1.0, 0.2, 0.2),
+        nhls::standard_stencils::heat_2d(1.0, 1.0, 1.0, 0.1, 0.05),
+    ]);
+    stencil.add_coupling(&dmatrix![-0.05, 0.02; 0.05, -0.02]);
+    block_compare(grid_bound, &stencil, 50, normal_ic_2d);
+}
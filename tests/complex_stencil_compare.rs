@@ -0,0 +1,168 @@
+use nhls::ap_solver::*;
+use nhls::direct_solver::*;
+use nhls::domain::*;
+use nhls::stencil::*;
+use nhls::util::*;
+use nhls::SolverInterface;
+
+pub const TEST_SOLVE_THREADS: usize = 8;
+
+/// Gaussian wave packet centered in `grid_bound`, moving along every axis.
+fn wave_packet<const GRID_DIMENSION: usize>(
+    grid_bound: AABB<GRID_DIMENSION>,
+) -> impl Fn(Coord<GRID_DIMENSION>) -> c64 + Copy + Send + Sync {
+    move |coord: Coord<GRID_DIMENSION>| {
+        let mut r_sq = 0.0;
+        let mut phase = 0.0;
+        for d in 0..GRID_DIMENSION {
+            let center = (grid_bound.bounds[(d, 0)] + grid_bound.bounds[(d, 1)])
+                as f64
+                / 2.0;
+            let x = coord[d] as f64 - center;
+            r_sq += x * x;
+            phase += 0.5 * coord[d] as f64;
+        }
+        c64::from_polar((-r_sq / 50.0).exp(), phase)
+    }
+}
+
+/// max |a - b| / max |b|
+fn relative_error(a: &[c64], b: &[c64]) -> f64 {
+    assert_eq!(a.len(), b.len());
+    let mut max_diff: f64 = 0.0;
+    let mut max_value: f64 = 0.0;
+    for (x, y) in a.iter().zip(b.iter()) {
+        max_diff = max_diff.max((x - y).norm());
+        max_value = max_value.max(y.norm());
+    }
+    max_diff / max_value
+}
+
+/// AP solver with C2C periodic solves against directly applying
+/// the complex stencil, with zero boundaries.
+fn complex_ap_compare<
+    const GRID_DIMENSION: usize,
+    const NEIGHBORHOOD_SIZE: usize,
+>(
+    grid_bound: AABB<GRID_DIMENSION>,
+    stencil: &Stencil<GRID_DIMENSION, NEIGHBORHOOD_SIZE, c64>,
+    n_steps: usize,
+    cutoff: i32,
+) {
+    let chunk_size = 100;
+    let ic = wave_packet(grid_bound);
+    let bc = ConstantCheck::new(0.0, grid_bound);
+
+    let mut direct_input =
+        OwnedDomain::<GRID_DIMENSION, c64>::new_scalar(grid_bound);
+    let mut direct_output =
+        OwnedDomain::<GRID_DIMENSION, c64>::new_scalar(grid_bound);
+    direct_input.par_set_values(ic, chunk_size);
+    box_apply(
+        &bc,
+        stencil,
+        &mut direct_input,
+        &mut direct_output,
+        n_steps,
+        0,
+        chunk_size,
+    );
+
+    let solver_params = SolverParameters {
+        cutoff,
+        chunk_size,
+        threads: TEST_SOLVE_THREADS,
+        aabb: grid_bound,
+        steps: n_steps,
+        ..Default::default()
+    };
+    let direct_solver = DirectFrustrumSolver {
+        bc: &bc,
+        stencil,
+        stencil_slopes: stencil.slopes(),
+        chunk_size,
+    };
+    let mut fft_buffer_1 =
+        OwnedDomain::<GRID_DIMENSION, c64>::new_scalar(grid_bound);
+    let mut fft_buffer_2 =
+        OwnedDomain::<GRID_DIMENSION, c64>::new_scalar(grid_bound);
+    let mut fft_input = fft_buffer_1.as_slice_domain();
+    let mut fft_output = fft_buffer_2.as_slice_domain();
+    fft_input.par_set_values(ic, chunk_size);
+    let mut fft_solver = generate_scalar_ap_solver::<GRID_DIMENSION, c64, _, _>(
+        stencil,
+        direct_solver,
+        &solver_params,
+    );
+    fft_solver.apply(&mut fft_input, &mut fft_output, 0);
+
+    let error = relative_error(fft_output.buffer(), direct_output.buffer());
+    assert!(error < 1e-9, "relative error: {error:e}");
+}
+
+#[test]
+fn schrodinger_1d_compare() {
+    let grid_bound = AABB::new(matrix![0, 999]);
+    let stencil = nhls::standard_stencils::schrodinger_1d(0.05, 1.0, 1.0);
+    complex_ap_compare(grid_bound, &stencil, 200, 20);
+}
+
+#[test]
+fn schrodinger_2d_compare() {
+    let grid_bound = AABB::new(matrix![0, 99; 0, 119]);
+    let stencil = nhls::standard_stencils::schrodinger_2d(0.05, 1.0, 1.0, 1.0);
+    complex_ap_compare(grid_bound, &stencil, 60, 10);
+}
+
+#[test]
+fn schrodinger_1d_plane_wave() {
+    // Plane waves are eigenvectors of the stencil,
+    // each step scales them by the stencil's symbol.
+    let n = 64;
+    let grid_bound = AABB::new(matrix![0, n - 1]);
+    let dt = 0.05;
+    let mass = 1.0;
+    let n_steps = 100;
+    let chunk_size = 100;
+    let stencil = nhls::standard_stencils::schrodinger_1d(dt, 1.0, mass);
+    let k = 2.0 * std::f64::consts::PI * 3.0 / n as f64;
+
+    let solver_params = SolverParameters {
+        cutoff: 20,
+        chunk_size,
+        threads: TEST_SOLVE_THREADS,
+        aabb: grid_bound,
+        steps: n_steps,
+        axis_boundaries: [AxisBoundary::Periodic],
+        ..Default::default()
+    };
+    let bc = WrapCheck::new(grid_bound);
+    let direct_solver = DirectFrustrumSolver {
+        bc: &bc,
+        stencil: &stencil,
+        stencil_slopes: stencil.slopes(),
+        chunk_size,
+    };
+    let mut buffer_1 = OwnedDomain::<1, c64>::new_scalar(grid_bound);
+    let mut buffer_2 = OwnedDomain::<1, c64>::new_scalar(grid_bound);
+    let mut input = buffer_1.as_slice_domain();
+    let mut output = buffer_2.as_slice_domain();
+    input.par_set_values(
+        |coord: Coord<1>| c64::from_polar(1.0, k * coord[0] as f64),
+        chunk_size,
+    );
+    let mut solver = generate_scalar_ap_solver::<1, c64, _, _>(
+        &stencil,
+        direct_solver,
+        &solver_params,
+    );
+    solver.apply(&mut input, &mut output, 0);
+
+    let r = dt / (2.0 * mass);
+    let symbol = c64::new(1.0, r * (2.0 * k.cos() - 2.0));
+    let scale = symbol.powu(n_steps as u32);
+    for (i, value) in output.buffer().iter().enumerate() {
+        let expected = scale * c64::from_polar(1.0, k * i as f64);
+        assert!((value - expected).norm() < 1e-9);
+    }
+}
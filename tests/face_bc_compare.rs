@@ -0,0 +1,83 @@
+use float_cmp::assert_approx_eq;
+use nhls::direct_solver::*;
+use nhls::domain::*;
+use nhls::util::*;
+use nhls::SolverInterface;
+
+/// Periodic in y with initial conditions that only depend on x,
+/// so every row of the 2D solve should match the 1D solve
+/// with the same boundary conditions in x.
+#[test]
+fn face_bc_2d_matches_1d() {
+    let n_steps = 200;
+    let chunk_size = 100;
+    let bound_1d = AABB::new(matrix![0, 99]);
+    let bound_2d = AABB::new(matrix![0, 99; 0, 29]);
+    let stencil_1d = nhls::standard_stencils::heat_1d(1.0, 1.0, 0.2);
+    let stencil_2d = nhls::standard_stencils::heat_2d(1.0, 1.0, 1.0, 0.2, 0.15);
+
+    // Heated wall at x min, insulated at x max
+    let bc_1d = FaceBC::new(bound_1d, ConstantCheck::new(1.0, bound_1d))
+        .with_face(0, 1, ReflectCheck::new(bound_1d));
+    let bc_2d = FaceBC::new(bound_2d, ConstantCheck::new(1.0, bound_2d))
+        .with_face(0, 1, ReflectCheck::new(bound_2d))
+        .with_axis(1, WrapCheck::new(bound_2d));
+
+    let ic = |x: i32| (x as f64 * 0.1).sin();
+
+    let mut buffer_1d_1 = OwnedDomain::new(bound_1d);
+    let mut buffer_1d_2 = OwnedDomain::new(bound_1d);
+    buffer_1d_1.par_set_values(|coord: Coord<1>| ic(coord[0]), chunk_size);
+    let mut input_1d = buffer_1d_1.as_slice_domain();
+    let mut output_1d = buffer_1d_2.as_slice_domain();
+    let mut solver_1d =
+        GeneralDirectBoxSolver::new(&bc_1d, &stencil_1d, n_steps, chunk_size);
+    solver_1d.apply(&mut input_1d, &mut output_1d, 0);
+
+    let mut buffer_2d_1 = OwnedDomain::new(bound_2d);
+    let mut buffer_2d_2 = OwnedDomain::new(bound_2d);
+    buffer_2d_1.par_set_values(|coord: Coord<2>| ic(coord[0]), chunk_size);
+    let mut input_2d = buffer_2d_1.as_slice_domain();
+    let mut output_2d = buffer_2d_2.as_slice_domain();
+    let mut solver_2d =
+        GeneralDirectBoxSolver::new(&bc_2d, &stencil_2d, n_steps, chunk_size);
+    solver_2d.apply(&mut input_2d, &mut output_2d, 0);
+
+    for coord in bound_2d.coord_iter() {
+        assert_approx_eq!(
+            f64,
+            output_2d.view(&coord),
+            output_1d.view(&vector![coord[0]]),
+            epsilon = 0.000000001
+        );
+    }
+}
+
+/// Using the same boundary condition on every face
+/// is the same as using it directly.
+#[test]
+fn face_bc_uniform() {
+    let n_steps = 50;
+    let chunk_size = 100;
+    let bound = AABB::new(matrix![0, 39; 0, 29]);
+    let stencil = nhls::standard_stencils::heat_2d(1.0, 1.0, 1.0, 0.2, 0.2);
+    let bc = ConstantCheck::new(0.5, bound);
+    let face_bc = FaceBC::new(bound, ConstantCheck::new(0.5, bound))
+        .with_face(0, 0, ConstantCheck::new(0.5, bound))
+        .with_axis(1, ConstantCheck::new(0.5, bound));
+
+    let ic = |coord: Coord<2>| (coord[0] * coord[1]) as f64 * 0.01;
+    let mut a_in = OwnedDomain::new(bound);
+    let mut a_out = OwnedDomain::new(bound);
+    let mut b_in = OwnedDomain::new(bound);
+    let mut b_out = OwnedDomain::new(bound);
+    a_in.par_set_values(ic, chunk_size);
+    b_in.par_set_values(ic, chunk_size);
+    box_apply(&bc, &stencil, &mut a_in, &mut a_out, n_steps, 0, chunk_size);
+    box_apply(
+        &face_bc, &stencil, &mut b_in, &mut b_out, n_steps, 0, chunk_size,
+    );
+    for i in 0..bound.buffer_size() {
+        assert_approx_eq!(f64, a_out.buffer()[i], b_out.buffer()[i]);
+    }
+}
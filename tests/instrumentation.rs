@@ -0,0 +1,86 @@
+use nhls::ap_solver::*;
+use nhls::direct_solver::*;
+use nhls::domain::*;
+use nhls::util::*;
+use nhls::SolverInterface;
+
+pub const TEST_SOLVE_THREADS: usize = 8;
+
+/// Two applies of an AP solve of a 2D heat stencil,
+/// writing instrumentation records to `instrument`.
+fn heat_2d_solve(instrument: Option<&std::path::Path>) -> Vec<f64> {
+    let grid_bound = AABB::new(matrix![0, 199; 0, 149]);
+    let stencil = nhls::standard_stencils::heat_2d(1.0, 1.0, 1.0, 0.2, 0.2);
+    let bc = ConstantCheck::new(1.0, grid_bound);
+    let chunk_size = 100;
+    let solver_params = SolverParameters {
+        cutoff: 20,
+        chunk_size,
+        threads: TEST_SOLVE_THREADS,
+        aabb: grid_bound,
+        steps: 130,
+        ..Default::default()
+    };
+    let direct_solver = DirectFrustrumSolver {
+        bc: &bc,
+        stencil: &stencil,
+        stencil_slopes: stencil.slopes(),
+        chunk_size,
+    };
+    let mut solver =
+        generate_ap_solver(&stencil, direct_solver, &solver_params);
+    if instrument.is_some() {
+        solver.enable_instrumentation();
+    }
+
+    let mut buffer_1 = OwnedDomain::new(grid_bound);
+    let mut buffer_2 = OwnedDomain::new(grid_bound);
+    let mut input = buffer_1.as_slice_domain();
+    let mut output = buffer_2.as_slice_domain();
+    input.par_set_values(
+        |c: Coord<2>| ((7 * c[0] + 13 * c[1]) % 64) as f64,
+        chunk_size,
+    );
+    solver.apply(&mut input, &mut output, 0);
+    std::mem::swap(&mut input, &mut output);
+    solver.apply(&mut input, &mut output, solver_params.steps);
+
+    if let Some(path) = instrument {
+        solver.write_instrumentation(&path);
+        solver.to_dot_file(&path.with_extension("dot"));
+    }
+    output.buffer().to_vec()
+}
+
+#[test]
+fn heat_2d_instrumentation() {
+    let path = std::env::temp_dir()
+        .join(format!("nhls_instrumentation_{}", std::process::id()));
+    let expected = heat_2d_solve(None);
+    let instrumented = heat_2d_solve(Some(&path));
+    assert_eq!(instrumented, expected);
+
+    let json = std::fs::read_to_string(path.with_extension("json")).unwrap();
+    assert!(json.contains("\"applies\": 2,"));
+    assert!(json.contains("\"kind\": \"periodic\""));
+    assert!(json.contains("\"kind\": \"direct\""));
+    assert!(json.contains("\"by_depth\""));
+
+    // Every node of the plan runs at least once per apply
+    let csv = std::fs::read_to_string(path.with_extension("csv")).unwrap();
+    let mut lines = csv.lines();
+    assert!(lines.next().unwrap().starts_with("node,kind,depth,threads"));
+    for line in lines {
+        let fields: Vec<&str> = line.split(',').collect();
+        let calls: usize = fields[4].parse().unwrap();
+        assert!(calls >= 2, "{line}");
+    }
+
+    let dot = std::fs::read_to_string(path.with_extension("dot")).unwrap();
+    assert!(dot.contains("fillcolor"));
+    assert!(dot.contains("calls: 2"));
+
+    for extension in ["json", "csv", "dot"] {
+        std::fs::remove_file(path.with_extension(extension)).unwrap();
+    }
+}
@@ -0,0 +1,135 @@
+use nhls::ap_solver::ap_periodic_ops_builder::*;
+use nhls::ap_solver::generate_plan::*;
+use nhls::ap_solver::scratch_builder::*;
+use nhls::ap_solver::solver::*;
+use nhls::ap_solver::*;
+use nhls::direct_solver::*;
+use nhls::domain::*;
+use nhls::initial_conditions::rand::*;
+use nhls::util::*;
+use nhls::SolverInterface;
+
+pub const TEST_SOLVE_THREADS: usize = 8;
+
+/// Same AP solve over `OwnedDomain`s and `MappedDomain`s,
+/// results must match exactly.
+#[test]
+fn heat_3d_ap_mapped() {
+    let grid_bound = AABB::new(matrix![0, 59; 0, 59; 0, 59]);
+    let stencil =
+        nhls::standard_stencils::heat_3d(1.0, 1.0, 1.0, 1.0, 0.1, 0.1, 0.1);
+    let bc = ConstantCheck::new(1.0, grid_bound);
+    let chunk_size = 100;
+    let n_steps = 40;
+    let solver_params = SolverParameters {
+        cutoff: 10,
+        chunk_size,
+        threads: TEST_SOLVE_THREADS,
+        aabb: grid_bound,
+        steps: n_steps,
+        ..Default::default()
+    };
+    let create_solver = || {
+        let direct_solver = DirectFrustrumSolver {
+            bc: &bc,
+            stencil: &stencil,
+            stencil_slopes: stencil.slopes(),
+            chunk_size,
+        };
+        generate_ap_solver(&stencil, direct_solver, &solver_params)
+    };
+
+    let mut owned_buffer_1 = OwnedDomain::new(grid_bound);
+    let mut owned_buffer_2 = OwnedDomain::new(grid_bound);
+    let mut owned_input = owned_buffer_1.as_slice_domain();
+    let mut owned_output = owned_buffer_2.as_slice_domain();
+    rand_ic(&mut owned_input, 1024, chunk_size);
+
+    let dir = std::env::temp_dir();
+    let mut mapped_buffer_1 =
+        MappedDomain::create_in(&dir, grid_bound).unwrap();
+    let mut mapped_buffer_2 =
+        MappedDomain::create_in(&dir, grid_bound).unwrap();
+    {
+        let mut mapped_input = mapped_buffer_1.as_slice_domain();
+        let mut mapped_output = mapped_buffer_2.as_slice_domain();
+        mapped_input
+            .buffer_mut()
+            .copy_from_slice(owned_input.buffer());
+
+        create_solver().apply(&mut owned_input, &mut owned_output, 0);
+        create_solver().apply(&mut mapped_input, &mut mapped_output, 0);
+        assert_eq!(owned_output.buffer(), mapped_output.buffer());
+    }
+
+    for domain in [&mapped_buffer_1, &mapped_buffer_2] {
+        assert_eq!(domain.mapped_bytes(), grid_bound.buffer_size() * 8);
+        assert!(domain.resident_bytes() <= domain.mapped_bytes());
+    }
+    mapped_buffer_1.print_report("input", None);
+}
+
+/// Same AP solve with the scratch on the heap and in a file.
+#[test]
+fn heat_2d_ap_mapped_scratch() {
+    let grid_bound = AABB::new(matrix![0, 199; 0, 199]);
+    let stencil = nhls::standard_stencils::heat_2d(1.0, 1.0, 1.0, 0.2, 0.2);
+    let bc = ConstantCheck::new(1.0, grid_bound);
+    let chunk_size = 100;
+    let mut solver_params = SolverParameters {
+        cutoff: 20,
+        chunk_size,
+        threads: TEST_SOLVE_THREADS,
+        aabb: grid_bound,
+        steps: 100,
+        ..Default::default()
+    };
+    let create_solver = |params: &SolverParameters<2>| {
+        let direct_solver = DirectFrustrumSolver {
+            bc: &bc,
+            stencil: &stencil,
+            stencil_slopes: stencil.slopes(),
+            chunk_size,
+        };
+        let planner_result = generate_plan(
+            stencil.slopes(),
+            || ApPeriodicOpsBuilder::new(&stencil, params),
+            params,
+        );
+        Solver::new(
+            direct_solver,
+            params,
+            planner_result,
+            ComplexBufferType::DomainOnly,
+        )
+    };
+
+    let mut heap_buffer_1 = OwnedDomain::new(grid_bound);
+    let mut heap_buffer_2 = OwnedDomain::new(grid_bound);
+    let mut heap_input = heap_buffer_1.as_slice_domain();
+    let mut heap_output = heap_buffer_2.as_slice_domain();
+    rand_ic(&mut heap_input, 1024, chunk_size);
+
+    let mut mapped_buffer_1 =
+        MappedDomain::create_in(std::env::temp_dir(), grid_bound).unwrap();
+    let mut mapped_buffer_2 = OwnedDomain::new(grid_bound);
+    let mut mapped_input = mapped_buffer_1.as_slice_domain();
+    let mut mapped_output = mapped_buffer_2.as_slice_domain();
+    mapped_input
+        .buffer_mut()
+        .copy_from_slice(heap_input.buffer());
+
+    let mut heap_solver = create_solver(&solver_params);
+    heap_solver.apply(&mut heap_input, &mut heap_output, 0);
+    assert!(!heap_solver.scratch_space.is_mapped());
+
+    solver_params.scratch_dir = Some(std::env::temp_dir());
+    let mut mapped_solver = create_solver(&solver_params);
+    mapped_solver.apply(&mut mapped_input, &mut mapped_output, 0);
+    assert!(mapped_solver.scratch_space.is_mapped());
+    assert_eq!(heap_output.buffer(), mapped_output.buffer());
+
+    let scratch = &mapped_solver.scratch_space;
+    assert!(scratch.resident_bytes().unwrap() <= scratch.size);
+    mapped_buffer_1.print_report("input", Some(scratch));
+}
@@ -0,0 +1,218 @@
+use float_cmp::assert_approx_eq;
+use nhls::ap_solver::ap_periodic_ops_builder::*;
+use nhls::ap_solver::generate_plan::*;
+use nhls::ap_solver::plan::*;
+use nhls::ap_solver::*;
+use nhls::direct_solver::*;
+use nhls::domain::*;
+use nhls::initial_conditions::normal_impulse::*;
+use nhls::util::*;
+use nhls::SolverInterface;
+
+pub const TEST_SOLVE_THREADS: usize = 8;
+
+/// Solve around obstacles with `GeneralDirectBoxSolver`
+/// and the masked AP solver, check that they agree.
+/// Returns the total of the output.
+fn obstacle_compare(
+    grid_bound: AABB<2>,
+    obstacles: &[AABB<2>],
+    obstacle_bc: ObstacleBC,
+    n_steps: usize,
+    cutoff: i32,
+) -> (f64, f64) {
+    let chunk_size = 100;
+    let stencil = nhls::standard_stencils::heat_2d(1.0, 1.0, 1.0, 0.2, 0.2);
+    let mask = Mask::from_aabbs(grid_bound, obstacles);
+    let bc =
+        MaskedCheck::new(ReflectCheck::new(grid_bound), &mask, obstacle_bc);
+
+    // Create domains, solid cells start out the same as the solver sets them
+    let buffer_size = grid_bound.buffer_size();
+    let mut direct_buffer_1 = OwnedDomain::new(grid_bound);
+    let mut direct_buffer_2 = OwnedDomain::new(grid_bound);
+    let mut fft_buffer_1 = OwnedDomain::new(grid_bound);
+    let mut fft_buffer_2 = OwnedDomain::new(grid_bound);
+    normal_ic_2d(&mut direct_buffer_1, 20.0, chunk_size);
+    for coord in grid_bound.coord_iter() {
+        if let Some(value) = bc.check_solid(&coord, 0) {
+            direct_buffer_1.set_coord(&coord, value);
+        }
+    }
+    fft_buffer_1
+        .buffer_mut()
+        .copy_from_slice(direct_buffer_1.buffer());
+    let initial_total: f64 = direct_buffer_1.buffer().iter().sum();
+
+    let mut direct_input_domain = direct_buffer_1.as_slice_domain();
+    let mut direct_output_domain = direct_buffer_2.as_slice_domain();
+    let mut fft_input_domain = fft_buffer_1.as_slice_domain();
+    let mut fft_output_domain = fft_buffer_2.as_slice_domain();
+
+    let solver_params = SolverParameters {
+        cutoff,
+        chunk_size,
+        threads: TEST_SOLVE_THREADS,
+        aabb: grid_bound,
+        steps: n_steps,
+        ..Default::default()
+    };
+    let direct_solver = DirectFrustrumSolver {
+        bc: &bc,
+        stencil: &stencil,
+        stencil_slopes: stencil.slopes(),
+        chunk_size,
+    };
+    let mut fft_solver = generate_masked_ap_solver(
+        &stencil,
+        &mask,
+        direct_solver,
+        &solver_params,
+    );
+    fft_solver.apply(&mut fft_input_domain, &mut fft_output_domain, 0);
+
+    let mut box_solver =
+        GeneralDirectBoxSolver::new(&bc, &stencil, n_steps, chunk_size);
+    box_solver.apply(&mut direct_input_domain, &mut direct_output_domain, 0);
+
+    for i in 0..buffer_size {
+        assert_approx_eq!(
+            f64,
+            fft_output_domain.buffer()[i],
+            direct_output_domain.buffer()[i],
+            epsilon = 0.000001
+        );
+    }
+    for coord in grid_bound.coord_iter() {
+        if let Some(value) = bc.check_solid(&coord, 0) {
+            assert_eq!(direct_output_domain.view(&coord), value);
+        }
+    }
+    (initial_total, direct_output_domain.buffer().iter().sum())
+}
+
+#[test]
+fn obstacle_zero_flux() {
+    let grid_bound = AABB::new(matrix![0, 199; 0, 149]);
+    let obstacles = [
+        AABB::new(matrix![20, 39; 100, 109]),
+        AABB::new(matrix![150, 151; 0, 80]),
+    ];
+    let (initial, result) =
+        obstacle_compare(grid_bound, &obstacles, ObstacleBC::ZeroFlux, 120, 20);
+    assert_approx_eq!(f64, result, initial, epsilon = 0.0000001);
+}
+
+#[test]
+fn obstacle_dirichlet() {
+    let grid_bound = AABB::new(matrix![0, 149; 0, 149]);
+    let obstacles = [AABB::new(matrix![90, 99; 60, 89])];
+    obstacle_compare(
+        grid_bound,
+        &obstacles,
+        ObstacleBC::Dirichlet(0.5),
+        100,
+        20,
+    );
+}
+
+#[test]
+fn obstacle_optimized_direct() {
+    // The optimized solver only sees obstacles through with_bc
+    let grid_bound = AABB::new(matrix![0, 59; 0, 49]);
+    let n_steps = 40;
+    let chunk_size = 100;
+    let stencil = nhls::standard_stencils::heat_2d(1.0, 1.0, 1.0, 0.2, 0.2);
+    let mask = Mask::from_aabbs(
+        grid_bound,
+        &[
+            AABB::new(matrix![20, 29; 10, 14]),
+            AABB::new(matrix![0, 4; 40, 49]),
+        ],
+    );
+    for obstacle_bc in [ObstacleBC::ZeroFlux, ObstacleBC::Dirichlet(0.5)] {
+        let bc = MaskedCheck::new(
+            ConstantCheck::new(0.25, grid_bound),
+            &mask,
+            obstacle_bc,
+        );
+
+        let mut opt_buffer_1 = OwnedDomain::new(grid_bound);
+        let mut opt_buffer_2 = OwnedDomain::new(grid_bound);
+        let mut naive_buffer_1 = OwnedDomain::new(grid_bound);
+        let mut naive_buffer_2 = OwnedDomain::new(grid_bound);
+        normal_ic_2d(&mut opt_buffer_1, 10.0, chunk_size);
+        naive_buffer_1
+            .buffer_mut()
+            .copy_from_slice(opt_buffer_1.buffer());
+        let mut opt_in = opt_buffer_1.as_slice_domain();
+        let mut opt_out = opt_buffer_2.as_slice_domain();
+        let mut naive_in = naive_buffer_1.as_slice_domain();
+        let mut naive_out = naive_buffer_2.as_slice_domain();
+
+        let mut opt_solver = Direct5Pt2DSolver::new(
+            &stencil,
+            n_steps,
+            TEST_SOLVE_THREADS,
+            chunk_size,
+        )
+        .with_bc(&bc);
+        opt_solver.apply(&mut opt_in, &mut opt_out, 0);
+
+        let mut box_solver =
+            GeneralDirectBoxSolver::new(&bc, &stencil, n_steps, chunk_size);
+        box_solver.apply(&mut naive_in, &mut naive_out, 0);
+
+        for i in 0..grid_bound.buffer_size() {
+            assert_approx_eq!(
+                f64,
+                opt_out.buffer()[i],
+                naive_out.buffer()[i],
+                epsilon = 0.000000001
+            );
+        }
+    }
+}
+
+#[test]
+fn obstacle_plan_is_clear() {
+    // Periodic solves must never read an obstacle
+    let grid_bound = AABB::new(matrix![0, 299; 0, 199]);
+    let mask = Mask::from_aabbs(
+        grid_bound,
+        &[
+            AABB::new(matrix![140, 159; 90, 109]),
+            AABB::new(matrix![10, 12; 0, 199]),
+        ],
+    );
+    let stencil = nhls::standard_stencils::heat_2d(1.0, 1.0, 1.0, 0.2, 0.2);
+    let params = SolverParameters {
+        cutoff: 20,
+        aabb: grid_bound,
+        steps: 200,
+        ..Default::default()
+    };
+    let is_clear = |aabb: &AABB<2>| mask.is_clear(aabb);
+    let result = generate_inhomogeneous_plan(
+        stencil.slopes(),
+        &is_clear,
+        || ApPeriodicOpsBuilder::new(&stencil, &params),
+        &params,
+    );
+
+    let mut n_periodic = 0;
+    for node in result.plan.nodes.iter() {
+        if let PlanNode::PeriodicSolve(periodic_node) = node {
+            n_periodic += 1;
+            if periodic_node.time_cut.is_none()
+                && periodic_node.input_aabb == grid_bound
+            {
+                // Central solve, only its output has to be clear
+                assert!(mask.is_clear(&periodic_node.output_aabb));
+            } else {
+                assert!(mask.is_clear(&periodic_node.input_aabb));
+            }
+        }
+    }
+    assert!(n_periodic > 0);
+}
@@ -0,0 +1,226 @@
+use float_cmp::assert_approx_eq;
+use nhls::ap_solver::ap_periodic_ops_builder::*;
+use nhls::ap_solver::generate_plan::*;
+use nhls::ap_solver::plan::*;
+use nhls::ap_solver::*;
+use nhls::direct_solver::*;
+use nhls::domain::*;
+use nhls::initial_conditions::normal_impulse::*;
+use nhls::initial_conditions::rand::*;
+use nhls::stencil::*;
+use nhls::util::*;
+use nhls::SolverInterface;
+
+pub const TEST_SOLVE_THREADS: usize = 8;
+
+/// AP solver with some periodic axes against `GeneralDirectBoxSolver`
+/// using the same wrapping boundary condition.
+fn periodic_axes_compare<
+    const GRID_DIMENSION: usize,
+    const NEIGHBORHOOD_SIZE: usize,
+>(
+    grid_bound: AABB<GRID_DIMENSION>,
+    axis_boundaries: [AxisBoundary; GRID_DIMENSION],
+    stencil: &Stencil<GRID_DIMENSION, NEIGHBORHOOD_SIZE>,
+    n_steps: usize,
+    cutoff: i32,
+) {
+    let chunk_size = 100;
+
+    // Create domains
+    let buffer_size = grid_bound.buffer_size();
+    let mut direct_buffer_1 = OwnedDomain::new(grid_bound);
+    let mut direct_buffer_2 = OwnedDomain::new(grid_bound);
+    let mut fft_buffer_1 = OwnedDomain::new(grid_bound);
+    let mut fft_buffer_2 = OwnedDomain::new(grid_bound);
+    let mut direct_input_domain = direct_buffer_1.as_slice_domain();
+    let mut direct_output_domain = direct_buffer_2.as_slice_domain();
+    let mut fft_input_domain = fft_buffer_1.as_slice_domain();
+    let mut fft_output_domain = fft_buffer_2.as_slice_domain();
+    rand_ic(&mut direct_input_domain, 1024, chunk_size);
+    fft_input_domain
+        .buffer_mut()
+        .copy_from_slice(direct_input_domain.buffer());
+
+    let solver_params = SolverParameters {
+        cutoff,
+        chunk_size,
+        threads: TEST_SOLVE_THREADS,
+        aabb: grid_bound,
+        steps: n_steps,
+        axis_boundaries,
+        ..Default::default()
+    };
+    let bc = solver_params.axis_bc(ConstantCheck::new(1.0, grid_bound));
+
+    let direct_solver = DirectFrustrumSolver {
+        bc: &bc,
+        stencil,
+        stencil_slopes: stencil.slopes(),
+        chunk_size,
+    };
+    let mut fft_solver =
+        generate_ap_solver(stencil, direct_solver, &solver_params);
+    fft_solver.apply(&mut fft_input_domain, &mut fft_output_domain, 0);
+
+    let mut box_solver =
+        GeneralDirectBoxSolver::new(&bc, stencil, n_steps, chunk_size);
+    box_solver.apply(&mut direct_input_domain, &mut direct_output_domain, 0);
+
+    for i in 0..buffer_size {
+        assert_approx_eq!(
+            f64,
+            fft_output_domain.buffer()[i],
+            direct_output_domain.buffer()[i],
+            epsilon = 0.000000001
+        );
+    }
+}
+
+#[test]
+fn heat_2d_periodic_y_compare() {
+    let grid_bound = AABB::new(matrix![0, 199; 0, 63]);
+    let stencil = nhls::standard_stencils::heat_2d(1.0, 1.0, 1.0, 0.2, 0.2);
+    periodic_axes_compare(
+        grid_bound,
+        [AxisBoundary::Aperiodic, AxisBoundary::Periodic],
+        &stencil,
+        300,
+        20,
+    );
+}
+
+#[test]
+fn heat_2d_periodic_x_compare() {
+    let grid_bound = AABB::new(matrix![0, 63; 0, 149]);
+    let stencil = nhls::standard_stencils::heat_2d(1.0, 1.0, 1.0, 0.2, 0.2);
+    periodic_axes_compare(
+        grid_bound,
+        [AxisBoundary::Periodic, AxisBoundary::Aperiodic],
+        &stencil,
+        200,
+        20,
+    );
+}
+
+#[test]
+fn heat_3d_periodic_y_compare() {
+    let grid_bound = AABB::new(matrix![0, 49; 0, 31; 0, 59]);
+    let stencil =
+        nhls::standard_stencils::heat_3d(1.0, 1.0, 1.0, 1.0, 0.1, 0.1, 0.1);
+    periodic_axes_compare(
+        grid_bound,
+        [
+            AxisBoundary::Aperiodic,
+            AxisBoundary::Periodic,
+            AxisBoundary::Aperiodic,
+        ],
+        &stencil,
+        60,
+        10,
+    );
+}
+
+#[test]
+fn heat_1d_periodic_compare() {
+    let grid_bound = AABB::new(matrix![0, 99]);
+    let stencil = nhls::standard_stencils::heat_1d(1.0, 1.0, 0.4);
+    periodic_axes_compare(
+        grid_bound,
+        [AxisBoundary::Periodic],
+        &stencil,
+        150,
+        20,
+    );
+}
+
+#[test]
+fn direct_opt_5pt2d_periodic_y_compare() {
+    let grid_bound = AABB::new(matrix![0, 159; 0, 47]);
+    let n_steps = 200;
+    let chunk_size = 100;
+    let stencil = nhls::standard_stencils::heat_2d(1.0, 1.0, 1.0, 0.2, 0.2);
+
+    let mut direct_input_domain = OwnedDomain::new(grid_bound);
+    let mut direct_output_domain = OwnedDomain::new(grid_bound);
+    let mut fft_buffer_1 = OwnedDomain::new(grid_bound);
+    let mut fft_buffer_2 = OwnedDomain::new(grid_bound);
+    let mut fft_input_domain = fft_buffer_1.as_slice_domain();
+    let mut fft_output_domain = fft_buffer_2.as_slice_domain();
+    normal_ic_2d(&mut direct_input_domain, 25.0, chunk_size);
+    normal_ic_2d(&mut fft_input_domain, 25.0, chunk_size);
+
+    let solver_params = SolverParameters {
+        cutoff: 20,
+        chunk_size,
+        threads: TEST_SOLVE_THREADS,
+        aabb: grid_bound,
+        steps: n_steps,
+        axis_boundaries: [AxisBoundary::Aperiodic, AxisBoundary::Periodic],
+        ..Default::default()
+    };
+    let bc = solver_params.axis_bc(ConstantCheck::new(0.5, grid_bound));
+    let direct_solver = DirectSolver5Pt2DOpt::new(&stencil).with_bc(&bc);
+    let mut fft_solver =
+        generate_ap_solver(&stencil, direct_solver, &solver_params);
+    fft_solver.apply(&mut fft_input_domain, &mut fft_output_domain, 0);
+
+    box_apply(
+        &bc,
+        &stencil,
+        &mut direct_input_domain,
+        &mut direct_output_domain,
+        n_steps,
+        0,
+        chunk_size,
+    );
+
+    for i in 0..grid_bound.buffer_size() {
+        assert_approx_eq!(
+            f64,
+            fft_output_domain.buffer()[i],
+            direct_output_domain.buffer()[i],
+            epsilon = 0.000000001
+        );
+    }
+}
+
+#[test]
+fn periodic_axes_plan() {
+    // Periodic axes are never cut, so plans are smaller
+    let grid_bound = AABB::new(matrix![0, 299; 0, 199]);
+    let stencil = nhls::standard_stencils::heat_2d(1.0, 1.0, 1.0, 0.2, 0.2);
+    let plan_nodes = |axis_boundaries: [AxisBoundary; 2]| {
+        let params = SolverParameters {
+            cutoff: 20,
+            aabb: grid_bound,
+            steps: 400,
+            axis_boundaries,
+            ..Default::default()
+        };
+        let result = generate_plan(
+            stencil.slopes(),
+            || ApPeriodicOpsBuilder::new(&stencil, &params),
+            &params,
+        );
+        result.plan.nodes
+    };
+
+    let aperiodic = plan_nodes([AxisBoundary::Aperiodic; 2]);
+    let periodic =
+        plan_nodes([AxisBoundary::Aperiodic, AxisBoundary::Periodic]);
+    assert!(periodic.len() < aperiodic.len());
+    for node in periodic.iter() {
+        let input_aabb = match node {
+            PlanNode::PeriodicSolve(p) => p.input_aabb,
+            PlanNode::DirectSolve(d) => {
+                assert_eq!(d.sloped_sides[(1, 0)], 0);
+                assert_eq!(d.sloped_sides[(1, 1)], 0);
+                d.input_aabb
+            }
+            _ => continue,
+        };
+        assert_eq!(input_aabb.bounds[(1, 0)], 0);
+        assert_eq!(input_aabb.bounds[(1, 1)], 199);
+    }
+}
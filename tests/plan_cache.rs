@@ -0,0 +1,92 @@
+use nhls::ap_solver::plan_file::*;
+use nhls::ap_solver::*;
+use nhls::direct_solver::*;
+use nhls::domain::*;
+use nhls::util::*;
+use nhls::SolverInterface;
+use std::path::Path;
+
+pub const TEST_SOLVE_THREADS: usize = 8;
+
+fn plan_cache_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir()
+        .join(format!("nhls_{name}_{}.plan", std::process::id()))
+}
+
+/// AP solve of a 2D heat stencil, `plan_cache` may be `None`.
+fn heat_2d_solve(n_steps: usize, plan_cache: Option<&Path>) -> Vec<f64> {
+    let grid_bound = AABB::new(matrix![0, 199; 0, 149]);
+    let stencil = nhls::standard_stencils::heat_2d(1.0, 1.0, 1.0, 0.2, 0.2);
+    let bc = ConstantCheck::new(1.0, grid_bound);
+    let chunk_size = 100;
+    let solver_params = SolverParameters {
+        cutoff: 20,
+        chunk_size,
+        threads: TEST_SOLVE_THREADS,
+        aabb: grid_bound,
+        steps: n_steps,
+        plan_cache: plan_cache.map(Path::to_path_buf),
+        ..Default::default()
+    };
+    let direct_solver = DirectFrustrumSolver {
+        bc: &bc,
+        stencil: &stencil,
+        stencil_slopes: stencil.slopes(),
+        chunk_size,
+    };
+    let mut solver =
+        generate_ap_solver(&stencil, direct_solver, &solver_params);
+
+    let mut buffer_1 = OwnedDomain::new(grid_bound);
+    let mut buffer_2 = OwnedDomain::new(grid_bound);
+    let mut input = buffer_1.as_slice_domain();
+    let mut output = buffer_2.as_slice_domain();
+    input.par_set_values(
+        |c: Coord<2>| ((7 * c[0] + 13 * c[1]) % 64) as f64,
+        chunk_size,
+    );
+    solver.apply(&mut input, &mut output, 0);
+    output.buffer().to_vec()
+}
+
+#[test]
+fn heat_2d_plan_cache() {
+    let path = plan_cache_path("heat_2d_plan_cache");
+    let n_steps = 130;
+    let expected = heat_2d_solve(n_steps, None);
+
+    // First run plans and writes the file
+    assert!(!path.exists());
+    let planned = heat_2d_solve(n_steps, Some(&path));
+    assert_eq!(planned, expected);
+    let saved = SavedPlan::<2>::load(&path).unwrap();
+    assert_eq!(saved.key.steps, n_steps);
+    assert!(!saved.op_descriptors.is_empty());
+
+    // Second run loads it
+    let loaded = heat_2d_solve(n_steps, Some(&path));
+    assert_eq!(loaded, expected);
+    assert_eq!(SavedPlan::<2>::load(&path).unwrap(), saved);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn plan_cache_mismatch() {
+    let path = plan_cache_path("plan_cache_mismatch");
+    heat_2d_solve(40, Some(&path));
+
+    // Different parameters replan and replace the file
+    let expected = heat_2d_solve(70, None);
+    let replanned = heat_2d_solve(70, Some(&path));
+    assert_eq!(replanned, expected);
+    assert_eq!(SavedPlan::<2>::load(&path).unwrap().key.steps, 70);
+
+    // So do damaged files
+    std::fs::write(&path, "nhls-plan 1\ndimension 2\nroot 3\n").unwrap();
+    let replanned = heat_2d_solve(70, Some(&path));
+    assert_eq!(replanned, expected);
+    assert!(SavedPlan::<2>::load(&path).is_ok());
+
+    std::fs::remove_file(&path).unwrap();
+}
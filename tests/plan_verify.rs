@@ -0,0 +1,61 @@
+use nhls::ap_solver::generate_plan::*;
+use nhls::ap_solver::periodic_ops::*;
+use nhls::ap_solver::*;
+use nhls::util::*;
+use rand::prelude::*;
+
+/// Random domain, slopes and boundaries, the plan must verify.
+/// Each axis slopes on at least one side,
+/// and at least one axis is aperiodic.
+fn random_plan_verify<const GRID_DIMENSION: usize>(
+    rng: &mut StdRng,
+    size_range: std::ops::Range<i32>,
+    steps_range: std::ops::Range<usize>,
+) {
+    let mut bounds = Bounds::<GRID_DIMENSION>::zero();
+    let mut stencil_slopes = Bounds::<GRID_DIMENSION>::zero();
+    let mut axis_boundaries = [AxisBoundary::Aperiodic; GRID_DIMENSION];
+    for d in 0..GRID_DIMENSION {
+        bounds[(d, 0)] = rng.gen_range(-50..50);
+        bounds[(d, 1)] = bounds[(d, 0)] + rng.gen_range(size_range.clone());
+        while stencil_slopes[(d, 0)] + stencil_slopes[(d, 1)] == 0 {
+            stencil_slopes[(d, 0)] = rng.gen_range(0..3);
+            stencil_slopes[(d, 1)] = rng.gen_range(0..3);
+        }
+        if d != 0 && rng.gen_bool(0.25) {
+            axis_boundaries[d] = AxisBoundary::Periodic;
+        }
+    }
+    let params = SolverParameters {
+        aabb: AABB::new(bounds),
+        steps: rng.gen_range(steps_range),
+        cutoff: rng.gen_range(8..24),
+        ratio: rng.gen_range(0.3..0.7),
+        axis_boundaries,
+        ..Default::default()
+    };
+    let plan = generate_plan(stencil_slopes, OpIdCollector::new, &params).plan;
+    if let Err(e) = plan.verify(&stencil_slopes, &params) {
+        panic!(
+            "{e}\n  aabb: {}\n  slopes: {stencil_slopes}\n  steps: {}\n  \
+             cutoff: {}\n  boundaries: {axis_boundaries:?}",
+            params.aabb, params.steps, params.cutoff
+        );
+    }
+}
+
+#[test]
+fn random_plans_2d() {
+    let mut rng = StdRng::seed_from_u64(0x6e686c73);
+    for _ in 0..50 {
+        random_plan_verify::<2>(&mut rng, 40..250, 1..400);
+    }
+}
+
+#[test]
+fn random_plans_3d() {
+    let mut rng = StdRng::seed_from_u64(0x6e686c73);
+    for _ in 0..10 {
+        random_plan_verify::<3>(&mut rng, 30..70, 1..60);
+    }
+}
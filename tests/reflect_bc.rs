@@ -0,0 +1,188 @@
+use float_cmp::assert_approx_eq;
+use nhls::ap_solver::*;
+use nhls::direct_solver::*;
+use nhls::domain::*;
+use nhls::initial_conditions::normal_impulse::*;
+use nhls::initial_conditions::rand::*;
+use nhls::stencil::*;
+use nhls::util::*;
+use nhls::SolverInterface;
+
+pub const TEST_SOLVE_THREADS: usize = 8;
+
+fn total<
+    const GRID_DIMENSION: usize,
+    DomainType: DomainView<GRID_DIMENSION>,
+>(
+    domain: &DomainType,
+) -> f64 {
+    domain.buffer().iter().sum()
+}
+
+/// Solve with `GeneralDirectBoxSolver` and the AP solver,
+/// check that they agree and that both conserve mass.
+fn reflect_compare<
+    const GRID_DIMENSION: usize,
+    const NEIGHBORHOOD_SIZE: usize,
+>(
+    grid_bound: AABB<GRID_DIMENSION>,
+    stencil: &Stencil<GRID_DIMENSION, NEIGHBORHOOD_SIZE>,
+    n_steps: usize,
+    cutoff: i32,
+    ic: fn(&mut OwnedDomain<GRID_DIMENSION>, f64, usize),
+) {
+    let chunk_size = 100;
+
+    // Create domains
+    let buffer_size = grid_bound.buffer_size();
+    let mut direct_buffer_1 = OwnedDomain::new(grid_bound);
+    let mut direct_buffer_2 = OwnedDomain::new(grid_bound);
+    let mut fft_buffer_1 = OwnedDomain::new(grid_bound);
+    let mut fft_buffer_2 = OwnedDomain::new(grid_bound);
+
+    // Wide spike, so plenty of mass reaches the boundary
+    ic(&mut direct_buffer_1, 5.0, chunk_size);
+    ic(&mut fft_buffer_1, 5.0, chunk_size);
+    let expected = total(&direct_buffer_1);
+
+    let mut direct_input_domain = direct_buffer_1.as_slice_domain();
+    let mut direct_output_domain = direct_buffer_2.as_slice_domain();
+    let mut fft_input_domain = fft_buffer_1.as_slice_domain();
+    let mut fft_output_domain = fft_buffer_2.as_slice_domain();
+
+    let bc = ReflectCheck::new(grid_bound);
+
+    // Create AP Solver
+    let solver_params = SolverParameters {
+        cutoff,
+        chunk_size,
+        threads: TEST_SOLVE_THREADS,
+        aabb: grid_bound,
+        steps: n_steps,
+        ..Default::default()
+    };
+    let direct_solver = DirectFrustrumSolver {
+        bc: &bc,
+        stencil,
+        stencil_slopes: stencil.slopes(),
+        chunk_size,
+    };
+    let mut fft_solver =
+        generate_ap_solver(stencil, direct_solver, &solver_params);
+    fft_solver.apply(&mut fft_input_domain, &mut fft_output_domain, 0);
+
+    let mut box_solver =
+        GeneralDirectBoxSolver::new(&bc, stencil, n_steps, chunk_size);
+    box_solver.apply(&mut direct_input_domain, &mut direct_output_domain, 0);
+
+    assert_approx_eq!(
+        f64,
+        total(&direct_output_domain),
+        expected,
+        epsilon = 0.0000001
+    );
+    assert_approx_eq!(
+        f64,
+        total(&fft_output_domain),
+        expected,
+        epsilon = 0.0000001
+    );
+    for i in 0..buffer_size {
+        assert_approx_eq!(
+            f64,
+            fft_output_domain.buffer()[i],
+            direct_output_domain.buffer()[i],
+            epsilon = 0.000001
+        );
+    }
+}
+
+#[test]
+fn heat_1d_reflect() {
+    let grid_bound = AABB::new(matrix![0, 499]);
+    let stencil = nhls::standard_stencils::heat_1d(1.0, 1.0, 0.4);
+    reflect_compare(grid_bound, &stencil, 400, 40, normal_ic_1d);
+}
+
+#[test]
+fn heat_2d_reflect() {
+    let grid_bound = AABB::new(matrix![0, 79; 0, 69]);
+    let stencil = nhls::standard_stencils::heat_2d(1.0, 1.0, 1.0, 0.2, 0.2);
+    reflect_compare(grid_bound, &stencil, 200, 20, normal_ic_2d);
+}
+
+#[test]
+fn heat_3d_reflect() {
+    let grid_bound = AABB::new(matrix![0, 29; 0, 29; 0, 29]);
+    let stencil =
+        nhls::standard_stencils::heat_3d(1.0, 1.0, 1.0, 1.0, 0.1, 0.1, 0.1);
+    reflect_compare(grid_bound, &stencil, 60, 10, normal_ic_3d);
+}
+
+#[test]
+fn anti_reflect_odd_symmetry() {
+    // Odd initial conditions stay odd, so the total stays at zero
+    let grid_bound = AABB::new(matrix![0, 99]);
+    let stencil = nhls::standard_stencils::heat_1d(1.0, 1.0, 0.3);
+    let chunk_size = 10;
+    let mut input = OwnedDomain::new(grid_bound);
+    let mut output = OwnedDomain::new(grid_bound);
+    input.par_set_values(
+        |coord: Coord<1>| if coord[0] < 50 { 1.0 } else { -1.0 },
+        chunk_size,
+    );
+    let bc = AntiReflectCheck::new(grid_bound);
+    box_apply(&bc, &stencil, &mut input, &mut output, 50, 0, chunk_size);
+    assert_approx_eq!(f64, total(&output), 0.0, epsilon = 0.0000001);
+    // Zero boundary pulls the edges down
+    assert!(output.buffer()[0] < 0.6);
+    assert!(output.buffer()[99] > -0.6);
+}
+
+#[test]
+fn direct_opt_5pt2d_reflect_compare() {
+    let grid_bound = AABB::new(matrix![0, 13; 0, 18]);
+    let n_steps = 13;
+    let chunk_size = 100;
+    let threads = 1;
+    let stencil = nhls::standard_stencils::heat_2d(1.0, 1.0, 1.0, 0.2, 0.2);
+    let bc = ReflectCheck::new(grid_bound);
+
+    let mut opt_buffer_1 = OwnedDomain::new(grid_bound);
+    let mut opt_buffer_2 = OwnedDomain::new(grid_bound);
+    let mut naive_buffer_1 = OwnedDomain::new(grid_bound);
+    let mut naive_buffer_2 = OwnedDomain::new(grid_bound);
+    let mut opt_in = opt_buffer_1.as_slice_domain();
+    let mut opt_out = opt_buffer_2.as_slice_domain();
+    let mut naive_in = naive_buffer_1.as_slice_domain();
+    let mut naive_out = naive_buffer_2.as_slice_domain();
+
+    rand_ic(&mut opt_in, 1024, chunk_size);
+    naive_in.buffer_mut().copy_from_slice(opt_in.buffer());
+    let expected = total(&naive_in);
+
+    let mut opt_solver =
+        Direct5Pt2DSolver::new(&stencil, n_steps, threads, chunk_size)
+            .with_bc(&bc);
+    opt_solver.apply(&mut opt_in, &mut opt_out, 0);
+
+    box_apply(
+        &bc,
+        &stencil,
+        &mut naive_in,
+        &mut naive_out,
+        n_steps,
+        0,
+        chunk_size,
+    );
+
+    assert_approx_eq!(f64, total(&naive_out), expected, epsilon = 0.000001);
+    for i in 0..grid_bound.buffer_size() {
+        assert_approx_eq!(
+            f64,
+            opt_out.buffer()[i],
+            naive_out.buffer()[i],
+            epsilon = 0.000000001
+        );
+    }
+}
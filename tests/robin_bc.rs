@@ -0,0 +1,163 @@
+use float_cmp::assert_approx_eq;
+use nhls::direct_solver::*;
+use nhls::domain::*;
+use nhls::initial_conditions::rand::*;
+use nhls::util::*;
+use nhls::SolverInterface;
+
+#[test]
+fn robin_neumann_matches_reflect() {
+    // Zero flux Robin is the same as reflecting
+    let grid_bound = AABB::new(matrix![0, 39; 0, 29]);
+    let n_steps = 40;
+    let chunk_size = 100;
+    let stencil = nhls::standard_stencils::heat_2d(1.0, 1.0, 1.0, 0.2, 0.2);
+    let robin = RobinCheck::new(0.0, 1.0, 0.0, 1.0, grid_bound);
+    let reflect = ReflectCheck::new(grid_bound);
+
+    let mut robin_in = OwnedDomain::new(grid_bound);
+    let mut robin_out = OwnedDomain::new(grid_bound);
+    let mut reflect_in = OwnedDomain::new(grid_bound);
+    let mut reflect_out = OwnedDomain::new(grid_bound);
+    rand_ic(&mut robin_in, 1024, chunk_size);
+    reflect_in.buffer_mut().copy_from_slice(robin_in.buffer());
+
+    box_apply(
+        &robin,
+        &stencil,
+        &mut robin_in,
+        &mut robin_out,
+        n_steps,
+        0,
+        chunk_size,
+    );
+    box_apply(
+        &reflect,
+        &stencil,
+        &mut reflect_in,
+        &mut reflect_out,
+        n_steps,
+        0,
+        chunk_size,
+    );
+    for i in 0..grid_bound.buffer_size() {
+        assert_approx_eq!(
+            f64,
+            robin_out.buffer()[i],
+            reflect_out.buffer()[i],
+            epsilon = 0.000000001
+        );
+    }
+}
+
+#[test]
+fn robin_convective_cooling() {
+    // h * u + k * du/dn = h * ambient
+    let (h, k, ambient) = (1.0, 1.0, 0.25);
+    let grid_bound = AABB::new(matrix![0, 49]);
+    let chunk_size = 10;
+    let stencil = nhls::standard_stencils::heat_1d(1.0, 1.0, 0.4);
+    let bc = RobinCheck::new(h, k, h * ambient, 1.0, grid_bound);
+
+    // Ambient is a steady state
+    let mut input = OwnedDomain::new(grid_bound);
+    let mut output = OwnedDomain::new(grid_bound);
+    input.par_set_values(|_| ambient, chunk_size);
+    box_apply(&bc, &stencil, &mut input, &mut output, 100, 0, chunk_size);
+    for v in output.buffer() {
+        assert_approx_eq!(f64, *v, ambient, epsilon = 0.000000001);
+    }
+
+    // Hot rod cools down towards ambient, fastest at the ends
+    let mut input = OwnedDomain::new(grid_bound);
+    let mut output = OwnedDomain::new(grid_bound);
+    input.par_set_values(|_| 1.0, chunk_size);
+    box_apply(&bc, &stencil, &mut input, &mut output, 2000, 0, chunk_size);
+    let values = output.buffer();
+    for v in values {
+        assert!(*v > ambient && *v < 1.0);
+    }
+    assert!(values[0] < values[25]);
+    assert!(values[49] < values[25]);
+    assert_approx_eq!(f64, values[0], values[49], epsilon = 0.000000001);
+}
+
+#[test]
+fn direct_opt_3pt1d_robin_compare() {
+    let grid_bound = AABB::new(matrix![0, 99]);
+    let n_steps = 17;
+    let chunk_size = 10;
+    let threads = 1;
+    let stencil = nhls::standard_stencils::heat_1d(1.0, 1.0, 0.3);
+    let bc = RobinCheck::new(2.0, 0.5, 3.0, 1.0, grid_bound);
+
+    let mut opt_buffer_1 = OwnedDomain::new(grid_bound);
+    let mut opt_buffer_2 = OwnedDomain::new(grid_bound);
+    let mut naive_buffer_1 = OwnedDomain::new(grid_bound);
+    let mut naive_buffer_2 = OwnedDomain::new(grid_bound);
+    let mut opt_in = opt_buffer_1.as_slice_domain();
+    let mut opt_out = opt_buffer_2.as_slice_domain();
+    let mut naive_in = naive_buffer_1.as_slice_domain();
+    let mut naive_out = naive_buffer_2.as_slice_domain();
+
+    rand_ic(&mut opt_in, 1024, chunk_size);
+    naive_in.buffer_mut().copy_from_slice(opt_in.buffer());
+
+    let mut opt_solver =
+        Direct3Pt1DSolver::new(&stencil, n_steps, threads, chunk_size)
+            .with_bc(&bc);
+    opt_solver.apply(&mut opt_in, &mut opt_out, 0);
+
+    box_apply(
+        &bc,
+        &stencil,
+        &mut naive_in,
+        &mut naive_out,
+        n_steps,
+        0,
+        chunk_size,
+    );
+
+    for i in 0..grid_bound.buffer_size() {
+        assert_approx_eq!(
+            f64,
+            opt_out.buffer()[i],
+            naive_out.buffer()[i],
+            epsilon = 0.000000001
+        );
+    }
+}
+
+#[test]
+fn face_bc_robin_corner() {
+    // Convective at x max, periodic in y.
+    // Initial conditions only depend on x, so rows stay the same.
+    let grid_bound = AABB::new(matrix![0, 29; 0, 19]);
+    let n_steps = 30;
+    let chunk_size = 100;
+    let stencil = nhls::standard_stencils::heat_2d(1.0, 1.0, 1.0, 0.2, 0.2);
+    let bc = FaceBC::new(grid_bound, ReflectCheck::new(grid_bound))
+        .with_face(0, 1, RobinCheck::new(1.0, 1.0, 0.0, 1.0, grid_bound))
+        .with_axis(1, WrapCheck::new(grid_bound));
+
+    let mut input = OwnedDomain::new(grid_bound);
+    let mut output = OwnedDomain::new(grid_bound);
+    input.par_set_values(|coord: Coord<2>| coord[0] as f64, chunk_size);
+    box_apply(
+        &bc,
+        &stencil,
+        &mut input,
+        &mut output,
+        n_steps,
+        0,
+        chunk_size,
+    );
+    for coord in grid_bound.coord_iter() {
+        assert_approx_eq!(
+            f64,
+            output.view(&coord),
+            output.view(&vector![coord[0], 0]),
+            epsilon = 0.000000001
+        );
+    }
+}
@@ -0,0 +1,234 @@
+//! Quantifies how far `f32` solves drift from `f64` solves.
+//! Each test prints the drift it measured,
+//! run with `--nocapture` to see them.
+
+use nhls::ap_solver::account_builder::*;
+use nhls::ap_solver::ap_periodic_ops_builder::*;
+use nhls::ap_solver::generate_plan::*;
+use nhls::ap_solver::scratch_builder::ComplexBufferType;
+use nhls::ap_solver::*;
+use nhls::direct_solver::*;
+use nhls::domain::*;
+use nhls::stencil::*;
+use nhls::util::*;
+use nhls::SolverInterface;
+
+pub const TEST_SOLVE_THREADS: usize = 8;
+
+/// Largest relative drift we accept between `f32` and `f64` solves.
+const F32_DRIFT_TOLERANCE: f64 = 1e-4;
+
+/// Smooth initial condition, with some structure along every axis.
+fn ic<const GRID_DIMENSION: usize>(coord: Coord<GRID_DIMENSION>) -> f64 {
+    let mut result = 1.0;
+    for d in 0..GRID_DIMENSION {
+        result += (coord[d] as f64 * 0.3 * (d + 1) as f64).sin();
+    }
+    result
+}
+
+/// max |a - b| / max |b|
+fn relative_drift(a: &[f32], b: &[f64]) -> f64 {
+    assert_eq!(a.len(), b.len());
+    let mut max_diff: f64 = 0.0;
+    let mut max_value: f64 = 0.0;
+    for (x, y) in a.iter().zip(b.iter()) {
+        max_diff = max_diff.max((*x as f64 - y).abs());
+        max_value = max_value.max(y.abs());
+    }
+    max_diff / max_value
+}
+
+/// Runs the same AP solve with `f32` and `f64` domains,
+/// returns the relative drift between them.
+fn ap_scalar_drift<
+    const GRID_DIMENSION: usize,
+    const NEIGHBORHOOD_SIZE: usize,
+    DirectSolverType: DirectSolverInterface<GRID_DIMENSION, f64>
+        + DirectSolverInterface<GRID_DIMENSION, f32>,
+    CreateDirectFn: Fn() -> DirectSolverType,
+>(
+    grid_bound: AABB<GRID_DIMENSION>,
+    stencil: &Stencil<GRID_DIMENSION, NEIGHBORHOOD_SIZE>,
+    create_direct_solver: CreateDirectFn,
+    n_steps: usize,
+    cutoff: i32,
+) -> f64 {
+    let chunk_size = 100;
+    let solver_params = SolverParameters {
+        cutoff,
+        chunk_size,
+        threads: TEST_SOLVE_THREADS,
+        aabb: grid_bound,
+        steps: n_steps,
+        ..Default::default()
+    };
+
+    let mut f64_buffer_1 = OwnedDomain::new(grid_bound);
+    let mut f64_buffer_2 = OwnedDomain::new(grid_bound);
+    let mut f64_input = f64_buffer_1.as_slice_domain();
+    let mut f64_output = f64_buffer_2.as_slice_domain();
+    f64_input.par_set_values(ic, chunk_size);
+    let mut f64_solver =
+        generate_ap_solver(stencil, create_direct_solver(), &solver_params);
+    f64_solver.apply(&mut f64_input, &mut f64_output, 0);
+
+    let mut f32_buffer_1 =
+        OwnedDomain::<GRID_DIMENSION, f32>::new_scalar(grid_bound);
+    let mut f32_buffer_2 =
+        OwnedDomain::<GRID_DIMENSION, f32>::new_scalar(grid_bound);
+    let mut f32_input = f32_buffer_1.as_slice_domain();
+    let mut f32_output = f32_buffer_2.as_slice_domain();
+    f32_input.par_set_values(|coord| ic(coord) as f32, chunk_size);
+    let mut f32_solver = generate_scalar_ap_solver::<GRID_DIMENSION, f32, _, _>(
+        stencil,
+        create_direct_solver(),
+        &solver_params,
+    );
+    f32_solver.apply(&mut f32_input, &mut f32_output, 0);
+
+    let drift = relative_drift(f32_output.buffer(), f64_output.buffer());
+    println!(
+        "{GRID_DIMENSION}D AP solve, {n_steps} steps, f32 drift: {drift:e}"
+    );
+    drift
+}
+
+#[test]
+fn heat_1d_ap_f32_drift() {
+    let grid_bound = AABB::new(matrix![0, 999]);
+    let stencil = nhls::standard_stencils::heat_1d(1.0, 1.0, 0.5);
+    let bc = ConstantCheck::new(1.0, grid_bound);
+    let drift = ap_scalar_drift(
+        grid_bound,
+        &stencil,
+        || DirectSolver3Pt1DOpt::new(&stencil, 100).with_bc(&bc),
+        400,
+        40,
+    );
+    assert!(drift < F32_DRIFT_TOLERANCE);
+}
+
+#[test]
+fn heat_2d_ap_f32_drift() {
+    let grid_bound = AABB::new(matrix![0, 199; 0, 199]);
+    let stencil = nhls::standard_stencils::heat_2d(1.0, 1.0, 1.0, 0.2, 0.2);
+    let bc = ConstantCheck::new(1.0, grid_bound);
+    let drift = ap_scalar_drift(
+        grid_bound,
+        &stencil,
+        || DirectFrustrumSolver {
+            bc: &bc,
+            stencil: &stencil,
+            stencil_slopes: stencil.slopes(),
+            chunk_size: 100,
+        },
+        200,
+        20,
+    );
+    assert!(drift < F32_DRIFT_TOLERANCE);
+}
+
+#[test]
+fn heat_2d_opt_ap_f32_drift() {
+    let grid_bound = AABB::new(matrix![0, 199; 0, 149]);
+    let stencil = nhls::standard_stencils::heat_2d(1.0, 1.0, 1.0, 0.2, 0.2);
+    let bc = ConstantCheck::new(1.0, grid_bound);
+    let drift = ap_scalar_drift(
+        grid_bound,
+        &stencil,
+        || DirectSolver5Pt2DOpt::new(&stencil).with_bc(&bc),
+        300,
+        20,
+    );
+    assert!(drift < F32_DRIFT_TOLERANCE);
+}
+
+#[test]
+fn heat_3d_ap_f32_drift() {
+    let grid_bound = AABB::new(matrix![0, 39; 0, 39; 0, 39]);
+    let stencil =
+        nhls::standard_stencils::heat_3d(1.0, 1.0, 1.0, 1.0, 0.1, 0.1, 0.1);
+    let bc = ConstantCheck::new(1.0, grid_bound);
+    let drift = ap_scalar_drift(
+        grid_bound,
+        &stencil,
+        || DirectFrustrumSolver {
+            bc: &bc,
+            stencil: &stencil,
+            stencil_slopes: stencil.slopes(),
+            chunk_size: 100,
+        },
+        50,
+        10,
+    );
+    assert!(drift < F32_DRIFT_TOLERANCE);
+}
+
+#[test]
+fn heat_2d_direct_f32_drift() {
+    // Baseline for the AP drift, no FFTs involved
+    let grid_bound = AABB::new(matrix![0, 99; 0, 99]);
+    let stencil = nhls::standard_stencils::heat_2d(1.0, 1.0, 1.0, 0.2, 0.2);
+    let bc = ConstantCheck::new(1.0, grid_bound);
+    let n_steps = 200;
+    let chunk_size = 100;
+
+    let mut f64_input = OwnedDomain::new(grid_bound);
+    let mut f64_output = OwnedDomain::new(grid_bound);
+    f64_input.par_set_values(ic, chunk_size);
+    box_apply(
+        &bc,
+        &stencil,
+        &mut f64_input,
+        &mut f64_output,
+        n_steps,
+        0,
+        chunk_size,
+    );
+
+    let mut f32_input = OwnedDomain::<2, f32>::new_scalar(grid_bound);
+    let mut f32_output = OwnedDomain::<2, f32>::new_scalar(grid_bound);
+    f32_input.par_set_values(|coord| ic(coord) as f32, chunk_size);
+    box_apply(
+        &bc,
+        &stencil,
+        &mut f32_input,
+        &mut f32_output,
+        n_steps,
+        0,
+        chunk_size,
+    );
+
+    let drift = relative_drift(f32_output.buffer(), f64_output.buffer());
+    println!("2D direct solve, {n_steps} steps, f32 drift: {drift:e}");
+    assert!(drift < F32_DRIFT_TOLERANCE);
+}
+
+#[test]
+fn f32_scratch_is_smaller() {
+    let grid_bound = AABB::new(matrix![0, 499; 0, 499]);
+    let stencil = nhls::standard_stencils::heat_2d(1.0, 1.0, 1.0, 0.2, 0.2);
+    let params = SolverParameters {
+        cutoff: 40,
+        aabb: grid_bound,
+        steps: 400,
+        ..Default::default()
+    };
+    let plan = generate_plan(
+        stencil.slopes(),
+        || ApPeriodicOpsBuilder::new(&stencil, &params),
+        &params,
+    )
+    .plan;
+
+    let complex_buffer_type = ComplexBufferType::DomainOnly;
+    let f64_blocks =
+        AccountBuilder::node_requirements::<f64>(&plan, complex_buffer_type)
+            [plan.root];
+    let f32_blocks =
+        AccountBuilder::node_requirements::<f32>(&plan, complex_buffer_type)
+            [plan.root];
+    println!("scratch blocks, f64: {f64_blocks}, f32: {f32_blocks}");
+    assert!((f32_blocks as f64) < 0.6 * f64_blocks as f64);
+}
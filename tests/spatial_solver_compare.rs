@@ -0,0 +1,181 @@
+use float_cmp::assert_approx_eq;
+use nhls::ap_solver::*;
+use nhls::direct_solver::*;
+use nhls::domain::*;
+use nhls::initial_conditions::normal_impulse::*;
+use nhls::stencil::*;
+use nhls::util::*;
+use nhls::SolverInterface;
+
+pub const TEST_SOLVE_THREADS: usize = 8;
+
+/// Compare AP solver against direct box solve for a spatial stencil
+fn spatial_2d_compare(
+    grid_bound: AABB<2>,
+    stencil: &RegionStencil<2, 5>,
+    n_steps: usize,
+    cutoff: i32,
+) {
+    let chunk_size = 100;
+
+    // Create domains
+    let buffer_size = grid_bound.buffer_size();
+    let mut direct_input_domain = OwnedDomain::new(grid_bound);
+    let mut direct_output_domain = OwnedDomain::new(grid_bound);
+    let mut fft_buffer_1 = OwnedDomain::new(grid_bound);
+    let mut fft_buffer_2 = OwnedDomain::new(grid_bound);
+    let mut fft_input_domain = fft_buffer_1.as_slice_domain();
+    let mut fft_output_domain = fft_buffer_2.as_slice_domain();
+
+    // Fill in with IC values (use normal dist for spike in the middle)
+    normal_ic_2d(&mut direct_input_domain, 25.0, chunk_size);
+    normal_ic_2d(&mut fft_input_domain, 25.0, chunk_size);
+
+    // Create BC
+    let bc = ConstantCheck::new(1.0, grid_bound);
+
+    // Create AP Solver
+    let solver_params = SolverParameters {
+        cutoff,
+        chunk_size,
+        threads: TEST_SOLVE_THREADS,
+        aabb: grid_bound,
+        steps: n_steps,
+        ..Default::default()
+    };
+    let direct_solver = SpatialDirectFrustrumSolver {
+        bc: &bc,
+        stencil,
+        stencil_slopes: stencil.slopes(),
+        chunk_size,
+    };
+    let mut fft_solver =
+        generate_spatial_ap_solver(stencil, direct_solver, &solver_params);
+    fft_solver.apply(&mut fft_input_domain, &mut fft_output_domain, 0);
+
+    spatial_box_apply(
+        &bc,
+        stencil,
+        &mut direct_input_domain,
+        &mut direct_output_domain,
+        n_steps,
+        0,
+        chunk_size,
+    );
+
+    for i in 0..buffer_size {
+        assert_approx_eq!(
+            f64,
+            fft_output_domain.buffer()[i],
+            direct_output_domain.buffer()[i],
+            epsilon = 0.0000001
+        );
+    }
+}
+
+#[test]
+fn spatial_2d_corner_inclusion() {
+    let grid_bound = AABB::new(matrix![0, 119; 0, 99]);
+    let mut stencil = RegionStencil::new(nhls::standard_stencils::heat_2d(
+        1.0, 1.0, 1.0, 0.2, 0.2,
+    ));
+    let inclusion = nhls::standard_stencils::heat_2d(1.0, 1.0, 1.0, 0.05, 0.02);
+    stencil.add_region(AABB::new(matrix![90, 110; 70, 90]), &inclusion);
+    spatial_2d_compare(grid_bound, &stencil, 200, 20);
+}
+
+#[test]
+fn spatial_2d_central_inclusion() {
+    // Every quadrant of the domain touches the inclusion
+    let grid_bound = AABB::new(matrix![0, 119; 0, 99]);
+    let mut stencil = RegionStencil::new(nhls::standard_stencils::heat_2d(
+        1.0, 1.0, 1.0, 0.2, 0.2,
+    ));
+    let inclusion = nhls::standard_stencils::heat_2d(1.0, 1.0, 1.0, 0.1, 0.1);
+    stencil.add_region(AABB::new(matrix![50, 70; 40, 60]), &inclusion);
+    spatial_2d_compare(grid_bound, &stencil, 150, 20);
+}
+
+#[test]
+fn spatial_2d_homogeneous() {
+    // Regions matching the background shouldn't change the plan
+    let grid_bound = AABB::new(matrix![0, 79; 0, 69]);
+    let background = nhls::standard_stencils::heat_2d(1.0, 1.0, 1.0, 0.2, 0.2);
+    let mut stencil = RegionStencil::new(nhls::standard_stencils::heat_2d(
+        1.0, 1.0, 1.0, 0.2, 0.2,
+    ));
+    stencil.add_region(AABB::new(matrix![10, 20; 10, 20]), &background);
+    spatial_2d_compare(grid_bound, &stencil, 100, 20);
+}
+
+#[test]
+fn spatial_2d_no_periodic_solve() {
+    // Inhomogeneous everywhere, so the planner falls back to direct solves
+    let grid_bound = AABB::new(matrix![0, 59; 0, 49]);
+    let mut stencil = RegionStencil::new(nhls::standard_stencils::heat_2d(
+        1.0, 1.0, 1.0, 0.2, 0.2,
+    ));
+    let other = nhls::standard_stencils::heat_2d(1.0, 1.0, 1.0, 0.1, 0.1);
+    stencil.add_region(grid_bound, &other);
+    spatial_2d_compare(grid_bound, &stencil, 30, 10);
+}
+
+#[test]
+fn spatial_1d_inclusion() {
+    let grid_bound = AABB::new(matrix![0, 499]);
+    let n_steps = 300;
+    let chunk_size = 100;
+
+    let mut stencil =
+        RegionStencil::new(nhls::standard_stencils::heat_1d(1.0, 1.0, 0.5));
+    let inclusion = nhls::standard_stencils::heat_1d(1.0, 1.0, 0.1);
+    stencil.add_region(AABB::new(matrix![300, 340]), &inclusion);
+
+    let buffer_size = grid_bound.buffer_size();
+    let mut direct_input_domain = OwnedDomain::new(grid_bound);
+    let mut direct_output_domain = OwnedDomain::new(grid_bound);
+    let mut fft_buffer_1 = OwnedDomain::new(grid_bound);
+    let mut fft_buffer_2 = OwnedDomain::new(grid_bound);
+    let mut fft_input_domain = fft_buffer_1.as_slice_domain();
+    let mut fft_output_domain = fft_buffer_2.as_slice_domain();
+    normal_ic_1d(&mut direct_input_domain, 25.0, chunk_size);
+    normal_ic_1d(&mut fft_input_domain, 25.0, chunk_size);
+
+    let bc = ConstantCheck::new(1.0, grid_bound);
+    let solver_params = SolverParameters {
+        cutoff: 40,
+        chunk_size,
+        threads: TEST_SOLVE_THREADS,
+        aabb: grid_bound,
+        steps: n_steps,
+        ..Default::default()
+    };
+    let direct_solver = SpatialDirectFrustrumSolver {
+        bc: &bc,
+        stencil: &stencil,
+        stencil_slopes: stencil.slopes(),
+        chunk_size,
+    };
+    let mut fft_solver =
+        generate_spatial_ap_solver(&stencil, direct_solver, &solver_params);
+    fft_solver.apply(&mut fft_input_domain, &mut fft_output_domain, 0);
+
+    spatial_box_apply(
+        &bc,
+        &stencil,
+        &mut direct_input_domain,
+        &mut direct_output_domain,
+        n_steps,
+        0,
+        chunk_size,
+    );
+
+    for i in 0..buffer_size {
+        assert_approx_eq!(
+            f64,
+            fft_output_domain.buffer()[i],
+            direct_output_domain.buffer()[i],
+            epsilon = 0.0000001
+        );
+    }
+}
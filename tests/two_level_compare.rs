@@ -0,0 +1,205 @@
+use float_cmp::assert_approx_eq;
+use nhls::ap_solver::*;
+use nhls::direct_solver::*;
+use nhls::domain::*;
+use nhls::fft_solver::*;
+use nhls::initial_conditions::normal_impulse::*;
+use nhls::stencil::*;
+use nhls::util::*;
+use nhls::TwoLevelSolverInterface;
+
+/// Compare periodic FFT solve against direct periodic solve
+/// for a two level stencil, starting from rest.
+fn two_level_compare<const GRID_DIMENSION: usize>(
+    grid_bound: AABB<GRID_DIMENSION>,
+    stencil: &TwoLevelStencil<GRID_DIMENSION>,
+    n_steps: usize,
+    ic: fn(&mut OwnedDomain<GRID_DIMENSION>, f64, usize),
+) {
+    let chunk_size = 100;
+
+    // Create domains
+    let buffer_size = grid_bound.buffer_size();
+    let mut direct_previous = OwnedDomain::new(grid_bound);
+    let mut direct_current = OwnedDomain::new(grid_bound);
+    let mut direct_scratch = OwnedDomain::new(grid_bound);
+    let mut fft_previous = OwnedDomain::new(grid_bound);
+    let mut fft_current = OwnedDomain::new(grid_bound);
+    let mut fft_next_previous = OwnedDomain::new(grid_bound);
+    let mut fft_next_current = OwnedDomain::new(grid_bound);
+
+    let plan_type = PlanType::Estimate;
+    let mut periodic_solver = TwoLevelPeriodicSolver::create(
+        stencil,
+        fft_current.buffer_mut(),
+        &grid_bound,
+        n_steps,
+        plan_type,
+        chunk_size,
+        8,
+    );
+
+    // Same displacement at both levels, i.e. zero initial velocity
+    ic(&mut direct_previous, 25.0, chunk_size);
+    ic(&mut direct_current, 25.0, chunk_size);
+    ic(&mut fft_previous, 25.0, chunk_size);
+    ic(&mut fft_current, 25.0, chunk_size);
+
+    periodic_solver.apply(
+        &mut fft_previous,
+        &mut fft_current,
+        &mut fft_next_previous,
+        &mut fft_next_current,
+    );
+    two_level_direct_periodic_apply(
+        stencil,
+        &mut direct_previous,
+        &mut direct_current,
+        &mut direct_scratch,
+        n_steps,
+        chunk_size,
+    );
+
+    for i in 0..buffer_size {
+        assert_approx_eq!(
+            f64,
+            fft_next_current.buffer()[i],
+            direct_current.buffer()[i],
+            epsilon = 0.00000001
+        );
+        assert_approx_eq!(
+            f64,
+            fft_next_previous.buffer()[i],
+            direct_previous.buffer()[i],
+            epsilon = 0.00000001
+        );
+    }
+}
+
+#[test]
+fn wave_1d_p_compare() {
+    let grid_bound = AABB::new(matrix![0, 999]);
+    let stencil = nhls::standard_stencils::wave_1d(0.5, 1.0, 1.0);
+    two_level_compare(grid_bound, &stencil, 400, normal_ic_1d);
+}
+
+#[test]
+fn wave_2d_p_compare() {
+    let grid_bound = AABB::new(matrix![0, 99; 0, 119]);
+    let stencil = nhls::standard_stencils::wave_2d(0.5, 1.0, 1.0, 1.0);
+    two_level_compare(grid_bound, &stencil, 60, normal_ic_2d);
+}
+
+#[test]
+fn wave_1d_single_step() {
+    let grid_bound = AABB::new(matrix![0, 99]);
+    let stencil = nhls::standard_stencils::wave_1d(0.9, 1.0, 1.0);
+    two_level_compare(grid_bound, &stencil, 1, normal_ic_1d);
+}
+
+/// Compare the two level AP solver against a direct box solve
+/// with the same boundary condition, starting from rest.
+fn two_level_ap_compare<const GRID_DIMENSION: usize>(
+    grid_bound: AABB<GRID_DIMENSION>,
+    stencil: &TwoLevelStencil<GRID_DIMENSION>,
+    n_steps: usize,
+    global_time: usize,
+    ic: fn(&mut OwnedDomain<GRID_DIMENSION>, f64, usize),
+) {
+    let chunk_size = 100;
+
+    // Create domains
+    let buffer_size = grid_bound.buffer_size();
+    let mut direct_buffer_1 = OwnedDomain::new(grid_bound);
+    let mut direct_buffer_2 = OwnedDomain::new(grid_bound);
+    let mut direct_buffer_3 = OwnedDomain::new(grid_bound);
+    let mut direct_buffer_4 = OwnedDomain::new(grid_bound);
+    let mut ap_buffer_1 = OwnedDomain::new(grid_bound);
+    let mut ap_buffer_2 = OwnedDomain::new(grid_bound);
+    let mut ap_buffer_3 = OwnedDomain::new(grid_bound);
+    let mut ap_buffer_4 = OwnedDomain::new(grid_bound);
+
+    // Same displacement at both levels, i.e. zero initial velocity
+    ic(&mut direct_buffer_1, 25.0, chunk_size);
+    ic(&mut direct_buffer_2, 25.0, chunk_size);
+    ic(&mut ap_buffer_1, 25.0, chunk_size);
+    ic(&mut ap_buffer_2, 25.0, chunk_size);
+
+    let mut direct_previous = direct_buffer_1.as_slice_domain();
+    let mut direct_current = direct_buffer_2.as_slice_domain();
+    let mut direct_next_previous = direct_buffer_3.as_slice_domain();
+    let mut direct_next_current = direct_buffer_4.as_slice_domain();
+    let mut ap_previous = ap_buffer_1.as_slice_domain();
+    let mut ap_current = ap_buffer_2.as_slice_domain();
+    let mut ap_next_previous = ap_buffer_3.as_slice_domain();
+    let mut ap_next_current = ap_buffer_4.as_slice_domain();
+
+    // Create BC
+    let bc = ConstantCheck::new(0.5, grid_bound);
+
+    // Create AP Solver
+    let solver_params = SolverParameters {
+        plan_type: PlanType::Estimate,
+        cutoff: 40,
+        chunk_size,
+        threads: 8,
+        aabb: grid_bound,
+        steps: n_steps,
+        ..Default::default()
+    };
+    let direct_solver = TwoLevelDirectFrustrumSolver {
+        bc: &bc,
+        stencil,
+        stencil_slopes: stencil.slopes(),
+        chunk_size,
+    };
+    let mut ap_solver =
+        generate_two_level_ap_solver(stencil, direct_solver, &solver_params);
+    ap_solver.apply(
+        &mut ap_previous,
+        &mut ap_current,
+        &mut ap_next_previous,
+        &mut ap_next_current,
+        global_time,
+    );
+
+    let mut box_solver =
+        TwoLevelDirectBoxSolver::new(&bc, stencil, n_steps, chunk_size);
+    box_solver.apply(
+        &mut direct_previous,
+        &mut direct_current,
+        &mut direct_next_previous,
+        &mut direct_next_current,
+        global_time,
+    );
+
+    for i in 0..buffer_size {
+        assert_approx_eq!(
+            f64,
+            ap_next_current.buffer()[i],
+            direct_next_current.buffer()[i],
+            epsilon = 0.00000001
+        );
+        assert_approx_eq!(
+            f64,
+            ap_next_previous.buffer()[i],
+            direct_next_previous.buffer()[i],
+            epsilon = 0.00000001
+        );
+    }
+}
+
+#[test]
+fn wave_1d_ap_compare() {
+    let grid_bound = AABB::new(matrix![0, 999]);
+    let stencil = nhls::standard_stencils::wave_1d(0.5, 1.0, 1.0);
+    two_level_ap_compare(grid_bound, &stencil, 400, 0, normal_ic_1d);
+    two_level_ap_compare(grid_bound, &stencil, 13, 3, normal_ic_1d);
+}
+
+#[test]
+fn wave_2d_ap_compare() {
+    let grid_bound = AABB::new(matrix![333, 431; 5, 106]);
+    let stencil = nhls::standard_stencils::wave_2d(0.5, 1.0, 1.0, 1.0);
+    two_level_ap_compare(grid_bound, &stencil, 90, 0, normal_ic_2d);
+}